@@ -0,0 +1,387 @@
+use axum::extract::Path;
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::routing::{delete, get};
+use axum::{Extension, Json, Router};
+use axum::middleware;
+use serde::Deserialize;
+use serde_json::json;
+use tracing::info;
+
+use crate::auth::jwt::UserInfo;
+use crate::auth::middleware::{auth_middleware, require_admin};
+use crate::config::auth_config::ApiKeyInfo;
+use crate::config::routes_config::RouteRule;
+use crate::config::CONFIG;
+
+/// 路由与API Key管理端点（仅admin角色）
+///
+/// 直接修改全局配置的内存态：认证中间件和动态路由每次请求都从
+/// 全局配置读取，改动立即生效，无需等待配置文件watcher。
+/// 注意改动不回写配置文件，下次文件热更新时会被文件内容覆盖
+pub fn admin_router() -> Router {
+    Router::new()
+        .route("/admin/routes", get(list_routes).post(upsert_route))
+        .route("/admin/routes/{id}", delete(delete_route))
+        .route("/admin/api-keys", get(list_api_keys).post(upsert_api_key))
+        .route("/admin/api-keys/{key}", delete(delete_api_key))
+        .route_layer(middleware::from_fn(require_admin))
+        .route_layer(middleware::from_fn(auth_middleware))
+}
+
+/// 查看当前生效的全部路由规则
+async fn list_routes() -> impl IntoResponse {
+    let config = CONFIG.read().await;
+    Json(config.routes.routes.clone())
+}
+
+/// 新增或按id替换路由规则
+async fn upsert_route(
+    Extension(user): Extension<UserInfo>,
+    Json(rule): Json<RouteRule>,
+) -> impl IntoResponse {
+    if rule.id.is_empty() || rule.path_prefix.is_empty() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "error": StatusCode::BAD_REQUEST.as_u16(),
+                "message": "id与path_prefix不能为空",
+            })),
+        );
+    }
+
+    let mut config = CONFIG.write().await;
+
+    // 先在副本上校验（与配置文件加载同一套校验），非法规则不落地
+    let mut candidate = config.routes.clone();
+    candidate.routes.retain(|r| r.id != rule.id);
+    candidate.routes.push(rule.clone());
+    if let Err(err) = candidate.validate() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "error": StatusCode::BAD_REQUEST.as_u16(),
+                "message": err.to_string(),
+            })),
+        );
+    }
+
+    let replaced = config.routes.routes.iter().any(|r| r.id == rule.id);
+    config.routes = candidate;
+
+    info!(
+        operator = %user.username,
+        operator_id = user.user_id,
+        route_id = %rule.id,
+        path_prefix = %rule.path_prefix,
+        replaced,
+        "管理端{}路由规则",
+        if replaced { "替换" } else { "新增" }
+    );
+    (StatusCode::OK, Json(json!({ "id": rule.id, "replaced": replaced })))
+}
+
+/// 按id删除路由规则
+async fn delete_route(
+    Extension(user): Extension<UserInfo>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    let mut config = CONFIG.write().await;
+    let before = config.routes.routes.len();
+    config.routes.routes.retain(|r| r.id != id);
+    if config.routes.routes.len() == before {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": StatusCode::NOT_FOUND.as_u16(),
+                "message": "路由规则不存在",
+            })),
+        );
+    }
+
+    info!(
+        operator = %user.username,
+        operator_id = user.user_id,
+        route_id = %id,
+        "管理端删除路由规则"
+    );
+    (StatusCode::OK, Json(json!({ "id": id })))
+}
+
+/// 查看API Key列表，密钥本身打码后返回
+async fn list_api_keys() -> impl IntoResponse {
+    let config = CONFIG.read().await;
+    let keys: Vec<_> = config
+        .auth
+        .api_key
+        .api_keys
+        .iter()
+        .map(|(key, info)| {
+            json!({
+                "key": mask_key(key),
+                "name": info.name,
+                "user_id": info.user_id,
+                "permissions": info.permissions,
+                "enabled": info.enabled,
+                "expires_at": info.expires_at,
+            })
+        })
+        .collect();
+    Json(keys)
+}
+
+/// 新增或替换API Key请求体
+#[derive(Deserialize)]
+struct UpsertApiKey {
+    /// 完整密钥
+    key: String,
+    #[serde(flatten)]
+    info: ApiKeyInfo,
+}
+
+/// 新增或替换API Key（用enabled=false的POST即可禁用某个密钥）
+async fn upsert_api_key(
+    Extension(user): Extension<UserInfo>,
+    Json(req): Json<UpsertApiKey>,
+) -> impl IntoResponse {
+    if req.key.is_empty() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "error": StatusCode::BAD_REQUEST.as_u16(),
+                "message": "key不能为空",
+            })),
+        );
+    }
+
+    let mut config = CONFIG.write().await;
+    let replaced = config
+        .auth
+        .api_key
+        .api_keys
+        .insert(req.key.clone(), req.info.clone())
+        .is_some();
+
+    info!(
+        operator = %user.username,
+        operator_id = user.user_id,
+        key = %mask_key(&req.key),
+        name = %req.info.name,
+        enabled = req.info.enabled,
+        replaced,
+        "管理端{}API Key",
+        if replaced { "替换" } else { "新增" }
+    );
+    (
+        StatusCode::OK,
+        Json(json!({ "key": mask_key(&req.key), "replaced": replaced })),
+    )
+}
+
+/// 删除API Key
+async fn delete_api_key(
+    Extension(user): Extension<UserInfo>,
+    Path(key): Path<String>,
+) -> impl IntoResponse {
+    let mut config = CONFIG.write().await;
+    if config.auth.api_key.api_keys.remove(&key).is_none() {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": StatusCode::NOT_FOUND.as_u16(),
+                "message": "API Key不存在",
+            })),
+        );
+    }
+
+    info!(
+        operator = %user.username,
+        operator_id = user.user_id,
+        key = %mask_key(&key),
+        "管理端删除API Key"
+    );
+    (StatusCode::OK, Json(json!({ "key": mask_key(&key) })))
+}
+
+/// 密钥打码：保留前4位，其余用*代替，避免在响应和日志中泄露完整密钥
+fn mask_key(key: &str) -> String {
+    let visible: String = key.chars().take(4).collect();
+    format!("{}****", visible)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::{Method, Request};
+    use http_body_util::BodyExt;
+    use std::collections::HashMap;
+    use tower::ServiceExt;
+
+    fn admin_user() -> UserInfo {
+        UserInfo {
+            user_id: 1,
+            username: "ops-admin".to_string(),
+            roles: vec!["admin".to_string()],
+            extra: HashMap::new(),
+        }
+    }
+
+    fn request(method: Method, uri: &str, body: serde_json::Value, user: Option<UserInfo>) -> Request<Body> {
+        let mut request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        if let Some(user) = user {
+            request.extensions_mut().insert(user);
+        }
+        request
+    }
+
+    /// 跳过auth_middleware（无网关jwt配置可用），仅保留admin角色守卫，
+    /// 处理器从请求扩展中读取UserInfo
+    fn test_router() -> Router {
+        Router::new()
+            .route("/admin/routes", get(list_routes).post(upsert_route))
+            .route("/admin/routes/{id}", delete(delete_route))
+            .route("/admin/api-keys", get(list_api_keys).post(upsert_api_key))
+            .route("/admin/api-keys/{key}", delete(delete_api_key))
+            .route_layer(middleware::from_fn(require_admin))
+    }
+
+    #[tokio::test]
+    async fn test_route_upsert_and_delete_take_effect_immediately() {
+        let app = test_router();
+        let rule = json!({
+            "id": "admin-test-route",
+            "name": "admin-test-route",
+            "path_prefix": "/api/admin-test-route",
+            "service_type": { "HttpService": "admin-test" },
+            "require_auth": false,
+            "path_rewrite": null
+        });
+
+        // 新增路由
+        let response = app
+            .clone()
+            .oneshot(request(Method::POST, "/admin/routes", rule.clone(), Some(admin_user())))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // 全局配置立即可见（动态路由按请求读取，无需重建）
+        {
+            let config = CONFIG.read().await;
+            assert!(config
+                .routes
+                .match_route("/api/admin-test-route/ping")
+                .is_some_and(|r| r.id == "admin-test-route"));
+        }
+
+        // 非法重写正则被校验拒绝，不落地
+        let mut bad_rule = rule.clone();
+        bad_rule["id"] = json!("admin-test-bad");
+        bad_rule["path_rewrite"] = json!({
+            "replace_prefix": null,
+            "regex_match": "([",
+            "regex_replace": "/x"
+        });
+        let response = app
+            .clone()
+            .oneshot(request(Method::POST, "/admin/routes", bad_rule, Some(admin_user())))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        // 删除路由
+        let response = app
+            .clone()
+            .oneshot(request(
+                Method::DELETE,
+                "/admin/routes/admin-test-route",
+                json!({}),
+                Some(admin_user()),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(CONFIG.read().await.routes.routes.iter().all(|r| r.id != "admin-test-route"));
+
+        // 再删一次404
+        let response = app
+            .oneshot(request(
+                Method::DELETE,
+                "/admin/routes/admin-test-route",
+                json!({}),
+                Some(admin_user()),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_api_key_management_masks_secrets() {
+        let app = test_router();
+
+        // 非admin角色被拒绝
+        let user = UserInfo {
+            roles: vec!["user".to_string()],
+            ..admin_user()
+        };
+        let response = app
+            .clone()
+            .oneshot(request(Method::GET, "/admin/api-keys", json!({}), Some(user)))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        // 新增API Key
+        let response = app
+            .clone()
+            .oneshot(request(
+                Method::POST,
+                "/admin/api-keys",
+                json!({
+                    "key": "sk-admin-test-secret",
+                    "name": "ops",
+                    "user_id": 42,
+                    "permissions": ["user"],
+                    "enabled": true,
+                    "expires_at": null
+                }),
+                Some(admin_user()),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(CONFIG.read().await.auth.api_key.api_keys.contains_key("sk-admin-test-secret"));
+
+        // 列表返回打码后的密钥，完整密钥不出现在响应中
+        let response = app
+            .clone()
+            .oneshot(request(Method::GET, "/admin/api-keys", json!({}), Some(admin_user())))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body.contains("sk-a****"));
+        assert!(!body.contains("sk-admin-test-secret"));
+
+        // 删除后认证中间件读取的配置立即不含该密钥
+        let response = app
+            .oneshot(request(
+                Method::DELETE,
+                "/admin/api-keys/sk-admin-test-secret",
+                json!({}),
+                Some(admin_user()),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(!CONFIG.read().await.auth.api_key.api_keys.contains_key("sk-admin-test-secret"));
+    }
+}
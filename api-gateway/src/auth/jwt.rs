@@ -94,11 +94,9 @@ pub fn extract_token<B>(request: &Request<B>, header_name: &str, header_prefix:
         .get(header_name)
         .and_then(|value| value.to_str().ok())
         .and_then(|auth_header| {
-            if auth_header.starts_with(header_prefix) {
-                Some(auth_header[header_prefix.len()..].to_string())
-            } else {
-                None
-            }
+            auth_header
+                .strip_prefix(header_prefix)
+                .map(|token| token.to_string())
         })
 }
 
@@ -179,11 +177,9 @@ fn extract_token_owned<B>(request: &Request<B>, header_name: &str, header_prefix
         .get(header_name)
         .and_then(|value| value.to_str().ok())
         .and_then(|auth_header| {
-            if auth_header.starts_with(header_prefix) {
-                Some(auth_header[header_prefix.len()..].to_string())
-            } else {
-                None
-            }
+            auth_header
+                .strip_prefix(header_prefix)
+                .map(|token| token.to_string())
         })
 }
 
@@ -226,4 +222,43 @@ pub async fn verify_token(token: String, jwt_config: &crate::config::auth_config
     };
     
     Ok(user_info)
-} 
\ No newline at end of file
+} 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::config::JwtConfig;
+
+    #[test]
+    fn test_roles_in_auth_service_token_visible_after_gateway_decode() {
+        let config = JwtConfig {
+            secret: "test_secret".to_string(),
+            expiration: 3600,
+        };
+        let roles = vec!["admin".to_string(), "user".to_string()];
+        let (token, _) =
+            common::auth::generate_jwt(&uuid::Uuid::new_v4(), "alice", &roles, &config).unwrap();
+
+        // 网关侧用自己的Claims定义解码auth-service签发的令牌，roles原样可见
+        let claims = decode::<Claims>(
+            &token,
+            &DecodingKey::from_secret(config.secret.as_bytes()),
+            &Validation::new(Algorithm::HS256),
+        )
+        .unwrap()
+        .claims;
+        assert_eq!(claims.username, "alice");
+        assert_eq!(claims.roles, roles);
+
+        // 不带roles声明的存量令牌解码为空角色列表
+        let (legacy, _) =
+            common::auth::generate_jwt(&uuid::Uuid::new_v4(), "bob", &[], &config).unwrap();
+        let claims = decode::<Claims>(
+            &legacy,
+            &DecodingKey::from_secret(config.secret.as_bytes()),
+            &Validation::new(Algorithm::HS256),
+        )
+        .unwrap()
+        .claims;
+        assert!(claims.roles.is_empty());
+    }
+}
@@ -1,11 +1,14 @@
 use axum::{
     middleware::Next,
-    response::Response,
-    http::Request,
+    response::{IntoResponse, Response},
+    http::{Request, StatusCode},
     body::{Bytes, Body},
+    Json,
 };
+use serde_json::json;
 use common::error::Error;
 use crate::auth::jwt::UserInfo;
+use crate::config::routes_config::RoutesConfig;
 use http_body_util::BodyExt;
 
 /// 认证中间件处理函数
@@ -60,6 +63,57 @@ where
     Ok(next.run(new_request).await)
 }
 
+/// 管理端点守卫：仅admin角色可访问，挂在认证中间件之后
+pub async fn require_admin(request: Request<Body>, next: Next) -> Result<Response, Error> {
+    authorize(request, next, vec!["admin".to_string()]).await
+}
+
+/// 按路由配置做RBAC校验的中间件，挂在认证中间件之后
+/// 所需角色在每次请求时从全局配置读取，配置文件热更新后无需重建路由即可生效
+pub async fn rbac_middleware(request: Request<Body>, next: Next) -> Result<Response, Error> {
+    let required_roles = {
+        let config = crate::config::CONFIG.read().await;
+        required_roles_for_path(&config.routes, request.uri().path()).to_vec()
+    };
+
+    // 未配置角色要求时任何已认证用户均可通过
+    if required_roles.is_empty() {
+        return Ok(next.run(request).await);
+    }
+
+    let user = request.extensions()
+        .get::<UserInfo>()
+        .cloned()
+        .ok_or(Error::Unauthorized)?;
+
+    if !has_required_roles(&user.roles, &required_roles) {
+        return Ok(forbidden_response(&required_roles));
+    }
+
+    Ok(next.run(request).await)
+}
+
+/// 按最长前缀匹配找到请求路径对应路由规则的角色要求
+fn required_roles_for_path<'a>(routes: &'a RoutesConfig, path: &str) -> &'a [String] {
+    routes
+        .match_route(path)
+        .map(|r| r.required_roles.as_slice())
+        .unwrap_or(&[])
+}
+
+/// 构建403响应，body中带上该路由要求的角色列表
+fn forbidden_response(required_roles: &[String]) -> Response {
+    (
+        StatusCode::FORBIDDEN,
+        Json(json!({
+            "error": StatusCode::FORBIDDEN.as_u16(),
+            "message": "insufficient permissions",
+            "required_roles": required_roles,
+        })),
+    )
+        .into_response()
+}
+
 /// 检查用户是否具有所需角色
 fn has_required_roles(user_roles: &[String], required_roles: &[String]) -> bool {
     // 如果用户具有admin角色，直接返回true
@@ -71,7 +125,7 @@ fn has_required_roles(user_roles: &[String], required_roles: &[String]) -> bool
     required_roles.iter().any(|required| user_roles.contains(required))
 }
 
-/// 创建需要特定角色的权限中间件
+// 创建需要特定角色的权限中间件
 // pub fn require_roles(
 //     roles: Vec<String>
 // ) -> axum::middleware::from_fn_with_state<(), impl Fn(Request<Body>, Next) -> Result<Response, Error>> {
@@ -86,4 +140,152 @@ fn has_required_roles(user_roles: &[String], required_roles: &[String]) -> bool
 /// 从请求中获取用户信息
 pub fn get_user_from_request<B>(request: &Request<B>) -> Option<UserInfo> {
     request.extensions().get::<UserInfo>().cloned()
-} 
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::routes_config::{RouteRule, ServiceType, RoutesConfig};
+    use axum::{routing::get, middleware, Router};
+    use std::collections::HashMap;
+    use tower::ServiceExt;
+
+    fn rule(path_prefix: &str, required_roles: Vec<&str>) -> RouteRule {
+        RouteRule {
+            id: path_prefix.to_string(),
+            name: path_prefix.to_string(),
+            path_prefix: path_prefix.to_string(),
+            service_type: ServiceType::HttpService("rbac-test".to_string()),
+            require_auth: true,
+            require_nonce: false,
+            required_roles: required_roles.into_iter().map(String::from).collect(),
+            required_countries: vec![],
+            methods: vec![],
+            rewrite_headers: HashMap::new(),
+            cache_ttl_secs: None,
+            cache_methods: vec![],
+            path_rewrite: None,
+        }
+    }
+
+    fn user(roles: Vec<&str>) -> UserInfo {
+        UserInfo {
+            user_id: 1,
+            username: "rbac-user".to_string(),
+            roles: roles.into_iter().map(String::from).collect(),
+            extra: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_required_roles_for_path_longest_prefix_wins() {
+        let routes = RoutesConfig {
+            routes: vec![
+                rule("/api/admin", vec!["admin"]),
+                rule("/api/admin/audit", vec!["auditor"]),
+                rule("/api/open", vec![]),
+            ],
+        };
+
+        // 命中更长的前缀时以其角色要求为准
+        assert_eq!(required_roles_for_path(&routes, "/api/admin/audit/logs"), &["auditor".to_string()]);
+        assert_eq!(required_roles_for_path(&routes, "/api/admin/users"), &["admin".to_string()]);
+        // 未配置角色或未匹配到规则时无角色要求
+        assert!(required_roles_for_path(&routes, "/api/open/ping").is_empty());
+        assert!(required_roles_for_path(&routes, "/api/unknown").is_empty());
+    }
+
+    #[test]
+    fn test_has_required_roles() {
+        let required = vec!["auditor".to_string(), "ops".to_string()];
+        // 任一角色匹配即可
+        assert!(has_required_roles(&["ops".to_string()], &required));
+        assert!(!has_required_roles(&["user".to_string()], &required));
+        // admin角色兜底放行
+        assert!(has_required_roles(&["admin".to_string()], &required));
+    }
+
+    #[tokio::test]
+    async fn test_require_admin_guards_endpoint() {
+        let app = Router::new()
+            .route("/admin/test", get(|| async { "ok" }))
+            .layer(middleware::from_fn(require_admin));
+
+        // 非admin角色被拒绝
+        let mut request = Request::builder()
+            .uri("/admin/test")
+            .body(Body::empty())
+            .unwrap();
+        request.extensions_mut().insert(user(vec!["user"]));
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        // admin角色放行
+        let mut request = Request::builder()
+            .uri("/admin/test")
+            .body(Body::empty())
+            .unwrap();
+        request.extensions_mut().insert(user(vec!["admin"]));
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // 未经过认证（无用户信息）直接401
+        let request = Request::builder()
+            .uri("/admin/test")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_rbac_middleware_enforces_route_roles() {
+        // 注入带角色要求的路由规则，中间件在请求时从全局配置读取
+        {
+            let mut config = crate::config::CONFIG.write().await;
+            config.routes.routes.push(rule("/api/rbac-test", vec!["auditor"]));
+        }
+
+        let app = Router::new()
+            .route("/api/rbac-test", get(|| async { "ok" }))
+            .layer(middleware::from_fn(rbac_middleware));
+
+        // 缺少所需角色返回403，body中带上角色要求
+        let mut request = Request::builder()
+            .uri("/api/rbac-test")
+            .body(Body::empty())
+            .unwrap();
+        request.extensions_mut().insert(user(vec!["user"]));
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["error"], 403);
+        assert_eq!(json["message"], "insufficient permissions");
+        assert_eq!(json["required_roles"], serde_json::json!(["auditor"]));
+
+        // 具备所需角色的用户正常通过
+        let mut request = Request::builder()
+            .uri("/api/rbac-test")
+            .body(Body::empty())
+            .unwrap();
+        request.extensions_mut().insert(user(vec!["auditor"]));
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // 角色要求热更新后立即生效，无需重建路由
+        {
+            let mut config = crate::config::CONFIG.write().await;
+            if let Some(r) = config.routes.routes.iter_mut().find(|r| r.id == "/api/rbac-test") {
+                r.required_roles = vec!["ops".to_string()];
+            }
+        }
+        let mut request = Request::builder()
+            .uri("/api/rbac-test")
+            .body(Body::empty())
+            .unwrap();
+        request.extensions_mut().insert(user(vec!["auditor"]));
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+}
\ No newline at end of file
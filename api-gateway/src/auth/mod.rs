@@ -1,12 +1,12 @@
 pub mod jwt;
 pub mod api_key;
 pub mod oauth2;
+pub mod hmac;
 pub mod middleware;
 
 use axum::http::Request;
 use axum::middleware::Next;
 use axum::response::Response;
-use axum::body::Bytes;
 use crate::config::CONFIG;
 use common::error::Error;
 
@@ -31,6 +31,30 @@ pub async fn authenticate(request: Request<axum::body::Body>, next: Next) -> Res
         }
     }
     
+    // HMAC请求签名认证：内部服务携带X-Signature头时优先走签名校验
+    if config.auth.hmac_auth.enabled && hmac::has_signature(&request) {
+        use http_body_util::BodyExt;
+
+        let (parts, body) = request.into_parts();
+        let bytes = body
+            .collect()
+            .await
+            .map_err(|e| Error::Internal(format!("无法读取请求体: {}", e)))?
+            .to_bytes();
+
+        let user_info = hmac::verify_signature(
+            &config.auth.hmac_auth,
+            parts.method.as_str(),
+            parts.uri.path(),
+            &parts.headers,
+            &bytes,
+        )?;
+
+        let mut request = Request::from_parts(parts, axum::body::Body::from(bytes));
+        request.extensions_mut().insert(user_info);
+        return Ok(next.run(request).await);
+    }
+
     // 使用对应的认证方式
     if config.auth.jwt.enabled {
         // 获取JWT token并验证
@@ -50,7 +74,7 @@ pub async fn authenticate(request: Request<axum::body::Body>, next: Next) -> Res
         let mut request = request;
         request.extensions_mut().insert(user_info);
         
-        return Ok(next.run(request).await);
+        Ok(next.run(request).await)
     } else if config.auth.api_key.enabled {
         // 从headers中获取API key
         let api_key_config = &config.auth.api_key;
@@ -102,10 +126,10 @@ pub async fn authenticate(request: Request<axum::body::Body>, next: Next) -> Res
         let mut request = request;
         request.extensions_mut().insert(user_info);
         
-        return Ok(next.run(request).await);
+        Ok(next.run(request).await)
     } else if config.auth.oauth2.enabled {
         // OAuth2认证逻辑
-        let token = match oauth2::extract_oauth_token(&request) {
+        let _token = match oauth2::extract_oauth_token(&request) {
             Some(t) => t,
             None => return Err(Error::Unauthorized),
         };
@@ -126,10 +150,10 @@ pub async fn authenticate(request: Request<axum::body::Body>, next: Next) -> Res
         let mut request = request;
         request.extensions_mut().insert(user_info);
         
-        return Ok(next.run(request).await);
+        Ok(next.run(request).await)
     } else {
         // 如果没有启用任何认证方式，返回未授权错误
-        return Err(Error::Unauthorized);
+        Err(Error::Unauthorized)
     }
 }
 
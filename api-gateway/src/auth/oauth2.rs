@@ -83,7 +83,7 @@ pub async fn authenticate_oauth2<B>(request: &Request<B>) -> Result<UserInfo, Er
                 .map(|s| s.to_string())
                 .collect()
         })
-        .unwrap_or_else(Vec::new);
+        .unwrap_or_default();
     
     // 构建扩展信息
     let mut extra = HashMap::new();
@@ -109,10 +109,8 @@ pub fn extract_oauth_token<B>(request: &Request<B>) -> Option<String> {
         .get("Authorization")
         .and_then(|value| value.to_str().ok());
     
-    if let Some(auth_header) = auth_header {
-        if auth_header.starts_with("Bearer ") {
-            return Some(auth_header[7..].to_string());
-        }
+    if let Some(token) = auth_header.and_then(|header| header.strip_prefix("Bearer ")) {
+        return Some(token.to_string());
     }
     
     // 然后尝试从查询参数中提取
@@ -197,7 +195,7 @@ where
                 .map(|s| s.to_string())
                 .collect()
         })
-        .unwrap_or_else(Vec::new);
+        .unwrap_or_default();
     
     // 构建扩展信息
     let mut extra = HashMap::new();
@@ -223,10 +221,8 @@ fn extract_oauth_token_owned<B>(request: &Request<B>) -> Option<String> {
         .get("Authorization")
         .and_then(|value| value.to_str().ok());
     
-    if let Some(auth_header) = auth_header {
-        if auth_header.starts_with("Bearer ") {
-            return Some(auth_header[7..].to_string());
-        }
+    if let Some(token) = auth_header.and_then(|header| header.strip_prefix("Bearer ")) {
+        return Some(token.to_string());
     }
     
     // 然后尝试从查询参数中提取
@@ -1,21 +1,15 @@
+use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use std::collections::HashMap;
-use tower::Service;
-use futures::future::BoxFuture;
 use parking_lot::RwLock;
-use axum::{
-    http::{Request, StatusCode},
-    response::{Response, IntoResponse},
-    body::Body,
-    Json,
-};
-use serde_json::json;
+use serde::Serialize;
 use crate::config::CONFIG;
 use tracing::{info, warn};
-use crate::proxy::service_proxy::ServiceProxy;
-use tower::layer::Layer;
-use tower::layer::util::Identity;
+
+/// 半开状态同时放行的探测请求上限，
+/// 超出的请求在探测完成前继续快速失败
+const HALF_OPEN_MAX_PROBES: u32 = 1;
 
 /// 熔断器状态
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -28,6 +22,17 @@ pub enum CircuitBreakerState {
     HalfOpen,
 }
 
+impl CircuitBreakerState {
+    /// 状态名，用于巡检端点的JSON输出
+    fn name(&self) -> &'static str {
+        match self {
+            CircuitBreakerState::Closed => "closed",
+            CircuitBreakerState::Open => "open",
+            CircuitBreakerState::HalfOpen => "half_open",
+        }
+    }
+}
+
 /// 服务熔断器
 #[derive(Clone)]
 pub struct CircuitBreaker {
@@ -41,34 +46,63 @@ pub struct CircuitBreaker {
     reset_timeout: Duration,
     /// 上次状态变更时间
     last_failure_time: Arc<RwLock<Instant>>,
+    /// 半开状态在途探测请求数
+    half_open_probes: Arc<AtomicU32>,
     /// 服务标识符
     service_id: String,
 }
 
 impl CircuitBreaker {
     /// 创建新的熔断器
-    pub fn new(service_id: &str, failure_threshold: u64, reset_timeout_secs: u64) -> Self {
+    pub fn new(service_id: &str, failure_threshold: u64, reset_timeout: Duration) -> Self {
         Self {
             state: Arc::new(RwLock::new(CircuitBreakerState::Closed)),
-            // TODO 需要根据实际情况定义连续失败次数，可以改成从配置文件中读取
-            failure_count: Arc::new(RwLock::new(5)),
-            // TODO 需要根据实际情况定义失败阈值，可以改成从配置文件中读取
+            failure_count: Arc::new(RwLock::new(0)),
             failure_threshold,
-            reset_timeout: Duration::from_secs(reset_timeout_secs),
+            reset_timeout,
             last_failure_time: Arc::new(RwLock::new(Instant::now())),
+            half_open_probes: Arc::new(AtomicU32::new(0)),
             service_id: service_id.to_string(),
         }
     }
-    
+
     /// 获取当前熔断器状态
     pub fn state(&self) -> CircuitBreakerState {
         *self.state.read()
     }
-    
+
+    /// 当前连续失败次数
+    pub fn failure_count(&self) -> u64 {
+        *self.failure_count.read()
+    }
+
+    /// 距离进入半开（可探测）状态的剩余秒数，非开启状态为None
+    pub fn seconds_until_half_open(&self) -> Option<u64> {
+        match *self.state.read() {
+            CircuitBreakerState::Open => Some(
+                self.reset_timeout
+                    .saturating_sub(self.last_failure_time.read().elapsed())
+                    .as_secs(),
+            ),
+            _ => None,
+        }
+    }
+
+    /// 只读检查：熔断打开且尚未到重置时间的端点视为隔离中，
+    /// 不触发任何状态转换，供实例选择时过滤候选端点使用
+    pub fn is_quarantined(&self) -> bool {
+        match *self.state.read() {
+            CircuitBreakerState::Open => {
+                self.last_failure_time.read().elapsed() < self.reset_timeout
+            }
+            _ => false,
+        }
+    }
+
     /// 记录成功请求
     pub fn record_success(&self) {
         let mut state = self.state.write();
-        
+
         match *state {
             CircuitBreakerState::Closed => {
                 // 重置失败计数
@@ -78,25 +112,34 @@ impl CircuitBreaker {
                 // 半开状态下的成功请求会关闭熔断器
                 *state = CircuitBreakerState::Closed;
                 *self.failure_count.write() = 0;
+                self.half_open_probes.store(0, Ordering::SeqCst);
                 info!("服务 {} 熔断器已关闭，服务恢复正常", self.service_id);
             }
             CircuitBreakerState::Open => {
-                // 开启状态不应该有请求，这是一个异常情况
-                warn!("服务 {} 熔断器在开启状态收到成功请求，可能是状态不一致", self.service_id);
+                // 重置窗口已过时端点重新进入候选池，成功即视为探测通过；
+                // 窗口内的成功请求属于状态不一致
+                if self.last_failure_time.read().elapsed() >= self.reset_timeout {
+                    *state = CircuitBreakerState::Closed;
+                    *self.failure_count.write() = 0;
+                    self.half_open_probes.store(0, Ordering::SeqCst);
+                    info!("服务 {} 熔断器已关闭，服务恢复正常", self.service_id);
+                } else {
+                    warn!("服务 {} 熔断器在开启状态收到成功请求，可能是状态不一致", self.service_id);
+                }
             }
         }
     }
-    
+
     /// 记录失败请求
     pub fn record_failure(&self) {
         let mut state = self.state.write();
-        
+
         match *state {
             CircuitBreakerState::Closed => {
                 // 增加失败计数
                 let mut failure_count = self.failure_count.write();
                 *failure_count += 1;
-                
+
                 // 如果失败计数达到阈值，打开熔断器
                 if *failure_count >= self.failure_threshold {
                     *state = CircuitBreakerState::Open;
@@ -108,6 +151,7 @@ impl CircuitBreaker {
                 // 半开状态下的失败请求会重新打开熔断器
                 *state = CircuitBreakerState::Open;
                 *self.last_failure_time.write() = Instant::now();
+                self.half_open_probes.store(0, Ordering::SeqCst);
                 warn!("服务 {} 熔断器从半开状态重新打开，服务仍不可用", self.service_id);
             }
             CircuitBreakerState::Open => {
@@ -116,25 +160,32 @@ impl CircuitBreaker {
             }
         }
     }
-    
+
     /// 检查熔断器状态并进行状态转换
     pub fn check(&self) -> bool {
         let mut state = self.state.write();
-        
+
         match *state {
             CircuitBreakerState::Open => {
                 // 如果已经超过重置超时时间，转换为半开状态
                 let last_failure = *self.last_failure_time.read();
                 if last_failure.elapsed() >= self.reset_timeout {
                     *state = CircuitBreakerState::HalfOpen;
+                    // 触发转换的请求即第一个探测请求
+                    self.half_open_probes.store(1, Ordering::SeqCst);
                     info!("服务 {} 熔断器切换为半开状态，尝试恢复服务", self.service_id);
                     return true; // 允许请求通过
                 }
                 false // 拒绝请求
             }
             CircuitBreakerState::HalfOpen => {
-                // 半开状态允许少量请求通过
-                true
+                // 半开状态只放行有限个并发探测请求，
+                // 探测完成（成功或失败）会转换状态并清零计数
+                self.half_open_probes
+                    .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |probes| {
+                        (probes < HALF_OPEN_MAX_PROBES).then_some(probes + 1)
+                    })
+                    .is_ok()
             }
             CircuitBreakerState::Closed => {
                 // 关闭状态正常允许请求
@@ -144,176 +195,228 @@ impl CircuitBreaker {
     }
 }
 
-/// 熔断中间件
-pub struct CircuitBreakerMiddleware<S> {
-    inner: S,
-    breakers: Arc<RwLock<HashMap<String, Arc<CircuitBreaker>>>>,
+/// 单个熔断器的巡检信息，/admin/circuit-breakers端点的输出条目
+#[derive(Debug, Clone, Serialize)]
+pub struct BreakerStatus {
+    /// 服务名
+    pub service: String,
+    /// 解析后的端点URL
+    pub endpoint: String,
+    /// 熔断器状态（closed/open/half_open）
+    pub state: &'static str,
+    /// 连续失败次数
+    pub failure_count: u64,
+    /// 距离进入半开状态的剩余秒数，非开启状态为null
+    pub seconds_until_half_open: Option<u64>,
+}
+
+/// 注册表键：服务名+解析后的端点URL
+type BreakerKey = (String, String);
+
+/// 端点级熔断器注册表
+///
+/// 熔断器按"服务名+解析后的端点"维护，单个故障实例触发熔断时
+/// 只隔离该端点，同服务的其他副本不受影响。隔离中的端点由
+/// ServiceProxy在选择实例时移出候选池，重置时间过后重新参与
+/// 选择作为恢复探测。
+#[derive(Clone)]
+pub struct CircuitBreakerRegistry {
+    breakers: Arc<RwLock<HashMap<BreakerKey, Arc<CircuitBreaker>>>>,
+    enabled: bool,
+    failure_threshold: u64,
+    reset_timeout: Duration,
 }
 
-impl<S> CircuitBreakerMiddleware<S> {
-    /// 创建新的熔断中间件
-    pub fn new(inner: S) -> Self {
+impl CircuitBreakerRegistry {
+    /// 创建新的注册表
+    pub fn new(enabled: bool, failure_threshold: u64, reset_timeout: Duration) -> Self {
         Self {
-            inner,
             breakers: Arc::new(RwLock::new(HashMap::new())),
+            enabled,
+            failure_threshold,
+            reset_timeout,
         }
     }
-    
-    /// 获取或创建服务熔断器
-    fn get_or_create_breaker(&self, service_id: &str) -> Arc<CircuitBreaker> {
-        let breakers = self.breakers.read();
-        
-        if let Some(breaker) = breakers.get(service_id) {
+
+    /// 从网关配置构建注册表
+    pub async fn from_gateway_config() -> Self {
+        let config = CONFIG.read().await;
+        Self::new(
+            config.circuit_breaker.enabled,
+            config.circuit_breaker.failure_threshold,
+            Duration::from_secs(config.circuit_breaker.half_open_timeout_secs),
+        )
+    }
+
+    /// 获取或创建端点熔断器
+    fn get_or_create(&self, service: &str, endpoint: &str) -> Arc<CircuitBreaker> {
+        let key = (service.to_string(), endpoint.to_string());
+
+        if let Some(breaker) = self.breakers.read().get(&key) {
             return breaker.clone();
         }
-        
-        // 如果不存在，创建新的熔断器
-        drop(breakers);
+
         let mut breakers = self.breakers.write();
-        
         // 双重检查
-        if let Some(breaker) = breakers.get(service_id) {
+        if let Some(breaker) = breakers.get(&key) {
             return breaker.clone();
         }
-        
-        // 从配置中读取熔断参数
-        let config_future = CONFIG.read();
-        let config = tokio::task::block_in_place(|| {
-            tokio::runtime::Handle::current().block_on(config_future)
-        });
-        
-        // 创建新的熔断器
+
         let breaker = Arc::new(CircuitBreaker::new(
-            service_id,
-            config.circuit_breaker.failure_threshold,
-            config.circuit_breaker.half_open_timeout_secs,
+            &format!("{}@{}", service, endpoint),
+            self.failure_threshold,
+            self.reset_timeout,
         ));
-        
-        breakers.insert(service_id.to_string(), breaker.clone());
+        breakers.insert(key, breaker.clone());
         breaker
     }
-}
 
-impl<S> Service<Request<Body>> for CircuitBreakerMiddleware<S>
-where
-    S: Service<Request<Body>, Response = Response> + Clone + Send + 'static,
-    S::Future: Send + 'static,
-{
-    type Response = Response;
-    type Error = S::Error;
-    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
-
-    fn poll_ready(&mut self, cx: &mut std::task::Context<'_>) -> std::task::Poll<Result<(), Self::Error>> {
-        self.inner.poll_ready(cx)
+    /// 端点是否处于隔离中；未启用熔断或端点尚无失败记录时不隔离
+    pub fn is_quarantined(&self, service: &str, endpoint: &str) -> bool {
+        if !self.enabled {
+            return false;
+        }
+        self.breakers
+            .read()
+            .get(&(service.to_string(), endpoint.to_string()))
+            .map(|b| b.is_quarantined())
+            .unwrap_or(false)
     }
 
-    fn call(&mut self, req: Request<Body>) -> Self::Future {
-        // 从请求路径或头部确定服务ID
-        let service_id = extract_service_id(&req);
-        let breaker = self.get_or_create_breaker(&service_id);
-        
-        // 检查熔断器状态
-        if !breaker.check() {
-            // 熔断器打开，快速失败
-            let json_response = Json(json!({
-                "error": 503,
-                "message": "服务暂时不可用，请稍后重试",
-                "service": service_id
-            }));
-            
-            let response = (StatusCode::SERVICE_UNAVAILABLE, json_response).into_response();
-            return Box::pin(async { Ok(response) });
+    /// 记录端点请求成功（只更新已存在的熔断器，不为健康端点建表项）
+    pub fn record_success(&self, service: &str, endpoint: &str) {
+        if !self.enabled {
+            return;
+        }
+        if let Some(breaker) = self
+            .breakers
+            .read()
+            .get(&(service.to_string(), endpoint.to_string()))
+        {
+            breaker.record_success();
         }
-        
-        // 克隆服务实例和熔断器，以便在异步闭包中使用
-        let mut svc = self.inner.clone();
-        let breaker_clone = breaker.clone();
-        
-        // 请求正常通过熔断器
-        Box::pin(async move {
-            match svc.call(req).await {
-                Ok(response) => {
-                    // 判断响应是否成功
-                    if response.status().is_success() {
-                        breaker_clone.record_success();
-                    } else {
-                        // 5xx错误被视为服务端错误，触发熔断
-                        if response.status().is_server_error() {
-                            breaker_clone.record_failure();
-                        }
-                    }
-                    Ok(response)
-                }
-                Err(err) => {
-                    // 请求失败
-                    breaker_clone.record_failure();
-                    Err(err)
-                }
-            }
-        })
     }
-}
 
-impl<S> Clone for CircuitBreakerMiddleware<S>
-where
-    S: Clone,
-{
-    fn clone(&self) -> Self {
-        Self {
-            inner: self.inner.clone(),
-            breakers: self.breakers.clone(),
+    /// 记录端点请求失败
+    pub fn record_failure(&self, service: &str, endpoint: &str) {
+        if !self.enabled {
+            return;
         }
+        self.get_or_create(service, endpoint).record_failure();
+    }
+
+    /// 导出所有熔断器的当前状态，按服务名+端点排序保证输出稳定
+    pub fn snapshot(&self) -> Vec<BreakerStatus> {
+        let mut statuses: Vec<BreakerStatus> = self
+            .breakers
+            .read()
+            .iter()
+            .map(|((service, endpoint), breaker)| BreakerStatus {
+                service: service.clone(),
+                endpoint: endpoint.clone(),
+                state: breaker.state().name(),
+                failure_count: breaker.failure_count(),
+                seconds_until_half_open: breaker.seconds_until_half_open(),
+            })
+            .collect();
+        statuses.sort_by(|a, b| (&a.service, &a.endpoint).cmp(&(&b.service, &b.endpoint)));
+        statuses
     }
 }
 
-/// 从请求中提取服务ID
-fn extract_service_id(req: &Request<Body>) -> String {
-    // 首先尝试从请求头中获取
-    if let Some(service) = req.headers().get("X-Target-Service") {
-        if let Ok(service_str) = service.to_str() {
-            return service_str.to_string();
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry_isolates_failing_endpoint_only() {
+        let registry = CircuitBreakerRegistry::new(true, 3, Duration::from_secs(60));
+
+        // 同服务两个端点，只有持续失败的端点被隔离
+        for _ in 0..3 {
+            registry.record_failure("user-service", "http://10.0.0.1:8080");
         }
+        registry.record_failure("user-service", "http://10.0.0.2:8080");
+
+        assert!(registry.is_quarantined("user-service", "http://10.0.0.1:8080"));
+        assert!(!registry.is_quarantined("user-service", "http://10.0.0.2:8080"));
+        // 其他服务不受影响
+        assert!(!registry.is_quarantined("friend-service", "http://10.0.0.1:8080"));
     }
-    
-    // 否则从路径中提取
-    let path = req.uri().path();
-    
-    // 简单的路径解析逻辑，根据路径前缀确定服务
-    if path.starts_with("/api/auth") {
-        "auth-service".to_string()
-    } else if path.starts_with("/api/users") {
-        "user-service".to_string()
-    } else if path.starts_with("/api/friends") {
-        "friend-service".to_string()
-    } else if path.starts_with("/api/groups") {
-        "group-service".to_string()
-    } else {
-        // 默认值
-        "unknown-service".to_string()
+
+    #[test]
+    fn test_registry_disabled_never_quarantines() {
+        let registry = CircuitBreakerRegistry::new(false, 1, Duration::from_secs(60));
+
+        for _ in 0..10 {
+            registry.record_failure("user-service", "http://10.0.0.1:8080");
+        }
+        assert!(!registry.is_quarantined("user-service", "http://10.0.0.1:8080"));
+        // 未启用时不维护任何熔断器
+        assert!(registry.snapshot().is_empty());
     }
-}
 
-/// 熔断中间件层
-#[derive(Clone)]
-pub struct CircuitBreakerLayer;
+    #[tokio::test]
+    async fn test_quarantine_lifts_after_reset_timeout() {
+        let registry = CircuitBreakerRegistry::new(true, 2, Duration::from_millis(50));
 
-impl CircuitBreakerLayer {
-    /// 创建新的熔断层
-    pub fn new() -> Self {
-        Self
+        registry.record_failure("user-service", "http://10.0.0.1:8080");
+        registry.record_failure("user-service", "http://10.0.0.1:8080");
+        assert!(registry.is_quarantined("user-service", "http://10.0.0.1:8080"));
+
+        // 重置时间过后端点重新进入候选池，探测成功关闭熔断器
+        tokio::time::sleep(Duration::from_millis(80)).await;
+        assert!(!registry.is_quarantined("user-service", "http://10.0.0.1:8080"));
+        registry.record_success("user-service", "http://10.0.0.1:8080");
+
+        let statuses = registry.snapshot();
+        assert_eq!(statuses.len(), 1);
+        assert_eq!(statuses[0].state, "closed");
+        assert_eq!(statuses[0].failure_count, 0);
     }
-}
 
-impl<S> Layer<S> for CircuitBreakerLayer {
-    type Service = CircuitBreakerMiddleware<S>;
+    #[test]
+    fn test_snapshot_reports_breaker_status() {
+        let registry = CircuitBreakerRegistry::new(true, 2, Duration::from_secs(60));
+
+        registry.record_failure("b-service", "http://10.0.0.2:8080");
+        registry.record_failure("a-service", "http://10.0.0.1:8080");
+        registry.record_failure("a-service", "http://10.0.0.1:8080");
 
-    fn layer(&self, inner: S) -> Self::Service {
-        CircuitBreakerMiddleware::new(inner)
+        let statuses = registry.snapshot();
+        assert_eq!(statuses.len(), 2);
+
+        // 按服务名+端点排序
+        assert_eq!(statuses[0].service, "a-service");
+        assert_eq!(statuses[0].endpoint, "http://10.0.0.1:8080");
+        assert_eq!(statuses[0].state, "open");
+        assert_eq!(statuses[0].failure_count, 2);
+        // 剩余秒数向下取整，打开后立即查询应接近完整的重置时间
+        assert!(statuses[0].seconds_until_half_open.unwrap() >= 58);
+
+        assert_eq!(statuses[1].service, "b-service");
+        assert_eq!(statuses[1].state, "closed");
+        assert_eq!(statuses[1].failure_count, 1);
+        assert!(statuses[1].seconds_until_half_open.is_none());
     }
-}
 
-/// 创建熔断中间件层
-pub async fn circuit_breaker_layer(_service_proxy: ServiceProxy) -> Identity {
-    // 简单实现，返回一个恒等中间件
-    Identity::new()
-} 
\ No newline at end of file
+    #[test]
+    fn test_half_open_allows_bounded_probes() {
+        let breaker = CircuitBreaker::new("probe-test", 1, Duration::from_millis(0));
+        breaker.record_failure();
+        assert_eq!(breaker.state(), CircuitBreakerState::Open);
+
+        // 重置时间为0：第一次check即转半开并作为探测放行
+        assert!(breaker.check());
+        assert_eq!(breaker.state(), CircuitBreakerState::HalfOpen);
+
+        // 探测额度已满，后续请求在探测完成前被拒绝
+        assert!(!breaker.check());
+        assert!(!breaker.check());
+
+        // 探测成功后关闭，恢复正常放行
+        breaker.record_success();
+        assert_eq!(breaker.state(), CircuitBreakerState::Closed);
+        assert!(breaker.check());
+    }
+}
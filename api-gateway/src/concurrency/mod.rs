@@ -0,0 +1,174 @@
+use tower::{Service, Layer};
+use std::sync::Arc;
+use std::{
+    task::{Context, Poll},
+    future::Future,
+    pin::Pin,
+};
+use axum::{
+    response::{IntoResponse, Response},
+    http::{Request, StatusCode},
+    body::Body,
+};
+use tokio::sync::Semaphore;
+use tracing::warn;
+
+use crate::config::CONFIG;
+
+/// 全局并发限制中间件层
+///
+/// 以信号量实现负载保护：在途请求达到上限后，新请求不排队，
+/// 直接返回503，避免过载时延迟雪崩拖垮整个网关。
+#[derive(Clone)]
+pub struct ConcurrencyLimitLayer {
+    semaphore: Arc<Semaphore>,
+    max_concurrent: usize,
+}
+
+impl ConcurrencyLimitLayer {
+    /// 创建指定并发上限的中间件层
+    pub fn new(max_concurrent: usize) -> Self {
+        Self {
+            semaphore: Arc::new(Semaphore::new(max_concurrent)),
+            max_concurrent,
+        }
+    }
+
+    /// 从网关全局配置创建，上限取max_concurrent_requests
+    pub async fn from_gateway_config() -> Self {
+        Self::new(CONFIG.read().await.max_concurrent_requests)
+    }
+}
+
+impl<S> Layer<S> for ConcurrencyLimitLayer {
+    type Service = ConcurrencyLimitService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ConcurrencyLimitService {
+            inner,
+            semaphore: self.semaphore.clone(),
+            max_concurrent: self.max_concurrent,
+        }
+    }
+}
+
+/// 全局并发限制中间件
+#[derive(Clone)]
+pub struct ConcurrencyLimitService<S> {
+    inner: S,
+    semaphore: Arc<Semaphore>,
+    max_concurrent: usize,
+}
+
+impl<S> Service<Request<Body>> for ConcurrencyLimitService<S>
+where
+    S: Service<Request<Body>, Response = Response> + Send + 'static + Clone,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        // 不排队：拿不到permit立即拒绝
+        let permit = self.semaphore.clone().try_acquire_owned();
+        let max_concurrent = self.max_concurrent;
+        let mut svc = self.inner.clone();
+
+        Box::pin(async move {
+            // permit持有至请求处理完成，drop时自动归还
+            let _permit = match permit {
+                Ok(permit) => permit,
+                Err(_) => {
+                    warn!("网关并发请求数已达上限 {}，请求被拒绝", max_concurrent);
+                    return Ok((
+                        StatusCode::SERVICE_UNAVAILABLE,
+                        axum::Json(serde_json::json!({
+                            "error": "server_overloaded",
+                            "message": "网关并发已饱和，请稍后重试"
+                        }))
+                    ).into_response());
+                }
+            };
+
+            svc.call(req).await
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::routing::get;
+    use axum::Router;
+    use std::time::Duration;
+
+    /// 启动一个慢后端并套上并发限制层，返回其地址
+    async fn start_limited_server(max_concurrent: usize) -> String {
+        let app = Router::new()
+            .route(
+                "/slow",
+                get(|| async {
+                    tokio::time::sleep(Duration::from_millis(500)).await;
+                    "ok"
+                }),
+            )
+            .layer(ConcurrencyLimitLayer::new(max_concurrent));
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        format!("http://{}", addr)
+    }
+
+    #[tokio::test]
+    async fn test_excess_concurrent_requests_get_503() {
+        let url = start_limited_server(2).await;
+
+        // 同时发4个请求：2个拿到permit正常处理，2个被立即拒绝
+        let client = reqwest::Client::new();
+        let mut handles = Vec::new();
+        for _ in 0..4 {
+            let client = client.clone();
+            let url = format!("{}/slow", url);
+            handles.push(tokio::spawn(async move {
+                client.get(&url).send().await.unwrap().status().as_u16()
+            }));
+        }
+
+        let mut ok = 0;
+        let mut overloaded = 0;
+        for handle in handles {
+            match handle.await.unwrap() {
+                200 => ok += 1,
+                503 => overloaded += 1,
+                status => panic!("意外的状态码: {}", status),
+            }
+        }
+        assert_eq!(ok, 2);
+        assert_eq!(overloaded, 2);
+    }
+
+    #[tokio::test]
+    async fn test_permits_are_released_after_completion() {
+        let url = start_limited_server(1).await;
+        let client = reqwest::Client::new();
+
+        // 串行请求每次都能拿到permit
+        for _ in 0..3 {
+            let status = client
+                .get(format!("{}/slow", url))
+                .send()
+                .await
+                .unwrap()
+                .status();
+            assert_eq!(status.as_u16(), 200);
+        }
+    }
+}
@@ -10,6 +10,9 @@ pub struct AuthConfig {
     pub api_key: ApiKeyConfig,
     /// OAuth2配置
     pub oauth2: OAuth2Config,
+    /// HMAC请求签名配置
+    #[serde(default)]
+    pub hmac_auth: HmacAuthConfig,
     /// IP白名单
     #[serde(default)]
     pub ip_whitelist: Vec<String>,
@@ -89,6 +92,44 @@ pub struct OAuth2Config {
     pub scope: String,
 }
 
+/// HMAC请求签名配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HmacAuthConfig {
+    /// 是否启用HMAC签名认证
+    pub enabled: bool,
+    /// 允许的最大时钟偏移（秒），时间戳偏移超出该值视为重放请求拒绝
+    pub max_clock_skew_secs: u64,
+    /// 签名密钥表，键为访问密钥ID（X-Access-Key头的值）
+    #[serde(default)]
+    pub keys: HashMap<String, HmacKeyInfo>,
+}
+
+impl Default for HmacAuthConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_clock_skew_secs: 300, // 5分钟
+            keys: HashMap::new(),
+        }
+    }
+}
+
+/// HMAC签名密钥信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HmacKeyInfo {
+    /// 共享密钥
+    pub secret: String,
+    /// 密钥名称（作为用户名注入）
+    pub name: String,
+    /// 关联用户ID
+    pub user_id: i64,
+    /// 权限列表
+    #[serde(default)]
+    pub roles: Vec<String>,
+    /// 是否启用
+    pub enabled: bool,
+}
+
 impl Default for AuthConfig {
     fn default() -> Self {
         Self {
@@ -117,6 +158,7 @@ impl Default for AuthConfig {
                 redirect_url: "".to_string(),
                 scope: "".to_string(),
             },
+            hmac_auth: HmacAuthConfig::default(),
             ip_whitelist: vec![
                 "127.0.0.1".to_string(),
                 "::1".to_string(),
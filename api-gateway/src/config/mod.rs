@@ -28,6 +28,15 @@ pub struct GatewayConfig {
     pub consul_url: String,
     /// 服务刷新间隔
     pub service_refresh_interval: u64,
+    /// 服务发现结果缓存TTL（秒）
+    #[serde(default = "default_service_cache_ttl_secs")]
+    pub service_cache_ttl_secs: u64,
+    /// 网关全局最大并发请求数，饱和后新请求直接返回503
+    #[serde(default = "default_max_concurrent_requests")]
+    pub max_concurrent_requests: usize,
+    /// 代理WebSocket连接的空闲超时（秒），客户端静默超过该时长后断开
+    #[serde(default = "default_ws_idle_timeout_secs")]
+    pub ws_idle_timeout_secs: u64,
     /// Metrics暴露端点
     pub metrics_endpoint: String,
     /// 链路追踪配置
@@ -36,6 +45,250 @@ pub struct GatewayConfig {
     pub retry: RetryConfig,
     /// 熔断配置
     pub circuit_breaker: CircuitBreakerConfig,
+    /// CORS配置
+    #[serde(default)]
+    pub cors: CorsConfig,
+    /// 各服务的负载均衡策略（服务名 -> Random/Weighted/RoundRobin/LeastConnections，
+    /// 取值沿用ws_lb_strategy命名；未配置的服务使用Weighted）
+    #[serde(default)]
+    pub lb_strategy: std::collections::HashMap<String, String>,
+    /// 各服务的上游连接池配置（服务名 -> 连接池参数）。
+    /// 配置了的服务使用独立的HTTP客户端，连接池与并发配额互不影响，
+    /// 避免单个慢服务占满共享连接池；未配置的服务共用默认客户端
+    #[serde(default)]
+    pub upstream_pools: std::collections::HashMap<String, UpstreamPoolConfig>,
+    /// 防重放nonce配置（require_nonce路由使用）
+    #[serde(default)]
+    pub nonce: NonceConfig,
+    /// 启动依赖门控配置
+    #[serde(default)]
+    pub startup: StartupConfig,
+    /// ip2region xdb文件路径，required_countries路由的国家/地区
+    /// 校验依赖该库；未配置时这类路由一律拒绝（fail-closed）
+    #[serde(default)]
+    pub geoip_xdb: Option<String>,
+}
+
+/// 启动依赖门控配置
+///
+/// 网关启动后先等待列出的依赖服务在Consul中出现健康实例，
+/// 再标记自身就绪（/ready返回200）；超时则降级进入就绪状态
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StartupConfig {
+    /// 需等待健康的依赖服务名列表，空表示不等待
+    #[serde(default)]
+    pub required_services: Vec<String>,
+    /// 等待超时（秒）
+    #[serde(default = "default_startup_timeout_secs")]
+    pub timeout_secs: u64,
+    /// 轮询间隔（秒）
+    #[serde(default = "default_startup_poll_interval_secs")]
+    pub poll_interval_secs: u64,
+}
+
+fn default_startup_timeout_secs() -> u64 {
+    60
+}
+
+fn default_startup_poll_interval_secs() -> u64 {
+    2
+}
+
+impl Default for StartupConfig {
+    fn default() -> Self {
+        Self {
+            required_services: Vec::new(),
+            timeout_secs: default_startup_timeout_secs(),
+            poll_interval_secs: default_startup_poll_interval_secs(),
+        }
+    }
+}
+
+/// 防重放nonce配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NonceConfig {
+    /// 存储后端："memory"为实例内存储，"redis"为跨网关实例共享
+    #[serde(default = "default_nonce_backend")]
+    pub backend: String,
+    /// Redis后端连接地址
+    #[serde(default = "default_nonce_redis_url")]
+    pub redis_url: String,
+    /// nonce有效期（秒），签发后超过该时长未使用即失效
+    #[serde(default = "default_nonce_ttl_secs")]
+    pub ttl_secs: u64,
+}
+
+impl Default for NonceConfig {
+    fn default() -> Self {
+        Self {
+            backend: default_nonce_backend(),
+            redis_url: default_nonce_redis_url(),
+            ttl_secs: default_nonce_ttl_secs(),
+        }
+    }
+}
+
+fn default_nonce_backend() -> String {
+    "memory".to_string()
+}
+
+fn default_nonce_redis_url() -> String {
+    "redis://127.0.0.1:6379".to_string()
+}
+
+fn default_nonce_ttl_secs() -> u64 {
+    120
+}
+
+/// 单个服务的上游连接池配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpstreamPoolConfig {
+    /// 每主机最大空闲连接数
+    #[serde(default = "default_pool_max_idle_per_host")]
+    pub pool_max_idle_per_host: usize,
+    /// 空闲连接回收时间（秒）
+    #[serde(default = "default_pool_idle_timeout_secs")]
+    pub pool_idle_timeout_secs: u64,
+    /// 请求超时（秒）
+    #[serde(default = "default_upstream_timeout_secs")]
+    pub timeout_secs: u64,
+    /// 该服务的最大在途请求数，0表示不限制；饱和后新请求直接返回503
+    #[serde(default)]
+    pub max_in_flight: usize,
+}
+
+impl Default for UpstreamPoolConfig {
+    fn default() -> Self {
+        Self {
+            pool_max_idle_per_host: default_pool_max_idle_per_host(),
+            pool_idle_timeout_secs: default_pool_idle_timeout_secs(),
+            timeout_secs: default_upstream_timeout_secs(),
+            max_in_flight: 0,
+        }
+    }
+}
+
+/// 每主机最大空闲连接数的默认值，与共享客户端一致
+fn default_pool_max_idle_per_host() -> usize {
+    100
+}
+
+/// 空闲连接回收时间的默认值（秒）
+fn default_pool_idle_timeout_secs() -> u64 {
+    90
+}
+
+/// 上游请求超时的默认值（秒），与共享客户端一致
+fn default_upstream_timeout_secs() -> u64 {
+    30
+}
+
+/// 服务发现结果缓存TTL的默认值（秒）
+fn default_service_cache_ttl_secs() -> u64 {
+    5
+}
+
+/// 全局最大并发请求数的默认值
+fn default_max_concurrent_requests() -> usize {
+    1024
+}
+
+/// WebSocket空闲超时的默认值（秒）
+fn default_ws_idle_timeout_secs() -> u64 {
+    300
+}
+
+/// CORS配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CorsConfig {
+    /// 允许的来源，"*"表示任意来源（不能与allow_credentials同时使用）
+    pub allowed_origins: Vec<String>,
+    /// 允许的方法
+    pub allowed_methods: Vec<String>,
+    /// 允许的请求头，"*"表示任意请求头
+    pub allowed_headers: Vec<String>,
+    /// 是否允许携带凭证
+    pub allow_credentials: bool,
+    /// 预检结果缓存时间（秒）
+    pub max_age_secs: u64,
+}
+
+impl Default for CorsConfig {
+    fn default() -> Self {
+        Self {
+            allowed_origins: vec!["*".to_string()],
+            allowed_methods: vec![
+                "GET".to_string(),
+                "POST".to_string(),
+                "PUT".to_string(),
+                "DELETE".to_string(),
+                "PATCH".to_string(),
+                "OPTIONS".to_string(),
+            ],
+            allowed_headers: vec!["*".to_string()],
+            // 浏览器拒绝通配来源+凭证的组合，默认关闭凭证
+            allow_credentials: false,
+            max_age_secs: 3600,
+        }
+    }
+}
+
+impl CorsConfig {
+    /// 校验配置组合是否合法：开启凭证时不允许通配来源
+    pub fn validate(&self) -> Result<()> {
+        if self.allow_credentials && self.allowed_origins.iter().any(|o| o == "*") {
+            return Err(anyhow!(
+                "CORS配置无效：allow_credentials开启时不允许通配来源\"*\"，请配置明确的来源列表"
+            ));
+        }
+        Ok(())
+    }
+
+    /// 根据配置构建CORS中间件层
+    pub fn build_layer(&self) -> tower_http::cors::CorsLayer {
+        use axum::http::{HeaderName, HeaderValue, Method};
+        use tower_http::cors::{AllowHeaders, AllowMethods, AllowOrigin, CorsLayer};
+
+        let origins = if self.allowed_origins.iter().any(|o| o == "*") {
+            AllowOrigin::any()
+        } else {
+            AllowOrigin::list(
+                self.allowed_origins
+                    .iter()
+                    .filter_map(|o| HeaderValue::from_str(o).ok()),
+            )
+        };
+
+        let methods = if self.allowed_methods.iter().any(|m| m == "*") {
+            AllowMethods::any()
+        } else {
+            AllowMethods::list(
+                self.allowed_methods
+                    .iter()
+                    .filter_map(|m| m.parse::<Method>().ok()),
+            )
+        };
+
+        let headers = if self.allowed_headers.iter().any(|h| h == "*") {
+            AllowHeaders::any()
+        } else {
+            AllowHeaders::list(
+                self.allowed_headers
+                    .iter()
+                    .filter_map(|h| h.parse::<HeaderName>().ok()),
+            )
+        };
+
+        let mut layer = CorsLayer::new()
+            .allow_origin(origins)
+            .allow_methods(methods)
+            .allow_headers(headers)
+            .max_age(std::time::Duration::from_secs(self.max_age_secs));
+        if self.allow_credentials {
+            layer = layer.allow_credentials(true);
+        }
+        layer
+    }
 }
 
 /// 追踪配置
@@ -43,10 +296,31 @@ pub struct GatewayConfig {
 pub struct TracingConfig {
     /// 是否启用OpenTelemetry
     pub enable_opentelemetry: bool,
-    /// Jaeger端点
-    pub jaeger_endpoint: Option<String>,
+    /// OTLP gRPC端点（如Jaeger/Collector的4317端口），未配置时用SDK默认值
+    #[serde(alias = "jaeger_endpoint")]
+    pub otlp_endpoint: Option<String>,
     /// 采样率
     pub sampling_ratio: f64,
+    /// trace级别请求体日志最多记录的字节数，超出部分截断
+    #[serde(default = "default_max_log_bytes")]
+    pub max_log_bytes: usize,
+    /// 请求体日志中打码的请求头（值替换为[REDACTED]），名称大小写不敏感
+    #[serde(default = "default_sanitize_headers")]
+    pub sanitize_headers: Vec<String>,
+}
+
+/// 请求体日志字节数的默认上限
+fn default_max_log_bytes() -> usize {
+    2048
+}
+
+/// 默认打码的请求头
+fn default_sanitize_headers() -> Vec<String> {
+    vec![
+        "authorization".to_string(),
+        "x-api-key".to_string(),
+        "cookie".to_string(),
+    ]
 }
 
 /// 重试配置
@@ -77,11 +351,16 @@ impl Default for GatewayConfig {
             auth: AuthConfig::default(),
             consul_url: "http://localhost:8500".to_string(),
             service_refresh_interval: 30,
+            service_cache_ttl_secs: default_service_cache_ttl_secs(),
+            max_concurrent_requests: default_max_concurrent_requests(),
+            ws_idle_timeout_secs: default_ws_idle_timeout_secs(),
             metrics_endpoint: "/metrics".to_string(),
             tracing: TracingConfig {
                 enable_opentelemetry: false,
-                jaeger_endpoint: None,
+                otlp_endpoint: None,
                 sampling_ratio: 0.1,
+                max_log_bytes: default_max_log_bytes(),
+                sanitize_headers: default_sanitize_headers(),
             },
             retry: RetryConfig {
                 max_retries: 3,
@@ -92,6 +371,12 @@ impl Default for GatewayConfig {
                 failure_threshold: 5,
                 half_open_timeout_secs: 30,
             },
+            cors: CorsConfig::default(),
+            lb_strategy: std::collections::HashMap::new(),
+            upstream_pools: std::collections::HashMap::new(),
+            nonce: NonceConfig::default(),
+            startup: StartupConfig::default(),
+            geoip_xdb: None,
         }
     }
 }
@@ -101,6 +386,20 @@ pub static CONFIG: Lazy<Arc<RwLock<GatewayConfig>>> = Lazy::new(|| {
     Arc::new(RwLock::new(GatewayConfig::default()))
 });
 
+/// 配置版本号，每次加载或热更新配置后自增。
+/// 在启动时快照配置的组件（如限流器）与该版本比较即可检测到热更新
+static CONFIG_VERSION: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+
+/// 当前配置版本
+pub fn config_version() -> u64 {
+    CONFIG_VERSION.load(std::sync::atomic::Ordering::Acquire)
+}
+
+/// 配置更新后递增版本号
+fn bump_config_version() {
+    CONFIG_VERSION.fetch_add(1, std::sync::atomic::Ordering::Release);
+}
+
 /// 加载配置
 pub async fn load_config(config_path: &str) -> Result<()> {
     let config_path = Path::new(config_path);
@@ -115,11 +414,17 @@ pub async fn load_config(config_path: &str) -> Result<()> {
     } else {
         return Err(anyhow!("不支持的配置文件格式"));
     };
-    
+
+    // 非法的CORS组合与非法的路由重写正则在加载期直接拒绝
+    config.cors.validate()?;
+    config.routes.validate()?;
+
     // 更新全局配置
     let mut global_config = CONFIG.write().await;
     *global_config = config;
-    
+    drop(global_config);
+    bump_config_version();
+
     info!("配置加载成功: {}", config_path.display());
     
     // 设置文件监听器，用于监控配置文件变化
@@ -164,8 +469,18 @@ fn setup_config_watcher(config_path: &Path) -> Result<()> {
                                     
                                     match config_result {
                                         Ok(new_config) => {
+                                            if let Err(e) = new_config
+                                                .cors
+                                                .validate()
+                                                .and_then(|_| new_config.routes.validate())
+                                            {
+                                                error!("热更新配置被拒绝: {}", e);
+                                                return;
+                                            }
                                             let mut global_config = CONFIG.write().await;
                                             *global_config = new_config;
+                                            drop(global_config);
+                                            bump_config_version();
                                             info!("热更新配置成功");
                                         },
                                         Err(e) => {
@@ -195,4 +510,99 @@ fn setup_config_watcher(config_path: &Path) -> Result<()> {
     info!("已设置配置文件监听器: {}", path_display);
     
     Ok(())
-} 
\ No newline at end of file
+} 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::{header, Method, Request, StatusCode};
+    use axum::routing::get;
+    use axum::Router;
+    use tower::ServiceExt;
+
+    #[test]
+    fn test_credentials_with_wildcard_origin_rejected() {
+        // 凭证+通配来源是浏览器拒绝的非法组合
+        let config = CorsConfig {
+            allow_credentials: true,
+            ..Default::default()
+        };
+        assert!(config.validate().is_err());
+
+        // 明确来源列表时允许开启凭证
+        let config = CorsConfig {
+            allow_credentials: true,
+            allowed_origins: vec!["http://app.example.com".to_string()],
+            ..Default::default()
+        };
+        assert!(config.validate().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_preflight_headers_match_config() {
+        let config = CorsConfig {
+            allowed_origins: vec!["http://app.example.com".to_string()],
+            allowed_methods: vec!["GET".to_string(), "POST".to_string()],
+            allowed_headers: vec!["content-type".to_string(), "x-request-id".to_string()],
+            allow_credentials: true,
+            max_age_secs: 600,
+        };
+        let app = Router::new()
+            .route("/api/test", get(|| async { "ok" }))
+            .layer(config.build_layer());
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method(Method::OPTIONS)
+                    .uri("/api/test")
+                    .header(header::ORIGIN, "http://app.example.com")
+                    .header(header::ACCESS_CONTROL_REQUEST_METHOD, "POST")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let headers = response.headers();
+        assert_eq!(
+            headers.get(header::ACCESS_CONTROL_ALLOW_ORIGIN).unwrap(),
+            "http://app.example.com"
+        );
+        assert_eq!(
+            headers.get(header::ACCESS_CONTROL_ALLOW_METHODS).unwrap(),
+            "GET,POST"
+        );
+        assert_eq!(
+            headers.get(header::ACCESS_CONTROL_ALLOW_HEADERS).unwrap(),
+            "content-type,x-request-id"
+        );
+        assert_eq!(
+            headers
+                .get(header::ACCESS_CONTROL_ALLOW_CREDENTIALS)
+                .unwrap(),
+            "true"
+        );
+        assert_eq!(headers.get(header::ACCESS_CONTROL_MAX_AGE).unwrap(), "600");
+
+        // 非允许来源的预检不应带allow-origin头
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(Method::OPTIONS)
+                    .uri("/api/test")
+                    .header(header::ORIGIN, "http://evil.example.com")
+                    .header(header::ACCESS_CONTROL_REQUEST_METHOD, "POST")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert!(response
+            .headers()
+            .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+            .is_none());
+    }
+}
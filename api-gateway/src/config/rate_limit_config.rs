@@ -4,6 +4,19 @@ use serde::{Serialize, Deserialize};
 /// 限流配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RateLimitConfig {
+    /// 限流后端："memory"为实例内限流，"redis"为跨实例分布式限流
+    #[serde(default = "default_backend")]
+    pub backend: String,
+    /// Redis后端连接地址
+    #[serde(default = "default_redis_url")]
+    pub redis_url: String,
+    /// Redis不可用时是否直接放行；false时回退到内存限流器
+    #[serde(default)]
+    pub fail_open_on_redis_error: bool,
+    /// 无条件豁免限流的路径前缀（健康检查/监控抓取/管理接口），
+    /// 避免监控突发流量被429拒绝导致可观测性中断
+    #[serde(default = "default_exempt_paths")]
+    pub exempt_paths: Vec<String>,
     /// 全局限流配置
     pub global: RateLimitRule,
     /// 按路径限流配置
@@ -12,6 +25,33 @@ pub struct RateLimitConfig {
     pub api_key_rules: HashMap<String, RateLimitRule>,
     /// 按IP限流配置
     pub ip_rules: HashMap<String, RateLimitRule>,
+    /// 按认证用户限流配置
+    #[serde(default)]
+    pub user_rules: UserRateLimitRules,
+}
+
+/// 按认证用户限流规则：default适用于所有认证用户，
+/// overrides按用户ID覆盖默认值（针对个别滥用账号收紧配额）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UserRateLimitRules {
+    /// 认证用户的默认限流规则，未配置时关闭用户维度限流
+    #[serde(default)]
+    pub default: Option<RateLimitRule>,
+    /// 按用户ID覆盖的限流规则
+    #[serde(default)]
+    pub overrides: HashMap<String, RateLimitRule>,
+}
+
+fn default_backend() -> String {
+    "memory".to_string()
+}
+
+fn default_redis_url() -> String {
+    "redis://127.0.0.1:6379".to_string()
+}
+
+fn default_exempt_paths() -> Vec<String> {
+    vec!["/health".to_string(), "/metrics".to_string()]
 }
 
 /// 按路径限流规则
@@ -24,7 +64,7 @@ pub struct PathRateLimitRule {
 }
 
 /// 限流规则
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct RateLimitRule {
     /// 每秒请求数
     pub requests_per_second: u32,
@@ -37,6 +77,10 @@ pub struct RateLimitRule {
 impl Default for RateLimitConfig {
     fn default() -> Self {
         Self {
+            backend: default_backend(),
+            redis_url: default_redis_url(),
+            fail_open_on_redis_error: false,
+            exempt_paths: default_exempt_paths(),
             global: RateLimitRule {
                 requests_per_second: 1000,
                 burst_size: 50,
@@ -73,6 +117,7 @@ impl Default for RateLimitConfig {
             ],
             api_key_rules: HashMap::new(),
             ip_rules: HashMap::new(),
+            user_rules: UserRateLimitRules::default(),
         }
     }
-} 
\ No newline at end of file
+}
\ No newline at end of file
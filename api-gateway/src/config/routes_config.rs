@@ -22,6 +22,12 @@ pub struct RouteRule {
     /// 是否需要认证
     #[serde(default)]
     pub require_auth: bool,
+    /// 访问所需角色（任一匹配即可），空表示认证用户均可访问
+    #[serde(default)]
+    pub required_roles: Vec<String>,
+    /// 允许访问的客户端国家/地区（按IP解析），空表示不限制
+    #[serde(default)]
+    pub required_countries: Vec<String>,
     /// 请求方法限制（如为空则表示全部允许）
     #[serde(default)]
     pub methods: Vec<String>,
@@ -30,6 +36,16 @@ pub struct RouteRule {
     pub rewrite_headers: HashMap<String, String>,
     /// 路径重写规则
     pub path_rewrite: Option<PathRewrite>,
+    /// 响应缓存TTL（秒），未设置表示该路由不启用响应缓存
+    #[serde(default)]
+    pub cache_ttl_secs: Option<u64>,
+    /// 参与响应缓存的请求方法，空表示仅GET
+    #[serde(default)]
+    pub cache_methods: Vec<String>,
+    /// 是否要求一次性防重放nonce（密码修改、账号注销等高价值操作），
+    /// nonce由网关/api/nonce端点签发，消费后立即失效
+    #[serde(default)]
+    pub require_nonce: bool,
 }
 
 /// 目标服务类型
@@ -58,11 +74,51 @@ pub enum ServiceType {
 pub struct PathRewrite {
     /// 路径前缀替换
     pub replace_prefix: Option<String>,
-    /// 正则表达式替换
+    /// 正则表达式替换（与前缀替换同时配置时正则优先）
     pub regex_match: Option<String>,
     pub regex_replace: Option<String>,
 }
 
+impl RoutesConfig {
+    /// 按最长前缀匹配查找路径对应的路由规则
+    pub fn match_route(&self, path: &str) -> Option<&RouteRule> {
+        self.routes
+            .iter()
+            .filter(|r| path.starts_with(&r.path_prefix))
+            .max_by_key(|r| r.path_prefix.len())
+    }
+
+    /// 校验路由配置：非法的重写正则在加载期直接拒绝，
+    /// 避免到请求时才发现pattern编译失败
+    pub fn validate(&self) -> anyhow::Result<()> {
+        for route in &self.routes {
+            let Some(rewrite) = &route.path_rewrite else {
+                continue;
+            };
+            match (&rewrite.regex_match, &rewrite.regex_replace) {
+                (Some(pattern), Some(_)) => {
+                    regex::Regex::new(pattern).map_err(|err| {
+                        anyhow::anyhow!(
+                            "路由 {} 的重写正则无效: {}: {}",
+                            route.id,
+                            pattern,
+                            err
+                        )
+                    })?;
+                }
+                (None, None) => {}
+                _ => {
+                    return Err(anyhow::anyhow!(
+                        "路由 {} 的regex_match与regex_replace必须同时配置",
+                        route.id
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
 impl Default for RoutesConfig {
     fn default() -> Self {
         Self {
@@ -74,8 +130,13 @@ impl Default for RoutesConfig {
                     path_prefix: "/api/auth".to_string(),
                     service_type: ServiceType::Auth,
                     require_auth: false,
+                    require_nonce: false,
+                    required_roles: vec![],
+                    required_countries: vec![],
                     methods: vec![],
                     rewrite_headers: HashMap::new(),
+                    cache_ttl_secs: None,
+                    cache_methods: vec![],
                     path_rewrite: Some(PathRewrite {
                         replace_prefix: Some("/".to_string()),
                         regex_match: None,
@@ -89,8 +150,13 @@ impl Default for RoutesConfig {
                     path_prefix: "/api/users".to_string(),
                     service_type: ServiceType::User,
                     require_auth: true,
+                    require_nonce: false,
+                    required_roles: vec![],
+                    required_countries: vec![],
                     methods: vec![],
                     rewrite_headers: HashMap::new(),
+                    cache_ttl_secs: None,
+                    cache_methods: vec![],
                     path_rewrite: None,
                 },
                 // 默认好友服务路由
@@ -100,8 +166,29 @@ impl Default for RoutesConfig {
                     path_prefix: "/api/friends".to_string(),
                     service_type: ServiceType::Friend,
                     require_auth: true,
+                    require_nonce: false,
+                    required_roles: vec![],
+                    required_countries: vec![],
+                    methods: vec![],
+                    rewrite_headers: HashMap::new(),
+                    cache_ttl_secs: None,
+                    cache_methods: vec![],
+                    path_rewrite: None,
+                },
+                // 公开群组搜索路由（无需认证即可发现公开群组）
+                RouteRule {
+                    id: "group-search".to_string(),
+                    name: "群组搜索".to_string(),
+                    path_prefix: "/api/groups/search".to_string(),
+                    service_type: ServiceType::Group,
+                    require_auth: false,
+                    require_nonce: false,
+                    required_roles: vec![],
+                    required_countries: vec![],
                     methods: vec![],
                     rewrite_headers: HashMap::new(),
+                    cache_ttl_secs: None,
+                    cache_methods: vec![],
                     path_rewrite: None,
                 },
                 // 默认群组服务路由
@@ -111,8 +198,29 @@ impl Default for RoutesConfig {
                     path_prefix: "/api/groups".to_string(),
                     service_type: ServiceType::Group,
                     require_auth: true,
+                    require_nonce: false,
+                    required_roles: vec![],
+                    required_countries: vec![],
                     methods: vec![],
                     rewrite_headers: HashMap::new(),
+                    cache_ttl_secs: None,
+                    cache_methods: vec![],
+                    path_rewrite: None,
+                },
+                // 会话列表路由（msg-server的GetConversations RPC）
+                RouteRule {
+                    id: "conversations".to_string(),
+                    name: "会话列表".to_string(),
+                    path_prefix: "/api/conversations".to_string(),
+                    service_type: ServiceType::Chat,
+                    require_auth: true,
+                    require_nonce: false,
+                    required_roles: vec![],
+                    required_countries: vec![],
+                    methods: vec!["GET".to_string()],
+                    rewrite_headers: HashMap::new(),
+                    cache_ttl_secs: None,
+                    cache_methods: vec![],
                     path_rewrite: None,
                 },
                 // 默认聊天服务路由
@@ -122,8 +230,13 @@ impl Default for RoutesConfig {
                     path_prefix: "/api/chat".to_string(),
                     service_type: ServiceType::Chat,
                     require_auth: true,
+                    require_nonce: false,
+                    required_roles: vec![],
+                    required_countries: vec![],
                     methods: vec![],
                     rewrite_headers: HashMap::new(),
+                    cache_ttl_secs: None,
+                    cache_methods: vec![],
                     path_rewrite: None,
                 },
             ],
@@ -0,0 +1,141 @@
+use std::sync::OnceLock;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use axum::middleware::Next;
+use axum::response::IntoResponse;
+use axum::Json;
+use common::utils::XdbGeoLocator;
+use serde_json::json;
+use tracing::{info, warn};
+
+use crate::config::CONFIG;
+
+/// 进程级的IP地理位置查询器，启动时从配置的xdb路径加载一次
+static LOCATOR: OnceLock<Option<XdbGeoLocator>> = OnceLock::new();
+
+/// 按配置初始化查询器，未配置路径或加载失败时保持不可用状态
+/// （required_countries路由将fail-closed拒绝所有请求）
+pub fn init(xdb_path: Option<&str>) {
+    let locator = match xdb_path {
+        Some(path) => match XdbGeoLocator::new(path) {
+            Ok(locator) => {
+                info!("ip2region xdb已加载: {}", path);
+                Some(locator)
+            }
+            Err(err) => {
+                warn!("加载ip2region xdb失败，国家/地区限制路由将拒绝所有请求: {}", err);
+                None
+            }
+        },
+        None => None,
+    };
+    let _ = LOCATOR.set(locator);
+}
+
+/// 解析客户端IP的国家/地区，查询器不可用或IP未收录时返回None
+fn country_of(ip: &str) -> Option<String> {
+    LOCATOR
+        .get()?
+        .as_ref()?
+        .locate(ip)
+        .map(|geo| geo.country)
+        .filter(|c| !c.is_empty())
+}
+
+/// 判断客户端国家是否满足路由的国家/地区要求
+///
+/// 要求列表为空时不限制；国家未知（IP未收录、xdb不可用）时
+/// fail-closed拒绝，避免通过伪造来源绕过限制
+fn country_allowed(required: &[String], country: Option<&str>) -> bool {
+    if required.is_empty() {
+        return true;
+    }
+    match country {
+        Some(country) => required.iter().any(|c| c == country),
+        None => false,
+    }
+}
+
+/// 按路由配置做国家/地区限制的中间件
+///
+/// 与RBAC一样在每次请求时从全局配置读取规则，配置热更新后立即生效
+pub async fn geo_gate(request: Request<Body>, next: Next) -> axum::response::Response {
+    let required_countries = {
+        let config = CONFIG.read().await;
+        config
+            .routes
+            .match_route(request.uri().path())
+            .map(|r| r.required_countries.clone())
+            .unwrap_or_default()
+    };
+
+    if required_countries.is_empty() {
+        return next.run(request).await;
+    }
+
+    let country = client_ip(&request).and_then(|ip| country_of(&ip));
+    if country_allowed(&required_countries, country.as_deref()) {
+        next.run(request).await
+    } else {
+        (
+            StatusCode::FORBIDDEN,
+            Json(json!({
+                "error": StatusCode::FORBIDDEN.as_u16(),
+                "message": "该地区不允许访问",
+            })),
+        )
+            .into_response()
+    }
+}
+
+/// 从代理头中提取客户端IP（与认证模块的提取逻辑一致）
+fn client_ip(request: &Request<Body>) -> Option<String> {
+    request
+        .headers()
+        .get("X-Forwarded-For")
+        .and_then(|value| value.to_str().ok())
+        .map(|s| s.split(',').next().unwrap_or("").trim().to_string())
+        .or_else(|| {
+            request
+                .headers()
+                .get("X-Real-IP")
+                .and_then(|value| value.to_str().ok())
+                .map(|s| s.to_string())
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_country_allowed() {
+        let required = vec!["中国".to_string(), "新加坡".to_string()];
+        // 列表内的国家放行
+        assert!(country_allowed(&required, Some("中国")));
+        assert!(!country_allowed(&required, Some("美国")));
+        // 国家未知时fail-closed
+        assert!(!country_allowed(&required, None));
+        // 未配置限制时不拦截
+        assert!(country_allowed(&[], Some("美国")));
+        assert!(country_allowed(&[], None));
+    }
+
+    #[test]
+    fn test_client_ip_prefers_forwarded_chain_head() {
+        let request = Request::builder()
+            .uri("/api/test")
+            .header("X-Forwarded-For", "203.0.113.5, 10.0.0.1")
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(client_ip(&request), Some("203.0.113.5".to_string()));
+
+        let request = Request::builder()
+            .uri("/api/test")
+            .header("X-Real-IP", "203.0.113.9")
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(client_ip(&request), Some("203.0.113.9".to_string()));
+    }
+}
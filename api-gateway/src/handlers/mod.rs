@@ -0,0 +1,646 @@
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tonic::transport::Channel;
+use tonic::Code;
+use tracing::{error, info};
+
+use common::proto::auth::auth_service_client::AuthServiceClient;
+use common::proto::auth::{CreateTokenRequest, InvalidateTokenRequest};
+use common::proto::user::user_service_client::UserServiceClient;
+use common::proto::user::{CreateUserRequest, User, VerifyPasswordRequest};
+
+use crate::proxy::channel_pool::GrpcChannelPool;
+
+/// 登录/注册/注销聚合处理器
+///
+/// auth-service只有令牌RPC，user-service只有账号RPC，完整的登录流程
+/// 需要网关编排两者：先到user-service验证密码（或创建账号），再到
+/// auth-service签发令牌对。gRPC通道取自与转码路径共享的通道池，
+/// 经Consul发现后按实例地址复用，不会每个请求重新建连
+pub struct AuthAggregator {
+    pool: GrpcChannelPool,
+}
+
+/// 登录请求体
+#[derive(Deserialize)]
+pub struct LoginRequest {
+    pub username: String,
+    pub password: String,
+}
+
+/// 注册请求体
+#[derive(Deserialize)]
+pub struct RegisterRequest {
+    pub username: String,
+    pub email: String,
+    pub password: String,
+    #[serde(default)]
+    pub nickname: Option<String>,
+    #[serde(default)]
+    pub avatar_url: Option<String>,
+}
+
+impl AuthAggregator {
+    pub fn new(pool: GrpcChannelPool) -> Self {
+        Self { pool }
+    }
+
+    /// POST /api/auth/login：验证密码后签发令牌对
+    pub async fn login(&self, Json(req): Json<LoginRequest>) -> Response {
+        let (url, channel) = match self.channel_for("user-service").await {
+            Ok(v) => v,
+            Err(response) => return response,
+        };
+
+        let verified = match UserServiceClient::new(channel)
+            .verify_password(VerifyPasswordRequest {
+                username: req.username.clone(),
+                password: req.password,
+            })
+            .await
+        {
+            Ok(response) => response.into_inner(),
+            Err(status) => return self.rpc_error("user-service", &url, status).await,
+        };
+
+        if !verified.valid {
+            return (
+                StatusCode::UNAUTHORIZED,
+                Json(json!({
+                    "error": StatusCode::UNAUTHORIZED.as_u16(),
+                    "message": "用户名或密码错误",
+                })),
+            )
+                .into_response();
+        }
+
+        let user = verified.user.unwrap_or_default();
+        info!("用户 {} 登录成功", user.username);
+        self.issue_tokens(user, StatusCode::OK).await
+    }
+
+    /// POST /api/auth/register：创建账号后直接签发令牌对（注册即登录）
+    pub async fn register(&self, Json(req): Json<RegisterRequest>) -> Response {
+        let (url, channel) = match self.channel_for("user-service").await {
+            Ok(v) => v,
+            Err(response) => return response,
+        };
+
+        let created = match UserServiceClient::new(channel)
+            .create_user(CreateUserRequest {
+                username: req.username,
+                email: req.email,
+                password: req.password,
+                nickname: req.nickname.unwrap_or_default(),
+                avatar_url: req.avatar_url.unwrap_or_default(),
+            })
+            .await
+        {
+            Ok(response) => response.into_inner(),
+            Err(status) => return self.rpc_error("user-service", &url, status).await,
+        };
+
+        let user = created.user.unwrap_or_default();
+        info!("用户 {} 注册成功", user.username);
+        self.issue_tokens(user, StatusCode::CREATED).await
+    }
+
+    /// POST /api/auth/logout：使Authorization头中的访问令牌失效
+    pub async fn logout(&self, headers: HeaderMap) -> Response {
+        let Some(token) = bearer_token(&headers) else {
+            return (
+                StatusCode::UNAUTHORIZED,
+                Json(json!({
+                    "error": StatusCode::UNAUTHORIZED.as_u16(),
+                    "message": "缺少Bearer令牌",
+                })),
+            )
+                .into_response();
+        };
+
+        let (url, channel) = match self.channel_for("auth-service").await {
+            Ok(v) => v,
+            Err(response) => return response,
+        };
+
+        match AuthServiceClient::new(channel)
+            .invalidate_token(InvalidateTokenRequest { token })
+            .await
+        {
+            Ok(response) => {
+                let success = response.into_inner().success;
+                (StatusCode::OK, Json(json!({ "success": success }))).into_response()
+            }
+            Err(status) => self.rpc_error("auth-service", &url, status).await,
+        }
+    }
+
+    /// 调用auth-service签发令牌对并组装登录/注册响应
+    async fn issue_tokens(&self, user: User, status_code: StatusCode) -> Response {
+        let (url, channel) = match self.channel_for("auth-service").await {
+            Ok(v) => v,
+            Err(response) => return response,
+        };
+
+        match AuthServiceClient::new(channel)
+            .create_token(CreateTokenRequest {
+                user_id: user.id.clone(),
+                username: user.username.clone(),
+                // 普通登录/注册暂不携带角色，管理端角色由独立流程签发
+                roles: vec![],
+            })
+            .await
+        {
+            Ok(response) => {
+                let tokens = response.into_inner();
+                (
+                    status_code,
+                    Json(json!({
+                        "access_token": tokens.access_token,
+                        "refresh_token": tokens.refresh_token,
+                        "expires_in": tokens.expires_in,
+                        "user": user_json(&user),
+                    })),
+                )
+                    .into_response()
+            }
+            Err(status) => self.rpc_error("auth-service", &url, status).await,
+        }
+    }
+
+    /// 获取服务的gRPC通道：从共享池按服务名取（发现与建连由池完成）
+    async fn channel_for(&self, service_name: &str) -> Result<(String, Channel), Response> {
+        match self.pool.get_channel(service_name).await {
+            Ok(v) => Ok(v),
+            Err(err) => {
+                error!("获取服务 {} 的gRPC通道失败: {}", service_name, err);
+                Err(service_unavailable(service_name))
+            }
+        }
+    }
+
+    /// 把gRPC错误映射为HTTP响应
+    ///
+    /// 传输层不可用时同时从池中逐出该通道，下次请求重新发现并建连
+    async fn rpc_error(&self, service_name: &str, url: &str, status: tonic::Status) -> Response {
+        error!("调用 {} 失败: {}", service_name, status);
+        if status.code() == Code::Unavailable {
+            self.pool.evict(url).await;
+            return service_unavailable(service_name);
+        }
+
+        let http_status = match status.code() {
+            Code::InvalidArgument => StatusCode::BAD_REQUEST,
+            Code::NotFound => StatusCode::NOT_FOUND,
+            Code::AlreadyExists => StatusCode::CONFLICT,
+            Code::Unauthenticated => StatusCode::UNAUTHORIZED,
+            Code::PermissionDenied => StatusCode::FORBIDDEN,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        (
+            http_status,
+            Json(json!({
+                "error": http_status.as_u16(),
+                "message": status.message(),
+            })),
+        )
+            .into_response()
+    }
+}
+
+/// 构建503服务不可用响应
+fn service_unavailable(service_name: &str) -> Response {
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        Json(json!({
+            "error": StatusCode::SERVICE_UNAVAILABLE.as_u16(),
+            "message": format!("服务暂时不可用: {}", service_name),
+        })),
+    )
+        .into_response()
+}
+
+/// 登录/注册响应中的用户信息（不透出时间戳等内部字段）
+fn user_json(user: &User) -> Value {
+    json!({
+        "id": user.id,
+        "username": user.username,
+        "email": user.email,
+        "nickname": user.nickname,
+        "avatar_url": user.avatar_url,
+        "status": user.status,
+    })
+}
+
+/// 从Authorization头中提取Bearer令牌
+fn bearer_token(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .filter(|token| !token.is_empty())
+        .map(|token| token.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proxy::service_proxy::ServiceDiscovery;
+    use std::sync::Arc;
+    use axum::body::Body;
+    use axum::http::Request;
+    use axum::routing::{get, post};
+    use axum::Router;
+    use common::proto::auth::auth_service_server::{AuthService, AuthServiceServer};
+    use common::proto::auth::*;
+    use common::proto::user::user_service_server::{UserService, UserServiceServer};
+    use common::proto::user::*;
+    use http_body_util::BodyExt;
+    use tonic::{Request as TonicRequest, Response as TonicResponse, Status};
+    use tower::ServiceExt;
+
+    fn test_user() -> User {
+        User {
+            id: "user-1".to_string(),
+            username: "alice".to_string(),
+            email: "alice@example.com".to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// user-service桩：只实现登录/注册用到的两个RPC
+    struct MockUserService;
+
+    #[tonic::async_trait]
+    impl UserService for MockUserService {
+        async fn verify_password(
+            &self,
+            request: TonicRequest<VerifyPasswordRequest>,
+        ) -> Result<TonicResponse<VerifyPasswordResponse>, Status> {
+            let req = request.into_inner();
+            let valid = req.username == "alice" && req.password == "secret";
+            Ok(TonicResponse::new(VerifyPasswordResponse {
+                valid,
+                user: valid.then(test_user),
+            }))
+        }
+
+        async fn create_user(
+            &self,
+            request: TonicRequest<CreateUserRequest>,
+        ) -> Result<TonicResponse<UserResponse>, Status> {
+            let req = request.into_inner();
+            if req.username == "taken" {
+                return Err(Status::already_exists("用户名已存在"));
+            }
+            Ok(TonicResponse::new(UserResponse {
+                user: Some(User {
+                    id: "user-2".to_string(),
+                    username: req.username,
+                    email: req.email,
+                    ..Default::default()
+                }),
+            }))
+        }
+
+        async fn get_user_by_id(
+            &self,
+            _: TonicRequest<GetUserByIdRequest>,
+        ) -> Result<TonicResponse<UserResponse>, Status> {
+            Err(Status::unimplemented("测试桩"))
+        }
+
+        async fn get_user_by_username(
+            &self,
+            _: TonicRequest<GetUserByUsernameRequest>,
+        ) -> Result<TonicResponse<UserResponse>, Status> {
+            Err(Status::unimplemented("测试桩"))
+        }
+
+        async fn update_user(
+            &self,
+            _: TonicRequest<UpdateUserRequest>,
+        ) -> Result<TonicResponse<UserResponse>, Status> {
+            Err(Status::unimplemented("测试桩"))
+        }
+
+        async fn search_users(
+            &self,
+            _: TonicRequest<SearchUsersRequest>,
+        ) -> Result<TonicResponse<SearchUsersResponse>, Status> {
+            Err(Status::unimplemented("测试桩"))
+        }
+
+        async fn batch_get_users(
+            &self,
+            _: TonicRequest<BatchGetUsersRequest>,
+        ) -> Result<TonicResponse<BatchGetUsersResponse>, Status> {
+            Err(Status::unimplemented("测试桩"))
+        }
+
+        async fn get_message_privacy(
+            &self,
+            _: TonicRequest<GetMessagePrivacyRequest>,
+        ) -> Result<TonicResponse<MessagePrivacyResponse>, Status> {
+            Err(Status::unimplemented("测试桩"))
+        }
+
+        async fn update_message_privacy(
+            &self,
+            _: TonicRequest<UpdateMessagePrivacyRequest>,
+        ) -> Result<TonicResponse<MessagePrivacyResponse>, Status> {
+            Err(Status::unimplemented("测试桩"))
+        }
+
+        async fn delete_account(
+            &self,
+            _: TonicRequest<DeleteAccountRequest>,
+        ) -> Result<TonicResponse<DeleteAccountResponse>, Status> {
+            Err(Status::unimplemented("测试桩"))
+        }
+
+        async fn export_user_data(
+            &self,
+            _: TonicRequest<ExportUserDataRequest>,
+        ) -> Result<TonicResponse<ExportUserDataResponse>, Status> {
+            Err(Status::unimplemented("测试桩"))
+        }
+
+        async fn get_user_data_export(
+            &self,
+            _: TonicRequest<GetUserDataExportRequest>,
+        ) -> Result<TonicResponse<GetUserDataExportResponse>, Status> {
+            Err(Status::unimplemented("测试桩"))
+        }
+
+        async fn upload_avatar(
+            &self,
+            _: TonicRequest<UploadAvatarRequest>,
+        ) -> Result<TonicResponse<UploadAvatarResponse>, Status> {
+            Err(Status::unimplemented("测试桩"))
+        }
+
+        async fn update_status(
+            &self,
+            _: TonicRequest<UpdateStatusRequest>,
+        ) -> Result<TonicResponse<StatusResponse>, Status> {
+            Err(Status::unimplemented("测试桩"))
+        }
+
+        async fn get_status(
+            &self,
+            _: TonicRequest<GetStatusRequest>,
+        ) -> Result<TonicResponse<StatusResponse>, Status> {
+            Err(Status::unimplemented("测试桩"))
+        }
+    }
+
+    /// auth-service桩：签发固定令牌，仅"valid-token"可注销成功
+    struct MockAuthService;
+
+    #[tonic::async_trait]
+    impl AuthService for MockAuthService {
+        async fn create_token(
+            &self,
+            request: TonicRequest<CreateTokenRequest>,
+        ) -> Result<TonicResponse<CreateTokenResponse>, Status> {
+            let req = request.into_inner();
+            Ok(TonicResponse::new(CreateTokenResponse {
+                access_token: format!("access-{}", req.user_id),
+                refresh_token: format!("refresh-{}", req.user_id),
+                expires_in: 3600,
+            }))
+        }
+
+        async fn invalidate_token(
+            &self,
+            request: TonicRequest<InvalidateTokenRequest>,
+        ) -> Result<TonicResponse<InvalidateTokenResponse>, Status> {
+            Ok(TonicResponse::new(InvalidateTokenResponse {
+                success: request.into_inner().token == "valid-token",
+            }))
+        }
+
+        async fn validate_token(
+            &self,
+            _: TonicRequest<ValidateTokenRequest>,
+        ) -> Result<TonicResponse<ValidateTokenResponse>, Status> {
+            Err(Status::unimplemented("测试桩"))
+        }
+
+        async fn refresh_token(
+            &self,
+            _: TonicRequest<RefreshTokenRequest>,
+        ) -> Result<TonicResponse<RefreshTokenResponse>, Status> {
+            Err(Status::unimplemented("测试桩"))
+        }
+
+        async fn invalidate_user_tokens(
+            &self,
+            _: TonicRequest<InvalidateUserTokensRequest>,
+        ) -> Result<TonicResponse<InvalidateUserTokensResponse>, Status> {
+            Err(Status::unimplemented("测试桩"))
+        }
+
+        async fn get_audit_log(
+            &self,
+            _: TonicRequest<GetAuditLogRequest>,
+        ) -> Result<TonicResponse<GetAuditLogResponse>, Status> {
+            Err(Status::unimplemented("测试桩"))
+        }
+    }
+
+    /// 在随机端口启动两个gRPC桩服务，返回各自的监听端口
+    async fn start_mock_services() -> (u16, u16) {
+        let user_listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let user_port = user_listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            tonic::transport::Server::builder()
+                .add_service(UserServiceServer::new(MockUserService))
+                .serve_with_incoming(tokio_stream::wrappers::TcpListenerStream::new(
+                    user_listener,
+                ))
+                .await
+                .unwrap();
+        });
+
+        let auth_listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let auth_port = auth_listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            tonic::transport::Server::builder()
+                .add_service(AuthServiceServer::new(MockAuthService))
+                .serve_with_incoming(tokio_stream::wrappers::TcpListenerStream::new(
+                    auth_listener,
+                ))
+                .await
+                .unwrap();
+        });
+
+        (user_port, auth_port)
+    }
+
+    /// 模拟Consul健康查询：user-service/auth-service各返回一个本地桩实例
+    async fn start_mock_consul(user_port: u16, auth_port: u16) -> String {
+        let app = Router::new().route(
+            "/v1/health/service/{name}",
+            get(move |axum::extract::Path(name): axum::extract::Path<String>| async move {
+                let port = match name.as_str() {
+                    "user-service" => user_port,
+                    "auth-service" => auth_port,
+                    _ => return "[]".to_string(),
+                };
+                serde_json::json!([
+                    { "Service": { "Address": "127.0.0.1", "Port": port } }
+                ])
+                .to_string()
+            }),
+        );
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        format!("http://{}", addr)
+    }
+
+    /// 构建完整的聚合端点测试环境：桩gRPC服务 + 桩Consul + 聚合路由
+    async fn test_app() -> Router {
+        let (user_port, auth_port) = start_mock_services().await;
+        let consul_url = start_mock_consul(user_port, auth_port).await;
+        let aggregator = Arc::new(AuthAggregator::new(GrpcChannelPool::new(Arc::new(
+            ServiceDiscovery::new(&consul_url),
+        ))));
+
+        let login = aggregator.clone();
+        let register = aggregator.clone();
+        let logout = aggregator;
+        Router::new()
+            .route(
+                "/api/auth/login",
+                post(move |payload| async move { login.login(payload).await }),
+            )
+            .route(
+                "/api/auth/register",
+                post(move |payload| async move { register.register(payload).await }),
+            )
+            .route(
+                "/api/auth/logout",
+                post(move |headers: HeaderMap| async move { logout.logout(headers).await }),
+            )
+    }
+
+    fn json_request(uri: &str, body: Value) -> Request<Body> {
+        Request::builder()
+            .method("POST")
+            .uri(uri)
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    }
+
+    async fn json_body(response: Response) -> Value {
+        let bytes = response.into_body().collect().await.unwrap().to_bytes();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_login_verifies_password_then_mints_tokens() {
+        let app = test_app().await;
+
+        // 正确口令：返回令牌对和用户信息
+        let response = app
+            .clone()
+            .oneshot(json_request(
+                "/api/auth/login",
+                json!({ "username": "alice", "password": "secret" }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = json_body(response).await;
+        assert_eq!(body["access_token"], "access-user-1");
+        assert_eq!(body["refresh_token"], "refresh-user-1");
+        assert_eq!(body["expires_in"], 3600);
+        assert_eq!(body["user"]["username"], "alice");
+
+        // 错误口令：401，不触发签发
+        let response = app
+            .oneshot(json_request(
+                "/api/auth/login",
+                json!({ "username": "alice", "password": "wrong" }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_register_creates_user_and_logs_in() {
+        let app = test_app().await;
+
+        let response = app
+            .clone()
+            .oneshot(json_request(
+                "/api/auth/register",
+                json!({ "username": "bob", "email": "bob@example.com", "password": "secret" }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let body = json_body(response).await;
+        assert_eq!(body["access_token"], "access-user-2");
+        assert_eq!(body["user"]["username"], "bob");
+
+        // 用户名冲突映射为409
+        let response = app
+            .oneshot(json_request(
+                "/api/auth/register",
+                json!({ "username": "taken", "email": "x@example.com", "password": "secret" }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+    }
+
+    #[tokio::test]
+    async fn test_logout_invalidates_bearer_token() {
+        let app = test_app().await;
+
+        // 缺少Authorization头
+        let response = app
+            .clone()
+            .oneshot(json_request("/api/auth/logout", json!({})))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        // 携带Bearer令牌时调用InvalidateToken
+        let mut request = json_request("/api/auth/logout", json!({}));
+        request.headers_mut().insert(
+            header::AUTHORIZATION,
+            "Bearer valid-token".parse().unwrap(),
+        );
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(json_body(response).await["success"], true);
+    }
+
+    #[test]
+    fn test_bearer_token_extraction() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(bearer_token(&headers), None);
+
+        headers.insert(header::AUTHORIZATION, "Bearer abc".parse().unwrap());
+        assert_eq!(bearer_token(&headers), Some("abc".to_string()));
+
+        // 非Bearer方案与空令牌均不接受
+        headers.insert(header::AUTHORIZATION, "Basic abc".parse().unwrap());
+        assert_eq!(bearer_token(&headers), None);
+        headers.insert(header::AUTHORIZATION, "Bearer ".parse().unwrap());
+        assert_eq!(bearer_token(&headers), None);
+    }
+}
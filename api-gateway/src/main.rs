@@ -1,3 +1,6 @@
+// 网关的部分中间件/客户端尚未接入主流程，先允许dead_code
+#![allow(dead_code)]
+
 use std::net::SocketAddr;
 use std::sync::Arc;
 use std::time::Duration;
@@ -6,9 +9,8 @@ use axum::{
     http::StatusCode,
     response::IntoResponse,
 };
-use axum_server::{self, Handle};
+use axum_server::Handle;
 use clap::Parser;
-use tower_http::cors::{CorsLayer, Any};
 use tower_http::trace::TraceLayer;
 use tower_http::timeout::TimeoutLayer;
 use tower_http::limit::RequestBodyLimitLayer;
@@ -17,8 +19,13 @@ use tokio::signal;
 use tracing::{info, error};
 
 mod config;
+mod admin;
 mod auth;
+mod geo;
+mod handlers;
+mod nonce;
 mod rate_limit;
+mod concurrency;
 mod circuit_breaker;
 mod metrics;
 #[path = "tracing/mod.rs"]
@@ -80,7 +87,10 @@ async fn main() -> anyhow::Result<()> {
     
     // 初始化Prometheus指标
     metrics::init_metrics();
-    
+
+    // 加载ip2region xdb（required_countries路由的国家/地区校验依赖）
+    geo::init(CONFIG.read().await.geoip_xdb.as_deref());
+
     // 初始化服务代理
     let service_proxy = proxy::ServiceProxy::new().await;
     
@@ -95,6 +105,29 @@ async fn main() -> anyhow::Result<()> {
     let addr = SocketAddr::from(([0, 0, 0, 0], port));
     info!("API网关服务监听: http://{}:{}", host, port);
     
+    // 启动依赖门控：等待配置的依赖服务在Consul中健康后才标记就绪，
+    // 等待期间/ready返回503，编排系统不会向网关导流；超时则降级就绪
+    {
+        let startup = CONFIG.read().await.startup.clone();
+        tokio::spawn(async move {
+            if !startup.required_services.is_empty() {
+                let registry = common::service_registry::ServiceRegistry::from_env();
+                match registry
+                    .wait_for_services(
+                        &startup.required_services,
+                        Duration::from_secs(startup.timeout_secs),
+                        Duration::from_secs(startup.poll_interval_secs),
+                    )
+                    .await
+                {
+                    Ok(()) => info!("依赖服务全部就绪"),
+                    Err(e) => error!("等待依赖服务就绪超时，降级进入就绪状态: {}", e),
+                }
+            }
+            router::set_ready();
+        });
+    }
+
     // 创建服务器句柄
     let handle = Handle::new();
     
@@ -125,23 +158,28 @@ async fn health_check() -> impl IntoResponse {
 
 /// 配置中间件
 async fn configure_middleware(app: Router, _service_proxy: proxy::ServiceProxy) -> Router {
-    // 添加链路追踪中间件
+    // 添加链路追踪中间件（每个请求一个span，延续上游traceparent）
+    let app = app.layer(axum::middleware::from_fn(tracing_setup::trace_middleware));
     let app = app.layer(TraceLayer::new_for_http());
+
+    // 添加请求体日志中间件（仅RUST_LOG包含trace时生效）
+    let app = app.layer(tracing_setup::BodyLogLayer::from_gateway_config().await);
     
     // 添加指标中间件
     let app = app.layer(metrics::MetricsLayer);
-    
-    // 添加CORS中间件
-    let cors = CorsLayer::new()
-        .allow_origin(Any)
-        .allow_methods(Any)
-        .allow_headers(Any)
-        .allow_credentials(true);
-    
+
+    // 添加CORS中间件（从配置构建，load_config已拒绝凭证+通配来源的非法组合）
+    let cors = CONFIG.read().await.cors.build_layer();
+
+    // 添加全局并发上限（饱和后直接返回503的负载保护）
+    let app = app.layer(concurrency::ConcurrencyLimitLayer::from_gateway_config().await);
+
     // 添加请求体大小限制和超时
+    // RequestIdLayer放最外层，保证被限流/超时短路的响应也带关联ID
     app.layer(cors)
-       .layer(TimeoutLayer::new(Duration::from_secs(30)))
+       .layer(TimeoutLayer::with_status_code(StatusCode::REQUEST_TIMEOUT, Duration::from_secs(30)))
        .layer(RequestBodyLimitLayer::new(10 * 1024 * 1024))
+       .layer(tracing_setup::RequestIdLayer)
 }
 
 /// 优雅关闭信号处理
@@ -172,7 +210,10 @@ async fn shutdown_signal(handle: Handle, service_proxy: proxy::ServiceProxy) {
     
     // 清理资源
     service_proxy.shutdown().await;
-    
+
+    // 冲刷并关闭OpenTelemetry导出器
+    tracing_setup::shutdown_tracer();
+
     // 发送优雅关闭信号，设置30秒超时
     handle.graceful_shutdown(Some(Duration::from_secs(30)));
     
@@ -1,9 +1,10 @@
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use tower::Layer;
 use tower::Service;
 use futures::future::BoxFuture;
-use metrics::{counter, histogram};
-use prometheus::{Registry, TextEncoder, Encoder};
+use prometheus::{
+    HistogramOpts, HistogramVec, IntCounterVec, IntGauge, Opts, Registry, TextEncoder, Encoder,
+};
 use axum::{
     http::{Request, Response, StatusCode},
     body::Body,
@@ -19,35 +20,241 @@ static REGISTRY: Lazy<Arc<Registry>> = Lazy::new(|| {
     Arc::new(registry)
 });
 
+// 请求耗时直方图的桶边界（秒），覆盖毫秒级到长尾慢请求
+const DURATION_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// 收到的请求数，按方法、路由模板与目标服务统计
+static REQUESTS_TOTAL: Lazy<IntCounterVec> = Lazy::new(|| {
+    let counter = IntCounterVec::new(
+        Opts::new("gateway_requests_total", "网关收到的HTTP请求总数"),
+        &["method", "route", "service"],
+    )
+    .unwrap();
+    REGISTRY.register(Box::new(counter.clone())).unwrap();
+    counter
+});
+
+/// 请求处理耗时（含转发与中间件）
+static REQUEST_DURATION: Lazy<HistogramVec> = Lazy::new(|| {
+    let histogram = HistogramVec::new(
+        HistogramOpts::new("gateway_request_duration_seconds", "网关请求处理耗时（秒）")
+            .buckets(DURATION_BUCKETS.to_vec()),
+        &["method", "route", "service"],
+    )
+    .unwrap();
+    REGISTRY.register(Box::new(histogram.clone())).unwrap();
+    histogram
+});
+
+/// 返回的响应数，按状态码统计
+static RESPONSES_TOTAL: Lazy<IntCounterVec> = Lazy::new(|| {
+    let counter = IntCounterVec::new(
+        Opts::new("gateway_responses_total", "网关返回的HTTP响应总数"),
+        &["method", "route", "service", "status"],
+    )
+    .unwrap();
+    REGISTRY.register(Box::new(counter.clone())).unwrap();
+    counter
+});
+
+/// 正在处理中的请求数
+static IN_FLIGHT: Lazy<IntGauge> = Lazy::new(|| {
+    let gauge = IntGauge::new("gateway_in_flight_requests", "网关正在处理中的请求数").unwrap();
+    REGISTRY.register(Box::new(gauge.clone())).unwrap();
+    gauge
+});
+
+/// 上游转发耗时，由ServiceProxy打点
+static UPSTREAM_DURATION: Lazy<HistogramVec> = Lazy::new(|| {
+    let histogram = HistogramVec::new(
+        HistogramOpts::new(
+            "gateway_upstream_duration_seconds",
+            "上游服务转发耗时（秒）",
+        )
+        .buckets(DURATION_BUCKETS.to_vec()),
+        &["service", "outcome"],
+    )
+    .unwrap();
+    REGISTRY.register(Box::new(histogram.clone())).unwrap();
+    histogram
+});
+
+/// 按后端端点统计的上游请求数（label基数受后端实例数约束）
+static BACKEND_REQUESTS: Lazy<IntCounterVec> = Lazy::new(|| {
+    let counter = IntCounterVec::new(
+        Opts::new("gateway_backend_requests_total", "按后端端点统计的上游请求总数"),
+        &["service", "endpoint"],
+    )
+    .unwrap();
+    REGISTRY.register(Box::new(counter.clone())).unwrap();
+    counter
+});
+
+/// 按后端端点统计的上游耗时
+static BACKEND_DURATION: Lazy<HistogramVec> = Lazy::new(|| {
+    let histogram = HistogramVec::new(
+        HistogramOpts::new(
+            "gateway_backend_duration_seconds",
+            "按后端端点统计的上游请求耗时（秒）",
+        )
+        .buckets(DURATION_BUCKETS.to_vec()),
+        &["service", "endpoint"],
+    )
+    .unwrap();
+    REGISTRY.register(Box::new(histogram.clone())).unwrap();
+    histogram
+});
+
+/// 按后端端点统计的连接类失败数
+static BACKEND_CONNECT_ERRORS: Lazy<IntCounterVec> = Lazy::new(|| {
+    let counter = IntCounterVec::new(
+        Opts::new(
+            "gateway_backend_connect_errors_total",
+            "按后端端点统计的上游连接失败总数",
+        ),
+        &["service", "endpoint"],
+    )
+    .unwrap();
+    REGISTRY.register(Box::new(counter.clone())).unwrap();
+    counter
+});
+
+/// 按后端端点与状态码类别统计的上游响应数
+static BACKEND_RESPONSES: Lazy<IntCounterVec> = Lazy::new(|| {
+    let counter = IntCounterVec::new(
+        Opts::new(
+            "gateway_backend_responses_total",
+            "按后端端点与状态码类别统计的上游响应总数",
+        ),
+        &["service", "endpoint", "class"],
+    )
+    .unwrap();
+    REGISTRY.register(Box::new(counter.clone())).unwrap();
+    counter
+});
+
+/// 服务发现缓存中的条目数
+static DISCOVERY_CACHE_SIZE: Lazy<IntGauge> = Lazy::new(|| {
+    let gauge = IntGauge::new(
+        "gateway_discovery_cache_entries",
+        "服务发现缓存中的条目数",
+    )
+    .unwrap();
+    REGISTRY.register(Box::new(gauge.clone())).unwrap();
+    gauge
+});
+
+/// 服务发现最近一次刷新时间（Unix秒）
+static DISCOVERY_LAST_REFRESH: Lazy<IntGauge> = Lazy::new(|| {
+    let gauge = IntGauge::new(
+        "gateway_discovery_last_refresh_timestamp_seconds",
+        "服务发现最近一次刷新完成的Unix时间戳（秒）",
+    )
+    .unwrap();
+    REGISTRY.register(Box::new(gauge.clone())).unwrap();
+    gauge
+});
+
 /// 获取全局Registry
 pub fn get_registry() -> Arc<Registry> {
     REGISTRY.clone()
 }
 
-/// 初始化指标系统
+/// 初始化指标系统：提前注册所有收集器，使/metrics从启动起就输出完整序列
 pub fn init_metrics() {
-    // 注册默认的收集器
-    let _registry = get_registry();
+    Lazy::force(&REQUESTS_TOTAL);
+    Lazy::force(&REQUEST_DURATION);
+    Lazy::force(&RESPONSES_TOTAL);
+    Lazy::force(&IN_FLIGHT);
+    Lazy::force(&UPSTREAM_DURATION);
+    Lazy::force(&BACKEND_REQUESTS);
+    Lazy::force(&BACKEND_DURATION);
+    Lazy::force(&BACKEND_CONNECT_ERRORS);
+    Lazy::force(&BACKEND_RESPONSES);
+    Lazy::force(&DISCOVERY_CACHE_SIZE);
+    Lazy::force(&DISCOVERY_LAST_REFRESH);
     info!("Prometheus指标已初始化");
 }
 
+/// 记录一次到具体后端端点的上游请求发出
+pub fn record_backend_request(service: &str, endpoint: &str) {
+    BACKEND_REQUESTS.with_label_values(&[service, endpoint]).inc();
+}
+
+/// 记录一次后端端点的响应：耗时与状态码类别
+pub fn observe_backend_response(
+    service: &str,
+    endpoint: &str,
+    status: StatusCode,
+    duration: Duration,
+) {
+    BACKEND_DURATION
+        .with_label_values(&[service, endpoint])
+        .observe(duration.as_secs_f64());
+    BACKEND_RESPONSES
+        .with_label_values(&[service, endpoint, status_class(status)])
+        .inc();
+}
+
+/// 记录一次后端端点的连接类失败（未收到响应）
+pub fn record_backend_connect_error(service: &str, endpoint: &str) {
+    BACKEND_CONNECT_ERRORS
+        .with_label_values(&[service, endpoint])
+        .inc();
+}
+
+/// 更新服务发现缓存条目数
+pub fn set_discovery_cache_size(entries: usize) {
+    DISCOVERY_CACHE_SIZE.set(entries as i64);
+}
+
+/// 标记服务发现完成了一次刷新
+pub fn mark_discovery_refresh() {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    DISCOVERY_LAST_REFRESH.set(now);
+}
+
+/// 状态码归并为类别，限制label基数
+fn status_class(status: StatusCode) -> &'static str {
+    match status.as_u16() / 100 {
+        1 => "1xx",
+        2 => "2xx",
+        3 => "3xx",
+        4 => "4xx",
+        _ => "5xx",
+    }
+}
+
+/// 记录一次上游转发耗时，5xx视为失败
+pub fn observe_upstream_duration(service: &str, status: StatusCode, duration: Duration) {
+    let outcome = if status.is_server_error() { "error" } else { "ok" };
+    UPSTREAM_DURATION
+        .with_label_values(&[service, outcome])
+        .observe(duration.as_secs_f64());
+}
+
 /// 指标请求处理函数
 pub async fn get_metrics_handler() -> impl IntoResponse {
     let encoder = TextEncoder::new();
     let registry = get_registry();
-    
+
     // 收集所有指标
     let metric_families = registry.gather();
     let mut buffer = Vec::new();
     encoder.encode(&metric_families, &mut buffer).unwrap_or_else(|e| {
         eprintln!("无法编码指标: {}", e);
     });
-    
+
     let metrics_text = String::from_utf8(buffer).unwrap_or_else(|e| {
         eprintln!("无法将指标转换为UTF-8: {}", e);
         String::from("metrics encoding error")
     });
-    
+
     (StatusCode::OK, metrics_text)
 }
 
@@ -83,95 +290,228 @@ where
     }
 
     fn call(&mut self, req: Request<Body>) -> Self::Future {
-        // 获取请求路径
-        let path = req.uri().path().to_string();
-        let method = req.method().clone();
-        
-        // 获取服务名称
-        let service = extract_service_name(&path);
-        
-        // 增加请求计数
-        counter!("gateway.requests.total", 
-            "method" => method.to_string(), 
-            "path" => path.clone(), 
-            "service" => service.clone()
-        );
-        
+        // 用路由模板而非原始路径做label，避免指标基数无界增长
+        let route = route_template(req.uri().path());
+        let method = req.method().to_string();
+        let service = extract_service_name(req.uri().path());
+
+        REQUESTS_TOTAL
+            .with_label_values(&[&method, route, service])
+            .inc();
+        IN_FLIGHT.inc();
+
         // 开始计时
         let start = Instant::now();
-        
+
         // 克隆服务
         let mut svc = self.inner.clone();
 
         Box::pin(async move {
             let result = svc.call(req).await;
-            
-            // 计算请求处理时间
-            let duration = start.elapsed();
-            
-            match &result {
-                Ok(response) => {
-                    let status = response.status().as_u16();
-                    
-                    // 记录请求处理时间（以秒为单位）
-                    let duration_secs = duration.as_secs_f64();
-                    histogram!("gateway.request.duration").record(duration_secs);
-                    
-                    // 统计状态码
-                    let path_clone = path.clone();
-                    let service_clone = service.clone();
-                    counter!("gateway.responses.total",
-                        "method" => method.to_string(),
-                        "path" => path_clone,
-                        "service" => service_clone,
-                        "status" => status.to_string()
-                    );
-                    
-                    // 统计错误状态码
-                    if status >= 400 {
-                        counter!("gateway.errors.total",
-                            "method" => method.to_string(),
-                            "path" => path,
-                            "service" => service,
-                            "status" => status.to_string()
-                        );
-                    }
-                }
-                Err(_) => {
-                    // 统计请求失败
-                    counter!("gateway.errors.total",
-                        "method" => method.to_string(),
-                        "path" => path,
-                        "service" => service,
-                        "status" => "error"
-                    );
-                }
-            }
-            
+
+            IN_FLIGHT.dec();
+            REQUEST_DURATION
+                .with_label_values(&[&method, route, service])
+                .observe(start.elapsed().as_secs_f64());
+
+            let status = match &result {
+                Ok(response) => response.status().as_u16().to_string(),
+                Err(_) => "error".to_string(),
+            };
+            RESPONSES_TOTAL
+                .with_label_values(&[&method, route, service, &status])
+                .inc();
+
             result
         })
     }
 }
 
 /// 从路径中提取服务名称
-fn extract_service_name(path: &str) -> String {
+fn extract_service_name(path: &str) -> &'static str {
+    if path.starts_with("/api/auth") {
+        "auth"
+    } else if path.starts_with("/api/users") {
+        "user"
+    } else if path.starts_with("/api/friends") {
+        "friend"
+    } else if path.starts_with("/api/groups") {
+        "group"
+    } else if path.starts_with("/api/chat") {
+        "chat"
+    } else if path.starts_with("/metrics") {
+        "metrics"
+    } else {
+        "unknown"
+    }
+}
+
+/// 将原始路径归并为有限集合的路由模板
+fn route_template(path: &str) -> &'static str {
     if path.starts_with("/api/auth") {
-        "auth".to_string()
+        "/api/auth/*"
     } else if path.starts_with("/api/users") {
-        "user".to_string()
+        "/api/users/*"
     } else if path.starts_with("/api/friends") {
-        "friend".to_string()
+        "/api/friends/*"
+    } else if path.starts_with("/api/groups/search") {
+        "/api/groups/search"
     } else if path.starts_with("/api/groups") {
-        "group".to_string()
+        "/api/groups/*"
+    } else if path.starts_with("/api/chat") {
+        "/api/chat/*"
+    } else if path.starts_with("/admin") {
+        "/admin/*"
     } else if path.starts_with("/metrics") {
-        "metrics".to_string()
+        "/metrics"
+    } else if path.starts_with("/health") {
+        "/health"
     } else {
-        "unknown".to_string()
+        "other"
     }
 }
 
-/// 创建指标中间件
-pub fn metrics_middleware() -> impl tower::Layer<tower::util::BoxCloneService<axum::http::Request<Body>, axum::response::Response<Body>, tower::BoxError>> + Clone {
-    // 创建MetricsLayer实例
-    MetricsLayer
-} 
\ No newline at end of file
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tower::ServiceExt;
+
+    // 返回固定状态码的内层服务
+    fn inner_service(
+        status: StatusCode,
+    ) -> tower::util::BoxCloneService<Request<Body>, Response<Body>, std::convert::Infallible>
+    {
+        tower::util::BoxCloneService::new(tower::service_fn(move |_req: Request<Body>| async move {
+            Ok(Response::builder()
+                .status(status)
+                .body(Body::empty())
+                .unwrap())
+        }))
+    }
+
+    async fn scrape() -> String {
+        let response = get_metrics_handler().await.into_response();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_middleware_populates_prometheus_series() {
+        init_metrics();
+
+        let mut ok_svc = MetricsLayer.layer(inner_service(StatusCode::OK));
+        let mut not_found_svc = MetricsLayer.layer(inner_service(StatusCode::NOT_FOUND));
+
+        for _ in 0..3 {
+            let req = Request::builder()
+                .method("GET")
+                .uri("/api/users/42/profile")
+                .body(Body::empty())
+                .unwrap();
+            ok_svc.ready().await.unwrap().call(req).await.unwrap();
+        }
+        let req = Request::builder()
+            .method("POST")
+            .uri("/api/groups/123/members")
+            .body(Body::empty())
+            .unwrap();
+        not_found_svc.ready().await.unwrap().call(req).await.unwrap();
+
+        let text = scrape().await;
+
+        // 请求计数带路由模板label，而非原始路径
+        assert!(text.contains(
+            r#"gateway_requests_total{method="GET",route="/api/users/*",service="user"} 3"#
+        ));
+        assert!(!text.contains("/api/users/42"));
+
+        // 耗时直方图有对应样本
+        assert!(text.contains(
+            r#"gateway_request_duration_seconds_count{method="GET",route="/api/users/*",service="user"} 3"#
+        ));
+        assert!(text.contains("gateway_request_duration_seconds_bucket"));
+
+        // 响应按状态码统计
+        assert!(text.contains(
+            r#"gateway_responses_total{method="GET",route="/api/users/*",service="user",status="200"} 3"#
+        ));
+        assert!(text.contains(
+            r#"gateway_responses_total{method="POST",route="/api/groups/*",service="group",status="404"} 1"#
+        ));
+
+        // 请求都已结束，在途计数归零
+        assert!(text.contains("gateway_in_flight_requests 0"));
+    }
+
+    #[tokio::test]
+    async fn test_upstream_duration_recorded_by_outcome() {
+        init_metrics();
+
+        observe_upstream_duration("user", StatusCode::OK, Duration::from_millis(15));
+        observe_upstream_duration("user", StatusCode::BAD_GATEWAY, Duration::from_millis(40));
+
+        let text = scrape().await;
+        assert!(text.contains(
+            r#"gateway_upstream_duration_seconds_count{outcome="ok",service="user"}"#
+        ));
+        assert!(text.contains(
+            r#"gateway_upstream_duration_seconds_count{outcome="error",service="user"}"#
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_backend_and_discovery_series_exposed() {
+        init_metrics();
+
+        record_backend_request("user-service", "http://10.0.0.1:8080");
+        observe_backend_response(
+            "user-service",
+            "http://10.0.0.1:8080",
+            StatusCode::OK,
+            Duration::from_millis(12),
+        );
+        observe_backend_response(
+            "user-service",
+            "http://10.0.0.1:8080",
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Duration::from_millis(80),
+        );
+        record_backend_connect_error("user-service", "http://10.0.0.2:8080");
+        set_discovery_cache_size(3);
+        mark_discovery_refresh();
+
+        let text = scrape().await;
+
+        // 按端点区分的序列，可在Grafana里定位慢后端
+        assert!(text.contains(
+            r#"gateway_backend_requests_total{endpoint="http://10.0.0.1:8080",service="user-service"} 1"#
+        ));
+        assert!(text.contains(
+            r#"gateway_backend_duration_seconds_count{endpoint="http://10.0.0.1:8080",service="user-service"} 2"#
+        ));
+        assert!(text.contains(
+            r#"gateway_backend_responses_total{class="2xx",endpoint="http://10.0.0.1:8080",service="user-service"} 1"#
+        ));
+        assert!(text.contains(
+            r#"gateway_backend_responses_total{class="5xx",endpoint="http://10.0.0.1:8080",service="user-service"} 1"#
+        ));
+        assert!(text.contains(
+            r#"gateway_backend_connect_errors_total{endpoint="http://10.0.0.2:8080",service="user-service"} 1"#
+        ));
+
+        // 服务发现健康度
+        assert!(text.contains("gateway_discovery_cache_entries 3"));
+        assert!(text.contains("gateway_discovery_last_refresh_timestamp_seconds"));
+        assert!(!text.contains("gateway_discovery_last_refresh_timestamp_seconds 0\n"));
+    }
+
+    #[test]
+    fn test_route_template_bounds_cardinality() {
+        assert_eq!(route_template("/api/users/42"), "/api/users/*");
+        assert_eq!(route_template("/api/groups/search?q=rust"), "/api/groups/search");
+        assert_eq!(route_template("/api/groups/1/members"), "/api/groups/*");
+        assert_eq!(route_template("/favicon.ico"), "other");
+    }
+}
@@ -0,0 +1,289 @@
+//! 高价值操作的防重放nonce
+//!
+//! 密码修改、账号注销等请求被抓包后可以原样重放，仅靠JWT无法防御
+//! （令牌在有效期内可重复使用）。配置了require_nonce的路由要求请求
+//! 携带一次性nonce：先通过/api/nonce端点签发，随请求在`x-nonce`头中
+//! 提交，校验时原子地消费，同一nonce第二次提交即被拒绝。
+//!
+//! 后端与限流器同构：memory为实例内存储，redis为跨网关实例共享
+//! （多副本部署时必须用redis，否则nonce在其他实例上不可见）。
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use once_cell::sync::Lazy;
+use serde_json::json;
+use tokio::sync::OnceCell;
+use tracing::{error, warn};
+
+use crate::auth::jwt::UserInfo;
+use crate::config::{NonceConfig, CONFIG};
+
+/// 携带nonce的请求头
+pub const NONCE_HEADER: &str = "x-nonce";
+
+/// 内存后端保留的nonce条目上限，超过时清理已过期条目
+const MEMORY_CAPACITY: usize = 4096;
+
+/// 防重放nonce存储
+pub struct NonceStore {
+    /// nonce有效期
+    ttl: Duration,
+    /// 内存后端：nonce键 -> 过期时刻
+    memory: Mutex<HashMap<String, Instant>>,
+    /// Redis后端，backend为"redis"时启用；初始化失败时回退到内存后端
+    redis: Option<redis::Client>,
+}
+
+impl NonceStore {
+    /// 按配置构建存储
+    pub fn from_config(config: &NonceConfig) -> Self {
+        let redis = if config.backend == "redis" {
+            match redis::Client::open(config.redis_url.as_str()) {
+                Ok(client) => Some(client),
+                Err(e) => {
+                    error!("nonce存储Redis初始化失败，使用内存后端: {}", e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        Self {
+            ttl: Duration::from_secs(config.ttl_secs),
+            memory: Mutex::new(HashMap::new()),
+            redis,
+        }
+    }
+
+    /// 仅内存后端的存储（测试用）
+    #[cfg(test)]
+    fn memory(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            memory: Mutex::new(HashMap::new()),
+            redis: None,
+        }
+    }
+
+    /// nonce有效期（秒）
+    pub fn ttl_secs(&self) -> u64 {
+        self.ttl.as_secs()
+    }
+
+    /// nonce与签发对象绑定，防止把他人的nonce拿来用
+    fn key(identity: &str, nonce: &str) -> String {
+        format!("gw:nonce:{}:{}", identity, nonce)
+    }
+
+    /// 签发一次性nonce
+    pub async fn issue(&self, identity: &str) -> Result<String, redis::RedisError> {
+        let nonce = uuid::Uuid::new_v4().simple().to_string();
+        let key = Self::key(identity, &nonce);
+
+        if let Some(client) = &self.redis {
+            let mut conn = client.get_multiplexed_async_connection().await?;
+            redis::cmd("SET")
+                .arg(&key)
+                .arg(1)
+                .arg("EX")
+                .arg(self.ttl.as_secs().max(1))
+                .query_async::<()>(&mut conn)
+                .await?;
+        } else {
+            let mut memory = self.memory.lock().unwrap();
+            // 容量超限时清理已过期条目，防止未被消费的nonce无限堆积
+            if memory.len() >= MEMORY_CAPACITY {
+                let now = Instant::now();
+                memory.retain(|_, expires_at| *expires_at > now);
+            }
+            memory.insert(key, Instant::now() + self.ttl);
+        }
+
+        Ok(nonce)
+    }
+
+    /// 消费nonce：存在且未过期时返回true并删除，重复消费返回false
+    pub async fn consume(&self, identity: &str, nonce: &str) -> Result<bool, redis::RedisError> {
+        let key = Self::key(identity, nonce);
+
+        if let Some(client) = &self.redis {
+            let mut conn = client.get_multiplexed_async_connection().await?;
+            // DEL原子返回删除数，两个并发请求只有一个能拿到1
+            let deleted: i64 = redis::cmd("DEL").arg(&key).query_async(&mut conn).await?;
+            Ok(deleted == 1)
+        } else {
+            let mut memory = self.memory.lock().unwrap();
+            match memory.remove(&key) {
+                Some(expires_at) => Ok(expires_at > Instant::now()),
+                None => Ok(false),
+            }
+        }
+    }
+}
+
+/// 全局nonce存储，首次使用时按配置初始化
+static STORE: Lazy<OnceCell<NonceStore>> = Lazy::new(OnceCell::new);
+
+async fn store() -> &'static NonceStore {
+    STORE
+        .get_or_init(|| async {
+            let config = CONFIG.read().await.nonce.clone();
+            NonceStore::from_config(&config)
+        })
+        .await
+}
+
+/// 请求者身份：认证用户按用户ID绑定，匿名请求共用"-"
+fn identity(user: Option<&UserInfo>) -> String {
+    user.map(|u| u.user_id.to_string()).unwrap_or_else(|| "-".to_string())
+}
+
+/// nonce签发端点处理函数（挂在认证中间件之后）
+pub async fn issue_nonce_handler(request: Request<Body>) -> Response {
+    let identity = identity(request.extensions().get::<UserInfo>());
+    let store = store().await;
+
+    match store.issue(&identity).await {
+        Ok(nonce) => (
+            StatusCode::OK,
+            Json(json!({
+                "nonce": nonce,
+                "expires_in_secs": store.ttl_secs(),
+            })),
+        )
+            .into_response(),
+        Err(e) => {
+            error!("签发nonce失败: {}", e);
+            (
+                StatusCode::SERVICE_UNAVAILABLE,
+                Json(json!({
+                    "error": "nonce_unavailable",
+                    "message": "nonce服务暂时不可用，请稍后重试"
+                })),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// 防重放校验中间件
+///
+/// 要求请求在`x-nonce`头中携带有效nonce，校验即消费；
+/// 缺失、已使用或已过期的nonce一律拒绝。存储不可用时拒绝请求
+/// （放行会使防重放形同虚设）。
+pub async fn nonce_middleware(request: Request<Body>, next: Next) -> Response {
+    let nonce = request
+        .headers()
+        .get(NONCE_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.to_string());
+
+    let Some(nonce) = nonce else {
+        return (
+            StatusCode::FORBIDDEN,
+            Json(json!({
+                "error": "nonce_required",
+                "message": format!("该操作需要防重放nonce，请先从/api/nonce获取并放入{}头", NONCE_HEADER)
+            })),
+        )
+            .into_response();
+    };
+
+    let identity = identity(request.extensions().get::<UserInfo>());
+    match store().await.consume(&identity, &nonce).await {
+        Ok(true) => next.run(request).await,
+        Ok(false) => {
+            warn!("拒绝无效或已使用的nonce: 路径={}", request.uri().path());
+            (
+                StatusCode::FORBIDDEN,
+                Json(json!({
+                    "error": "invalid_nonce",
+                    "message": "nonce无效、已使用或已过期"
+                })),
+            )
+                .into_response()
+        }
+        Err(e) => {
+            error!("校验nonce失败: {}", e);
+            (
+                StatusCode::SERVICE_UNAVAILABLE,
+                Json(json!({
+                    "error": "nonce_unavailable",
+                    "message": "nonce服务暂时不可用，请稍后重试"
+                })),
+            )
+                .into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::routing::post;
+    use axum::Router;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_nonce_is_single_use() {
+        let store = NonceStore::memory(Duration::from_secs(60));
+        let nonce = store.issue("user-1").await.unwrap();
+
+        // 首次消费成功，重复消费被拒绝
+        assert!(store.consume("user-1", &nonce).await.unwrap());
+        assert!(!store.consume("user-1", &nonce).await.unwrap());
+
+        // 他人的nonce不可用
+        let nonce = store.issue("user-1").await.unwrap();
+        assert!(!store.consume("user-2", &nonce).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_expired_nonce_rejected() {
+        let store = NonceStore::memory(Duration::ZERO);
+        let nonce = store.issue("user-1").await.unwrap();
+        assert!(!store.consume("user-1", &nonce).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_middleware_requires_and_consumes_nonce() {
+        let app = Router::new().route(
+            "/api/sensitive",
+            post(|| async { "ok" }).route_layer(axum::middleware::from_fn(nonce_middleware)),
+        );
+        let request = |nonce: Option<&str>| {
+            let mut builder = Request::builder().method("POST").uri("/api/sensitive");
+            if let Some(nonce) = nonce {
+                builder = builder.header(NONCE_HEADER, nonce);
+            }
+            builder.body(Body::empty()).unwrap()
+        };
+
+        // 未携带nonce的请求被拒绝
+        let response = app.clone().oneshot(request(None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        // 有效nonce放行一次（匿名请求绑定到"-"身份）
+        let nonce = store().await.issue("-").await.unwrap();
+        let response = app.clone().oneshot(request(Some(&nonce))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // 同一nonce重放被拒绝
+        let response = app.clone().oneshot(request(Some(&nonce))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        // 伪造的nonce同样被拒绝
+        let response = app.oneshot(request(Some("forged"))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+}
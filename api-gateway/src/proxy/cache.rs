@@ -0,0 +1,188 @@
+use std::num::NonZeroUsize;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use axum::{
+    body::{Body, Bytes},
+    http::{HeaderMap, HeaderValue, Response, StatusCode},
+};
+use once_cell::sync::Lazy;
+
+/// 响应缓存条目数上限
+const CACHE_CAPACITY: usize = 1024;
+
+/// 可缓存响应体大小上限：缓存需要整体缓冲响应体，
+/// 超过该大小的响应不缓存，保持流式透传
+pub const MAX_CACHEABLE_BODY: usize = 256 * 1024;
+
+/// 全局响应缓存（方法+路径+查询串+认证身份 -> 已缓存响应）
+static CACHE: Lazy<Mutex<lru::LruCache<String, CachedResponse>>> = Lazy::new(|| {
+    Mutex::new(lru::LruCache::new(NonZeroUsize::new(CACHE_CAPACITY).unwrap()))
+});
+
+/// 已缓存的上游响应
+#[derive(Clone)]
+pub struct CachedResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Bytes,
+    stored_at: Instant,
+    ttl: Duration,
+}
+
+impl CachedResponse {
+    pub fn new(status: StatusCode, headers: HeaderMap, body: Bytes, ttl: Duration) -> Self {
+        Self {
+            status,
+            headers,
+            body,
+            stored_at: Instant::now(),
+            ttl,
+        }
+    }
+
+    fn expired(&self) -> bool {
+        self.stored_at.elapsed() > self.ttl
+    }
+
+    /// 还原为响应并标记X-Cache: HIT
+    pub fn into_response(self) -> Response<Body> {
+        let mut builder = Response::builder().status(self.status);
+        let headers = builder.headers_mut().unwrap();
+        *headers = self.headers;
+        headers.insert("x-cache", HeaderValue::from_static("HIT"));
+        builder.body(Body::from(self.body)).unwrap()
+    }
+}
+
+/// 构建缓存键
+///
+/// 认证身份参与键值，避免按用户出不同内容的接口（如“我的资料”）
+/// 把某个用户的响应串给其他用户
+pub fn cache_key(method: &str, path_query: &str, identity: Option<&str>) -> String {
+    format!("{} {} {}", method, path_query, identity.unwrap_or("-"))
+}
+
+/// 查询缓存，过期条目即时摘除
+pub fn get(key: &str) -> Option<CachedResponse> {
+    let mut cache = CACHE.lock().unwrap();
+    match cache.get(key) {
+        Some(entry) if !entry.expired() => Some(entry.clone()),
+        Some(_) => {
+            cache.pop(key);
+            None
+        }
+        None => None,
+    }
+}
+
+/// 写入缓存
+pub fn store(key: String, response: CachedResponse) {
+    CACHE.lock().unwrap().put(key, response);
+}
+
+/// 客户端是否要求绕过缓存（Cache-Control: no-cache/no-store或Pragma: no-cache）
+pub fn client_no_cache(headers: &HeaderMap) -> bool {
+    let cache_control = headers
+        .get(axum::http::header::CACHE_CONTROL)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("");
+    if cache_control.contains("no-cache") || cache_control.contains("no-store") {
+        return true;
+    }
+    headers
+        .get(axum::http::header::PRAGMA)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.contains("no-cache"))
+}
+
+/// 上游响应是否允许缓存
+///
+/// Cache-Control带no-store/no-cache/private的响应不缓存；
+/// 带Vary的响应按内容协商变体处理成本高，第一版保守地一律不缓存
+pub fn upstream_cacheable(headers: &HeaderMap) -> bool {
+    if headers.contains_key(axum::http::header::VARY) {
+        return false;
+    }
+    let cache_control = headers
+        .get(axum::http::header::CACHE_CONTROL)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("");
+    !(cache_control.contains("no-store")
+        || cache_control.contains("no-cache")
+        || cache_control.contains("private"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cached(ttl: Duration) -> CachedResponse {
+        CachedResponse::new(
+            StatusCode::OK,
+            HeaderMap::new(),
+            Bytes::from_static(b"cached body"),
+            ttl,
+        )
+    }
+
+    #[test]
+    fn test_get_returns_entry_until_ttl_expires() {
+        let key = cache_key("GET", "/api/users/1?full=1", Some("42"));
+
+        store(key.clone(), cached(Duration::from_secs(60)));
+        assert!(get(&key).is_some());
+
+        // TTL为零的条目立即过期并被摘除
+        store(key.clone(), cached(Duration::ZERO));
+        assert!(get(&key).is_none());
+        assert!(get(&key).is_none());
+    }
+
+    #[test]
+    fn test_key_includes_identity() {
+        assert_ne!(
+            cache_key("GET", "/api/users/me", Some("1")),
+            cache_key("GET", "/api/users/me", Some("2"))
+        );
+        assert_ne!(
+            cache_key("GET", "/api/users/me", Some("1")),
+            cache_key("GET", "/api/users/me", None)
+        );
+    }
+
+    #[test]
+    fn test_client_no_cache_directives() {
+        let mut headers = HeaderMap::new();
+        assert!(!client_no_cache(&headers));
+
+        headers.insert("cache-control", HeaderValue::from_static("no-cache"));
+        assert!(client_no_cache(&headers));
+
+        let mut headers = HeaderMap::new();
+        headers.insert("pragma", HeaderValue::from_static("no-cache"));
+        assert!(client_no_cache(&headers));
+    }
+
+    #[test]
+    fn test_upstream_cacheable_respects_cache_control_and_vary() {
+        let mut headers = HeaderMap::new();
+        assert!(upstream_cacheable(&headers));
+
+        headers.insert("cache-control", HeaderValue::from_static("max-age=60"));
+        assert!(upstream_cacheable(&headers));
+
+        headers.insert("cache-control", HeaderValue::from_static("private"));
+        assert!(!upstream_cacheable(&headers));
+
+        let mut headers = HeaderMap::new();
+        headers.insert("vary", HeaderValue::from_static("accept-encoding"));
+        assert!(!upstream_cacheable(&headers));
+    }
+
+    #[test]
+    fn test_cached_response_marks_hit_header() {
+        let resp = cached(Duration::from_secs(60)).into_response();
+        assert_eq!(resp.headers().get("x-cache").unwrap(), "HIT");
+    }
+}
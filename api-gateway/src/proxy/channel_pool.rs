@@ -0,0 +1,250 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+use tonic::transport::Channel;
+use tracing::{debug, warn};
+
+use common::proto::health::health_check_response::ServingStatus;
+use common::proto::health::health_client::HealthClient;
+use common::proto::health::HealthCheckRequest;
+
+use crate::proxy::grpc_client::create_grpc_channel;
+use crate::proxy::service_proxy::{DiscoveryError, ServiceDiscovery};
+
+/// 网关出站gRPC通道池
+///
+/// 通道按目标URL键控、懒建连并跨请求复用（tonic的Channel自带HTTP/2
+/// 多路复用）。池内部全部经Arc共享，克隆ServiceProxy或各处理器时
+/// 沿用同一份通道缓存；后台任务按标准gRPC健康检查协议周期性探测
+/// 缓存中的通道，失活的被逐出，下次请求重新发现并建连
+#[derive(Clone)]
+pub struct GrpcChannelPool {
+    /// 服务发现：get_channel按服务名解析实例地址
+    discovery: Arc<ServiceDiscovery>,
+    /// 目标URL -> 已建立的gRPC通道
+    channels: Arc<RwLock<HashMap<String, Channel>>>,
+}
+
+/// 获取服务通道失败的原因
+#[derive(Debug)]
+pub enum ChannelPoolError {
+    /// 服务发现失败（Consul不可达或无健康实例）
+    Discovery(DiscoveryError),
+    /// 与实例建连失败
+    Connect(tonic::transport::Error),
+}
+
+impl std::fmt::Display for ChannelPoolError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChannelPoolError::Discovery(err) => write!(f, "服务发现失败: {}", err),
+            ChannelPoolError::Connect(err) => write!(f, "建立gRPC连接失败: {}", err),
+        }
+    }
+}
+
+impl GrpcChannelPool {
+    pub fn new(discovery: Arc<ServiceDiscovery>) -> Self {
+        Self {
+            discovery,
+            channels: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// 获取服务的gRPC通道：经服务发现解析实例地址后按地址复用通道
+    ///
+    /// 返回的URL供调用方在RPC失败时定位并逐出对应通道
+    pub async fn get_channel(
+        &self,
+        service_name: &str,
+    ) -> Result<(String, Channel), ChannelPoolError> {
+        let url = self
+            .discovery
+            .get_service_url(service_name)
+            .await
+            .map_err(ChannelPoolError::Discovery)?;
+        let channel = self
+            .channel_for_url(&url)
+            .await
+            .map_err(ChannelPoolError::Connect)?;
+        Ok((url, channel))
+    }
+
+    /// 获取目标URL的通道，不存在时建连后缓存
+    pub async fn channel_for_url(
+        &self,
+        target_url: &str,
+    ) -> Result<Channel, tonic::transport::Error> {
+        if let Some(channel) = self.channels.read().await.get(target_url) {
+            return Ok(channel.clone());
+        }
+
+        let channel = create_grpc_channel(target_url).await?;
+        // 并发建连时保留先写入的通道，后建立的直接丢弃
+        let mut channels = self.channels.write().await;
+        Ok(channels
+            .entry(target_url.to_string())
+            .or_insert(channel)
+            .clone())
+    }
+
+    /// 逐出目标URL的缓存通道，下次请求重新建连
+    pub async fn evict(&self, target_url: &str) {
+        if self.channels.write().await.remove(target_url).is_some() {
+            debug!("已逐出gRPC通道: {}", target_url);
+        }
+    }
+
+    /// 当前缓存的通道数量
+    pub async fn len(&self) -> usize {
+        self.channels.read().await.len()
+    }
+
+    /// 池中是否没有任何缓存通道
+    pub async fn is_empty(&self) -> bool {
+        self.channels.read().await.is_empty()
+    }
+
+    /// 按标准gRPC健康检查协议探测所有缓存的通道，逐出失活的
+    ///
+    /// 返回(目标URL, 是否健康)，供日志与健康端点使用
+    pub async fn health_check_all(&self) -> Vec<(String, bool)> {
+        let snapshot: Vec<(String, Channel)> = self
+            .channels
+            .read()
+            .await
+            .iter()
+            .map(|(url, channel)| (url.clone(), channel.clone()))
+            .collect();
+
+        let mut results = Vec::with_capacity(snapshot.len());
+        for (url, channel) in snapshot {
+            let request = HealthCheckRequest {
+                service: String::new(),
+            };
+            let healthy = match HealthClient::new(channel).check(request).await {
+                Ok(response) => response.into_inner().status() == ServingStatus::Serving,
+                Err(_) => false,
+            };
+            if !healthy {
+                warn!("gRPC通道 {} 健康检查未通过，已逐出", url);
+                self.evict(&url).await;
+            }
+            results.push((url, healthy));
+        }
+        results
+    }
+
+    /// 启动周期健康检查后台任务
+    ///
+    /// 池经Arc共享，任务随最后一个克隆被丢弃而失去作用（通道缓存
+    /// 清空后探测列表为空），无需显式停止
+    pub fn start_health_check_task(&self, interval: Duration) {
+        let pool = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                pool.health_check_all().await;
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::proto::health::health_server::{Health, HealthServer};
+    use common::proto::health::HealthCheckResponse;
+    use tonic::{Request as TonicRequest, Response as TonicResponse, Status};
+
+    /// 健康检查桩：按构造参数固定返回SERVING或NOT_SERVING
+    struct MockHealth {
+        serving: bool,
+    }
+
+    #[tonic::async_trait]
+    impl Health for MockHealth {
+        async fn check(
+            &self,
+            _: TonicRequest<HealthCheckRequest>,
+        ) -> Result<TonicResponse<HealthCheckResponse>, Status> {
+            let status = if self.serving {
+                ServingStatus::Serving
+            } else {
+                ServingStatus::NotServing
+            };
+            Ok(TonicResponse::new(HealthCheckResponse {
+                status: status as i32,
+            }))
+        }
+
+        type WatchStream =
+            tokio_stream::wrappers::ReceiverStream<Result<HealthCheckResponse, Status>>;
+
+        async fn watch(
+            &self,
+            _: TonicRequest<HealthCheckRequest>,
+        ) -> Result<TonicResponse<Self::WatchStream>, Status> {
+            Err(Status::unimplemented("测试桩"))
+        }
+    }
+
+    /// 在随机端口启动健康检查桩，返回其URL
+    async fn start_mock_backend(serving: bool) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            tonic::transport::Server::builder()
+                .add_service(HealthServer::new(MockHealth { serving }))
+                .serve_with_incoming(tokio_stream::wrappers::TcpListenerStream::new(listener))
+                .await
+                .unwrap();
+        });
+        url
+    }
+
+    fn test_pool() -> GrpcChannelPool {
+        GrpcChannelPool::new(Arc::new(ServiceDiscovery::new("http://127.0.0.1:1")))
+    }
+
+    #[tokio::test]
+    async fn test_channel_reused_and_shared_across_clones() {
+        let backend_url = start_mock_backend(true).await;
+        let pool = test_pool();
+
+        pool.channel_for_url(&backend_url).await.unwrap();
+        assert_eq!(pool.len().await, 1);
+
+        // 重复获取同一URL不会新建条目
+        pool.channel_for_url(&backend_url).await.unwrap();
+        assert_eq!(pool.len().await, 1);
+
+        // 克隆共享同一份缓存，任一侧逐出对双方可见
+        let cloned = pool.clone();
+        assert_eq!(cloned.len().await, 1);
+        cloned.evict(&backend_url).await;
+        assert_eq!(pool.len().await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_health_check_evicts_unhealthy_channels() {
+        let healthy_url = start_mock_backend(true).await;
+        let unhealthy_url = start_mock_backend(false).await;
+        let pool = test_pool();
+
+        pool.channel_for_url(&healthy_url).await.unwrap();
+        pool.channel_for_url(&unhealthy_url).await.unwrap();
+        assert_eq!(pool.len().await, 2);
+
+        // NOT_SERVING的通道被逐出，健康的保留
+        let results = pool.health_check_all().await;
+        assert_eq!(results.len(), 2);
+        for (url, healthy) in results {
+            assert_eq!(healthy, url == healthy_url);
+        }
+        assert_eq!(pool.len().await, 1);
+        assert!(pool.channel_for_url(&healthy_url).await.is_ok());
+    }
+}
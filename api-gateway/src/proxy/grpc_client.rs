@@ -1,19 +1,24 @@
 use axum::{
     body::Body,
-    http::{Request, Response, StatusCode},
+    http::{Method, Request, Response, StatusCode},
     response::IntoResponse,
 };
-use tonic::transport::{Channel, Endpoint};
 use std::time::Duration;
-use tracing::info;
+use tonic::transport::{Channel, Endpoint};
+use tonic::Code;
+use tracing::{debug, error};
 use serde_json::json;
 use axum::Json;
 
+use common::proto::{auth, friend, group, user};
+
+use crate::proxy::channel_pool::GrpcChannelPool;
+
 /// gRPC客户端工厂接口
 pub trait GrpcClientFactory: Send + Sync {
     /// 转发gRPC请求
     fn forward_request(&self, req: Request<Body>, target_url: String) -> futures::future::BoxFuture<'static, Response<Body>>;
-    
+
     /// 检查健康状态
     fn check_health(&self) -> futures::future::BoxFuture<'static, bool>;
 }
@@ -55,59 +60,328 @@ impl BaseGrpcClient {
             .connect_timeout(Duration::from_secs(config.connect_timeout_secs))
             .timeout(Duration::from_secs(config.timeout_secs))
             .concurrency_limit(config.concurrency_limit);
-            
+
         // load_balancing 策略在新版本中通过不同方式配置，这里暂时移除
-        
+
         let channel = endpoint.connect().await?;
-        
+
         Ok(Self {
             channel,
             config,
         })
     }
-    
+
     /// 获取共享通道
     pub fn channel(&self) -> Channel {
         self.channel.clone()
     }
 }
 
+/// JSON请求体大小上限（2MB），防止转码端点被超大请求拖垮
+const MAX_TRANSCODE_BODY_BYTES: usize = 2 * 1024 * 1024;
+
+/// 转码失败的分类，由forward_request映射为HTTP响应
+enum TranscodeError {
+    /// JSON请求体无法反序列化为目标消息
+    InvalidBody(serde_json::Error),
+    /// 后端返回的gRPC错误
+    Rpc(tonic::Status),
+    /// 响应消息序列化为JSON失败
+    Serialize(serde_json::Error),
+    /// 方法未在转码表中注册（调用前已校验，正常不会出现）
+    UnknownMethod,
+}
+
+/// 定义支持JSON↔gRPC转码的服务与方法映射
+///
+/// 每个条目展开为dispatch_call中的一个分支：JSON请求体反序列化为
+/// 请求消息，经tonic客户端调用后把响应消息序列化回JSON
+macro_rules! transcode_services {
+    (
+        $( $svc:literal => $client:ty { $( $name:literal => $method:ident ( $request:ty ) ),+ $(,)? } ),+ $(,)?
+    ) => {
+        /// 支持转码的服务名清单
+        fn supported_services() -> Vec<&'static str> {
+            vec![ $( $svc ),+ ]
+        }
+
+        /// 服务支持转码的方法清单；未知服务返回None
+        fn supported_methods(service: &str) -> Option<&'static [&'static str]> {
+            match service {
+                $( $svc => Some(&[ $( $name ),+ ]), )+
+                _ => None,
+            }
+        }
+
+        /// 把JSON请求体转为对应的tonic客户端调用，响应消息转回JSON
+        async fn dispatch_call(
+            service: &str,
+            method: &str,
+            channel: Channel,
+            metadata: tonic::metadata::MetadataMap,
+            body: serde_json::Value,
+        ) -> Result<serde_json::Value, TranscodeError> {
+            match (service, method) {
+                $( $(
+                    ($svc, $name) => {
+                        let message: $request =
+                            serde_json::from_value(body).map_err(TranscodeError::InvalidBody)?;
+                        let mut request = tonic::Request::new(message);
+                        *request.metadata_mut() = metadata;
+                        let response = <$client>::new(channel)
+                            .$method(request)
+                            .await
+                            .map_err(TranscodeError::Rpc)?;
+                        serde_json::to_value(response.into_inner()).map_err(TranscodeError::Serialize)
+                    }
+                )+ )+
+                _ => Err(TranscodeError::UnknownMethod),
+            }
+        }
+    };
+}
+
+transcode_services! {
+    "auth" => auth::auth_service_client::AuthServiceClient<Channel> {
+        "ValidateToken" => validate_token(auth::ValidateTokenRequest),
+        "CreateToken" => create_token(auth::CreateTokenRequest),
+        "RefreshToken" => refresh_token(auth::RefreshTokenRequest),
+        "InvalidateToken" => invalidate_token(auth::InvalidateTokenRequest),
+        "InvalidateUserTokens" => invalidate_user_tokens(auth::InvalidateUserTokensRequest),
+        "GetAuditLog" => get_audit_log(auth::GetAuditLogRequest),
+    },
+    "user" => user::user_service_client::UserServiceClient<Channel> {
+        "CreateUser" => create_user(user::CreateUserRequest),
+        "GetUserById" => get_user_by_id(user::GetUserByIdRequest),
+        "GetUserByUsername" => get_user_by_username(user::GetUserByUsernameRequest),
+        "UpdateUser" => update_user(user::UpdateUserRequest),
+        "VerifyPassword" => verify_password(user::VerifyPasswordRequest),
+        "SearchUsers" => search_users(user::SearchUsersRequest),
+        "BatchGetUsers" => batch_get_users(user::BatchGetUsersRequest),
+        "GetMessagePrivacy" => get_message_privacy(user::GetMessagePrivacyRequest),
+        "UpdateMessagePrivacy" => update_message_privacy(user::UpdateMessagePrivacyRequest),
+        "DeleteAccount" => delete_account(user::DeleteAccountRequest),
+        "ExportUserData" => export_user_data(user::ExportUserDataRequest),
+        "GetUserDataExport" => get_user_data_export(user::GetUserDataExportRequest),
+        "UploadAvatar" => upload_avatar(user::UploadAvatarRequest),
+        "UpdateStatus" => update_status(user::UpdateStatusRequest),
+        "GetStatus" => get_status(user::GetStatusRequest),
+    },
+    "friend" => friend::friend_service_client::FriendServiceClient<Channel> {
+        "SendFriendRequest" => send_friend_request(friend::SendFriendRequestRequest),
+        "AcceptFriendRequest" => accept_friend_request(friend::AcceptFriendRequestRequest),
+        "RejectFriendRequest" => reject_friend_request(friend::RejectFriendRequestRequest),
+        "GetFriendList" => get_friend_list(friend::GetFriendListRequest),
+        "GetFriendRequests" => get_friend_requests(friend::GetFriendRequestsRequest),
+        "GetSentFriendRequests" => get_sent_friend_requests(friend::GetSentFriendRequestsRequest),
+        "DeleteFriend" => delete_friend(friend::DeleteFriendRequest),
+        "CheckFriendship" => check_friendship(friend::CheckFriendshipRequest),
+        "BlockUser" => block_user(friend::BlockUserRequest),
+        "UnblockUser" => unblock_user(friend::UnblockUserRequest),
+        "UpdateFriendRemark" => update_friend_remark(friend::UpdateFriendRemarkRequest),
+        "SetFriendNote" => set_friend_note(friend::SetFriendNoteRequest),
+    },
+    "group" => group::group_service_client::GroupServiceClient<Channel> {
+        "CreateGroup" => create_group(group::CreateGroupRequest),
+        "GetGroup" => get_group(group::GetGroupRequest),
+        "UpdateGroup" => update_group(group::UpdateGroupRequest),
+        "DeleteGroup" => delete_group(group::DeleteGroupRequest),
+        "AddMember" => add_member(group::AddMemberRequest),
+        "RemoveMember" => remove_member(group::RemoveMemberRequest),
+        "UpdateMemberRole" => update_member_role(group::UpdateMemberRoleRequest),
+        "GetMembers" => get_members(group::GetMembersRequest),
+        "GetUserGroups" => get_user_groups(group::GetUserGroupsRequest),
+        "CheckMembership" => check_membership(group::CheckMembershipRequest),
+        "TransferOwnership" => transfer_ownership(group::TransferOwnershipRequest),
+        "MuteMember" => mute_member(group::MuteMemberRequest),
+        "UnmuteMember" => unmute_member(group::UnmuteMemberRequest),
+        "UpdateGroupAnnouncement" => update_group_announcement(group::UpdateGroupAnnouncementRequest),
+        "SearchGroups" => search_groups(group::SearchGroupsRequest),
+        "RequestJoin" => request_join(group::RequestJoinRequest),
+        "ApproveJoin" => approve_join(group::ApproveJoinRequest),
+        "RejectJoin" => reject_join(group::RejectJoinRequest),
+        "ListJoinRequests" => list_join_requests(group::ListJoinRequestsRequest),
+    },
+}
+
 /// 通用gRPC客户端工厂
+///
+/// 把 POST /rpc/{service}/{method} 的JSON请求体转码为对应的gRPC调用。
+/// 支持proto已收录在common中的auth/user/friend/group四个服务，
+/// 方法名使用proto中的原始名称（如CreateToken）
 pub struct GenericGrpcClientFactory {
-    // 在实际应用中，需要根据特定的proto定义创建更具体的客户端
-    // 这里只作为通用接口示例
+    /// 共享的出站通道池：工厂实例按请求创建，通道随池跨请求复用
+    pool: GrpcChannelPool,
 }
 
 impl GenericGrpcClientFactory {
-    /// 创建新的通用gRPC客户端工厂
-    pub fn new() -> Self {
-        Self {}
+    /// 创建新的通用gRPC客户端工厂，复用传入的通道池
+    pub fn new(pool: GrpcChannelPool) -> Self {
+        Self { pool }
+    }
+}
+
+/// 构建出站gRPC元数据，把请求的`X-Request-ID`复制为`x-request-id`，
+/// 保证关联ID跨HTTP/gRPC协议边界继续透传
+pub fn outgoing_metadata(req: &Request<Body>) -> tonic::metadata::MetadataMap {
+    let mut metadata = tonic::metadata::MetadataMap::new();
+    if let Some(request_id) = req
+        .headers()
+        .get("x-request-id")
+        .and_then(|v| v.to_str().ok())
+    {
+        if let Ok(value) = request_id.parse() {
+            metadata.insert("x-request-id", value);
+        }
+    }
+    metadata
+}
+
+/// 构建统一格式的JSON错误响应
+fn error_response(status: StatusCode, message: String) -> Response<Body> {
+    (
+        status,
+        Json(json!({
+            "error": status.as_u16(),
+            "message": message,
+        })),
+    )
+        .into_response()
+}
+
+/// 把gRPC状态码映射为HTTP状态码（与登录聚合处理器及common::Error的映射一致）
+fn http_status_for(code: Code) -> StatusCode {
+    match code {
+        Code::InvalidArgument => StatusCode::BAD_REQUEST,
+        Code::NotFound => StatusCode::NOT_FOUND,
+        Code::AlreadyExists => StatusCode::CONFLICT,
+        Code::Unauthenticated => StatusCode::UNAUTHORIZED,
+        Code::PermissionDenied => StatusCode::FORBIDDEN,
+        Code::ResourceExhausted => StatusCode::TOO_MANY_REQUESTS,
+        Code::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
     }
 }
 
 impl GrpcClientFactory for GenericGrpcClientFactory {
-    fn forward_request(&self, _req: Request<Body>, target_url: String) -> futures::future::BoxFuture<'static, Response<Body>> {
+    fn forward_request(&self, req: Request<Body>, target_url: String) -> futures::future::BoxFuture<'static, Response<Body>> {
+        let metadata = outgoing_metadata(&req);
+        let pool = self.pool.clone();
         Box::pin(async move {
-            // TODO: 实现真正的gRPC请求转发逻辑
-            // 需要根据特定的proto定义实现客户端
-            // 这里返回未实现消息
-            info!("收到gRPC请求，目标: {}", target_url);
-
-            (
-                StatusCode::NOT_IMPLEMENTED,
-                Json(json!({
-                    "error": "not_implemented",
-                    "message": "gRPC转发功能将在后续版本实现",
-                    "target": target_url,
-                }))
-            ).into_response()
+            let (parts, body) = req.into_parts();
+            if parts.method != Method::POST {
+                return error_response(
+                    StatusCode::METHOD_NOT_ALLOWED,
+                    "gRPC转码仅支持POST请求".to_string(),
+                );
+            }
+
+            // 解析 /rpc/{service}/{method}
+            let path = parts.uri.path().to_string();
+            let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+            let (service, method) = match segments.as_slice() {
+                ["rpc", service, method] => (service.to_string(), method.to_string()),
+                _ => {
+                    return error_response(
+                        StatusCode::NOT_FOUND,
+                        "gRPC转码路径格式为 /rpc/{service}/{method}".to_string(),
+                    )
+                }
+            };
+
+            // 未知服务/方法返回404并附上支持的清单
+            let Some(methods) = supported_methods(&service) else {
+                return error_response(
+                    StatusCode::NOT_FOUND,
+                    format!(
+                        "未知服务 {}，支持的服务: {}",
+                        service,
+                        supported_services().join(", ")
+                    ),
+                );
+            };
+            if !methods.contains(&method.as_str()) {
+                return error_response(
+                    StatusCode::NOT_FOUND,
+                    format!(
+                        "服务 {} 不支持方法 {}，支持的方法: {}",
+                        service,
+                        method,
+                        methods.join(", ")
+                    ),
+                );
+            }
+
+            // 读取JSON请求体，空body按空对象处理（缺省字段取proto默认值）
+            let bytes = match axum::body::to_bytes(body, MAX_TRANSCODE_BODY_BYTES).await {
+                Ok(bytes) => bytes,
+                Err(err) => {
+                    return error_response(
+                        StatusCode::PAYLOAD_TOO_LARGE,
+                        format!("读取请求体失败: {}", err),
+                    )
+                }
+            };
+            let json_body: serde_json::Value = if bytes.is_empty() {
+                json!({})
+            } else {
+                match serde_json::from_slice(&bytes) {
+                    Ok(value) => value,
+                    Err(err) => {
+                        return error_response(
+                            StatusCode::BAD_REQUEST,
+                            format!("请求体不是有效的JSON: {}", err),
+                        )
+                    }
+                }
+            };
+
+            // 从共享池获取通道并转码调用
+            let channel = match pool.channel_for_url(&target_url).await {
+                Ok(channel) => channel,
+                Err(err) => {
+                    error!("连接gRPC后端 {} 失败: {}", target_url, err);
+                    return error_response(
+                        StatusCode::SERVICE_UNAVAILABLE,
+                        format!("服务暂时不可用: {}", service),
+                    );
+                }
+            };
+
+            debug!("gRPC转码调用 {}/{}，目标: {}", service, method, target_url);
+            match dispatch_call(&service, &method, channel, metadata, json_body).await {
+                Ok(value) => (StatusCode::OK, Json(value)).into_response(),
+                Err(TranscodeError::InvalidBody(err)) => error_response(
+                    StatusCode::BAD_REQUEST,
+                    format!("请求体与 {} 不匹配: {}", method, err),
+                ),
+                Err(TranscodeError::Serialize(err)) => error_response(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    format!("响应序列化失败: {}", err),
+                ),
+                Err(TranscodeError::UnknownMethod) => error_response(
+                    StatusCode::NOT_FOUND,
+                    format!("服务 {} 不支持方法 {}", service, method),
+                ),
+                Err(TranscodeError::Rpc(status)) => {
+                    error!("gRPC调用 {}/{} 失败: {}", service, method, status);
+                    // 传输层不可用时从池中逐出该通道，下次请求重新建连
+                    if status.code() == Code::Unavailable {
+                        pool.evict(&target_url).await;
+                    }
+                    error_response(http_status_for(status.code()), status.message().to_string())
+                }
+            }
         })
     }
-    
+
     fn check_health(&self) -> futures::future::BoxFuture<'static, bool> {
+        let pool = self.pool.clone();
         Box::pin(async move {
-            // TODO: 实现真正的gRPC健康检查逻辑
-            false
+            // 探测委托给通道池（失活通道顺带被逐出），
+            // 池中没有通道时视为不健康
+            let results = pool.health_check_all().await;
+            !results.is_empty() && results.iter().all(|(_, healthy)| *healthy)
         })
     }
 }
@@ -118,6 +392,177 @@ pub async fn create_grpc_channel(target_url: &str) -> Result<Channel, tonic::tra
         .connect_timeout(Duration::from_secs(5))
         .timeout(Duration::from_secs(30))
         .concurrency_limit(100);
-        
+
     endpoint.connect().await
-} 
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::proto::auth::auth_service_server::{AuthService, AuthServiceServer};
+    use common::proto::auth::*;
+    use http_body_util::BodyExt;
+    use tonic::{Request as TonicRequest, Response as TonicResponse, Status};
+
+    /// auth-service桩：CreateToken回显用户ID，ValidateToken固定拒绝
+    struct MockAuthService;
+
+    #[tonic::async_trait]
+    impl AuthService for MockAuthService {
+        async fn create_token(
+            &self,
+            request: TonicRequest<CreateTokenRequest>,
+        ) -> Result<TonicResponse<CreateTokenResponse>, Status> {
+            let req = request.into_inner();
+            Ok(TonicResponse::new(CreateTokenResponse {
+                access_token: format!("access-{}", req.user_id),
+                refresh_token: format!("refresh-{}", req.user_id),
+                expires_in: 3600,
+            }))
+        }
+
+        async fn validate_token(
+            &self,
+            _: TonicRequest<ValidateTokenRequest>,
+        ) -> Result<TonicResponse<ValidateTokenResponse>, Status> {
+            Err(Status::unauthenticated("令牌无效"))
+        }
+
+        async fn refresh_token(
+            &self,
+            _: TonicRequest<RefreshTokenRequest>,
+        ) -> Result<TonicResponse<RefreshTokenResponse>, Status> {
+            Err(Status::unimplemented("测试桩"))
+        }
+
+        async fn invalidate_token(
+            &self,
+            _: TonicRequest<InvalidateTokenRequest>,
+        ) -> Result<TonicResponse<InvalidateTokenResponse>, Status> {
+            Err(Status::unimplemented("测试桩"))
+        }
+
+        async fn invalidate_user_tokens(
+            &self,
+            _: TonicRequest<InvalidateUserTokensRequest>,
+        ) -> Result<TonicResponse<InvalidateUserTokensResponse>, Status> {
+            Err(Status::unimplemented("测试桩"))
+        }
+
+        async fn get_audit_log(
+            &self,
+            _: TonicRequest<GetAuditLogRequest>,
+        ) -> Result<TonicResponse<GetAuditLogResponse>, Status> {
+            Err(Status::unimplemented("测试桩"))
+        }
+    }
+
+    /// 在随机端口启动auth-service桩，返回目标URL
+    async fn start_mock_auth() -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            tonic::transport::Server::builder()
+                .add_service(AuthServiceServer::new(MockAuthService))
+                .serve_with_incoming(tokio_stream::wrappers::TcpListenerStream::new(listener))
+                .await
+                .unwrap();
+        });
+        format!("http://127.0.0.1:{}", port)
+    }
+
+    fn test_pool() -> GrpcChannelPool {
+        GrpcChannelPool::new(std::sync::Arc::new(
+            crate::proxy::service_proxy::ServiceDiscovery::new("http://127.0.0.1:1"),
+        ))
+    }
+
+    fn rpc_request(path: &str, body: serde_json::Value) -> Request<Body> {
+        Request::builder()
+            .method("POST")
+            .uri(path)
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    }
+
+    async fn json_body(response: Response<Body>) -> serde_json::Value {
+        let bytes = response.into_body().collect().await.unwrap().to_bytes();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_transcodes_json_to_grpc_and_back() {
+        let url = start_mock_auth().await;
+        let factory = GenericGrpcClientFactory::new(test_pool());
+
+        let response = factory
+            .forward_request(
+                rpc_request(
+                    "/rpc/auth/CreateToken",
+                    json!({ "user_id": "u1", "username": "alice" }),
+                ),
+                url,
+            )
+            .await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = json_body(response).await;
+        assert_eq!(body["access_token"], "access-u1");
+        assert_eq!(body["refresh_token"], "refresh-u1");
+        assert_eq!(body["expires_in"], 3600);
+    }
+
+    #[tokio::test]
+    async fn test_maps_grpc_status_to_http_status() {
+        let url = start_mock_auth().await;
+        let factory = GenericGrpcClientFactory::new(test_pool());
+
+        // unauthenticated -> 401
+        let response = factory
+            .forward_request(
+                rpc_request("/rpc/auth/ValidateToken", json!({ "token": "x" })),
+                url,
+            )
+            .await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(json_body(response).await["message"], "令牌无效");
+    }
+
+    #[tokio::test]
+    async fn test_unknown_service_and_method_list_supported() {
+        let url = start_mock_auth().await;
+        let factory = GenericGrpcClientFactory::new(test_pool());
+
+        // 未知服务：404并列出支持的服务
+        let response = factory
+            .forward_request(rpc_request("/rpc/nosuch/Foo", json!({})), url.clone())
+            .await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let message = json_body(response).await["message"].as_str().unwrap().to_string();
+        assert!(message.contains("auth"));
+        assert!(message.contains("group"));
+
+        // 未知方法：404并列出该服务支持的方法
+        let response = factory
+            .forward_request(rpc_request("/rpc/auth/Nope", json!({})), url)
+            .await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let message = json_body(response).await["message"].as_str().unwrap().to_string();
+        assert!(message.contains("CreateToken"));
+    }
+
+    #[tokio::test]
+    async fn test_invalid_json_body_returns_400() {
+        let url = start_mock_auth().await;
+        let factory = GenericGrpcClientFactory::new(test_pool());
+
+        let request = Request::builder()
+            .method("POST")
+            .uri("/rpc/auth/CreateToken")
+            .body(Body::from("{not-json"))
+            .unwrap();
+        let response = factory.forward_request(request, url).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+}
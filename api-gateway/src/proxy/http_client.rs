@@ -1,9 +1,13 @@
-use reqwest::{Client, Response, Error};
+use rand::Rng;
+use reqwest::header::{HeaderValue, CONTENT_TYPE, RETRY_AFTER};
+use reqwest::{Client, Error, Method, Response};
 use std::time::Duration;
 use hyper::http::HeaderMap;
 use tracing::debug;
 use std::error::Error as StdError;
 
+use crate::config::CONFIG;
+
 /// HTTP客户端配置
 #[derive(Debug, Clone)]
 pub struct HttpClientConfig {
@@ -13,7 +17,7 @@ pub struct HttpClientConfig {
     pub timeout: u64,
     /// 重试次数
     pub max_retries: u32,
-    /// 重试间隔（毫秒）
+    /// 重试间隔（毫秒），作为指数退避的基数
     pub retry_interval: u64,
     /// 是否启用gzip压缩
     pub enable_gzip: bool,
@@ -51,117 +55,151 @@ impl HttpClient {
             .pool_max_idle_per_host(100)
             .build()
             .unwrap_or_default();
-        
+
         Self { client, config }
     }
-    
+
     /// 使用默认配置创建客户端
     pub fn default() -> Self {
         Self::new(HttpClientConfig::default())
     }
-    
+
+    /// 从网关全局配置创建客户端，重试参数取retry节
+    pub async fn from_gateway_config() -> Self {
+        let config = CONFIG.read().await;
+        Self::new(HttpClientConfig {
+            max_retries: config.retry.max_retries as u32,
+            retry_interval: config.retry.retry_interval_ms,
+            ..HttpClientConfig::default()
+        })
+    }
+
     /// 发送GET请求
     pub async fn get(&self, url: &str, headers: Option<HeaderMap>) -> Result<Response, Error> {
-        let mut req = self.client.get(url);
-        
-        // 添加请求头
-        if let Some(headers) = headers {
-            req = req.headers(headers);
-        }
-        
-        self.send_with_retry(req, self.config.max_retries).await
+        self.send_with_retry(Method::GET, url, headers, None).await
     }
-    
+
     /// 发送POST请求
     pub async fn post(&self, url: &str, headers: Option<HeaderMap>, body: Option<Vec<u8>>) -> Result<Response, Error> {
-        let mut req = self.client.post(url);
-        
-        // 添加请求头
-        if let Some(headers) = headers {
-            req = req.headers(headers);
-        }
-        
-        // 添加请求体
-        if let Some(body) = body {
-            req = req.body(body);
-        }
-        
-        self.send_with_retry(req, self.config.max_retries).await
+        self.send_with_retry(Method::POST, url, headers, body).await
     }
-    
+
     /// 发送POST JSON请求
     pub async fn post_json<T: serde::Serialize>(&self, url: &str, headers: Option<HeaderMap>, json: &T) -> Result<Response, Error> {
-        let mut req = self.client.post(url);
-        
-        // 添加请求头
-        if let Some(headers) = headers {
-            req = req.headers(headers);
-        }
-        
-        // 添加JSON请求体
-        req = req.json(json);
-        
-        self.send_with_retry(req, self.config.max_retries).await
+        let body = match serde_json::to_vec(json) {
+            Ok(body) => body,
+            // 序列化失败时交给reqwest生成对应的builder错误
+            Err(_) => return self.client.post(url).json(json).send().await,
+        };
+
+        let mut headers = headers.unwrap_or_default();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+
+        self.send_with_retry(Method::POST, url, Some(headers), Some(body)).await
     }
-    
+
     /// 发送PUT请求
     pub async fn put(&self, url: &str, headers: Option<HeaderMap>, body: Option<Vec<u8>>) -> Result<Response, Error> {
-        let mut req = self.client.put(url);
-        
-        // 添加请求头
-        if let Some(headers) = headers {
-            req = req.headers(headers);
-        }
-        
-        // 添加请求体
-        if let Some(body) = body {
-            req = req.body(body);
-        }
-        
-        self.send_with_retry(req, self.config.max_retries).await
+        self.send_with_retry(Method::PUT, url, headers, body).await
     }
-    
+
     /// 发送DELETE请求
     pub async fn delete(&self, url: &str, headers: Option<HeaderMap>) -> Result<Response, Error> {
-        let mut req = self.client.delete(url);
-        
-        // 添加请求头
-        if let Some(headers) = headers {
-            req = req.headers(headers);
-        }
-        
-        self.send_with_retry(req, self.config.max_retries).await
+        self.send_with_retry(Method::DELETE, url, headers, None).await
     }
-    
+
     /// 带重试的请求发送
-    async fn send_with_retry(&self, req: reqwest::RequestBuilder, retries: u32) -> Result<Response, Error> {
-        let mut attempts = 0;
-
-        // 对于第一次请求，直接发送
-        let first_req = req;
-
-        match first_req.send().await {
-            Ok(response) => {
-                // TODO 如果第一次请求成功但需要重试
-                if is_retryable_status(&response) && attempts < retries {
-                    // 记录URL和方法，用于重建请求
-                    let _url = response.url().clone();
-                    // 继续处理重试逻辑...
+    ///
+    /// 请求由方法/URL/请求头/请求体重建，因此可以真正地多次发送。
+    /// 幂等方法（GET/HEAD/PUT/DELETE）在可重试状态码和连接类错误时重试；
+    /// POST仅在携带Idempotency-Key且发生连接/超时错误时重试。
+    async fn send_with_retry(
+        &self,
+        method: Method,
+        url: &str,
+        headers: Option<HeaderMap>,
+        body: Option<Vec<u8>>,
+    ) -> Result<Response, Error> {
+        let retries = self.config.max_retries;
+        let idempotent = is_idempotent(&method);
+        let has_idempotency_key = headers
+            .as_ref()
+            .is_some_and(|h| h.contains_key("idempotency-key"));
+
+        let mut attempt = 0;
+        loop {
+            let mut req = self.client.request(method.clone(), url);
+            if let Some(headers) = &headers {
+                req = req.headers(headers.clone());
+            }
+            if let Some(body) = &body {
+                req = req.body(body.clone());
+            }
+
+            match req.send().await {
+                Ok(response) => {
+                    if idempotent && is_retryable_status(&response) && attempt < retries {
+                        attempt += 1;
+                        // 429/503优先尊重服务端给出的Retry-After
+                        let delay = retry_after_ms(&response)
+                            .unwrap_or_else(|| self.backoff_ms(attempt));
+                        debug!(
+                            "请求返回 {}, 尝试重试 ({}/{}), 等待 {}ms",
+                            response.status(),
+                            attempt,
+                            retries,
+                            delay
+                        );
+                        tokio::time::sleep(Duration::from_millis(delay)).await;
+                        continue;
+                    }
                     return Ok(response);
                 }
-                Ok(response)
-            },
-            Err(err) => {
-                if is_retryable_error(&err) && attempts < retries {
-                    attempts += 1;
-                    debug!("请求错误: {}, 尝试重试 ({}/{})", err, attempts, retries);
-                    tokio::time::sleep(Duration::from_millis(self.config.retry_interval)).await;
-                    // TODO 这里应该重建请求，但简化为直接返回错误
+                Err(err) => {
+                    let connect_like = err.is_connect() || err.is_timeout();
+                    let retryable = if idempotent {
+                        is_retryable_error(&err)
+                    } else {
+                        method == Method::POST && has_idempotency_key && connect_like
+                    };
+                    if retryable && attempt < retries {
+                        attempt += 1;
+                        let delay = self.backoff_ms(attempt);
+                        debug!("请求错误: {}, 尝试重试 ({}/{}), 等待 {}ms", err, attempt, retries, delay);
+                        tokio::time::sleep(Duration::from_millis(delay)).await;
+                        continue;
+                    }
+                    return Err(err);
                 }
-                Err(err)
             }
         }
     }
+
+    /// 指数退避 + 随机抖动（毫秒）
+    fn backoff_ms(&self, attempt: u32) -> u64 {
+        let base = self.config.retry_interval.saturating_mul(1u64 << (attempt - 1).min(10));
+        base + rand::rng().random_range(0..=base / 2)
+    }
+}
+
+/// 方法是否幂等，幂等方法可以安全地整体重试
+fn is_idempotent(method: &Method) -> bool {
+    matches!(*method, Method::GET | Method::HEAD | Method::PUT | Method::DELETE)
+}
+
+/// 从429/503响应中解析Retry-After（秒），返回毫秒
+fn retry_after_ms(response: &Response) -> Option<u64> {
+    if !matches!(response.status().as_u16(), 429 | 503) {
+        return None;
+    }
+    response
+        .headers()
+        .get(RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .parse::<u64>()
+        .ok()
+        .map(|secs| secs * 1000)
 }
 
 /// 检查状态码是否可重试
@@ -189,4 +227,111 @@ fn is_reset_error(err: &Error) -> bool {
         }
     }
     false
-} 
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::StatusCode;
+    use axum::routing::{get, post};
+    use axum::Router;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    /// 启动一个前两次返回失败状态、之后返回200的mock服务
+    async fn start_flaky_server(hits: Arc<AtomicUsize>, fail_status: StatusCode) -> String {
+        let handler = move || {
+            let hits = hits.clone();
+            async move {
+                let n = hits.fetch_add(1, Ordering::SeqCst);
+                if n < 2 {
+                    (fail_status, "fail")
+                } else {
+                    (StatusCode::OK, "ok")
+                }
+            }
+        };
+        let app = Router::new()
+            .route("/", get(handler.clone()))
+            .route("/", post(handler));
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        format!("http://{}", addr)
+    }
+
+    fn fast_client() -> HttpClient {
+        HttpClient::new(HttpClientConfig {
+            max_retries: 3,
+            retry_interval: 10,
+            ..HttpClientConfig::default()
+        })
+    }
+
+    #[tokio::test]
+    async fn test_get_retries_until_success() {
+        let hits = Arc::new(AtomicUsize::new(0));
+        let url = start_flaky_server(hits.clone(), StatusCode::SERVICE_UNAVAILABLE).await;
+
+        let response = fast_client().get(&url, None).await.unwrap();
+
+        // 失败两次后第三次成功
+        assert_eq!(response.status().as_u16(), 200);
+        assert_eq!(hits.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_post_without_idempotency_key_is_not_retried() {
+        let hits = Arc::new(AtomicUsize::new(0));
+        let url = start_flaky_server(hits.clone(), StatusCode::SERVICE_UNAVAILABLE).await;
+
+        let response = fast_client().post(&url, None, None).await.unwrap();
+
+        // POST非幂等，失败状态原样返回且只发送一次
+        assert_eq!(response.status().as_u16(), 503);
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_retry_after_is_honored_on_429() {
+        let hits = Arc::new(AtomicUsize::new(0));
+        let handler = move |hits: Arc<AtomicUsize>| async move {
+            let n = hits.fetch_add(1, Ordering::SeqCst);
+            if n < 2 {
+                (
+                    StatusCode::TOO_MANY_REQUESTS,
+                    [(RETRY_AFTER.as_str(), "0")],
+                    "fail",
+                )
+            } else {
+                (StatusCode::OK, [(RETRY_AFTER.as_str(), "0")], "ok")
+            }
+        };
+        let hits_clone = hits.clone();
+        let app = Router::new().route("/", get(move || handler(hits_clone.clone())));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        // Retry-After: 0 使重试立即发生，整体耗时远小于指数退避
+        let client = HttpClient::new(HttpClientConfig {
+            max_retries: 3,
+            retry_interval: 60_000,
+            ..HttpClientConfig::default()
+        });
+        let started = std::time::Instant::now();
+        let response = client
+            .get(&format!("http://{}", addr), None)
+            .await
+            .unwrap();
+
+        assert_eq!(response.status().as_u16(), 200);
+        assert_eq!(hits.load(Ordering::SeqCst), 3);
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+}
@@ -0,0 +1,298 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+use tracing::{info, warn};
+
+use crate::proxy::service_proxy::LoadBalancingStrategy;
+
+/// 连续失败达到该次数后实例进入冷却期
+pub const UNHEALTHY_FAILURE_THRESHOLD: u32 = 3;
+
+/// 不健康实例的默认冷却时长，冷却结束后重新参与选择
+pub const UNHEALTHY_COOLDOWN: Duration = Duration::from_secs(30);
+
+/// 单个后端实例的运行时状态
+struct EndpointState {
+    /// 在途请求数
+    in_flight: AtomicUsize,
+    /// 连续连接失败次数，成功一次即清零
+    consecutive_failures: AtomicU32,
+    /// 不健康冷却截止时间，None表示健康
+    unhealthy_until: Mutex<Option<Instant>>,
+}
+
+impl EndpointState {
+    fn new() -> Self {
+        Self {
+            in_flight: AtomicUsize::new(0),
+            consecutive_failures: AtomicU32::new(0),
+            unhealthy_until: Mutex::new(None),
+        }
+    }
+
+    /// 实例当前是否参与选择（冷却期结束即视为可用）
+    fn is_available(&self) -> bool {
+        match *self.unhealthy_until.lock() {
+            Some(until) => Instant::now() >= until,
+            None => true,
+        }
+    }
+}
+
+/// 健康感知负载均衡器
+///
+/// 跟踪每个实例的在途请求数与连续失败次数：
+/// 连续失败达到阈值的实例进入冷却期，期间不再被选中；
+/// 冷却结束后重新参与选择，由之后的成败决定去留。
+pub struct LoadBalancer {
+    /// 实例URL -> 运行时状态
+    endpoints: Mutex<HashMap<String, Arc<EndpointState>>>,
+    /// RoundRobin游标（按服务名）
+    cursors: Mutex<HashMap<String, usize>>,
+    /// 不健康冷却时长
+    cooldown: Duration,
+}
+
+impl LoadBalancer {
+    pub fn new() -> Self {
+        Self {
+            endpoints: Mutex::new(HashMap::new()),
+            cursors: Mutex::new(HashMap::new()),
+            cooldown: UNHEALTHY_COOLDOWN,
+        }
+    }
+
+    /// 指定冷却时长（测试用）
+    #[cfg(test)]
+    pub fn with_cooldown(mut self, cooldown: Duration) -> Self {
+        self.cooldown = cooldown;
+        self
+    }
+
+    /// 从候选实例中选择一个并计入在途请求
+    ///
+    /// 冷却期内的实例被跳过；全部实例都在冷却期时退回完整列表，
+    /// 避免服务彻底不可用。候选列表为空时返回None。
+    pub fn pick(
+        &self,
+        service_name: &str,
+        strategy: LoadBalancingStrategy,
+        candidates: &[String],
+    ) -> Option<String> {
+        if candidates.is_empty() {
+            return None;
+        }
+
+        let states: Vec<(&String, Arc<EndpointState>)> = {
+            let mut endpoints = self.endpoints.lock();
+            candidates
+                .iter()
+                .map(|url| {
+                    let state = endpoints
+                        .entry(url.clone())
+                        .or_insert_with(|| Arc::new(EndpointState::new()))
+                        .clone();
+                    (url, state)
+                })
+                .collect()
+        };
+
+        let available: Vec<&(&String, Arc<EndpointState>)> =
+            states.iter().filter(|(_, s)| s.is_available()).collect();
+        let pool = if available.is_empty() {
+            warn!("服务 {} 的全部实例均在冷却期，退回完整列表", service_name);
+            states.iter().collect()
+        } else {
+            available
+        };
+
+        let (url, state) = match strategy {
+            LoadBalancingStrategy::LeastConnections => pool
+                .iter()
+                .min_by_key(|(_, s)| s.in_flight.load(Ordering::SeqCst))
+                .expect("候选列表非空"),
+            // RoundRobin及其他策略按游标轮询
+            _ => {
+                let mut cursors = self.cursors.lock();
+                let cursor = cursors.entry(service_name.to_string()).or_insert(0);
+                let idx = *cursor % pool.len();
+                *cursor = cursor.wrapping_add(1);
+                pool[idx]
+            }
+        };
+
+        state.in_flight.fetch_add(1, Ordering::SeqCst);
+        Some((*url).clone())
+    }
+
+    /// 请求成功：清零失败计数并解除冷却
+    pub fn report_success(&self, url: &str) {
+        if let Some(state) = self.state_of(url) {
+            Self::dec_in_flight(&state);
+            state.consecutive_failures.store(0, Ordering::SeqCst);
+            let mut unhealthy = state.unhealthy_until.lock();
+            if unhealthy.take().is_some() {
+                info!("实例 {} 请求成功，恢复参与负载均衡", url);
+            }
+        }
+    }
+
+    /// 请求失败：累计连续失败次数，达到阈值后进入冷却期
+    pub fn report_failure(&self, url: &str) {
+        let state = {
+            let mut endpoints = self.endpoints.lock();
+            endpoints
+                .entry(url.to_string())
+                .or_insert_with(|| Arc::new(EndpointState::new()))
+                .clone()
+        };
+
+        Self::dec_in_flight(&state);
+        let failures = state.consecutive_failures.fetch_add(1, Ordering::SeqCst) + 1;
+        if failures >= UNHEALTHY_FAILURE_THRESHOLD {
+            warn!(
+                "实例 {} 连续失败{}次，冷却{}秒后重新参与选择",
+                url,
+                failures,
+                self.cooldown.as_secs()
+            );
+            *state.unhealthy_until.lock() = Some(Instant::now() + self.cooldown);
+            state.consecutive_failures.store(0, Ordering::SeqCst);
+        }
+    }
+
+    /// 实例当前在途请求数（无记录时为0）
+    pub fn in_flight(&self, url: &str) -> usize {
+        self.state_of(url)
+            .map(|s| s.in_flight.load(Ordering::SeqCst))
+            .unwrap_or(0)
+    }
+
+    fn state_of(&self, url: &str) -> Option<Arc<EndpointState>> {
+        self.endpoints.lock().get(url).cloned()
+    }
+
+    fn dec_in_flight(state: &EndpointState) {
+        // 在途数可能因为未经由pick的回报而为0，避免下溢
+        let _ = state
+            .in_flight
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1));
+    }
+}
+
+impl Default for LoadBalancer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidates() -> Vec<String> {
+        vec![
+            "http://127.0.0.1:8001".to_string(),
+            "http://127.0.0.1:8002".to_string(),
+        ]
+    }
+
+    #[test]
+    fn test_round_robin_cycles_endpoints() {
+        let lb = LoadBalancer::new();
+        let urls = candidates();
+
+        let picks: Vec<String> = (0..4)
+            .map(|_| {
+                let url = lb
+                    .pick("user-service", LoadBalancingStrategy::RoundRobin, &urls)
+                    .unwrap();
+                lb.report_success(&url);
+                url
+            })
+            .collect();
+
+        assert_eq!(picks[0], picks[2]);
+        assert_eq!(picks[1], picks[3]);
+        assert_ne!(picks[0], picks[1]);
+    }
+
+    #[test]
+    fn test_least_connections_prefers_idle_endpoint() {
+        let lb = LoadBalancer::new();
+        let urls = candidates();
+
+        // 两个在途请求各占一个实例
+        let first = lb
+            .pick("user-service", LoadBalancingStrategy::LeastConnections, &urls)
+            .unwrap();
+        let second = lb
+            .pick("user-service", LoadBalancingStrategy::LeastConnections, &urls)
+            .unwrap();
+        assert_ne!(first, second);
+
+        // 第一个请求完成后，新请求应落到空闲的实例
+        lb.report_success(&first);
+        assert_eq!(lb.in_flight(&first), 0);
+        let third = lb
+            .pick("user-service", LoadBalancingStrategy::LeastConnections, &urls)
+            .unwrap();
+        assert_eq!(third, first);
+    }
+
+    #[test]
+    fn test_failing_endpoint_enters_cooldown_and_recovers() {
+        let lb = LoadBalancer::new().with_cooldown(Duration::from_millis(50));
+        let urls = candidates();
+        let failing = &urls[0];
+        let healthy = &urls[1];
+
+        // 连续失败达到阈值后进入冷却期
+        for _ in 0..UNHEALTHY_FAILURE_THRESHOLD {
+            lb.report_failure(failing);
+        }
+
+        // 冷却期内流量全部转移到健康实例
+        for _ in 0..20 {
+            let url = lb
+                .pick("user-service", LoadBalancingStrategy::RoundRobin, &urls)
+                .unwrap();
+            assert_eq!(&url, healthy, "冷却期内不应选中故障实例");
+            lb.report_success(&url);
+        }
+
+        // 冷却期结束后故障实例重新参与选择
+        std::thread::sleep(Duration::from_millis(60));
+        let mut seen_failing = false;
+        for _ in 0..10 {
+            let url = lb
+                .pick("user-service", LoadBalancingStrategy::RoundRobin, &urls)
+                .unwrap();
+            if &url == failing {
+                seen_failing = true;
+            }
+            lb.report_success(&url);
+        }
+        assert!(seen_failing, "冷却期结束后故障实例应重新接流");
+    }
+
+    #[test]
+    fn test_all_unhealthy_falls_back_to_full_list() {
+        let lb = LoadBalancer::new();
+        let urls = candidates();
+
+        for url in &urls {
+            for _ in 0..UNHEALTHY_FAILURE_THRESHOLD {
+                lb.report_failure(url);
+            }
+        }
+
+        // 全部实例都在冷却期时仍能选出实例
+        assert!(lb
+            .pick("user-service", LoadBalancingStrategy::RoundRobin, &urls)
+            .is_some());
+    }
+}
@@ -1,7 +1,11 @@
 pub mod service_proxy;
+pub mod cache;
+pub mod channel_pool;
+pub mod load_balancer;
 pub mod grpc_client;
 pub mod http_client;
 pub mod utils;
+pub mod ws_proxy;
 
 // 重新导出一些常用项
 pub use service_proxy::ServiceProxy;
@@ -7,22 +7,179 @@ use axum::{
 use std::collections::HashMap;
 use std::time::Duration;
 use tokio::sync::RwLock;
-use tracing::{info, error, debug, warn};
+use tracing::{info, error, debug, warn, Instrument};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use opentelemetry::trace::TraceContextExt;
 use reqwest::Client;
 use crate::config::CONFIG;
 use crate::config::routes_config::ServiceType;
 use crate::auth::jwt::UserInfo;
 use rand::Rng;
+use common::service_registry::{ServiceEndpoint, ServiceRegistry};
 use crate::proxy::grpc_client::GrpcClientFactory;
 
+/// 发现结果LRU缓存的容量（按服务名计）
+const SERVICE_CACHE_CAPACITY: usize = 64;
+
+/// 发现结果缓存的默认TTL（秒），可通过配置service_cache_ttl_secs调整
+const DEFAULT_SERVICE_CACHE_TTL_SECS: u64 = 5;
+
+/// 实例默认权重，ServiceMeta未设置weight的实例使用该值
+const DEFAULT_INSTANCE_WEIGHT: u32 = 100;
+
+/// 出站gRPC通道池的健康检查间隔（秒）
+const GRPC_POOL_HEALTH_CHECK_INTERVAL_SECS: u64 = 30;
+
+/// 测试用的单条上游尝试记录：(服务名, 第几次尝试, 结果)
+#[cfg(test)]
+type AttemptRecord = (String, usize, &'static str);
+
+/// 测试用的上游尝试记录
+#[cfg(test)]
+static UPSTREAM_ATTEMPTS: once_cell::sync::Lazy<parking_lot::Mutex<Vec<AttemptRecord>>> =
+    once_cell::sync::Lazy::new(|| parking_lot::Mutex::new(Vec::new()));
+
+/// 记录一次上游转发尝试（attempt从1开始）
+///
+/// 按尝试序号与结果分别打点，便于区分"重试一次后恢复"与
+/// "持续失败被重试掩盖"两种形态
+fn record_upstream_attempt(service_name: &str, attempt: usize, outcome: &'static str) {
+    metrics::counter!(
+        "gateway.upstream.attempt",
+        "service" => service_name.to_string(),
+        "attempt" => attempt.to_string(),
+        "outcome" => outcome,
+    )
+    .increment(1);
+    info!(service = service_name, attempt, outcome, "上游转发尝试完成");
+    #[cfg(test)]
+    UPSTREAM_ATTEMPTS
+        .lock()
+        .push((service_name.to_string(), attempt, outcome));
+}
+
+/// 记录一次转发的最终结果与总尝试次数
+fn record_upstream_outcome(service_name: &str, attempts: usize, outcome: &'static str) {
+    metrics::counter!(
+        "gateway.upstream.outcome",
+        "service" => service_name.to_string(),
+        "outcome" => outcome,
+    )
+    .increment(1);
+    info!(service = service_name, attempts, outcome, "上游转发结束");
+}
+
+/// 负载均衡策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoadBalancingStrategy {
+    /// 均匀随机
+    Random,
+    /// 按实例权重随机（权重来自Consul ServiceMeta的weight，可被管理端覆盖）
+    Weighted,
+    /// 轮询（健康感知，由LoadBalancer执行选择）
+    RoundRobin,
+    /// 最少在途连接（健康感知，由LoadBalancer执行选择）
+    LeastConnections,
+}
+
+impl LoadBalancingStrategy {
+    /// 按配置名解析策略（沿用ws_lb_strategy的取值命名）
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "Random" => Some(Self::Random),
+            "Weighted" => Some(Self::Weighted),
+            "RoundRobin" => Some(Self::RoundRobin),
+            "LeastConnections" => Some(Self::LeastConnections),
+            _ => None,
+        }
+    }
+}
+
+/// 加权随机选择器
+///
+/// 构建累积权重前缀和，随机落点后二分查找所在桶。
+/// 在Consul中给金丝雀实例设置`weight=10`、存量实例保持默认100，
+/// 即可得到约10%/90%的灰度分流，无需改动网关配置。
+pub struct WeightedRandom {
+    urls: Vec<String>,
+    /// 累积权重前缀和，prefix_sums[i]为前i+1个实例的权重之和
+    prefix_sums: Vec<u64>,
+}
+
+impl WeightedRandom {
+    /// 从（地址，权重）列表构建选择器；总权重为0时返回None
+    pub fn new(instances: &[(String, u32)]) -> Option<Self> {
+        let mut urls = Vec::with_capacity(instances.len());
+        let mut prefix_sums = Vec::with_capacity(instances.len());
+        let mut total: u64 = 0;
+
+        for (url, weight) in instances {
+            if *weight == 0 {
+                continue;
+            }
+            total += *weight as u64;
+            urls.push(url.clone());
+            prefix_sums.push(total);
+        }
+
+        if total == 0 {
+            return None;
+        }
+        Some(Self { urls, prefix_sums })
+    }
+
+    /// 加权随机选取一个实例
+    pub fn pick(&self) -> &str {
+        let total = *self.prefix_sums.last().expect("构造时保证非空");
+        let point = rand::random::<u32>() as u64 % total;
+        // 二分查找第一个前缀和大于落点的桶
+        let idx = self.prefix_sums.partition_point(|&sum| sum <= point);
+        &self.urls[idx]
+    }
+}
+
+/// 发现结果缓存条目：实例列表与写入时间
+type CacheEntry = (Vec<(String, u32)>, std::time::Instant);
+
+/// 服务发现错误
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiscoveryError {
+    /// 服务在Consul中存在但没有健康实例
+    NoHealthyInstances(String),
+    /// 其他发现失败（网络、解析等）
+    Other(String),
+}
+
+impl std::fmt::Display for DiscoveryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DiscoveryError::NoHealthyInstances(name) => {
+                write!(f, "服务 {} 没有健康实例", name)
+            }
+            DiscoveryError::Other(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
 /// 服务发现接口
 pub struct ServiceDiscovery {
-    // 服务地址缓存
-    services: RwLock<HashMap<String, Vec<String>>>,
-    // Consul客户端
-    consul_client: Client,
-    // Consul URL
-    consul_url: String,
+    // 静态注入的服务地址（地址，权重），优先于watch结果，供测试与固定地址部署使用
+    services: RwLock<HashMap<String, Vec<(String, u32)>>>,
+    // 发现结果LRU缓存（服务名 -> (实例列表, 写入时间)），TTL内直接命中，
+    // 限制Consul查询频率；空列表表示"无健康实例"，作为负面缓存同样受TTL约束
+    cache: Arc<std::sync::Mutex<lru::LruCache<String, CacheEntry>>>,
+    // 缓存条目的TTL
+    cache_ttl: Duration,
+    // 管理端覆盖的实例权重（实例URL -> 权重），0表示摘除（发版排水），
+    // 优先于ServiceMeta中的权重
+    weights: RwLock<HashMap<String, u32>>,
+    // 负载均衡策略
+    lb_strategy: LoadBalancingStrategy,
+    // 服务注册中心客户端：通过Consul阻塞查询watch各服务的实例列表，
+    // 实例下线后缓存即时失效，不再出现死实例继续接流的问题
+    registry: ServiceRegistry,
+    // 已建立的watch（服务名 -> 端点列表接收端）
+    watches: RwLock<HashMap<String, tokio::sync::watch::Receiver<Vec<ServiceEndpoint>>>>,
 }
 
 impl ServiceDiscovery {
@@ -30,17 +187,38 @@ impl ServiceDiscovery {
     pub fn new(consul_url: &str) -> Self {
         Self {
             services: RwLock::new(HashMap::new()),
-            consul_client: Client::builder()
-                .timeout(Duration::from_secs(5))
-                .build()
-                .unwrap_or_default(),
-            consul_url: consul_url.to_string(),
+            cache: Arc::new(std::sync::Mutex::new(lru::LruCache::new(
+                std::num::NonZeroUsize::new(SERVICE_CACHE_CAPACITY).unwrap(),
+            ))),
+            cache_ttl: Duration::from_secs(DEFAULT_SERVICE_CACHE_TTL_SECS),
+            weights: RwLock::new(HashMap::new()),
+            lb_strategy: LoadBalancingStrategy::Weighted,
+            registry: ServiceRegistry::new(consul_url),
+            watches: RwLock::new(HashMap::new()),
         }
     }
-    
-    /// 发现服务地址
-    pub async fn discover_service(&self, service_name: &str) -> Result<Vec<String>, String> {
-        // 首先尝试从缓存获取
+
+    /// 指定负载均衡策略
+    pub fn with_strategy(mut self, strategy: LoadBalancingStrategy) -> Self {
+        self.lb_strategy = strategy;
+        self
+    }
+
+    /// 指定发现结果缓存的TTL
+    pub fn with_cache_ttl(mut self, ttl: Duration) -> Self {
+        self.cache_ttl = ttl;
+        self
+    }
+
+    /// 发现服务地址及其权重
+    ///
+    /// 首次查询某服务时通过`ServiceRegistry::watch_service`建立watch，
+    /// 之后直接读取由Consul阻塞查询保持实时的端点缓存。
+    pub async fn discover_service(
+        &self,
+        service_name: &str,
+    ) -> Result<Vec<(String, u32)>, DiscoveryError> {
+        // 静态注入的地址优先
         {
             let services = self.services.read().await;
             if let Some(addresses) = services.get(service_name) {
@@ -49,67 +227,128 @@ impl ServiceDiscovery {
                 }
             }
         }
-        
-        // 缓存中不存在，从Consul获取
-        let consul_url = format!("{}/v1/catalog/service/{}", self.consul_url, service_name);
-        
-        match self.consul_client.get(&consul_url).send().await {
-            Ok(response) => {
-                if response.status().is_success() {
-                    match response.json::<Vec<serde_json::Value>>().await {
-                        Ok(services) => {
-                            let mut addresses = Vec::new();
-                            
-                            for service in services {
-                                if let (Some(address), Some(port)) = (
-                                    service.get("ServiceAddress").and_then(|a| a.as_str()),
-                                    service.get("ServicePort").and_then(|p| p.as_u64()),
-                                ) {
-                                    // 构建服务地址
-                                    let addr = if address.is_empty() {
-                                        // 如果ServiceAddress为空，使用Address
-                                        if let Some(addr) = service.get("Address").and_then(|a| a.as_str()) {
-                                            format!("http://{}:{}", addr, port)
-                                        } else {
-                                            continue;
-                                        }
-                                    } else {
-                                        format!("http://{}:{}", address, port)
-                                    };
-                                    
-                                    addresses.push(addr);
-                                }
-                            }
-                            
-                            if addresses.is_empty() {
-                                return Err(format!("无法找到服务: {}", service_name));
-                            }
-                            
-                            // 更新缓存
-                            {
-                                let mut services = self.services.write().await;
-                                services.insert(service_name.to_string(), addresses.clone());
-                            }
-                            
-                            Ok(addresses)
-                        },
-                        Err(e) => Err(format!("解析服务发现响应失败: {}", e)),
-                    }
-                } else {
-                    Err(format!("服务发现请求失败: HTTP {}", response.status()))
+
+        // LRU缓存命中且未过期时直接返回，限制Consul/watch读取频率
+        {
+            let mut cache = self.cache.lock().unwrap();
+            if let Some((addresses, birth)) = cache.get(service_name) {
+                if birth.elapsed() <= self.cache_ttl {
+                    return Self::addresses_or_no_instances(service_name, addresses.clone());
                 }
-            },
-            Err(e) => Err(format!("服务发现请求错误: {}", e)),
+            }
+        }
+
+        // 已建立watch时读取实时端点并回填缓存
+        {
+            let watches = self.watches.read().await;
+            if let Some(rx) = watches.get(service_name) {
+                let addresses = Self::endpoints_to_addresses(&rx.borrow());
+                self.store_cache(service_name, addresses.clone());
+                return Self::addresses_or_no_instances(service_name, addresses);
+            }
+        }
+
+        // 建立watch（内部完成首次即时查询）
+        match self.registry.watch_service(service_name).await {
+            Ok(rx) => {
+                let addresses = Self::endpoints_to_addresses(&rx.borrow());
+                self.watches
+                    .write()
+                    .await
+                    .insert(service_name.to_string(), rx);
+                self.store_cache(service_name, addresses.clone());
+                Self::addresses_or_no_instances(service_name, addresses)
+            }
+            Err(e) => Err(DiscoveryError::Other(format!("服务发现请求错误: {}", e))),
         }
     }
-    
-    /// 获取服务地址（使用简单的负载均衡）
-    pub async fn get_service_url(&self, service_name: &str) -> Result<String, String> {
+
+    /// 端点列表转为（地址，权重）
+    fn endpoints_to_addresses(endpoints: &[ServiceEndpoint]) -> Vec<(String, u32)> {
+        endpoints
+            .iter()
+            .map(|e| (e.url.clone(), e.weight))
+            .collect()
+    }
+
+    /// 空列表映射为NoHealthyInstances
+    fn addresses_or_no_instances(
+        service_name: &str,
+        addresses: Vec<(String, u32)>,
+    ) -> Result<Vec<(String, u32)>, DiscoveryError> {
+        if addresses.is_empty() {
+            return Err(DiscoveryError::NoHealthyInstances(service_name.to_string()));
+        }
+        Ok(addresses)
+    }
+
+    /// 写入发现结果缓存，记录写入时间作为TTL起点
+    fn store_cache(&self, service_name: &str, addresses: Vec<(String, u32)>) {
+        if addresses.is_empty() {
+            warn!("服务 {} 没有健康实例", service_name);
+        }
+        let mut cache = self.cache.lock().unwrap();
+        cache.put(service_name.to_string(), (addresses, std::time::Instant::now()));
+        crate::metrics::set_discovery_cache_size(cache.len());
+    }
+
+    /// 从发现缓存中摘除一个失败实例，使其在缓存TTL内不再被选中
+    pub fn evict_instance(&self, service_name: &str, instance_url: &str) {
+        let mut cache = self.cache.lock().unwrap();
+        if let Some((addresses, _)) = cache.get_mut(service_name) {
+            addresses.retain(|(url, _)| url != instance_url);
+        }
+    }
+
+    /// 设置实例权重，0表示摘除该实例（新请求不再选中，存量请求不受影响）
+    pub async fn set_instance_weight(&self, instance_url: &str, weight: u32) {
+        let mut weights = self.weights.write().await;
+        weights.insert(instance_url.to_string(), weight);
+        info!("实例 {} 权重已设置为 {}", instance_url, weight);
+    }
+
+    /// 获取当前显式设置过的实例权重
+    pub async fn get_instance_weights(&self) -> HashMap<String, u32> {
+        self.weights.read().await.clone()
+    }
+
+    /// 获取服务地址（权重为0的实例被跳过）
+    ///
+    /// 实例权重取Consul ServiceMeta中的weight（缺省100），
+    /// 管理端通过`set_instance_weight`设置的覆盖值优先。
+    pub async fn get_service_url(&self, service_name: &str) -> Result<String, DiscoveryError> {
         let addresses = self.discover_service(service_name).await?;
-        
-        // 简单的轮询负载均衡
-        let idx = rand::rng().random_range(0..addresses.len());
-        Ok(addresses[idx].clone())
+
+        let overrides = self.weights.read().await;
+        let weighted: Vec<(String, u32)> = addresses
+            .iter()
+            .map(|(addr, meta_weight)| {
+                (
+                    addr.clone(),
+                    overrides.get(addr).copied().unwrap_or(*meta_weight),
+                )
+            })
+            .collect();
+
+        match self.lb_strategy {
+            LoadBalancingStrategy::Weighted => {
+                if let Some(picker) = WeightedRandom::new(&weighted) {
+                    return Ok(picker.pick().to_string());
+                }
+                // 全部实例均被摘除时退回完整列表，避免服务完全不可用
+                warn!("服务 {} 的全部实例均被摘除，退回完整列表", service_name);
+                let idx = rand::rng().random_range(0..addresses.len());
+                Ok(addresses[idx].0.clone())
+            }
+            // RoundRobin/LeastConnections由ServiceProxy的LoadBalancer选择，
+            // 不应走到这里，防御性地退化为均匀随机
+            LoadBalancingStrategy::Random
+            | LoadBalancingStrategy::RoundRobin
+            | LoadBalancingStrategy::LeastConnections => {
+                let idx = rand::rng().random_range(0..addresses.len());
+                Ok(addresses[idx].0.clone())
+            }
+        }
     }
     
     /// 刷新服务缓存
@@ -125,78 +364,739 @@ impl ServiceDiscovery {
                 Err(e) => warn!("刷新服务 {} 缓存失败: {}", service_name, e),
             }
         }
+
+        crate::metrics::mark_discovery_refresh();
+        crate::metrics::set_discovery_cache_size(self.cache.lock().unwrap().len());
     }
 }
 
+/// 校验traceparent格式: 00-<32位trace-id>-<16位parent-id>-<2位flags>
+fn is_valid_traceparent(value: &str) -> bool {
+    let parts: Vec<&str> = value.split('-').collect();
+    parts.len() == 4
+        && parts[0].len() == 2
+        && parts[1].len() == 32
+        && parts[2].len() == 16
+        && parts[3].len() == 2
+        && parts.iter().all(|p| p.chars().all(|c| c.is_ascii_hexdigit()))
+        // trace-id全0为非法值
+        && parts[1].chars().any(|c| c != '0')
+}
+
+/// 生成新的traceparent（采样标记置1）
+fn new_traceparent() -> String {
+    let trace_id = uuid::Uuid::new_v4().simple().to_string();
+    let span_id = uuid::Uuid::new_v4().simple().to_string();
+    format!("00-{}-{}-01", trace_id, &span_id[..16])
+}
+
+/// 请求体缓冲上限：不超过该大小的请求体读入内存以支持跨实例重试，
+/// 超过（或chunked长度未知）的请求体流式透传
+const MAX_BUFFERED_BODY: usize = 1024 * 1024;
+
 /// 服务代理 - 负责转发请求到后端服务
+/// HTTP转发失败，按失败阶段分类供跨实例重试决策
+#[derive(Debug)]
+struct ForwardError {
+    /// 错误描述
+    message: String,
+    /// 失败发生在连接建立阶段（尚未发出任何字节，任何方法都可安全换实例重试）
+    connect_stage: bool,
+    /// 连接类可重试错误（连接失败/超时），仅幂等方法可据此重试
+    retryable: bool,
+}
+
+/// 单个服务的专属上游状态：独立连接池的HTTP客户端与可选的在途请求配额
+#[derive(Clone)]
+struct UpstreamState {
+    /// 该服务专属的HTTP客户端，连接池与共享客户端隔离
+    client: Client,
+    /// max_in_flight>0时的并发许可，饱和后新请求直接返回503
+    permits: Option<Arc<tokio::sync::Semaphore>>,
+}
+
+impl UpstreamState {
+    /// 按配置构建专属客户端与并发许可
+    fn from_config(config: &crate::config::UpstreamPoolConfig) -> Self {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(config.timeout_secs))
+            .pool_max_idle_per_host(config.pool_max_idle_per_host)
+            .pool_idle_timeout(Duration::from_secs(config.pool_idle_timeout_secs))
+            .build()
+            .unwrap_or_default();
+        let permits = if config.max_in_flight > 0 {
+            Some(Arc::new(tokio::sync::Semaphore::new(config.max_in_flight)))
+        } else {
+            None
+        };
+        Self { client, permits }
+    }
+}
+
 pub struct ServiceProxy {
     // 服务发现
     service_discovery: Arc<ServiceDiscovery>,
+    // 健康感知负载均衡器（RoundRobin/LeastConnections策略）
+    load_balancer: Arc<crate::proxy::load_balancer::LoadBalancer>,
+    // 各服务的负载均衡策略（来自配置lb_strategy，缺省Weighted）
+    strategies: HashMap<String, LoadBalancingStrategy>,
     // HTTP 客户端
     http_client: Client,
-    // gRPC 客户端工厂
-    grpc_clients: RwLock<HashMap<String, Arc<dyn crate::proxy::grpc_client::GrpcClientFactory + Send + Sync>>>,
+    // 各服务的专属上游状态（来自配置upstream_pools，未配置的服务共用http_client）
+    upstreams: HashMap<String, UpstreamState>,
+    // 出站gRPC通道池：通道按目标URL懒建连并跨请求复用，
+    // 转码路径与登录聚合处理器共用同一个池
+    grpc_pool: crate::proxy::channel_pool::GrpcChannelPool,
+    // 端点级熔断器：隔离中的端点在实例选择时被移出候选池
+    breakers: crate::circuit_breaker::CircuitBreakerRegistry,
 }
 
 impl ServiceProxy {
     /// 创建新的服务代理
     pub async fn new() -> Self {
         let config = CONFIG.read().await;
-        
+
         // 创建服务发现
-        let service_discovery = Arc::new(ServiceDiscovery::new(&config.consul_url));
-        
+        let service_discovery = Arc::new(
+            ServiceDiscovery::new(&config.consul_url)
+                .with_cache_ttl(Duration::from_secs(config.service_cache_ttl_secs)),
+        );
+
+        // 解析各服务的负载均衡策略
+        let mut strategies = HashMap::new();
+        for (service, name) in &config.lb_strategy {
+            match LoadBalancingStrategy::from_name(name) {
+                Some(strategy) => {
+                    strategies.insert(service.clone(), strategy);
+                }
+                None => warn!("服务 {} 配置了未知的负载均衡策略 {}，使用Weighted", service, name),
+            }
+        }
+
         // 创建HTTP客户端
         let http_client = Client::builder()
             .timeout(Duration::from_secs(30))
             .pool_max_idle_per_host(100)
             .build()
             .unwrap_or_default();
-        
+
+        // 为配置了上游连接池的服务创建专属客户端，连接池与并发配额互不影响
+        let upstreams = config
+            .upstream_pools
+            .iter()
+            .map(|(service, pool)| (service.clone(), UpstreamState::from_config(pool)))
+            .collect();
+
+        // 端点级熔断器与全局熔断配置共用参数
+        let breakers = crate::circuit_breaker::CircuitBreakerRegistry::new(
+            config.circuit_breaker.enabled,
+            config.circuit_breaker.failure_threshold,
+            Duration::from_secs(config.circuit_breaker.half_open_timeout_secs),
+        );
+
+        // 出站gRPC通道池，后台任务周期探测并逐出失活通道
+        let grpc_pool =
+            crate::proxy::channel_pool::GrpcChannelPool::new(service_discovery.clone());
+        grpc_pool
+            .start_health_check_task(Duration::from_secs(GRPC_POOL_HEALTH_CHECK_INTERVAL_SECS));
+
         Self {
             service_discovery,
+            load_balancer: Arc::new(crate::proxy::load_balancer::LoadBalancer::new()),
+            strategies,
             http_client,
-            grpc_clients: RwLock::new(HashMap::new()),
+            upstreams,
+            grpc_pool,
+            breakers,
         }
     }
-    
+
+    /// 服务使用的HTTP客户端：配置了上游连接池的服务用专属客户端，其余共用默认客户端
+    fn client_for(&self, service_name: &str) -> &Client {
+        self.upstreams
+            .get(service_name)
+            .map(|u| &u.client)
+            .unwrap_or(&self.http_client)
+    }
+
+    /// 尝试获取服务的在途请求许可
+    ///
+    /// 未配置max_in_flight的服务返回Ok(None)不受限制；
+    /// 配额耗尽时返回Err，调用方直接以503拒绝，避免慢服务积压请求
+    fn try_acquire_upstream_permit(
+        &self,
+        service_name: &str,
+    ) -> Result<Option<tokio::sync::OwnedSemaphorePermit>, ()> {
+        match self.upstreams.get(service_name).and_then(|u| u.permits.clone()) {
+            Some(permits) => match permits.try_acquire_owned() {
+                Ok(permit) => Ok(Some(permit)),
+                Err(_) => Err(()),
+            },
+            None => Ok(None),
+        }
+    }
+
+    /// 服务配置的负载均衡策略（未配置时为Weighted）
+    fn strategy_for(&self, service_name: &str) -> LoadBalancingStrategy {
+        self.strategies
+            .get(service_name)
+            .copied()
+            .unwrap_or(LoadBalancingStrategy::Weighted)
+    }
+
+    /// 选择后端实例
+    ///
+    /// RoundRobin/LeastConnections由LoadBalancer在健康实例中选择并计入在途请求，
+    /// 其余策略沿用ServiceDiscovery的（加权）随机选择。
+    async fn select_backend(
+        &self,
+        service_name: &str,
+        strategy: LoadBalancingStrategy,
+    ) -> Result<String, DiscoveryError> {
+        match strategy {
+            LoadBalancingStrategy::RoundRobin | LoadBalancingStrategy::LeastConnections => {
+                let addresses = self.service_discovery.discover_service(service_name).await?;
+
+                // 管理端摘除（权重0）与熔断隔离中的实例不参与选择；
+                // 全部被摘除时退回完整列表，避免整体不可用
+                let overrides = self.service_discovery.get_instance_weights().await;
+                let mut candidates: Vec<String> = addresses
+                    .iter()
+                    .filter(|(url, weight)| overrides.get(url).copied().unwrap_or(*weight) > 0)
+                    .filter(|(url, _)| !self.breakers.is_quarantined(service_name, url))
+                    .map(|(url, _)| url.clone())
+                    .collect();
+                if candidates.is_empty() {
+                    candidates = addresses.iter().map(|(url, _)| url.clone()).collect();
+                }
+
+                self.load_balancer
+                    .pick(service_name, strategy, &candidates)
+                    .ok_or_else(|| DiscoveryError::NoHealthyInstances(service_name.to_string()))
+            }
+            _ => {
+                // （加权）随机选择可能命中熔断隔离中的端点，重选几次避开；
+                // 仍未避开（如仅剩隔离端点）时放行该端点作为恢复探测
+                let mut url = self.service_discovery.get_service_url(service_name).await?;
+                for _ in 0..3 {
+                    if !self.breakers.is_quarantined(service_name, &url) {
+                        break;
+                    }
+                    url = self.service_discovery.get_service_url(service_name).await?;
+                }
+                Ok(url)
+            }
+        }
+    }
+
     /// 转发请求到后端服务
     pub async fn forward_request(&self, req: Request<Body>, service_type: &ServiceType) -> Response<Body> {
         // 获取目标服务名称
         let service_name = self.get_service_name(service_type);
-        
+        let strategy = self.strategy_for(&service_name);
+        // 负载均衡器仅跟踪自己选出的实例
+        let balanced = matches!(
+            strategy,
+            LoadBalancingStrategy::RoundRobin | LoadBalancingStrategy::LeastConnections
+        );
+
         // 获取目标服务地址
-        match self.service_discovery.get_service_url(&service_name).await {
-            Ok(service_url) => {
-                debug!("转发请求到服务: {}", service_url);
-                
-                // 根据服务类型选择转发方式
-                match service_type {
-                    ServiceType::HttpService(_) | ServiceType::Auth | ServiceType::User | ServiceType::Friend | ServiceType::Group | ServiceType::Static | ServiceType::Chat => {
-                        // 转发HTTP请求
-                        self.forward_http_request(req, &service_url).await
-                    },
-                    ServiceType::GrpcService(_) => {
-                        // 转发gRPC请求
-                        self.forward_grpc_request(req, &service_url).await
-                    },
-                }
-            },
+        let service_url = match self.select_backend(&service_name, strategy).await {
+            Ok(url) => url,
+            Err(DiscoveryError::NoHealthyInstances(name)) => {
+                error!("服务 {} 没有健康实例", name);
+
+                // 服务已注册但全部实例不健康，明确提示
+                return (
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    axum::Json(serde_json::json!({
+                        "error": "no_healthy_instances",
+                        "message": format!("服务 {} 当前没有健康实例，请稍后重试", name)
+                    }))
+                ).into_response();
+            }
             Err(e) => {
                 error!("无法获取服务地址: {}", e);
-                
+
                 // 返回服务不可用错误
-                (
+                return (
                     StatusCode::SERVICE_UNAVAILABLE,
                     axum::Json(serde_json::json!({
                         "error": "service_unavailable",
                         "message": format!("服务暂时不可用: {}", service_name)
                     }))
-                ).into_response()
+                ).into_response();
             }
+        };
+
+        debug!("转发请求到服务: {}", service_url);
+
+        // 上游转发耗时从实例选定后开始计
+        let upstream_start = std::time::Instant::now();
+
+        // 根据服务类型选择转发方式
+        match service_type {
+            // Chat路径上的WebSocket升级请求走WS代理
+            ServiceType::Chat if crate::proxy::ws_proxy::is_websocket_upgrade(&req) => {
+                let path = req.uri().path().to_string();
+                let path_query = req
+                    .uri()
+                    .path_and_query()
+                    .map(|v| v.as_str())
+                    .unwrap_or("")
+                    .to_string();
+                let backend_ws_url = format!(
+                    "{}{}",
+                    service_url.replacen("http", "ws", 1),
+                    path_query
+                );
+                // 路由规则要求认证时，升级前先做JWT校验
+                let require_auth = CONFIG
+                    .read()
+                    .await
+                    .routes
+                    .routes
+                    .iter()
+                    .find(|r| path.starts_with(&r.path_prefix))
+                    .map(|r| r.require_auth)
+                    .unwrap_or(false);
+                let response =
+                    crate::proxy::ws_proxy::proxy_websocket(req, backend_ws_url, require_auth).await;
+                crate::metrics::observe_upstream_duration(
+                    &service_name,
+                    response.status(),
+                    upstream_start.elapsed(),
+                );
+                self.report_to_balancer(balanced, &service_url, &response);
+                response
+            },
+            ServiceType::GrpcService(_) => {
+                // 转发gRPC请求（单次尝试，无跨实例重试）
+                let span = tracing::info_span!(
+                    "upstream_attempt",
+                    service = %service_name,
+                    url = %service_url,
+                    attempt = 1usize
+                );
+                let response = self.forward_grpc_request(req, &service_url).instrument(span).await;
+                let outcome = if response.status() == StatusCode::BAD_GATEWAY {
+                    "error"
+                } else {
+                    "ok"
+                };
+                record_upstream_attempt(&service_name, 1, outcome);
+                record_upstream_outcome(&service_name, 1, outcome);
+                crate::metrics::observe_upstream_duration(
+                    &service_name,
+                    response.status(),
+                    upstream_start.elapsed(),
+                );
+                if outcome == "error" {
+                    self.breakers.record_failure(&service_name, &service_url);
+                } else {
+                    self.breakers.record_success(&service_name, &service_url);
+                }
+                self.report_to_balancer(balanced, &service_url, &response);
+                response
+            },
+            ServiceType::HttpService(_) | ServiceType::Auth | ServiceType::User | ServiceType::Friend | ServiceType::Group | ServiceType::Static | ServiceType::Chat => {
+                // 启用了响应缓存的路由先查缓存，命中则完全不回源
+                let cache_rule = {
+                    let config = CONFIG.read().await;
+                    config
+                        .routes
+                        .routes
+                        .iter()
+                        .find(|r| req.uri().path().starts_with(&r.path_prefix))
+                        .and_then(|r| {
+                            r.cache_ttl_secs
+                                .map(|ttl| (ttl, r.cache_methods.clone(), r.path_prefix.clone()))
+                        })
+                };
+                if let Some((ttl_secs, cache_methods, route_prefix)) = cache_rule {
+                    // 方法白名单为空时默认只缓存GET
+                    let method_cacheable = if cache_methods.is_empty() {
+                        req.method() == axum::http::Method::GET
+                    } else {
+                        cache_methods
+                            .iter()
+                            .any(|m| m.eq_ignore_ascii_case(req.method().as_str()))
+                    };
+                    if method_cacheable {
+                        let response = self
+                            .forward_http_cached(
+                                req,
+                                &service_name,
+                                strategy,
+                                balanced,
+                                service_url,
+                                ttl_secs,
+                                &route_prefix,
+                            )
+                            .await;
+                        crate::metrics::observe_upstream_duration(
+                            &service_name,
+                            response.status(),
+                            upstream_start.elapsed(),
+                        );
+                        return response;
+                    }
+                }
+
+                // 转发HTTP请求（连接类失败时跨实例重试）
+                let response = self
+                    .forward_http_with_retry(req, &service_name, strategy, balanced, service_url)
+                    .await;
+                crate::metrics::observe_upstream_duration(
+                    &service_name,
+                    response.status(),
+                    upstream_start.elapsed(),
+                );
+                response
+            },
         }
     }
-    
+
+    /// 把转发结果回报给端点熔断器：5xx视为端点故障，其余视为成功
+    fn record_to_breaker(&self, service_name: &str, service_url: &str, response: &Response<Body>) {
+        if response.status().is_server_error() {
+            self.breakers.record_failure(service_name, service_url);
+        } else {
+            self.breakers.record_success(service_name, service_url);
+        }
+    }
+
+    /// 把转发结果回报给负载均衡器：
+    /// 后端连接失败在转发内部被映射为502，以此作为失败信号
+    fn report_to_balancer(&self, balanced: bool, service_url: &str, response: &Response<Body>) {
+        if balanced {
+            if response.status() == StatusCode::BAD_GATEWAY {
+                self.load_balancer.report_failure(service_url);
+            } else {
+                self.load_balancer.report_success(service_url);
+            }
+        }
+    }
+
+    /// 带响应缓存的HTTP转发
+    ///
+    /// 缓存键包含方法、完整路径和认证身份；客户端带
+    /// `Cache-Control: no-cache`时跳过缓存命中但仍会回填。
+    /// 只缓存2xx、上游头允许且响应体不超过上限的响应，
+    /// 其余响应原样透传。
+    #[allow(clippy::too_many_arguments)]
+    async fn forward_http_cached(
+        &self,
+        req: Request<Body>,
+        service_name: &str,
+        strategy: LoadBalancingStrategy,
+        balanced: bool,
+        service_url: String,
+        ttl_secs: u64,
+        route_prefix: &str,
+    ) -> Response<Body> {
+        let identity = req
+            .extensions()
+            .get::<UserInfo>()
+            .map(|u| u.user_id.to_string());
+        let path_query = req
+            .uri()
+            .path_and_query()
+            .map(|v| v.as_str().to_string())
+            .unwrap_or_else(|| req.uri().path().to_string());
+        let key = crate::proxy::cache::cache_key(
+            req.method().as_str(),
+            &path_query,
+            identity.as_deref(),
+        );
+
+        if !crate::proxy::cache::client_no_cache(req.headers()) {
+            if let Some(hit) = crate::proxy::cache::get(&key) {
+                metrics::counter!("gateway.cache.hits", "route" => route_prefix.to_string())
+                    .increment(1);
+                debug!("响应缓存命中: {}", key);
+                return hit.into_response();
+            }
+        }
+        metrics::counter!("gateway.cache.misses", "route" => route_prefix.to_string()).increment(1);
+
+        let response = self
+            .forward_http_with_retry(req, service_name, strategy, balanced, service_url)
+            .await;
+
+        // 回填缓存：仅2xx、上游头允许且长度已知不超上限的响应
+        let cacheable = response.status().is_success()
+            && crate::proxy::cache::upstream_cacheable(response.headers())
+            && response
+                .headers()
+                .get(axum::http::header::CONTENT_LENGTH)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.parse::<usize>().ok())
+                .is_some_and(|len| len <= crate::proxy::cache::MAX_CACHEABLE_BODY);
+        if !cacheable {
+            return response;
+        }
+
+        let (parts, body) = response.into_parts();
+        let body_bytes =
+            match axum::body::to_bytes(body, crate::proxy::cache::MAX_CACHEABLE_BODY).await {
+                Ok(bytes) => bytes,
+                Err(e) => {
+                    // 读流失败时响应已不可恢复，按网关错误返回
+                    error!("缓存回填时读取响应体失败: {}", e);
+                    return (
+                        StatusCode::BAD_GATEWAY,
+                        axum::Json(serde_json::json!({
+                            "error": "bad_gateway",
+                            "message": "读取后端响应失败"
+                        })),
+                    )
+                        .into_response();
+                }
+            };
+
+        crate::proxy::cache::store(
+            key,
+            crate::proxy::cache::CachedResponse::new(
+                parts.status,
+                parts.headers.clone(),
+                body_bytes.clone(),
+                Duration::from_secs(ttl_secs),
+            ),
+        );
+
+        let mut response = Response::from_parts(parts, Body::from(body_bytes));
+        response
+            .headers_mut()
+            .insert("x-cache", axum::http::HeaderValue::from_static("MISS"));
+        response
+    }
+
+    /// 带跨实例重试的HTTP转发
+    ///
+    /// 连接类失败时把故障实例从发现缓存中摘除并改选其他实例重试，
+    /// 次数上限取网关配置的retry.max_retries。非幂等方法仅在失败明确
+    /// 发生于连接建立阶段（尚未向后端发出任何字节）时才允许重试。
+    ///
+    /// 请求体长度已知且不超过[`MAX_BUFFERED_BODY`]时先读入内存，
+    /// 重试可以重发；大文件上传和chunked请求体改为流式透传，
+    /// 内存占用有界，但流只能消费一次，因此不参与重试。
+    async fn forward_http_with_retry(
+        &self,
+        req: Request<Body>,
+        service_name: &str,
+        strategy: LoadBalancingStrategy,
+        balanced: bool,
+        first_url: String,
+    ) -> Response<Body> {
+        // 在途请求许可：配置了max_in_flight的服务饱和后直接503，
+        // 防止单个慢服务占满网关资源。许可持有到响应头返回为止，
+        // 响应体的流式传输不计入配额
+        let _permit = match self.try_acquire_upstream_permit(service_name) {
+            Ok(permit) => permit,
+            Err(()) => {
+                metrics::counter!(
+                    "gateway.upstream.saturated",
+                    "service" => service_name.to_string()
+                )
+                .increment(1);
+                warn!("服务 {} 在途请求已达上限，拒绝新请求", service_name);
+                return (
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    axum::Json(serde_json::json!({
+                        "error": "upstream_saturated",
+                        "message": format!("服务 {} 当前请求过多，请稍后重试", service_name)
+                    })),
+                )
+                    .into_response();
+            }
+        };
+
+        let max_retries = CONFIG.read().await.retry.max_retries;
+        let (parts, body) = req.into_parts();
+
+        let content_length = parts
+            .headers
+            .get(axum::http::header::CONTENT_LENGTH)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<u64>().ok());
+        let buffer = match content_length {
+            Some(len) => len as usize <= MAX_BUFFERED_BODY,
+            // 长度未知：带请求体的方法按chunked上传流式处理，
+            // 其余方法缓冲出空体，保留跨实例重试能力
+            None => !matches!(parts.method.as_str(), "POST" | "PUT" | "PATCH"),
+        };
+
+        if !buffer {
+            // 流式单次转发：失败后请求体已部分消费，无法重放
+            let stream_body = reqwest::Body::wrap_stream(body.into_data_stream());
+            let span = tracing::info_span!(
+                "upstream_attempt",
+                service = %service_name,
+                url = %first_url,
+                attempt = 1usize
+            );
+            return match self
+                .forward_http_request(&parts, stream_body, service_name, &first_url)
+                .instrument(span)
+                .await
+            {
+                Ok(response) => {
+                    record_upstream_attempt(service_name, 1, "ok");
+                    record_upstream_outcome(service_name, 1, "ok");
+                    self.record_to_breaker(service_name, &first_url, &response);
+                    if balanced {
+                        self.load_balancer.report_success(&first_url);
+                    }
+                    response
+                }
+                Err(failure) => {
+                    record_upstream_attempt(service_name, 1, "error");
+                    record_upstream_outcome(service_name, 1, "error");
+                    self.breakers.record_failure(service_name, &first_url);
+                    if balanced {
+                        self.load_balancer.report_failure(&first_url);
+                    }
+                    self.service_discovery.evict_instance(service_name, &first_url);
+                    (
+                        StatusCode::BAD_GATEWAY,
+                        axum::Json(serde_json::json!({
+                            "error": "bad_gateway",
+                            "message": format!("无法转发请求到后端服务: {}", failure.message)
+                        }))
+                    ).into_response()
+                }
+            };
+        }
+
+        // 读取请求体，供重试时重发
+        let body_bytes = axum::body::to_bytes(body, MAX_BUFFERED_BODY).await.unwrap_or_default();
+        let idempotent = matches!(
+            parts.method.as_str(),
+            "GET" | "HEAD" | "PUT" | "DELETE" | "OPTIONS"
+        );
+
+        let mut service_url = first_url;
+        let mut tried: Vec<String> = Vec::new();
+        loop {
+            let attempt = tried.len() + 1;
+            let span = tracing::info_span!(
+                "upstream_attempt",
+                service = %service_name,
+                url = %service_url,
+                attempt
+            );
+            match self
+                .forward_http_request(
+                    &parts,
+                    reqwest::Body::from(body_bytes.clone()),
+                    service_name,
+                    &service_url,
+                )
+                .instrument(span)
+                .await
+            {
+                Ok(response) => {
+                    record_upstream_attempt(service_name, attempt, "ok");
+                    record_upstream_outcome(service_name, attempt, "ok");
+                    self.record_to_breaker(service_name, &service_url, &response);
+                    if balanced {
+                        self.load_balancer.report_success(&service_url);
+                    }
+                    return response;
+                }
+                Err(failure) => {
+                    record_upstream_attempt(service_name, attempt, "error");
+                    self.breakers.record_failure(service_name, &service_url);
+                    if balanced {
+                        self.load_balancer.report_failure(&service_url);
+                    }
+                    // 故障实例从发现缓存摘除，TTL内不再被选中
+                    self.service_discovery.evict_instance(service_name, &service_url);
+                    tried.push(service_url.clone());
+
+                    let may_retry = if idempotent {
+                        failure.retryable
+                    } else {
+                        failure.connect_stage
+                    };
+                    if may_retry && tried.len() <= max_retries {
+                        if let Some(next) = self.pick_untried(service_name, strategy, &tried).await {
+                            metrics::counter!(
+                                "gateway.upstream.retries",
+                                "service" => service_name.to_string()
+                            )
+                            .increment(1);
+                            warn!(
+                                "实例 {} 连接失败，改用实例 {} 重试 ({}/{})",
+                                service_url,
+                                next,
+                                tried.len(),
+                                max_retries
+                            );
+                            service_url = next;
+                            continue;
+                        }
+                    }
+
+                    record_upstream_outcome(service_name, attempt, "error");
+                    return (
+                        StatusCode::BAD_GATEWAY,
+                        axum::Json(serde_json::json!({
+                            "error": "bad_gateway",
+                            "message": format!("无法转发请求到后端服务: {}", failure.message)
+                        }))
+                    ).into_response();
+                }
+            }
+        }
+    }
+
+    /// 在尚未尝试过的实例中另选一个
+    async fn pick_untried(
+        &self,
+        service_name: &str,
+        strategy: LoadBalancingStrategy,
+        tried: &[String],
+    ) -> Option<String> {
+        // 随机类策略可能重复命中已失败实例，先重选几次
+        for _ in 0..4 {
+            if let Ok(url) = self.select_backend(service_name, strategy).await {
+                if !tried.contains(&url) {
+                    return Some(url);
+                }
+            }
+        }
+        // 仍未命中时从完整列表中线性找一个未试过的，
+        // 优先避开熔断隔离中的端点，实在没有时才兜底使用
+        let addresses = self.service_discovery.discover_service(service_name).await.ok()?;
+        let untried: Vec<String> = addresses
+            .into_iter()
+            .map(|(url, _)| url)
+            .filter(|url| !tried.contains(url))
+            .collect();
+        untried
+            .iter()
+            .find(|url| !self.breakers.is_quarantined(service_name, url))
+            .cloned()
+            .or_else(|| untried.into_iter().next())
+    }
+
+    /// 端点级熔断器注册表（供/admin/circuit-breakers巡检端点使用）
+    pub fn circuit_breakers(&self) -> crate::circuit_breaker::CircuitBreakerRegistry {
+        self.breakers.clone()
+    }
+
+    /// 获取服务发现实例（供管理端点调整实例权重）
+    pub fn service_discovery(&self) -> Arc<ServiceDiscovery> {
+        self.service_discovery.clone()
+    }
+
+    /// 获取出站gRPC通道池（登录聚合处理器与代理共用通道缓存）
+    pub fn grpc_pool(&self) -> crate::proxy::channel_pool::GrpcChannelPool {
+        self.grpc_pool.clone()
+    }
+
     /// 从服务类型获取服务名称
     fn get_service_name(&self, service_type: &ServiceType) -> String {
         match service_type {
@@ -212,13 +1112,23 @@ impl ServiceProxy {
     }
     
     /// 转发HTTP请求
-    async fn forward_http_request(&self, req: Request<Body>, service_url: &str) -> Response<Body> {
+    ///
+    /// 请求体与响应体均流式透传，不整体缓冲；
+    /// 连接类失败以`ForwardError`返回，由调用方决定是否换实例重试
+    async fn forward_http_request(
+        &self,
+        parts: &axum::http::request::Parts,
+        body: reqwest::Body,
+        service_name: &str,
+        service_url: &str,
+    ) -> Result<Response<Body>, ForwardError> {
+        let client = self.client_for(service_name);
         // 获取配置
         let config = CONFIG.read().await;
         
         // 获取路径
-        let path = req.uri().path().to_string();
-        let path_query = req.uri().path_and_query().map(|v| v.as_str()).unwrap_or(&path);
+        let path = parts.uri.path().to_string();
+        let path_query = parts.uri.path_and_query().map(|v| v.as_str()).unwrap_or(&path);
         
         // 查找匹配的路由规则
         let route_rule = config.routes.routes.iter()
@@ -240,45 +1150,90 @@ impl ServiceProxy {
         
         debug!("转发HTTP请求: {} -> {}", path, target_url);
         
-        // 创建新的请求
-        let (parts, body) = req.into_parts();
-        
-        // 读取请求体
-        let body_bytes = axum::body::to_bytes(body, 1024 * 1024 * 10).await.unwrap_or_default();
-        
         // 创建reqwest请求
         let mut client_req = match parts.method.as_str() {
-            "GET" => self.http_client.get(&target_url),
-            "POST" => self.http_client.post(&target_url).body(body_bytes),
-            "PUT" => self.http_client.put(&target_url).body(body_bytes),
-            "DELETE" => self.http_client.delete(&target_url),
-            "PATCH" => self.http_client.patch(&target_url).body(body_bytes),
-            "HEAD" => self.http_client.head(&target_url),
-            "OPTIONS" => self.http_client.request(reqwest::Method::OPTIONS, &target_url),
+            "GET" => client.get(&target_url),
+            "POST" => client.post(&target_url).body(body),
+            "PUT" => client.put(&target_url).body(body),
+            "DELETE" => client.delete(&target_url),
+            "PATCH" => client.patch(&target_url).body(body),
+            "HEAD" => client.head(&target_url),
+            "OPTIONS" => client.request(reqwest::Method::OPTIONS, &target_url),
             _ => {
-                return (
+                return Ok((
                     StatusCode::METHOD_NOT_ALLOWED,
                     axum::Json(serde_json::json!({
                         "error": "method_not_allowed",
                         "message": format!("不支持的HTTP方法: {}", parts.method)
                     }))
-                ).into_response();
+                ).into_response());
             }
         };
         
         // 转发请求头
-        for (name, value) in parts.headers {
-            if let Some(name) = name {
-                // 忽略一些特定的头
-                if name.as_str() == "host" || name.as_str() == "content-length" {
-                    continue;
-                }
-                
-                if let Ok(value) = value.to_str() {
-                    client_req = client_req.header(name.as_str(), value);
+        let mut headers = reqwest::header::HeaderMap::new();
+        for (name, value) in parts.headers.iter() {
+            // 忽略一些特定的头
+            if name.as_str() == "host" || name.as_str() == "content-length" {
+                continue;
+            }
+
+            headers.insert(name.clone(), value.clone());
+        }
+
+        // 应用路由配置的请求头重写（值为空表示删除该头）
+        if let Some(rule) = route_rule {
+            for (name, value) in &rule.rewrite_headers {
+                let header_name = match reqwest::header::HeaderName::from_bytes(name.as_bytes()) {
+                    Ok(n) => n,
+                    Err(_) => {
+                        warn!("无效的重写请求头名称: {}", name);
+                        continue;
+                    }
+                };
+
+                if value.is_empty() {
+                    headers.remove(&header_name);
+                } else if let Ok(header_value) = reqwest::header::HeaderValue::from_str(value) {
+                    headers.insert(header_name, header_value);
+                } else {
+                    warn!("无效的重写请求头值: {}={}", name, value);
                 }
             }
         }
+
+        // 传播W3C trace上下文，保证链路在网关边界不中断
+        // 当前处于OTel span内时注入其上下文（traceparent/tracestate），
+        // 后端服务以网关span为父继续链路；未启用OTel时退回原有策略：
+        // 上游带了合法的traceparent则原样透传，否则生成新的trace
+        let span_cx = tracing::Span::current().context();
+        if span_cx.span().span_context().is_valid() {
+            opentelemetry::global::get_text_map_propagator(|p| {
+                p.inject_context(&span_cx, &mut crate::tracing_setup::HeaderInjector(&mut headers))
+            });
+        } else {
+            let traceparent = headers
+                .get("traceparent")
+                .and_then(|v| v.to_str().ok())
+                .filter(|v| is_valid_traceparent(v))
+                .map(|v| v.to_string())
+                .unwrap_or_else(new_traceparent);
+            if let Ok(value) = reqwest::header::HeaderValue::from_str(&traceparent) {
+                headers.insert("traceparent", value);
+            }
+        }
+
+        // 请求ID：沿用上游的X-Request-ID，否则生成新的
+        let request_id = headers
+            .get("x-request-id")
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.to_string())
+            .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+        if let Ok(value) = reqwest::header::HeaderValue::from_str(&request_id) {
+            headers.insert("x-request-id", value);
+        }
+
+        client_req = client_req.headers(headers);
         
         // 从请求扩展获取用户信息，并添加到请求头中
         if let Some(user_info) = parts.extensions.get::<UserInfo>() {
@@ -298,24 +1253,33 @@ impl ServiceProxy {
         client_req = client_req.header("X-Original-Path", path);
         client_req = client_req.header("X-Original-Method", parts.method.as_str());
         
-        // 发送请求
-        match client_req.send().await {
+        // 发送请求，按后端端点打点请求数、耗时与结果
+        crate::metrics::record_backend_request(service_name, service_url);
+        let send_start = std::time::Instant::now();
+        let mut response = match client_req.send().await {
             Ok(resp) => {
+                crate::metrics::observe_backend_response(
+                    service_name,
+                    service_url,
+                    resp.status(),
+                    send_start.elapsed(),
+                );
                 // 构建响应
                 let mut builder = Response::builder()
                     .status(resp.status());
-                
-                // 转发响应头
+
+                // 转发响应头（transfer-encoding由本端hyper按实际分帧重新生成，
+                // content-length原样保留）
                 let headers = builder.headers_mut().unwrap();
                 for (name, value) in resp.headers() {
+                    if name == reqwest::header::TRANSFER_ENCODING {
+                        continue;
+                    }
                     headers.insert(name, value.clone());
                 }
-                
-                // 读取响应体
-                let body_bytes = resp.bytes().await.unwrap_or_default();
-                
-                // 构建响应
-                builder.body(Body::from(body_bytes)).unwrap_or_else(|_| {
+
+                // 响应体流式透传，大文件下载不在网关整体缓冲
+                builder.body(Body::from_stream(resp.bytes_stream())).unwrap_or_else(|_| {
                     Response::builder()
                         .status(StatusCode::INTERNAL_SERVER_ERROR)
                         .body(Body::from("无法构建响应"))
@@ -324,22 +1288,29 @@ impl ServiceProxy {
             },
             Err(e) => {
                 error!("转发HTTP请求失败: {}", e);
-                
-                (
-                    StatusCode::BAD_GATEWAY,
-                    axum::Json(serde_json::json!({
-                        "error": "bad_gateway",
-                        "message": format!("无法转发请求到后端服务: {}", e)
-                    }))
-                ).into_response()
+                crate::metrics::record_backend_connect_error(service_name, service_url);
+
+                return Err(ForwardError {
+                    message: e.to_string(),
+                    connect_stage: e.is_connect(),
+                    retryable: e.is_connect() || e.is_timeout(),
+                });
             }
+        };
+
+        // 把请求ID带回响应头，便于客户端与日志关联排查
+        if let Ok(value) = axum::http::HeaderValue::from_str(&request_id) {
+            response.headers_mut().insert("x-request-id", value);
         }
+
+        Ok(response)
     }
     
     /// 转发gRPC请求
     async fn forward_grpc_request(&self, req: Request<Body>, service_url: &str) -> Response<Body> {
-        // 使用GenericGrpcClientFactory处理gRPC请求
-        let factory = crate::proxy::grpc_client::GenericGrpcClientFactory::new();
+        // 使用GenericGrpcClientFactory处理gRPC请求，通道取自共享池
+        let factory =
+            crate::proxy::grpc_client::GenericGrpcClientFactory::new(self.grpc_pool.clone());
         factory.forward_request(req, service_url.to_string()).await
     }
     
@@ -360,7 +1331,8 @@ impl ServiceProxy {
     /// 添加shutdown方法
     pub async fn shutdown(&self) {
         info!("准备关闭服务代理...");
-        // 清理资源或关闭连接的代码
+        // 优雅断开所有代理中的WebSocket连接
+        crate::proxy::ws_proxy::shutdown_all();
     }
 }
 
@@ -369,8 +1341,1139 @@ impl Clone for ServiceProxy {
     fn clone(&self) -> Self {
         Self {
             service_discovery: self.service_discovery.clone(),
+            load_balancer: self.load_balancer.clone(),
+            strategies: self.strategies.clone(),
             http_client: self.http_client.clone(),
-            grpc_clients: RwLock::new(HashMap::new()),
+            // 克隆共享并发许可（Arc），各克隆实例计入同一配额
+            upstreams: self.upstreams.clone(),
+            // 共享同一个通道池，已建立的gRPC通道在克隆间继续复用
+            grpc_pool: self.grpc_pool.clone(),
+            breakers: self.breakers.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::routes_config::RouteRule;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use axum::routing::get;
+    use axum::Router;
+
+    // 启动一个模拟Consul：健康查询始终返回空列表（所有实例均为critical），
+    // 并记录被请求的次数
+    async fn start_mock_consul(hits: Arc<AtomicUsize>) -> String {
+        let app = Router::new().route(
+            "/v1/health/service/{name}",
+            get(move || {
+                let hits = hits.clone();
+                async move {
+                    hits.fetch_add(1, Ordering::SeqCst);
+                    "[]"
+                }
+            }),
+        );
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        format!("http://{}", addr)
+    }
+
+    #[tokio::test]
+    async fn test_all_critical_returns_no_healthy_instances() {
+        let hits = Arc::new(AtomicUsize::new(0));
+        let consul_url = start_mock_consul(hits.clone()).await;
+        let discovery = ServiceDiscovery::new(&consul_url);
+
+        let err = discovery.discover_service("user-service").await.unwrap_err();
+        assert_eq!(
+            err,
+            DiscoveryError::NoHealthyInstances("user-service".to_string())
+        );
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+
+        // 负面缓存生效期间不再请求Consul
+        let err = discovery.discover_service("user-service").await.unwrap_err();
+        assert_eq!(
+            err,
+            DiscoveryError::NoHealthyInstances("user-service".to_string())
+        );
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_lru_cache_hit_and_ttl_expiry() {
+        // Consul不可达：新鲜缓存应直接命中，过期后才会尝试重新拉取
+        let discovery = ServiceDiscovery::new("http://127.0.0.1:1");
+        let entry = vec![("http://10.0.0.1:7001".to_string(), DEFAULT_INSTANCE_WEIGHT)];
+
+        // 新鲜条目：TTL内命中，不访问Consul
+        discovery.cache.lock().unwrap().put(
+            "user-service".to_string(),
+            (entry.clone(), std::time::Instant::now()),
+        );
+        let addresses = discovery.discover_service("user-service").await.unwrap();
+        assert_eq!(addresses, entry);
+
+        // 过期条目：触发重新拉取，Consul不可达时返回错误而不是旧数据
+        let stale_birth = std::time::Instant::now()
+            .checked_sub(Duration::from_secs(60))
+            .unwrap();
+        discovery
+            .cache
+            .lock()
+            .unwrap()
+            .put("user-service".to_string(), (entry, stale_birth));
+        let err = discovery.discover_service("user-service").await.unwrap_err();
+        assert!(matches!(err, DiscoveryError::Other(_)));
+    }
+
+    #[tokio::test]
+    async fn test_drained_instance_receives_no_new_traffic() {
+        let instance_a = "http://127.0.0.1:7001".to_string();
+        let instance_b = "http://127.0.0.1:7002".to_string();
+
+        let discovery = ServiceDiscovery::new("http://127.0.0.1:1");
+        // 预填服务缓存，避免依赖真实Consul
+        discovery.services.write().await.insert(
+            "user-service".to_string(),
+            vec![
+                (instance_a.clone(), DEFAULT_INSTANCE_WEIGHT),
+                (instance_b.clone(), DEFAULT_INSTANCE_WEIGHT),
+            ],
+        );
+
+        // 摘除实例A，新请求应全部落到实例B
+        discovery.set_instance_weight(&instance_a, 0).await;
+        for _ in 0..50 {
+            let url = discovery.get_service_url("user-service").await.unwrap();
+            assert_eq!(url, instance_b);
+        }
+
+        // 恢复权重后实例A重新接流
+        discovery.set_instance_weight(&instance_a, DEFAULT_INSTANCE_WEIGHT).await;
+        let mut seen_a = false;
+        for _ in 0..200 {
+            if discovery.get_service_url("user-service").await.unwrap() == instance_a {
+                seen_a = true;
+                break;
+            }
+        }
+        assert!(seen_a, "恢复权重后实例A应重新收到流量");
+    }
+
+    #[tokio::test]
+    async fn test_all_drained_falls_back_to_full_list() {
+        let instance_a = "http://127.0.0.1:7003".to_string();
+
+        let discovery = ServiceDiscovery::new("http://127.0.0.1:1");
+        discovery
+            .services
+            .write()
+            .await
+            .insert(
+                "user-service".to_string(),
+                vec![(instance_a.clone(), DEFAULT_INSTANCE_WEIGHT)],
+            );
+
+        // 全部实例被摘除时退回完整列表，避免服务完全不可用
+        discovery.set_instance_weight(&instance_a, 0).await;
+        let url = discovery.get_service_url("user-service").await.unwrap();
+        assert_eq!(url, instance_a);
+    }
+
+    #[tokio::test]
+    async fn test_discover_parses_service_meta_weight() {
+        // 模拟Consul：一个金丝雀实例weight=10，一个未设置权重的实例
+        let app = Router::new().route(
+            "/v1/health/service/{name}",
+            get(|| async {
+                r#"[
+                    {"Service": {"Address": "10.0.0.1", "Port": 7001, "Meta": {"weight": "10"}}},
+                    {"Service": {"Address": "10.0.0.2", "Port": 7002}}
+                ]"#
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let consul_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        let discovery = ServiceDiscovery::new(&consul_url);
+        let instances = discovery.discover_service("user-service").await.unwrap();
+        assert_eq!(
+            instances,
+            vec![
+                ("http://10.0.0.1:7001".to_string(), 10),
+                ("http://10.0.0.2:7002".to_string(), DEFAULT_INSTANCE_WEIGHT),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_weighted_random_respects_weights() {
+        // 10/90分流：金丝雀实例的命中率应明显低于存量实例
+        let canary = "http://127.0.0.1:7001".to_string();
+        let stable = "http://127.0.0.1:7002".to_string();
+        let picker =
+            WeightedRandom::new(&[(canary.clone(), 10), (stable.clone(), 90)]).unwrap();
+
+        let mut canary_hits = 0;
+        for _ in 0..10_000 {
+            if picker.pick() == canary {
+                canary_hits += 1;
+            }
+        }
+
+        // 期望值1000，给足余量避免偶发失败
+        assert!(
+            (500..=1500).contains(&canary_hits),
+            "金丝雀命中{}次，应接近10%",
+            canary_hits
+        );
+    }
+
+    #[test]
+    fn test_weighted_random_skips_zero_weight_and_empty() {
+        let picker = WeightedRandom::new(&[
+            ("http://127.0.0.1:7001".to_string(), 0),
+            ("http://127.0.0.1:7002".to_string(), 50),
+        ])
+        .unwrap();
+        for _ in 0..100 {
+            assert_eq!(picker.pick(), "http://127.0.0.1:7002");
+        }
+
+        // 总权重为0时无法构建
+        assert!(WeightedRandom::new(&[("http://127.0.0.1:7001".to_string(), 0)]).is_none());
+        assert!(WeightedRandom::new(&[]).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_rewrite_headers_applied_before_forwarding() {
+        use crate::config::routes_config::{RouteRule, ServiceType};
+
+        // 模拟后端：把收到的请求头原样返回
+        let backend = Router::new().route(
+            "/api/hdr-test/echo",
+            get(|headers: axum::http::HeaderMap| async move {
+                let map: serde_json::Map<String, serde_json::Value> = headers
+                    .iter()
+                    .map(|(k, v)| {
+                        (
+                            k.as_str().to_string(),
+                            serde_json::Value::from(v.to_str().unwrap_or_default()),
+                        )
+                    })
+                    .collect();
+                axum::Json(serde_json::Value::Object(map))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let backend_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            axum::serve(listener, backend).await.unwrap();
+        });
+
+        // 注入带重写规则的路由：注入X-Forwarded-Proto，删除Cookie
+        {
+            let mut config = CONFIG.write().await;
+            config.routes.routes.push(RouteRule {
+                id: "hdr-test".to_string(),
+                name: "hdr-test".to_string(),
+                path_prefix: "/api/hdr-test".to_string(),
+                service_type: ServiceType::HttpService("hdr-test".to_string()),
+                require_auth: false,
+                require_nonce: false,
+                required_roles: vec![],
+                required_countries: vec![],
+                methods: vec![],
+                rewrite_headers: HashMap::from([
+                    ("x-forwarded-proto".to_string(), "https".to_string()),
+                    ("cookie".to_string(), String::new()),
+                ]),
+                cache_ttl_secs: None,
+                cache_methods: vec![],
+                path_rewrite: None,
+            });
+        }
+
+        let proxy = ServiceProxy {
+            service_discovery: Arc::new(ServiceDiscovery::new("http://127.0.0.1:1")),
+            load_balancer: Arc::new(crate::proxy::load_balancer::LoadBalancer::new()),
+            strategies: HashMap::new(),
+            http_client: Client::new(),
+            upstreams: HashMap::new(),
+            grpc_pool: crate::proxy::channel_pool::GrpcChannelPool::new(Arc::new(
+                ServiceDiscovery::new("http://127.0.0.1:1"),
+            )),
+            breakers: crate::circuit_breaker::CircuitBreakerRegistry::new(
+                true,
+                3,
+                Duration::from_secs(60),
+            ),
+        };
+
+        let req = Request::builder()
+            .uri("/api/hdr-test/echo")
+            .header("cookie", "session=secret")
+            .header("x-custom", "kept")
+            .body(Body::empty())
+            .unwrap();
+        let (parts, _) = req.into_parts();
+        let resp = proxy
+            .forward_http_request(&parts, reqwest::Body::default(), "test-service", &backend_url)
+            .await
+            .unwrap();
+
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(resp.into_body(), 4096).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["x-forwarded-proto"], "https");
+        assert_eq!(json["x-custom"], "kept");
+        assert!(json.get("cookie").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_websocket_proxy_echo() {
+        use axum::extract::ws::{Message as WsMessage, WebSocketUpgrade};
+        use axum::routing::any;
+        use futures::{SinkExt, StreamExt};
+        use tokio_tungstenite::tungstenite::Message as TMessage;
+
+        // echo后端：收到什么帧回什么帧
+        let backend = Router::new().route(
+            "/ws/chat",
+            get(|ws: WebSocketUpgrade| async move {
+                ws.on_upgrade(|mut socket| async move {
+                    while let Some(Ok(msg)) = socket.recv().await {
+                        if matches!(msg, WsMessage::Close(_)) {
+                            break;
+                        }
+                        if socket.send(msg).await.is_err() {
+                            break;
+                        }
+                    }
+                })
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let backend_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            axum::serve(listener, backend).await.unwrap();
+        });
+
+        // 网关：预填服务缓存，Chat路径走WS代理
+        let discovery = ServiceDiscovery::new("http://127.0.0.1:1");
+        discovery
+            .services
+            .write()
+            .await
+            .insert(
+                "chat-service".to_string(),
+                vec![(backend_url, DEFAULT_INSTANCE_WEIGHT)],
+            );
+        let proxy = Arc::new(ServiceProxy {
+            service_discovery: Arc::new(discovery),
+            load_balancer: Arc::new(crate::proxy::load_balancer::LoadBalancer::new()),
+            strategies: HashMap::new(),
+            http_client: Client::new(),
+            upstreams: HashMap::new(),
+            grpc_pool: crate::proxy::channel_pool::GrpcChannelPool::new(Arc::new(
+                ServiceDiscovery::new("http://127.0.0.1:1"),
+            )),
+            breakers: crate::circuit_breaker::CircuitBreakerRegistry::new(
+                true,
+                3,
+                Duration::from_secs(60),
+            ),
+        });
+        let gateway = Router::new().route(
+            "/ws/chat",
+            any(move |req: Request<Body>| {
+                let proxy = proxy.clone();
+                async move { proxy.forward_request(req, &ServiceType::Chat).await }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let gateway_addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, gateway).await.unwrap();
+        });
+
+        // 客户端通过网关连接，文本和二进制帧应原样回显
+        let (mut ws, _) =
+            tokio_tungstenite::connect_async(format!("ws://{}/ws/chat", gateway_addr))
+                .await
+                .unwrap();
+
+        ws.send(TMessage::Text("hello".into())).await.unwrap();
+        let echoed = ws.next().await.unwrap().unwrap();
+        assert_eq!(echoed, TMessage::Text("hello".into()));
+
+        ws.send(TMessage::Binary(vec![1u8, 2, 3].into())).await.unwrap();
+        let echoed = ws.next().await.unwrap().unwrap();
+        assert_eq!(echoed, TMessage::Binary(vec![1u8, 2, 3].into()));
+
+        ws.close(None).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_websocket_auth_required_route() {
+        use crate::auth::jwt::Claims;
+        use crate::config::routes_config::RouteRule;
+        use axum::extract::ws::{Message as WsMessage, WebSocketUpgrade};
+        use axum::routing::any;
+        use futures::{SinkExt, StreamExt};
+        use jsonwebtoken::{encode, EncodingKey, Header};
+        use tokio_tungstenite::tungstenite::Message as TMessage;
+
+        // echo后端
+        let backend = Router::new().route(
+            "/ws/secure",
+            get(|ws: WebSocketUpgrade| async move {
+                ws.on_upgrade(|mut socket| async move {
+                    while let Some(Ok(msg)) = socket.recv().await {
+                        if matches!(msg, WsMessage::Close(_)) {
+                            break;
+                        }
+                        if socket.send(msg).await.is_err() {
+                            break;
+                        }
+                    }
+                })
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let backend_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            axum::serve(listener, backend).await.unwrap();
+        });
+
+        // 注入要求认证的WS路由
+        let secret = {
+            let mut config = CONFIG.write().await;
+            config.routes.routes.push(RouteRule {
+                id: "ws-secure".to_string(),
+                name: "ws-secure".to_string(),
+                path_prefix: "/ws/secure".to_string(),
+                service_type: ServiceType::Chat,
+                require_auth: true,
+                require_nonce: false,
+                required_roles: vec![],
+                required_countries: vec![],
+                methods: vec![],
+                rewrite_headers: HashMap::new(),
+                cache_ttl_secs: None,
+                cache_methods: vec![],
+                path_rewrite: None,
+            });
+            config.auth.jwt.secret.clone()
+        };
+
+        let discovery = ServiceDiscovery::new("http://127.0.0.1:1");
+        discovery.services.write().await.insert(
+            "chat-service".to_string(),
+            vec![(backend_url, DEFAULT_INSTANCE_WEIGHT)],
+        );
+        let proxy = Arc::new(ServiceProxy {
+            service_discovery: Arc::new(discovery),
+            load_balancer: Arc::new(crate::proxy::load_balancer::LoadBalancer::new()),
+            strategies: HashMap::new(),
+            http_client: Client::new(),
+            upstreams: HashMap::new(),
+            grpc_pool: crate::proxy::channel_pool::GrpcChannelPool::new(Arc::new(
+                ServiceDiscovery::new("http://127.0.0.1:1"),
+            )),
+            breakers: crate::circuit_breaker::CircuitBreakerRegistry::new(
+                true,
+                3,
+                Duration::from_secs(60),
+            ),
+        });
+        let gateway_proxy = proxy.clone();
+        let gateway = Router::new().route(
+            "/ws/secure",
+            any(move |req: Request<Body>| {
+                let proxy = gateway_proxy.clone();
+                async move { proxy.forward_request(req, &ServiceType::Chat).await }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let gateway_addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, gateway).await.unwrap();
+        });
+
+        // 无token的升级请求被拒绝为401，不触达后端
+        let err = tokio_tungstenite::connect_async(format!("ws://{}/ws/secure", gateway_addr))
+            .await
+            .unwrap_err();
+        match err {
+            tokio_tungstenite::tungstenite::Error::Http(resp) => {
+                assert_eq!(resp.status().as_u16(), 401);
+            }
+            other => panic!("预期HTTP 401错误，实际: {:?}", other),
+        }
+
+        // 带有效token（查询参数）时正常建立连接并回显
+        let now = crate::auth::jwt::current_timestamp();
+        let token = encode(
+            &Header::default(),
+            &Claims {
+                sub: "42".to_string(),
+                iss: None,
+                exp: now + 3600,
+                iat: now,
+                username: "tester".to_string(),
+                roles: vec![],
+                extra: HashMap::new(),
+            },
+            &EncodingKey::from_secret(secret.as_bytes()),
+        )
+        .unwrap();
+        let (mut ws, _) = tokio_tungstenite::connect_async(format!(
+            "ws://{}/ws/secure?token={}",
+            gateway_addr, token
+        ))
+        .await
+        .unwrap();
+
+        ws.send(TMessage::Text("secure hello".into())).await.unwrap();
+        let echoed = ws.next().await.unwrap().unwrap();
+        assert_eq!(echoed, TMessage::Text("secure hello".into()));
+
+        ws.close(None).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_traceparent_propagated_to_backend() {
+        // 模拟后端：把收到的请求头原样返回
+        let backend = Router::new().route(
+            "/api/trace/echo",
+            get(|headers: axum::http::HeaderMap| async move {
+                let map: serde_json::Map<String, serde_json::Value> = headers
+                    .iter()
+                    .map(|(k, v)| {
+                        (
+                            k.as_str().to_string(),
+                            serde_json::Value::from(v.to_str().unwrap_or_default()),
+                        )
+                    })
+                    .collect();
+                axum::Json(serde_json::Value::Object(map))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let backend_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            axum::serve(listener, backend).await.unwrap();
+        });
+
+        let proxy = ServiceProxy {
+            service_discovery: Arc::new(ServiceDiscovery::new("http://127.0.0.1:1")),
+            load_balancer: Arc::new(crate::proxy::load_balancer::LoadBalancer::new()),
+            strategies: HashMap::new(),
+            http_client: Client::new(),
+            upstreams: HashMap::new(),
+            grpc_pool: crate::proxy::channel_pool::GrpcChannelPool::new(Arc::new(
+                ServiceDiscovery::new("http://127.0.0.1:1"),
+            )),
+            breakers: crate::circuit_breaker::CircuitBreakerRegistry::new(
+                true,
+                3,
+                Duration::from_secs(60),
+            ),
+        };
+
+        // 上游没有traceparent时，网关应生成合法的traceparent并带上X-Request-ID
+        let req = Request::builder()
+            .uri("/api/trace/echo")
+            .body(Body::empty())
+            .unwrap();
+        let (parts, _) = req.into_parts();
+        let resp = proxy
+            .forward_http_request(&parts, reqwest::Body::default(), "test-service", &backend_url)
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let request_id = resp
+            .headers()
+            .get("x-request-id")
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.to_string())
+            .expect("响应应带有x-request-id");
+        let body = axum::body::to_bytes(resp.into_body(), 4096).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let traceparent = json["traceparent"].as_str().unwrap();
+        assert!(is_valid_traceparent(traceparent), "非法的traceparent: {}", traceparent);
+        assert_eq!(json["x-request-id"], serde_json::Value::from(request_id));
+
+        // 上游带了合法的traceparent时原样透传
+        let upstream = "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01";
+        let req = Request::builder()
+            .uri("/api/trace/echo")
+            .header("traceparent", upstream)
+            .body(Body::empty())
+            .unwrap();
+        let (parts, _) = req.into_parts();
+        let resp = proxy
+            .forward_http_request(&parts, reqwest::Body::default(), "test-service", &backend_url)
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(resp.into_body(), 4096).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["traceparent"], upstream);
+    }
+
+    #[tokio::test]
+    async fn test_no_healthy_instances_maps_to_503() {
+        let hits = Arc::new(AtomicUsize::new(0));
+        let consul_url = start_mock_consul(hits).await;
+
+        let proxy = ServiceProxy {
+            service_discovery: Arc::new(ServiceDiscovery::new(&consul_url)),
+            load_balancer: Arc::new(crate::proxy::load_balancer::LoadBalancer::new()),
+            strategies: HashMap::new(),
+            http_client: Client::new(),
+            upstreams: HashMap::new(),
+            grpc_pool: crate::proxy::channel_pool::GrpcChannelPool::new(Arc::new(
+                ServiceDiscovery::new("http://127.0.0.1:1"),
+            )),
+            breakers: crate::circuit_breaker::CircuitBreakerRegistry::new(
+                true,
+                3,
+                Duration::from_secs(60),
+            ),
+        };
+
+        let req = Request::builder()
+            .uri("/api/users/me")
+            .body(Body::empty())
+            .unwrap();
+        let resp = proxy.forward_request(req, &ServiceType::User).await;
+
+        assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let body = axum::body::to_bytes(resp.into_body(), 1024).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["error"], "no_healthy_instances");
+    }
+
+    #[tokio::test]
+    async fn test_retries_on_alternate_instance_when_backend_down() {
+        // 存活后端
+        let hits = Arc::new(AtomicUsize::new(0));
+        let hits_clone = hits.clone();
+        let backend = Router::new().route(
+            "/ok",
+            get(move || {
+                let hits = hits_clone.clone();
+                async move {
+                    hits.fetch_add(1, Ordering::SeqCst);
+                    "ok"
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let live_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            axum::serve(listener, backend).await.unwrap();
+        });
+
+        // 死实例：占用端口后立即释放，连接会被拒绝
+        let dead_listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let dead_url = format!("http://{}", dead_listener.local_addr().unwrap());
+        drop(dead_listener);
+
+        let discovery = ServiceDiscovery::new("http://127.0.0.1:1");
+        discovery.services.write().await.insert(
+            "retry-test".to_string(),
+            vec![
+                (dead_url, DEFAULT_INSTANCE_WEIGHT),
+                (live_url, DEFAULT_INSTANCE_WEIGHT),
+            ],
+        );
+        let proxy = ServiceProxy {
+            service_discovery: Arc::new(discovery),
+            load_balancer: Arc::new(crate::proxy::load_balancer::LoadBalancer::new()),
+            strategies: HashMap::new(),
+            http_client: Client::new(),
+            upstreams: HashMap::new(),
+            grpc_pool: crate::proxy::channel_pool::GrpcChannelPool::new(Arc::new(
+                ServiceDiscovery::new("http://127.0.0.1:1"),
+            )),
+            breakers: crate::circuit_breaker::CircuitBreakerRegistry::new(
+                true,
+                3,
+                Duration::from_secs(60),
+            ),
+        };
+
+        // 无论首选实例是否为死实例，重试都应落在存活实例上
+        for _ in 0..5 {
+            let req = Request::builder().uri("/ok").body(Body::empty()).unwrap();
+            let resp = proxy
+                .forward_request(req, &ServiceType::HttpService("retry-test".to_string()))
+                .await;
+            assert_eq!(resp.status(), StatusCode::OK);
+        }
+        assert_eq!(hits.load(Ordering::SeqCst), 5);
+    }
+
+    #[tokio::test]
+    async fn test_second_attempt_success_records_two_attempts() {
+        // 存活后端
+        let backend = Router::new().route("/ok", get(|| async { "ok" }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let live_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            axum::serve(listener, backend).await.unwrap();
+        });
+
+        // 死实例：占用端口后立即释放，连接会被拒绝
+        let dead_listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let dead_url = format!("http://{}", dead_listener.local_addr().unwrap());
+        drop(dead_listener);
+
+        let discovery = ServiceDiscovery::new("http://127.0.0.1:1");
+        discovery.services.write().await.insert(
+            "attempt-test".to_string(),
+            vec![
+                (dead_url.clone(), DEFAULT_INSTANCE_WEIGHT),
+                (live_url, DEFAULT_INSTANCE_WEIGHT),
+            ],
+        );
+        let proxy = ServiceProxy {
+            service_discovery: Arc::new(discovery),
+            load_balancer: Arc::new(crate::proxy::load_balancer::LoadBalancer::new()),
+            strategies: HashMap::new(),
+            http_client: Client::new(),
+            upstreams: HashMap::new(),
+            grpc_pool: crate::proxy::channel_pool::GrpcChannelPool::new(Arc::new(
+                ServiceDiscovery::new("http://127.0.0.1:1"),
+            )),
+            breakers: crate::circuit_breaker::CircuitBreakerRegistry::new(
+                true,
+                3,
+                Duration::from_secs(60),
+            ),
+        };
+
+        // 首选实例固定为死实例，第二次尝试落在存活实例上
+        let req = Request::builder().uri("/ok").body(Body::empty()).unwrap();
+        let resp = proxy
+            .forward_http_with_retry(
+                req,
+                "attempt-test",
+                LoadBalancingStrategy::Random,
+                false,
+                dead_url,
+            )
+            .await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        // 两次尝试各记录一次：第1次失败、第2次成功
+        let attempts: Vec<_> = UPSTREAM_ATTEMPTS
+            .lock()
+            .iter()
+            .filter(|(service, _, _)| service == "attempt-test")
+            .cloned()
+            .collect();
+        assert_eq!(attempts.len(), 2);
+        assert_eq!(attempts[0], ("attempt-test".to_string(), 1, "error"));
+        assert_eq!(attempts[1], ("attempt-test".to_string(), 2, "ok"));
+    }
+
+    #[tokio::test]
+    async fn test_route_cache_serves_hits_without_forwarding() {
+        // 后端每次被击中都返回递增的计数
+        let hits = Arc::new(AtomicUsize::new(0));
+        let hits_clone = hits.clone();
+        let backend = Router::new().route(
+            "/cache-test/info",
+            get(move || {
+                let hits = hits_clone.clone();
+                async move { format!("count-{}", hits.fetch_add(1, Ordering::SeqCst)) }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let backend_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            axum::serve(listener, backend).await.unwrap();
+        });
+
+        // 注入启用响应缓存的路由
+        {
+            let mut config = CONFIG.write().await;
+            config.routes.routes.push(RouteRule {
+                id: "cache-test".to_string(),
+                name: "cache-test".to_string(),
+                path_prefix: "/cache-test".to_string(),
+                service_type: ServiceType::HttpService("cache-test".to_string()),
+                require_auth: false,
+                require_nonce: false,
+                required_roles: vec![],
+                required_countries: vec![],
+                methods: vec![],
+                rewrite_headers: HashMap::new(),
+                cache_ttl_secs: Some(60),
+                cache_methods: vec![],
+                path_rewrite: None,
+            });
+        }
+
+        let discovery = ServiceDiscovery::new("http://127.0.0.1:1");
+        discovery.services.write().await.insert(
+            "cache-test".to_string(),
+            vec![(backend_url, DEFAULT_INSTANCE_WEIGHT)],
+        );
+        let proxy = ServiceProxy {
+            service_discovery: Arc::new(discovery),
+            load_balancer: Arc::new(crate::proxy::load_balancer::LoadBalancer::new()),
+            strategies: HashMap::new(),
+            http_client: Client::new(),
+            upstreams: HashMap::new(),
+            grpc_pool: crate::proxy::channel_pool::GrpcChannelPool::new(Arc::new(
+                ServiceDiscovery::new("http://127.0.0.1:1"),
+            )),
+            breakers: crate::circuit_breaker::CircuitBreakerRegistry::new(
+                true,
+                3,
+                Duration::from_secs(60),
+            ),
+        };
+        let service_type = ServiceType::HttpService("cache-test".to_string());
+
+        // 首次请求回源，响应标记MISS
+        let req = Request::builder()
+            .uri("/cache-test/info")
+            .body(Body::empty())
+            .unwrap();
+        let resp = proxy.forward_request(req, &service_type).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers().get("x-cache").unwrap(), "MISS");
+        let body = axum::body::to_bytes(resp.into_body(), 1024).await.unwrap();
+        assert_eq!(&body[..], b"count-0");
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+
+        // 第二次请求命中缓存，不再回源
+        let req = Request::builder()
+            .uri("/cache-test/info")
+            .body(Body::empty())
+            .unwrap();
+        let resp = proxy.forward_request(req, &service_type).await;
+        assert_eq!(resp.headers().get("x-cache").unwrap(), "HIT");
+        let body = axum::body::to_bytes(resp.into_body(), 1024).await.unwrap();
+        assert_eq!(&body[..], b"count-0");
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+
+        // 客户端no-cache绕过缓存命中，重新回源
+        let req = Request::builder()
+            .uri("/cache-test/info")
+            .header("cache-control", "no-cache")
+            .body(Body::empty())
+            .unwrap();
+        let resp = proxy.forward_request(req, &service_type).await;
+        let body = axum::body::to_bytes(resp.into_body(), 1024).await.unwrap();
+        assert_eq!(&body[..], b"count-1");
+        assert_eq!(hits.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_streams_large_bodies_through_proxy() {
+        use futures::StreamExt;
+
+        const CHUNK: usize = 1024 * 1024;
+        const CHUNKS: usize = 100;
+
+        // 后端：上传侧按流消费并返回收到的字节数，下载侧流式吐100MB
+        let backend = Router::new()
+            .route(
+                "/upload",
+                axum::routing::post(|req: Request<Body>| async move {
+                    let mut stream = req.into_body().into_data_stream();
+                    let mut total = 0usize;
+                    while let Some(chunk) = stream.next().await {
+                        total += chunk.unwrap().len();
+                    }
+                    total.to_string()
+                }),
+            )
+            .route(
+                "/download",
+                get(|| async {
+                    let chunk = axum::body::Bytes::from(vec![0x5au8; CHUNK]);
+                    Body::from_stream(futures::stream::iter(
+                        (0..CHUNKS).map(move |_| Ok::<_, std::convert::Infallible>(chunk.clone())),
+                    ))
+                }),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let backend_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            axum::serve(listener, backend).await.unwrap();
+        });
+
+        let discovery = ServiceDiscovery::new("http://127.0.0.1:1");
+        discovery.services.write().await.insert(
+            "stream-test".to_string(),
+            vec![(backend_url, DEFAULT_INSTANCE_WEIGHT)],
+        );
+        let proxy = ServiceProxy {
+            service_discovery: Arc::new(discovery),
+            load_balancer: Arc::new(crate::proxy::load_balancer::LoadBalancer::new()),
+            strategies: HashMap::new(),
+            http_client: Client::new(),
+            upstreams: HashMap::new(),
+            grpc_pool: crate::proxy::channel_pool::GrpcChannelPool::new(Arc::new(
+                ServiceDiscovery::new("http://127.0.0.1:1"),
+            )),
+            breakers: crate::circuit_breaker::CircuitBreakerRegistry::new(
+                true,
+                3,
+                Duration::from_secs(60),
+            ),
+        };
+
+        // 上传：100MB、长度未知（chunked）的请求体应流式到达后端
+        let chunk = axum::body::Bytes::from(vec![0xa5u8; CHUNK]);
+        let upload_body = Body::from_stream(futures::stream::iter(
+            (0..CHUNKS).map(move |_| Ok::<_, std::convert::Infallible>(chunk.clone())),
+        ));
+        let req = Request::builder()
+            .method("POST")
+            .uri("/upload")
+            .body(upload_body)
+            .unwrap();
+        let resp = proxy
+            .forward_request(req, &ServiceType::HttpService("stream-test".to_string()))
+            .await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(resp.into_body(), 64).await.unwrap();
+        assert_eq!(
+            String::from_utf8_lossy(&body),
+            (CHUNK * CHUNKS).to_string()
+        );
+
+        // 下载：按流消费响应体，总量应为100MB
+        let req = Request::builder().uri("/download").body(Body::empty()).unwrap();
+        let resp = proxy
+            .forward_request(req, &ServiceType::HttpService("stream-test".to_string()))
+            .await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let mut stream = resp.into_body().into_data_stream();
+        let mut total = 0usize;
+        while let Some(chunk) = stream.next().await {
+            total += chunk.unwrap().len();
+        }
+        assert_eq!(total, CHUNK * CHUNKS);
+    }
+
+    #[tokio::test]
+    async fn test_upstream_limits_are_isolated_per_service() {
+        // 慢后端：挂起直到收到放行信号；快后端：立即返回
+        let (release_tx, release_rx) = tokio::sync::watch::channel(false);
+        let slow_backend = Router::new().route(
+            "/slow",
+            get(move || {
+                let mut release = release_rx.clone();
+                async move {
+                    while !*release.borrow_and_update() {
+                        if release.changed().await.is_err() {
+                            break;
+                        }
+                    }
+                    "slow-ok"
+                }
+            }),
+        );
+        let fast_backend = Router::new().route("/fast", get(|| async { "fast-ok" }));
+
+        let slow_listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let slow_url = format!("http://{}", slow_listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            axum::serve(slow_listener, slow_backend).await.unwrap();
+        });
+        let fast_listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let fast_url = format!("http://{}", fast_listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            axum::serve(fast_listener, fast_backend).await.unwrap();
+        });
+
+        let discovery = ServiceDiscovery::new("http://127.0.0.1:1");
+        discovery.services.write().await.insert(
+            "slow-upstream".to_string(),
+            vec![(slow_url, DEFAULT_INSTANCE_WEIGHT)],
+        );
+        discovery.services.write().await.insert(
+            "fast-upstream".to_string(),
+            vec![(fast_url, DEFAULT_INSTANCE_WEIGHT)],
+        );
+
+        // 仅慢服务配置在途请求上限，快服务使用独立的默认配置
+        let mut upstreams = HashMap::new();
+        upstreams.insert(
+            "slow-upstream".to_string(),
+            UpstreamState::from_config(&crate::config::UpstreamPoolConfig {
+                max_in_flight: 2,
+                ..Default::default()
+            }),
+        );
+        upstreams.insert(
+            "fast-upstream".to_string(),
+            UpstreamState::from_config(&crate::config::UpstreamPoolConfig::default()),
+        );
+        let proxy = Arc::new(ServiceProxy {
+            service_discovery: Arc::new(discovery),
+            load_balancer: Arc::new(crate::proxy::load_balancer::LoadBalancer::new()),
+            strategies: HashMap::new(),
+            http_client: Client::new(),
+            upstreams,
+            grpc_pool: crate::proxy::channel_pool::GrpcChannelPool::new(Arc::new(
+                ServiceDiscovery::new("http://127.0.0.1:1"),
+            )),
+            breakers: crate::circuit_breaker::CircuitBreakerRegistry::new(
+                true,
+                3,
+                Duration::from_secs(60),
+            ),
+        });
+
+        // 4个并发请求打向慢服务：2个占满配额挂起，2个被直接503拒绝
+        let mut slow_tasks = Vec::new();
+        for _ in 0..4 {
+            let proxy = proxy.clone();
+            slow_tasks.push(tokio::spawn(async move {
+                let req = Request::builder().uri("/slow").body(Body::empty()).unwrap();
+                proxy
+                    .forward_request(req, &ServiceType::HttpService("slow-upstream".to_string()))
+                    .await
+                    .status()
+            }));
+        }
+        // 等待在途请求到达后端并占满配额
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        // 慢服务饱和期间，快服务的请求不受影响
+        for _ in 0..3 {
+            let req = Request::builder().uri("/fast").body(Body::empty()).unwrap();
+            let resp = proxy
+                .forward_request(req, &ServiceType::HttpService("fast-upstream".to_string()))
+                .await;
+            assert_eq!(resp.status(), StatusCode::OK);
+        }
+
+        // 放行慢后端，配额内的2个请求正常完成，超出的2个已被503拒绝
+        release_tx.send(true).unwrap();
+        let mut statuses: Vec<StatusCode> = Vec::new();
+        for task in slow_tasks {
+            statuses.push(task.await.unwrap());
+        }
+        statuses.sort();
+        assert_eq!(
+            statuses,
+            vec![
+                StatusCode::OK,
+                StatusCode::OK,
+                StatusCode::SERVICE_UNAVAILABLE,
+                StatusCode::SERVICE_UNAVAILABLE
+            ]
+        );
+
+        // 配额随请求完成释放，慢服务恢复可用
+        let req = Request::builder().uri("/slow").body(Body::empty()).unwrap();
+        let resp = proxy
+            .forward_request(req, &ServiceType::HttpService("slow-upstream".to_string()))
+            .await;
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_dynamic_route_added_without_rebuild() {
+        use http_body_util::BodyExt;
+        use tower::ServiceExt;
+
+        // 启动模拟后端
+        let backend = Router::new().route("/api/dyn-test/ping", get(|| async { "pong" }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let backend_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            axum::serve(listener, backend).await.unwrap();
+        });
+
+        // Consul不可达，直接注入服务实例
+        let discovery = ServiceDiscovery::new("http://127.0.0.1:1");
+        discovery.services.write().await.insert(
+            "dyn-test".to_string(),
+            vec![(backend_url, DEFAULT_INSTANCE_WEIGHT)],
+        );
+        let proxy = Arc::new(ServiceProxy {
+            service_discovery: Arc::new(discovery),
+            load_balancer: Arc::new(crate::proxy::load_balancer::LoadBalancer::new()),
+            strategies: HashMap::new(),
+            http_client: Client::new(),
+            upstreams: HashMap::new(),
+            grpc_pool: crate::proxy::channel_pool::GrpcChannelPool::new(Arc::new(
+                ServiceDiscovery::new("http://127.0.0.1:1"),
+            )),
+            breakers: crate::circuit_breaker::CircuitBreakerRegistry::new(
+                true,
+                3,
+                Duration::from_secs(60),
+            ),
+        });
+
+        // 构建一次路由器，此时配置中还没有该前缀的路由规则
+        let app = crate::router::RouterBuilder::new(proxy).build().await.unwrap();
+        let request = || {
+            Request::builder()
+                .uri("/api/dyn-test/ping")
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        // 未配置的前缀返回404
+        let response = app.clone().oneshot(request()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        // 向全局配置追加新路由（等效于配置文件热更新），不重建Router
+        {
+            let mut config = crate::config::CONFIG.write().await;
+            config.routes.routes.push(RouteRule {
+                id: "dyn-test".to_string(),
+                name: "dyn-test".to_string(),
+                path_prefix: "/api/dyn-test".to_string(),
+                service_type: ServiceType::HttpService("dyn-test".to_string()),
+                require_auth: false,
+                require_nonce: false,
+                required_roles: vec![],
+                required_countries: vec![],
+                methods: vec![],
+                rewrite_headers: HashMap::new(),
+                cache_ttl_secs: None,
+                cache_methods: vec![],
+                path_rewrite: None,
+            });
+        }
+
+        // 同一个Router实例立即开始代理新前缀
+        let response = app.clone().oneshot(request()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(&body[..], b"pong");
+
+        // 删除路由后同样立即生效
+        {
+            let mut config = crate::config::CONFIG.write().await;
+            config.routes.routes.retain(|r| r.id != "dyn-test");
         }
+        let response = app.oneshot(request()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
     }
 }
\ No newline at end of file
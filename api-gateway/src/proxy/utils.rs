@@ -1,32 +1,77 @@
+use once_cell::sync::Lazy;
 use regex::Regex;
+use std::collections::HashMap;
+use std::sync::RwLock;
 use crate::config::routes_config::PathRewrite;
-use tracing::debug;
+use tracing::{debug, warn};
 use hyper::http::{self, header::HeaderValue};
 
-/// 应用路径重写规则
-pub fn apply_path_rewrite(path: &str, path_prefix: &str, rewrite: &PathRewrite) -> String {
-    let mut result = path.to_string();
-    
-    // 应用前缀替换
-    if let Some(replace_prefix) = &rewrite.replace_prefix {
-        if path.starts_with(path_prefix) {
-            result = format!("{}{}", replace_prefix, &path[path_prefix.len()..]);
-            debug!("应用前缀替换: {} -> {}", path, result);
+/// 已编译正则的进程级缓存，按pattern索引
+///
+/// 正则在首次使用时编译一次，之后直接复用；路由热更新引入的
+/// 新pattern按需补充编译，旧条目保留（pattern数量有限，不做淘汰）
+static REGEX_CACHE: Lazy<RwLock<HashMap<String, Regex>>> = Lazy::new(Default::default);
+
+/// 取缓存的已编译正则，缺失时编译并缓存
+///
+/// 非法pattern在配置加载时已被拒绝（见RoutesConfig::validate），
+/// 这里返回None仅是兜底，不会在正常请求路径上出现
+fn cached_regex(pattern: &str) -> Option<Regex> {
+    if let Some(re) = REGEX_CACHE.read().unwrap().get(pattern) {
+        return Some(re.clone());
+    }
+    match Regex::new(pattern) {
+        Ok(re) => {
+            REGEX_CACHE
+                .write()
+                .unwrap()
+                .insert(pattern.to_string(), re.clone());
+            Some(re)
+        }
+        Err(err) => {
+            warn!("路径重写正则编译失败，跳过重写: {}: {}", pattern, err);
+            None
         }
     }
-    
-    // 应用正则替换
+}
+
+/// 应用路径重写规则
+///
+/// 只重写路径部分，查询串原样保留。前缀替换与正则替换同时配置时
+/// 正则优先；正则替换支持捕获组引用（如$1）
+pub fn apply_path_rewrite(path_query: &str, path_prefix: &str, rewrite: &PathRewrite) -> String {
+    // 查询串不参与重写
+    let (path, query) = match path_query.split_once('?') {
+        Some((path, query)) => (path, Some(query)),
+        None => (path_query, None),
+    };
+
+    let mut result = path.to_string();
+
     if let (Some(regex_match), Some(regex_replace)) = (&rewrite.regex_match, &rewrite.regex_replace) {
-        if let Ok(re) = Regex::new(regex_match) {
-            let replaced = re.replace_all(&result, regex_replace).to_string();
+        // 正则替换，同时配置时优先于前缀替换
+        if let Some(re) = cached_regex(regex_match) {
+            let replaced = re.replace_all(&result, regex_replace.as_str()).to_string();
             if replaced != result {
                 debug!("应用正则替换: {} -> {}", result, replaced);
                 result = replaced;
             }
         }
+    } else if let Some(replace_prefix) = &rewrite.replace_prefix {
+        // 前缀替换，拼接时避免产生双斜杠
+        if let Some(rest) = path.strip_prefix(path_prefix) {
+            result = match (replace_prefix.ends_with('/'), rest.starts_with('/')) {
+                (true, true) => format!("{}{}", replace_prefix, &rest[1..]),
+                _ => format!("{}{}", replace_prefix, rest),
+            };
+            debug!("应用前缀替换: {} -> {}", path, result);
+        }
+    }
+
+    match query {
+        Some(query) => format!("{}?{}", result, query),
+        None => result,
     }
-    
-    result
 }
 
 /// 提取服务类型
@@ -67,4 +112,70 @@ pub fn join_url(base: &str, path: &str) -> String {
         (false, false) => format!("{}/{}", base, path),
         _ => format!("{}{}", base, path),
     }
-} 
\ No newline at end of file
+} 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn regex_rewrite(pattern: &str, replace: &str) -> PathRewrite {
+        PathRewrite {
+            replace_prefix: None,
+            regex_match: Some(pattern.to_string()),
+            regex_replace: Some(replace.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_regex_rewrite_with_capture_group() {
+        let rewrite = regex_rewrite(r"^/api/users/(\d+)/avatar$", "/internal/avatars/$1");
+
+        assert_eq!(
+            apply_path_rewrite("/api/users/42/avatar", "/api/users", &rewrite),
+            "/internal/avatars/42"
+        );
+        // 不匹配的路径原样保留
+        assert_eq!(
+            apply_path_rewrite("/api/users/42/profile", "/api/users", &rewrite),
+            "/api/users/42/profile"
+        );
+    }
+
+    #[test]
+    fn test_regex_rewrite_keeps_query_string() {
+        let rewrite = regex_rewrite(r"^/api/users/(\d+)/avatar$", "/internal/avatars/$1");
+
+        // 查询串不参与匹配，重写后原样拼回
+        assert_eq!(
+            apply_path_rewrite("/api/users/42/avatar?size=64&v=2", "/api/users", &rewrite),
+            "/internal/avatars/42?size=64&v=2"
+        );
+    }
+
+    #[test]
+    fn test_regex_takes_precedence_over_prefix() {
+        let rewrite = PathRewrite {
+            replace_prefix: Some("/v2".to_string()),
+            regex_match: Some(r"^/api/users/(\d+)$".to_string()),
+            regex_replace: Some("/internal/users/$1".to_string()),
+        };
+
+        assert_eq!(
+            apply_path_rewrite("/api/users/7", "/api/users", &rewrite),
+            "/internal/users/7"
+        );
+    }
+
+    #[test]
+    fn test_prefix_rewrite_applies_without_regex() {
+        let rewrite = PathRewrite {
+            replace_prefix: Some("/".to_string()),
+            regex_match: None,
+            regex_replace: None,
+        };
+
+        assert_eq!(
+            apply_path_rewrite("/api/auth/login?next=home", "/api/auth", &rewrite),
+            "/login?next=home"
+        );
+    }
+}
@@ -0,0 +1,249 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use axum::{
+    body::Body,
+    extract::ws::{CloseFrame, Message, WebSocket, WebSocketUpgrade},
+    extract::FromRequestParts,
+    http::{header, Request, Response},
+    response::IntoResponse,
+};
+use futures::{SinkExt, StreamExt};
+use once_cell::sync::Lazy;
+use tokio_tungstenite::connect_async;
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+use tokio_tungstenite::tungstenite::protocol::CloseFrame as TCloseFrame;
+use tokio_tungstenite::tungstenite::Message as TMessage;
+use tracing::{debug, error, info, warn};
+
+use crate::auth::jwt::verify_token;
+use crate::config::CONFIG;
+
+/// 当前经网关代理的WebSocket连接数
+static OPEN_CONNECTIONS: AtomicUsize = AtomicUsize::new(0);
+
+/// 关闭广播：网关优雅退出时通知所有在途代理连接发送关闭帧
+static SHUTDOWN: Lazy<tokio::sync::watch::Sender<bool>> =
+    Lazy::new(|| tokio::sync::watch::channel(false).0);
+
+/// 判断请求是否为WebSocket升级请求
+pub fn is_websocket_upgrade(req: &Request<Body>) -> bool {
+    req.headers()
+        .get(header::UPGRADE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.eq_ignore_ascii_case("websocket"))
+}
+
+/// 当前代理中的WebSocket连接数
+pub fn open_connection_count() -> usize {
+    OPEN_CONNECTIONS.load(Ordering::SeqCst)
+}
+
+/// 通知所有代理连接优雅关闭（网关shutdown时调用）
+pub fn shutdown_all() {
+    let _ = SHUTDOWN.send(true);
+}
+
+/// 将客户端的WebSocket连接代理到后端服务
+///
+/// `require_auth`为true时先做JWT认证（token取Authorization头或
+/// `token`查询参数，浏览器WebSocket API无法自定义请求头），
+/// 再完成与客户端的协议升级并双向拷贝帧。
+pub async fn proxy_websocket(
+    req: Request<Body>,
+    backend_ws_url: String,
+    require_auth: bool,
+) -> Response<Body> {
+    let (mut parts, _body) = req.into_parts();
+
+    // 认证在协议升级前完成，未授权的连接不触达后端
+    if require_auth {
+        let config = CONFIG.read().await;
+        if config.auth.jwt.enabled {
+            let token = token_from_parts(&parts, &config.auth.jwt.header_name, &config.auth.jwt.header_prefix);
+            let user_info = match token {
+                Some(token) => verify_token(token, &config.auth.jwt).await,
+                None => Err(common::error::Error::Unauthorized),
+            };
+            match user_info {
+                Ok(user_info) => {
+                    debug!("WebSocket连接认证通过: user_id={}", user_info.user_id);
+                }
+                Err(e) => {
+                    warn!("WebSocket连接认证失败: {}", e);
+                    return e.into_response();
+                }
+            }
+        }
+    }
+
+    let upgrade = match WebSocketUpgrade::from_request_parts(&mut parts, &()).await {
+        Ok(upgrade) => upgrade,
+        Err(e) => {
+            error!("WebSocket升级失败: {}", e);
+            return e.into_response();
+        }
+    };
+
+    upgrade
+        .on_upgrade(move |client_ws| async move {
+            let open = OPEN_CONNECTIONS.fetch_add(1, Ordering::SeqCst) + 1;
+            metrics::gauge!("gateway.ws.open_connections").set(open as f64);
+
+            if let Err(e) = bridge(client_ws, &backend_ws_url).await {
+                error!("WebSocket代理出错: {}", e);
+            }
+
+            let open = OPEN_CONNECTIONS.fetch_sub(1, Ordering::SeqCst) - 1;
+            metrics::gauge!("gateway.ws.open_connections").set(open as f64);
+        })
+        .into_response()
+}
+
+/// 从Authorization头或`token`查询参数提取token
+fn token_from_parts(
+    parts: &axum::http::request::Parts,
+    header_name: &str,
+    header_prefix: &str,
+) -> Option<String> {
+    if let Some(token) = parts
+        .headers
+        .get(header_name)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix(header_prefix))
+    {
+        return Some(token.to_string());
+    }
+
+    parts.uri.query().and_then(|query| {
+        query.split('&').find_map(|pair| {
+            pair.strip_prefix("token=")
+                .filter(|t| !t.is_empty())
+                .map(|t| t.to_string())
+        })
+    })
+}
+
+/// 在客户端与后端之间双向转发WebSocket帧
+///
+/// 任一方向结束、客户端空闲超时或网关进入关闭流程时断开；
+/// 后两种情况会先向双方发送关闭帧。
+/// 空闲超时只按客户端方向计时：后端主动推送不代表客户端还活着。
+async fn bridge(client_ws: WebSocket, backend_ws_url: &str) -> anyhow::Result<()> {
+    let (backend_ws, _) = connect_async(backend_ws_url).await?;
+    debug!("已连接后端WebSocket: {}", backend_ws_url);
+
+    let idle_timeout = Duration::from_secs(CONFIG.read().await.ws_idle_timeout_secs);
+    let mut shutdown_rx = SHUTDOWN.subscribe();
+
+    let (mut backend_tx, mut backend_rx) = backend_ws.split();
+    let (mut client_tx, mut client_rx) = client_ws.split();
+
+    // 客户端 -> 后端；返回true表示因空闲超时退出
+    let client_to_backend = async {
+        loop {
+            let msg = match tokio::time::timeout(idle_timeout, client_rx.next()).await {
+                Ok(Some(Ok(msg))) => msg,
+                Ok(_) => return false,
+                Err(_) => return true,
+            };
+            let is_close = matches!(msg, Message::Close(_));
+            if backend_tx.send(client_msg_to_backend(msg)).await.is_err() {
+                return false;
+            }
+            if is_close {
+                return false;
+            }
+        }
+    };
+
+    // 后端 -> 客户端
+    let backend_to_client = async {
+        while let Some(Ok(msg)) = backend_rx.next().await {
+            let is_close = matches!(msg, TMessage::Close(_));
+            let Some(msg) = backend_msg_to_client(msg) else {
+                continue;
+            };
+            if client_tx.send(msg).await.is_err() {
+                break;
+            }
+            if is_close {
+                break;
+            }
+        }
+    };
+
+    /// 断开原因
+    enum Disconnect {
+        /// 某一方向正常结束
+        StreamEnded,
+        /// 客户端空闲超时
+        IdleTimeout,
+        /// 网关关闭
+        Shutdown,
+    }
+
+    let reason = tokio::select! {
+        idle = client_to_backend => {
+            if idle { Disconnect::IdleTimeout } else { Disconnect::StreamEnded }
+        }
+        _ = backend_to_client => Disconnect::StreamEnded,
+        _ = shutdown_rx.wait_for(|v| *v) => Disconnect::Shutdown,
+    };
+
+    // 超时/关闭时向双方发送关闭帧再断开
+    match reason {
+        Disconnect::StreamEnded => {}
+        Disconnect::IdleTimeout => {
+            info!("WebSocket连接空闲超时，关闭连接");
+            let _ = client_tx
+                .send(Message::Close(Some(CloseFrame {
+                    code: 1001,
+                    reason: "idle timeout".into(),
+                })))
+                .await;
+            let _ = backend_tx.send(TMessage::Close(None)).await;
+        }
+        Disconnect::Shutdown => {
+            info!("网关正在关闭，断开WebSocket连接");
+            let _ = client_tx
+                .send(Message::Close(Some(CloseFrame {
+                    code: 1001,
+                    reason: "gateway shutting down".into(),
+                })))
+                .await;
+            let _ = backend_tx.send(TMessage::Close(None)).await;
+        }
+    }
+
+    Ok(())
+}
+
+/// axum侧消息转换为tungstenite消息
+fn client_msg_to_backend(msg: Message) -> TMessage {
+    match msg {
+        Message::Text(text) => TMessage::Text(text.as_str().into()),
+        Message::Binary(data) => TMessage::Binary(data),
+        Message::Ping(data) => TMessage::Ping(data),
+        Message::Pong(data) => TMessage::Pong(data),
+        Message::Close(frame) => TMessage::Close(frame.map(|f| TCloseFrame {
+            code: CloseCode::from(f.code),
+            reason: f.reason.as_str().into(),
+        })),
+    }
+}
+
+/// tungstenite侧消息转换为axum消息，原始帧不转发
+fn backend_msg_to_client(msg: TMessage) -> Option<Message> {
+    match msg {
+        TMessage::Text(text) => Some(Message::Text(text.as_str().into())),
+        TMessage::Binary(data) => Some(Message::Binary(data)),
+        TMessage::Ping(data) => Some(Message::Ping(data)),
+        TMessage::Pong(data) => Some(Message::Pong(data)),
+        TMessage::Close(frame) => Some(Message::Close(frame.map(|f| CloseFrame {
+            code: f.code.into(),
+            reason: f.reason.as_str().into(),
+        }))),
+        TMessage::Frame(_) => None,
+    }
+}
@@ -1,3 +1,5 @@
+pub mod redis_limiter;
+
 use governor::{
     Quota, RateLimiter,
     clock::DefaultClock,
@@ -12,84 +14,406 @@ use std::{
 };
 use axum::{
     response::{IntoResponse, Response},
-    http::{Request, StatusCode, HeaderMap, HeaderValue},
+    http::Request,
     extract::ConnectInfo,
     body::Body,
 };
 use std::net::SocketAddr;
 use governor::clock::Clock;
 use crate::config::CONFIG;
-use serde_json::json;
-use tracing::warn;
+use crate::config::rate_limit_config::{PathRateLimitRule, RateLimitConfig};
+use tracing::{error, warn};
 
-/// 限流中间件
-pub struct RateLimitLayer {
-    global_limiter: Arc<RateLimiter<NotKeyed, InMemoryState, DefaultClock>>,
-    path_limiters: Arc<std::collections::HashMap<String, Arc<RateLimiter<NotKeyed, InMemoryState, DefaultClock>>>>,
-    ip_limiters: Arc<parking_lot::RwLock<std::collections::HashMap<String, Arc<RateLimiter<NotKeyed, InMemoryState, DefaultClock>>>>>,
+use self::redis_limiter::{RateCheck, RedisRateLimiter};
+
+/// 直接限流器类型(无分键)
+type DirectRateLimiter = RateLimiter<NotKeyed, InMemoryState, DefaultClock>;
+
+/// 用户限流器空闲多久后被清理
+const USER_LIMITER_IDLE_SECS: u64 = 300;
+
+/// 按用户的限流器条目，last_seen用于空闲清理，
+/// rule用于在配置热更新后检测配额变化并重建限流器
+struct UserLimiterEntry {
+    limiter: Arc<DirectRateLimiter>,
+    last_seen: std::time::Instant,
+    rule: crate::config::rate_limit_config::RateLimitRule,
 }
 
-impl RateLimitLayer {
-    /// 创建新的限流层
-    pub async fn new() -> Self {
-        let config = CONFIG.read().await;
-        let rate_limit_config = &config.rate_limit;
-        
-        // 创建全局限流器
-        let global_limiter = Arc::new(RateLimiter::direct(Quota::per_second(
-            std::num::NonZeroU32::new(rate_limit_config.global.requests_per_second).unwrap()
-        ).allow_burst(
-            std::num::NonZeroU32::new(rate_limit_config.global.burst_size).unwrap()
-        )));
-        
-        // 创建路径限流器
+/// 配置派生的限流状态
+///
+/// 配置热更新时整体换新一份（见[`RateLimitLayer::apply_config`]），
+/// 配额未变化的限流器在新状态中复用，突发余量不因热更新而重置。
+struct LimiterState {
+    global_limiter: Arc<DirectRateLimiter>,
+    path_limiters: std::collections::HashMap<String, Arc<DirectRateLimiter>>,
+    ip_limiters: parking_lot::RwLock<std::collections::HashMap<String, Arc<DirectRateLimiter>>>,
+    /// 限流配置快照，Redis后端按规则计算限流键
+    config: RateLimitConfig,
+    /// Redis分布式限流器，backend为"redis"时启用；
+    /// Redis出错时回退到内存限流器（或按配置直接放行）
+    redis: Option<Arc<RedisRateLimiter>>,
+}
+
+/// 按规则构建内存限流器
+fn build_limiter(rule: &crate::config::rate_limit_config::RateLimitRule) -> Arc<DirectRateLimiter> {
+    Arc::new(RateLimiter::direct(Quota::per_second(
+        std::num::NonZeroU32::new(rule.requests_per_second).unwrap()
+    ).allow_burst(
+        std::num::NonZeroU32::new(rule.burst_size).unwrap()
+    )))
+}
+
+impl LimiterState {
+    /// 从配置构建状态；previous给出时复用其中配额未变化的限流器
+    fn build(rate_limit_config: &RateLimitConfig, previous: Option<&LimiterState>) -> Self {
+        let old_config = previous.map(|p| &p.config);
+
+        // 全局限流器：规则未变时保留令牌桶状态
+        let global_limiter = match previous {
+            Some(prev) if old_config.map(|c| &c.global) == Some(&rate_limit_config.global) => {
+                prev.global_limiter.clone()
+            }
+            _ => build_limiter(&rate_limit_config.global),
+        };
+
+        // 路径限流器：同前缀且规则相同的复用
         let mut path_limiters = std::collections::HashMap::new();
         for path_rule in &rate_limit_config.path_rules {
-            if path_rule.rule.enabled {
-                let limiter = Arc::new(RateLimiter::direct(Quota::per_second(
-                    std::num::NonZeroU32::new(path_rule.rule.requests_per_second).unwrap()
-                ).allow_burst(
-                    std::num::NonZeroU32::new(path_rule.rule.burst_size).unwrap()
-                )));
-                path_limiters.insert(path_rule.path_prefix.clone(), limiter);
+            if !path_rule.rule.enabled {
+                continue;
             }
+            let unchanged = old_config
+                .and_then(|c| c.path_rules.iter().find(|r| r.path_prefix == path_rule.path_prefix))
+                .map(|r| r.rule == path_rule.rule)
+                .unwrap_or(false);
+            let limiter = if unchanged {
+                previous.and_then(|p| p.path_limiters.get(&path_rule.path_prefix).cloned())
+            } else {
+                None
+            };
+            path_limiters.insert(
+                path_rule.path_prefix.clone(),
+                limiter.unwrap_or_else(|| build_limiter(&path_rule.rule)),
+            );
         }
-        
-        // 创建IP限流器
+
+        // IP限流器：配置内规则同上复用；add_ip_limiter动态加入的
+        // （不在新旧配置中的）条目原样保留
         let mut ip_limiters = std::collections::HashMap::new();
         for (ip, rule) in &rate_limit_config.ip_rules {
-            if rule.enabled {
-                let limiter = Arc::new(RateLimiter::direct(Quota::per_second(
-                    std::num::NonZeroU32::new(rule.requests_per_second).unwrap()
-                ).allow_burst(
-                    std::num::NonZeroU32::new(rule.burst_size).unwrap()
-                )));
-                ip_limiters.insert(ip.clone(), limiter);
+            if !rule.enabled {
+                continue;
+            }
+            let unchanged = old_config
+                .and_then(|c| c.ip_rules.get(ip))
+                .map(|r| r == rule)
+                .unwrap_or(false);
+            let limiter = if unchanged {
+                previous.and_then(|p| p.ip_limiters.read().get(ip).cloned())
+            } else {
+                None
+            };
+            ip_limiters.insert(ip.clone(), limiter.unwrap_or_else(|| build_limiter(rule)));
+        }
+        if let Some(prev) = previous {
+            for (ip, limiter) in prev.ip_limiters.read().iter() {
+                let configured = rate_limit_config.ip_rules.contains_key(ip)
+                    || old_config.map(|c| c.ip_rules.contains_key(ip)).unwrap_or(false);
+                if !configured {
+                    ip_limiters.entry(ip.clone()).or_insert_with(|| limiter.clone());
+                }
             }
         }
-        
+
+        // Redis后端：连接参数未变时复用既有客户端
+        let redis_unchanged = old_config
+            .map(|c| c.backend == rate_limit_config.backend && c.redis_url == rate_limit_config.redis_url)
+            .unwrap_or(false);
+        let redis = if rate_limit_config.backend == "redis" {
+            if redis_unchanged {
+                previous.and_then(|p| p.redis.clone())
+            } else {
+                match RedisRateLimiter::new(&rate_limit_config.redis_url) {
+                    Ok(limiter) => Some(Arc::new(limiter)),
+                    Err(e) => {
+                        error!("Redis限流器初始化失败，使用内存限流器: {}", e);
+                        None
+                    }
+                }
+            }
+        } else {
+            None
+        };
+
         Self {
             global_limiter,
-            path_limiters: Arc::new(path_limiters),
-            ip_limiters: Arc::new(parking_lot::RwLock::new(ip_limiters)),
+            path_limiters,
+            ip_limiters: parking_lot::RwLock::new(ip_limiters),
+            config: rate_limit_config.clone(),
+            redis,
         }
     }
-    
+
+    /// 匹配最长前缀的启用路径规则
+    fn find_path_rule(&self, path: &str) -> Option<&PathRateLimitRule> {
+        self.config
+            .path_rules
+            .iter()
+            .filter(|r| r.rule.enabled && path.starts_with(&r.path_prefix))
+            .max_by_key(|r| r.path_prefix.len())
+    }
+
+    /// 查找适用于该用户的限流规则：按ID覆盖优先，其次是认证用户默认规则
+    fn user_rule_for(&self, user_id: &str) -> Option<&crate::config::rate_limit_config::RateLimitRule> {
+        self.config
+            .user_rules
+            .overrides
+            .get(user_id)
+            .or(self.config.user_rules.default.as_ref())
+            .filter(|r| r.enabled)
+    }
+
+    /// 路径是否在限流豁免名单中（前缀匹配）
+    fn is_exempt(&self, path: &str) -> bool {
+        self.config
+            .exempt_paths
+            .iter()
+            .any(|prefix| path.starts_with(prefix.as_str()))
+    }
+
     /// 获取路径限流器
-    fn get_path_limiter(&self, path: &str) -> Option<Arc<RateLimiter<NotKeyed, InMemoryState, DefaultClock>>> {
+    fn get_path_limiter(&self, path: &str) -> Option<Arc<DirectRateLimiter>> {
         // 尝试匹配最长的路径前缀
         self.path_limiters.iter()
             .filter(|(prefix, _)| path.starts_with(*prefix))
             .max_by_key(|(prefix, _)| prefix.len())
             .map(|(_, limiter)| limiter.clone())
     }
-    
+
     /// 获取IP限流器
-    fn get_ip_limiter(&self, ip: &str) -> Option<Arc<RateLimiter<NotKeyed, InMemoryState, DefaultClock>>> {
+    fn get_ip_limiter(&self, ip: &str) -> Option<Arc<DirectRateLimiter>> {
         // 检查是否有针对该IP的限流器
         self.ip_limiters.read().get(ip).cloned()
     }
-    
+}
+
+/// 限流中间件
+pub struct RateLimitLayer {
+    /// 配置派生的限流状态，整体以Arc持有以便在await点间安全使用
+    state: parking_lot::RwLock<Arc<LimiterState>>,
+    /// 按认证用户的限流器，懒创建，空闲后清理防止无界增长
+    user_limiters: Arc<parking_lot::RwLock<std::collections::HashMap<String, UserLimiterEntry>>>,
+    /// 构建状态时的全局配置版本，配置热更新后在下一次判定时重建状态
+    config_version: std::sync::atomic::AtomicU64,
+}
+
+impl RateLimitLayer {
+    /// 创建新的限流层
+    pub async fn new() -> Self {
+        let config = CONFIG.read().await;
+        Self::from_config(&config.rate_limit)
+    }
+
+    /// 从配置构建限流层
+    pub fn from_config(rate_limit_config: &RateLimitConfig) -> Self {
+        Self {
+            state: parking_lot::RwLock::new(Arc::new(LimiterState::build(rate_limit_config, None))),
+            user_limiters: Arc::new(parking_lot::RwLock::new(std::collections::HashMap::new())),
+            config_version: std::sync::atomic::AtomicU64::new(crate::config::config_version()),
+        }
+    }
+
+    /// 应用新的限流配置，配额未变化的限流器保留令牌桶状态
+    fn apply_config(&self, rate_limit_config: &RateLimitConfig) {
+        let mut state = self.state.write();
+        *state = Arc::new(LimiterState::build(rate_limit_config, Some(state.as_ref())));
+    }
+
+    /// 配置热更新（notify监听器更新了CONFIG）后按新配置重建限流状态
+    async fn maybe_reload(&self) {
+        use std::sync::atomic::Ordering;
+
+        let current = crate::config::config_version();
+        if self.config_version.load(Ordering::Acquire) == current {
+            return;
+        }
+
+        let rate_limit_config = CONFIG.read().await.rate_limit.clone();
+        self.apply_config(&rate_limit_config);
+        self.config_version.store(current, Ordering::Release);
+        tracing::info!("限流配置已随配置热更新重建");
+    }
+
+    /// 对一次请求执行限流判定
+    ///
+    /// Redis后端生效时在集群维度判定；Redis出错时按配置直接放行，
+    /// 或回退到本实例的内存限流器（默认），避免静默放大配额。
+    pub async fn check(&self, path: &str, ip: &str, user_id: Option<&str>) -> RateCheck {
+        self.maybe_reload().await;
+        let state = self.state.read().clone();
+
+        // 豁免路径（健康检查/监控/管理）无条件放行
+        if state.is_exempt(path) {
+            return RateCheck::allowed();
+        }
+
+        if let Some(redis) = &state.redis {
+            // 熔断生效中不再尝试Redis，避免每个请求都承担一次连接超时
+            if redis.is_open() {
+                if state.config.fail_open_on_redis_error {
+                    return RateCheck::allowed();
+                }
+            } else {
+                match Self::check_redis(&state, redis, path, ip, user_id).await {
+                    Ok(check) => return check,
+                    Err(e) if state.config.fail_open_on_redis_error => {
+                        warn!("Redis限流不可用，按配置放行请求: {}", e);
+                        return RateCheck::allowed();
+                    }
+                    Err(e) => {
+                        warn!("Redis限流不可用，回退到内存限流器: {}", e);
+                    }
+                }
+            }
+        }
+        self.check_local(&state, path, ip, user_id)
+    }
+
+    /// Redis后端判定：全局、路径、IP、用户四个维度的键共享于所有网关实例
+    async fn check_redis(
+        state: &LimiterState,
+        redis: &RedisRateLimiter,
+        path: &str,
+        ip: &str,
+        user_id: Option<&str>,
+    ) -> Result<RateCheck, redis::RedisError> {
+        let mut denied: Option<RateCheck> = None;
+
+        if state.config.global.enabled {
+            let check = redis.check("gw:rl:global", &state.config.global).await?;
+            if !check.allowed {
+                denied = Some(check);
+            }
+        }
+
+        if let Some(rule) = state.find_path_rule(path) {
+            let key = format!("gw:rl:path:{}:{}", rule.path_prefix, ip);
+            let check = redis.check(&key, &rule.rule).await?;
+            if !check.allowed {
+                denied = Some(Self::max_wait(denied, check));
+            }
+        }
+
+        if let Some(rule) = state.config.ip_rules.get(ip).filter(|r| r.enabled) {
+            let key = format!("gw:rl:ip:{}", ip);
+            let check = redis.check(&key, rule).await?;
+            if !check.allowed {
+                denied = Some(Self::max_wait(denied, check));
+            }
+        }
+
+        if let Some(user_id) = user_id {
+            if let Some(rule) = state.user_rule_for(user_id) {
+                let key = format!("gw:rl:user:{}", user_id);
+                let check = redis.check(&key, rule).await?;
+                if !check.allowed {
+                    denied = Some(Self::max_wait(denied, check));
+                }
+            }
+        }
+
+        Ok(denied.unwrap_or_else(RateCheck::allowed))
+    }
+
+    fn max_wait(denied: Option<RateCheck>, check: RateCheck) -> RateCheck {
+        match denied {
+            Some(prev) if prev.retry_after_secs >= check.retry_after_secs => prev,
+            _ => check,
+        }
+    }
+
+    /// 内存限流器判定（单实例）
+    fn check_local(&self, state: &LimiterState, path: &str, ip: &str, user_id: Option<&str>) -> RateCheck {
+        let global_check = state.global_limiter.check();
+
+        let path_check = if let Some(path_limiter) = state.get_path_limiter(path) {
+            path_limiter.check()
+        } else {
+            Ok(())
+        };
+
+        let ip_check = if let Some(ip_limiter) = state.get_ip_limiter(ip) {
+            ip_limiter.check()
+        } else {
+            Ok(())
+        };
+
+        let user_check = match user_id.and_then(|id| state.user_rule_for(id).map(|rule| (id, rule.clone()))) {
+            Some((id, rule)) => self.get_user_limiter(id, &rule).check(),
+            None => Ok(()),
+        };
+
+        if global_check.is_ok() && path_check.is_ok() && ip_check.is_ok() && user_check.is_ok() {
+            return RateCheck::allowed();
+        }
+
+        // 计算建议的重试等待时间
+        let clock = governor::clock::DefaultClock::default();
+        let mut wait_time = 0;
+        for check in [global_check, path_check, ip_check, user_check] {
+            if let Err(wait) = check {
+                let wait_duration = wait.wait_time_from(clock.now());
+                wait_time = std::cmp::max(wait_time, wait_duration.as_secs());
+            }
+        }
+
+        RateCheck {
+            allowed: false,
+            retry_after_secs: wait_time,
+        }
+    }
+
+    /// 获取（或懒创建）按用户的限流器，并刷新最近活跃时间；
+    /// 配置热更新改变了该用户的配额时重建限流器
+    fn get_user_limiter(
+        &self,
+        user_id: &str,
+        rule: &crate::config::rate_limit_config::RateLimitRule,
+    ) -> Arc<DirectRateLimiter> {
+        let now = std::time::Instant::now();
+        let mut limiters = self.user_limiters.write();
+
+        if let Some(entry) = limiters.get_mut(user_id) {
+            if entry.rule == *rule {
+                entry.last_seen = now;
+                return entry.limiter.clone();
+            }
+            // 配额变了，按新规则重建
+            limiters.remove(user_id);
+        }
+
+        // 新用户出现时顺带清理空闲条目，限制map无界增长
+        let idle = std::time::Duration::from_secs(USER_LIMITER_IDLE_SECS);
+        limiters.retain(|_, entry| now.duration_since(entry.last_seen) < idle);
+
+        let limiter = build_limiter(rule);
+        limiters.insert(user_id.to_string(), UserLimiterEntry {
+            limiter: limiter.clone(),
+            last_seen: now,
+            rule: rule.clone(),
+        });
+        limiter
+    }
+
+    /// 清理空闲超过idle_for的用户限流器，返回剩余条目数
+    pub fn evict_idle_user_limiters(&self, idle_for: std::time::Duration) -> usize {
+        let now = std::time::Instant::now();
+        let mut limiters = self.user_limiters.write();
+        limiters.retain(|_, entry| now.duration_since(entry.last_seen) < idle_for);
+        limiters.len()
+    }
+
     /// 为新IP创建限流器
     pub fn add_ip_limiter(&self, ip: &str, requests_per_second: u32, burst_size: u32) {
         let limiter = Arc::new(RateLimiter::direct(Quota::per_second(
@@ -97,8 +421,8 @@ impl RateLimitLayer {
         ).allow_burst(
             std::num::NonZeroU32::new(burst_size).unwrap()
         )));
-        
-        self.ip_limiters.write().insert(ip.to_string(), limiter);
+
+        self.state.read().ip_limiters.write().insert(ip.to_string(), limiter);
     }
 }
 
@@ -135,74 +459,40 @@ where
     fn call(&mut self, req: Request<Body>) -> Self::Future {
         // 获取请求路径
         let path = req.uri().path().to_string();
-        
+
         // 获取客户端IP
         let ip = req.extensions()
             .get::<ConnectInfo<SocketAddr>>()
             .map(|connect_info| connect_info.0.ip().to_string())
             .unwrap_or_else(|| "unknown".to_string());
-        
-        // 检查全局限流
-        let global_limiter = self.rate_limit_layer.global_limiter.clone();
-        let global_check = global_limiter.check();
-        
-        // 检查路径限流
-        let path_check = if let Some(path_limiter) = self.rate_limit_layer.get_path_limiter(&path) {
-            path_limiter.check()
-        } else {
-            Ok(())
-        };
-        
-        // 检查IP限流
-        let ip_check = if let Some(ip_limiter) = self.rate_limit_layer.get_ip_limiter(&ip) {
-            ip_limiter.check()
-        } else {
-            Ok(())
-        };
-        
+
+        // 认证中间件先于限流执行时，扩展中带有UserInfo，按用户维度限流
+        let user_id = req.extensions()
+            .get::<crate::auth::jwt::UserInfo>()
+            .map(|user| user.user_id.to_string());
+
+        let layer = self.rate_limit_layer.clone();
         let mut svc = self.inner.clone();
-        
+
         Box::pin(async move {
-            // 如果任何一个限流器拒绝请求，返回429错误
-            if global_check.is_err() || path_check.is_err() || ip_check.is_err() {
-                // 生成剩余等待时间头
-                let mut headers = HeaderMap::new();
-                let mut wait_time = 0;
-
-                // 获取当前时间
-                let clock = governor::clock::DefaultClock::default();
-                
-                if let Err(wait) = global_check {
-                    let wait_duration = wait.wait_time_from(clock.now());
-                    wait_time = std::cmp::max(wait_time, wait_duration.as_secs());
-                }
-                
-                if let Err(wait) = path_check {
-                    let wait_duration = wait.wait_time_from(clock.now());
-                    wait_time = std::cmp::max(wait_time, wait_duration.as_secs());
-                }
-                
-                if let Err(wait) = ip_check {
-                    let wait_duration = wait.wait_time_from(clock.now());
-                    wait_time = std::cmp::max(wait_time, wait_duration.as_secs());
-                }
-                
-                if wait_time > 0 {
-                    headers.insert("Retry-After", HeaderValue::from(wait_time));
-                }
-                
-                warn!("请求被限流: 路径={}, IP={}", path, ip);
-                
-                // 返回429错误
-                let json_response = axum::Json(json!({
-                    "error": 429,
-                    "message": "请求过于频繁，请稍后重试",
-                    "retry_after": wait_time,
-                }));
-                
-                return Ok((StatusCode::TOO_MANY_REQUESTS, headers, json_response).into_response());
+            // Redis后端在集群维度判定，内存后端在实例维度判定
+            let check = layer.check(&path, &ip, user_id.as_deref()).await;
+
+            if !check.allowed {
+                warn!(
+                    "请求被限流: 路径={}, IP={}, 用户={}",
+                    path,
+                    ip,
+                    user_id.as_deref().unwrap_or("-")
+                );
+
+                // 统一经由common::Error生成带Retry-After头的429响应
+                let error = common::error::Error::RateLimited {
+                    retry_after_secs: check.retry_after_secs,
+                };
+                return Ok(error.into_response());
             }
-            
+
             // 请求通过限流检查，继续处理
             svc.call(req).await.map_err(Into::into)
         })
@@ -248,4 +538,259 @@ impl<S> Layer<S> for RateLimit {
 /// 创建限流中间件层
 pub async fn rate_limit_layer() -> RateLimit {
     RateLimit::new().await
-} 
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::rate_limit_config::{RateLimitRule, UserRateLimitRules};
+    use std::collections::HashMap;
+
+    fn test_config(backend: &str, redis_url: &str, fail_open: bool) -> RateLimitConfig {
+        RateLimitConfig {
+            backend: backend.to_string(),
+            redis_url: redis_url.to_string(),
+            fail_open_on_redis_error: fail_open,
+            // 全局突发1：同一实例第二个请求即被内存限流器拒绝
+            global: RateLimitRule {
+                requests_per_second: 1,
+                burst_size: 1,
+                enabled: true,
+            },
+            path_rules: vec![],
+            api_key_rules: HashMap::new(),
+            ip_rules: HashMap::new(),
+            user_rules: UserRateLimitRules::default(),
+            exempt_paths: vec!["/health".to_string(), "/metrics".to_string()],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_redis_error_falls_back_to_memory_limiter() {
+        // 不可达的Redis端口：判定应回退到内存限流器而不是放行
+        let layer = RateLimitLayer::from_config(&test_config("redis", "redis://127.0.0.1:9", false));
+
+        let first = layer.check("/api/users", "1.2.3.4", None).await;
+        assert!(first.allowed, "回退后首个请求应在突发额度内");
+
+        let second = layer.check("/api/users", "1.2.3.4", None).await;
+        assert!(!second.allowed, "回退后超出突发额度的请求应被拒绝");
+    }
+
+    #[tokio::test]
+    async fn test_exempt_paths_are_never_rate_limited() {
+        // 全局突发1：普通路径第二个请求就会被拒绝，
+        // 豁免路径无论多少请求都放行
+        let layer = RateLimitLayer::from_config(&test_config("memory", "", false));
+
+        for _ in 0..20 {
+            assert!(layer.check("/health", "1.2.3.4", None).await.allowed);
+            assert!(layer.check("/metrics", "1.2.3.4", None).await.allowed);
+        }
+
+        // 普通路径仍然受限流约束
+        assert!(layer.check("/api/users", "1.2.3.4", None).await.allowed);
+        assert!(!layer.check("/api/users", "1.2.3.4", None).await.allowed);
+    }
+
+    #[tokio::test]
+    async fn test_redis_error_fail_open_allows_requests() {
+        // 配置fail_open后Redis不可用时直接放行
+        let layer = RateLimitLayer::from_config(&test_config("redis", "redis://127.0.0.1:9", true));
+
+        for _ in 0..5 {
+            let check = layer.check("/api/users", "1.2.3.4", None).await;
+            assert!(check.allowed, "fail_open下Redis不可用不应限流");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_per_user_limits_are_independent() {
+        // 全局额度放宽，仅认证用户默认规则生效（突发2）
+        let mut config = test_config("memory", "", false);
+        config.global.requests_per_second = 1000;
+        config.global.burst_size = 1000;
+        config.user_rules = UserRateLimitRules {
+            default: Some(RateLimitRule {
+                requests_per_second: 1,
+                burst_size: 2,
+                enabled: true,
+            }),
+            overrides: HashMap::new(),
+        };
+        let layer = RateLimitLayer::from_config(&config);
+
+        // 用户1用完自己的突发额度后被拒绝
+        assert!(layer.check("/api/users", "1.2.3.4", Some("1")).await.allowed);
+        assert!(layer.check("/api/users", "1.2.3.4", Some("1")).await.allowed);
+        assert!(!layer.check("/api/users", "1.2.3.4", Some("1")).await.allowed);
+
+        // 同IP的用户2与未认证请求不受用户1的配额影响
+        assert!(layer.check("/api/users", "1.2.3.4", Some("2")).await.allowed);
+        assert!(layer.check("/api/users", "1.2.3.4", None).await.allowed);
+    }
+
+    #[tokio::test]
+    async fn test_user_override_rule_takes_precedence() {
+        let mut config = test_config("memory", "", false);
+        config.global.requests_per_second = 1000;
+        config.global.burst_size = 1000;
+        config.user_rules = UserRateLimitRules {
+            default: Some(RateLimitRule {
+                requests_per_second: 100,
+                burst_size: 100,
+                enabled: true,
+            }),
+            // 针对滥用账号12345收紧到突发1
+            overrides: HashMap::from([(
+                "12345".to_string(),
+                RateLimitRule {
+                    requests_per_second: 1,
+                    burst_size: 1,
+                    enabled: true,
+                },
+            )]),
+        };
+        let layer = RateLimitLayer::from_config(&config);
+
+        assert!(layer.check("/api/users", "1.2.3.4", Some("12345")).await.allowed);
+        assert!(!layer.check("/api/users", "1.2.3.4", Some("12345")).await.allowed);
+
+        // 其他用户仍按默认规则放行
+        for _ in 0..10 {
+            assert!(layer.check("/api/users", "1.2.3.4", Some("67890")).await.allowed);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_idle_user_limiters_are_evicted() {
+        let mut config = test_config("memory", "", false);
+        config.global.requests_per_second = 1000;
+        config.global.burst_size = 1000;
+        config.user_rules = UserRateLimitRules {
+            default: Some(RateLimitRule {
+                requests_per_second: 10,
+                burst_size: 10,
+                enabled: true,
+            }),
+            overrides: HashMap::new(),
+        };
+        let layer = RateLimitLayer::from_config(&config);
+
+        for id in ["1", "2", "3"] {
+            layer.check("/api/users", "1.2.3.4", Some(id)).await;
+        }
+        assert_eq!(layer.user_limiters.read().len(), 3);
+
+        // 空闲阈值为零时全部条目都应被清理
+        assert_eq!(layer.evict_idle_user_limiters(std::time::Duration::ZERO), 0);
+        assert!(layer.user_limiters.read().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_reload_preserves_unchanged_limiter_state() {
+        // 全局突发2，消耗掉一个令牌后应用相同配置：
+        // 令牌桶状态应保留，而不是重置成满额度
+        let mut config = test_config("memory", "", false);
+        config.global.burst_size = 2;
+        let layer = RateLimitLayer::from_config(&config);
+
+        assert!(layer.check("/api/users", "1.2.3.4", None).await.allowed);
+
+        layer.apply_config(&config);
+
+        // 剩余1个令牌：放行一次后即被拒绝，说明状态未被重置
+        assert!(layer.check("/api/users", "1.2.3.4", None).await.allowed);
+        assert!(!layer.check("/api/users", "1.2.3.4", None).await.allowed);
+    }
+
+    #[tokio::test]
+    async fn test_reload_applies_changed_quota() {
+        // 突发1用尽后热更新放宽配额，新配额应立即生效
+        let config = test_config("memory", "", false);
+        let layer = RateLimitLayer::from_config(&config);
+
+        assert!(layer.check("/api/users", "1.2.3.4", None).await.allowed);
+        assert!(!layer.check("/api/users", "1.2.3.4", None).await.allowed);
+
+        let mut updated = config.clone();
+        updated.global.requests_per_second = 100;
+        updated.global.burst_size = 100;
+        layer.apply_config(&updated);
+
+        for _ in 0..10 {
+            assert!(layer.check("/api/users", "1.2.3.4", None).await.allowed);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reload_rebuilds_user_limiter_on_rule_change() {
+        // 用户突发1用尽后热更新放宽用户规则，该用户的限流器应按新规则重建
+        let mut config = test_config("memory", "", false);
+        config.global.requests_per_second = 1000;
+        config.global.burst_size = 1000;
+        config.user_rules = UserRateLimitRules {
+            default: Some(RateLimitRule {
+                requests_per_second: 1,
+                burst_size: 1,
+                enabled: true,
+            }),
+            overrides: HashMap::new(),
+        };
+        let layer = RateLimitLayer::from_config(&config);
+
+        assert!(layer.check("/api/users", "1.2.3.4", Some("1")).await.allowed);
+        assert!(!layer.check("/api/users", "1.2.3.4", Some("1")).await.allowed);
+
+        let mut updated = config.clone();
+        updated.user_rules.default = Some(RateLimitRule {
+            requests_per_second: 100,
+            burst_size: 100,
+            enabled: true,
+        });
+        layer.apply_config(&updated);
+
+        for _ in 0..10 {
+            assert!(layer.check("/api/users", "1.2.3.4", Some("1")).await.allowed);
+        }
+    }
+
+    #[tokio::test]
+    #[ignore = "需要本地Redis (redis://127.0.0.1:6379)"]
+    async fn test_global_rate_enforced_across_instances() {
+        // 两个限流层实例共享同一路径限流键，
+        // 合计放行量应为配置的突发额度，而不是每实例一份
+        let prefix = format!("/itest-{}", uuid::Uuid::new_v4());
+        let mut config = test_config("redis", "redis://127.0.0.1:6379", false);
+        config.global.enabled = false;
+        config.global.requests_per_second = 1000;
+        config.global.burst_size = 1000;
+        config.path_rules = vec![crate::config::rate_limit_config::PathRateLimitRule {
+            path_prefix: prefix.clone(),
+            rule: RateLimitRule {
+                requests_per_second: 1,
+                burst_size: 5,
+                enabled: true,
+            },
+        }];
+
+        let layer_a = RateLimitLayer::from_config(&config);
+        let layer_b = RateLimitLayer::from_config(&config);
+
+        let path = format!("{}/resource", prefix);
+        let mut allowed = 0;
+        for i in 0..12 {
+            let layer = if i % 2 == 0 { &layer_a } else { &layer_b };
+            if layer.check(&path, "1.2.3.4", None).await.allowed {
+                allowed += 1;
+            }
+        }
+
+        // 突发额度5，短时间内最多再补充1个令牌
+        assert!(
+            (5..=6).contains(&allowed),
+            "两实例合计放行{}个请求，应接近配置的突发额度5",
+            allowed
+        );
+    }
+}
@@ -0,0 +1,215 @@
+//! Redis滑动窗口限流器
+//!
+//! 通过Lua脚本在Redis的有序集合中原子地维护滑动窗口，多个网关实例共享
+//! 同一限流键，使配置的速率在集群维度生效（内存限流器是单实例的，
+//! N个副本会放行N倍流量）。
+//!
+//! Redis连续出错时熔断：冷却期内不再尝试Redis，由调用方直接走内存回退，
+//! 避免每个请求都承担一次连接超时。
+
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::{Duration, Instant};
+
+use redis::Script;
+
+use crate::config::rate_limit_config::RateLimitRule;
+
+/// 滑动窗口Lua脚本
+///
+/// KEYS[1]: 限流键
+/// ARGV[1]: 窗口内允许的请求数  ARGV[2]: 窗口长度（毫秒）
+/// ARGV[3]: 当前时间（毫秒）  ARGV[4]: 本次请求的唯一成员ID
+/// 返回 {是否放行(0/1), 建议等待毫秒数}
+///
+/// 先清除滑出窗口的成员再计数，未超限才写入本次请求，
+/// 因此被拒绝的请求不占用窗口名额。键TTL为窗口长度+1秒，防止冷键常驻内存。
+const SLIDING_WINDOW_SCRIPT: &str = r#"
+local key = KEYS[1]
+local limit = tonumber(ARGV[1])
+local window_ms = tonumber(ARGV[2])
+local now_ms = tonumber(ARGV[3])
+local member = ARGV[4]
+
+redis.call('ZREMRANGEBYSCORE', key, 0, now_ms - window_ms)
+local count = redis.call('ZCARD', key)
+if count < limit then
+    redis.call('ZADD', key, now_ms, member)
+    redis.call('PEXPIRE', key, window_ms + 1000)
+    return {1, 0}
+end
+
+-- 最早的请求滑出窗口后才有名额，以此估算等待时间
+local oldest = redis.call('ZRANGE', key, 0, 0, 'WITHSCORES')
+local wait_ms = 0
+if oldest[2] then
+    wait_ms = math.max(0, tonumber(oldest[2]) + window_ms - now_ms)
+end
+redis.call('PEXPIRE', key, window_ms + 1000)
+return {0, wait_ms}
+"#;
+
+/// 连续失败多少次后熔断Redis路径
+const BREAKER_FAILURE_THRESHOLD: u32 = 3;
+
+/// 熔断后的冷却时长
+const BREAKER_COOLDOWN: Duration = Duration::from_secs(30);
+
+/// 限流判定结果
+#[derive(Debug, Clone, Copy)]
+pub struct RateCheck {
+    pub allowed: bool,
+    /// 被拒绝时建议的重试等待秒数
+    pub retry_after_secs: u64,
+}
+
+impl RateCheck {
+    pub fn allowed() -> Self {
+        Self {
+            allowed: true,
+            retry_after_secs: 0,
+        }
+    }
+}
+
+/// 基于Redis的分布式限流器
+pub struct RedisRateLimiter {
+    client: redis::Client,
+    script: Script,
+    /// 连续失败计数，成功后归零
+    consecutive_failures: AtomicU32,
+    /// 熔断截止时间，None表示未熔断
+    open_until: parking_lot::Mutex<Option<Instant>>,
+}
+
+impl RedisRateLimiter {
+    pub fn new(redis_url: &str) -> Result<Self, redis::RedisError> {
+        let client = redis::Client::open(redis_url)?;
+        Ok(Self {
+            client,
+            script: Script::new(SLIDING_WINDOW_SCRIPT),
+            consecutive_failures: AtomicU32::new(0),
+            open_until: parking_lot::Mutex::new(None),
+        })
+    }
+
+    /// 熔断是否生效中：冷却期内调用方应跳过Redis直接回退
+    pub fn is_open(&self) -> bool {
+        let mut open_until = self.open_until.lock();
+        match *open_until {
+            Some(until) if Instant::now() < until => true,
+            Some(_) => {
+                // 冷却期结束，放行一次探测
+                *open_until = None;
+                false
+            }
+            None => false,
+        }
+    }
+
+    /// 对指定键执行一次滑动窗口判定
+    ///
+    /// 窗口参数由规则换算：窗口内允许`burst_size`个请求，
+    /// 窗口长度为`burst_size / requests_per_second`秒，
+    /// 与内存令牌桶的突发容量和持续速率语义一致。
+    pub async fn check(
+        &self,
+        key: &str,
+        rule: &RateLimitRule,
+    ) -> Result<RateCheck, redis::RedisError> {
+        let limit = rule.burst_size.max(1);
+        let window_ms =
+            (limit as u64 * 1000).div_ceil(rule.requests_per_second.max(1) as u64);
+
+        let result = self.run_script(key, limit, window_ms).await;
+        match &result {
+            Ok(_) => self.record_success(),
+            Err(_) => self.record_failure(),
+        }
+        result
+    }
+
+    async fn run_script(
+        &self,
+        key: &str,
+        limit: u32,
+        window_ms: u64,
+    ) -> Result<RateCheck, redis::RedisError> {
+        let mut conn = self.client.get_multiplexed_async_connection().await?;
+        let now_ms = chrono::Utc::now().timestamp_millis();
+
+        let (allowed, wait_ms): (i64, i64) = self
+            .script
+            .key(key)
+            .arg(limit)
+            .arg(window_ms)
+            .arg(now_ms)
+            .arg(uuid::Uuid::new_v4().to_string())
+            .invoke_async(&mut conn)
+            .await?;
+
+        Ok(RateCheck {
+            allowed: allowed == 1,
+            retry_after_secs: (wait_ms as u64).div_ceil(1000),
+        })
+    }
+
+    fn record_success(&self) {
+        self.consecutive_failures.store(0, Ordering::Relaxed);
+    }
+
+    fn record_failure(&self) {
+        let failures = self.consecutive_failures.fetch_add(1, Ordering::Relaxed) + 1;
+        if failures >= BREAKER_FAILURE_THRESHOLD {
+            *self.open_until.lock() = Some(Instant::now() + BREAKER_COOLDOWN);
+            self.consecutive_failures.store(0, Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(requests_per_second: u32, burst_size: u32) -> RateLimitRule {
+        RateLimitRule {
+            requests_per_second,
+            burst_size,
+            enabled: true,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_breaker_opens_after_consecutive_failures() {
+        // 不可达的Redis端口：连续失败达到阈值后熔断
+        let limiter = RedisRateLimiter::new("redis://127.0.0.1:9").unwrap();
+        assert!(!limiter.is_open());
+
+        for _ in 0..BREAKER_FAILURE_THRESHOLD {
+            assert!(limiter.check("gw:rl:test", &rule(1, 1)).await.is_err());
+        }
+        assert!(limiter.is_open(), "达到失败阈值后应进入熔断");
+    }
+
+    #[tokio::test]
+    #[ignore = "需要本地Redis (redis://127.0.0.1:6379)"]
+    async fn test_sliding_window_limits_and_sets_ttl() {
+        let limiter = RedisRateLimiter::new("redis://127.0.0.1:6379").unwrap();
+        let key = format!("gw:rl:itest:{}", uuid::Uuid::new_v4());
+
+        // 窗口内放行burst_size个请求，之后拒绝并给出等待建议
+        let r = rule(1, 3);
+        for _ in 0..3 {
+            assert!(limiter.check(&key, &r).await.unwrap().allowed);
+        }
+        let denied = limiter.check(&key, &r).await.unwrap();
+        assert!(!denied.allowed);
+        assert!(denied.retry_after_secs >= 1);
+
+        // 键TTL为窗口长度+1秒，不会常驻内存
+        let mut conn = limiter.client.get_multiplexed_async_connection().await.unwrap();
+        let ttl_ms: i64 = redis::cmd("PTTL").arg(&key).query_async(&mut conn).await.unwrap();
+        assert!(ttl_ms > 0 && ttl_ms <= 3 * 1000 + 1000);
+
+        let _: () = redis::cmd("DEL").arg(&key).query_async(&mut conn).await.unwrap();
+    }
+}
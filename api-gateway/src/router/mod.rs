@@ -1,7 +1,7 @@
 use std::sync::Arc;
 use axum::Router;
 use axum::routing::{get, any};
-use axum::http::{StatusCode, Request};
+use axum::http::{header, HeaderValue, Method, Request, StatusCode};
 use axum::response::IntoResponse;
 use axum::body::Body;
 use axum::middleware;
@@ -11,7 +11,6 @@ use tracing::info;
 use crate::config::CONFIG;
 use crate::proxy::service_proxy::ServiceProxy;
 use crate::auth::middleware::auth_middleware;
-use std::collections::HashMap;
 
 /// 路由构建器
 pub struct RouterBuilder {
@@ -32,55 +31,100 @@ impl RouterBuilder {
     pub async fn build(mut self) -> anyhow::Result<Router> {
         // 读取配置
         let config = CONFIG.read().await;
-        let routes_config = &config.routes;
-        
-        // 遍历路由配置，添加到路由器中
-        for route in &routes_config.routes {
-            let path = route.path_prefix.clone();
-            let service_type = route.service_type.clone();
-            let require_auth = route.require_auth;
-            
-            // 创建路由处理函数
-            let service_proxy = self.service_proxy.clone();
-            let handler = any(move |req: Request<Body>| {
-                let service_proxy = service_proxy.clone();
-                let service_type = service_type.clone();
-                async move {
-                    // 将请求转发到目标服务
-                    service_proxy.forward_request(req, &service_type).await
-                }
-            });
-            
-            // 根据是否需要认证添加中间件
-            let route_path = path.clone();
-            if require_auth {
-                info!("添加需要认证的路由: {}", route_path);
-                self.router = self.router.route(
-                    &route_path,
-                    handler.clone().route_layer(middleware::from_fn(auth_middleware))
-                );
-            } else {
-                info!("添加无需认证的路由: {}", route_path);
-                self.router = self.router.route(&route_path, handler.clone());
-            }
-            
-            // 处理通配符路径
-            let wildcard_path = format!("{}/*path", path);
-            if require_auth {
-                self.router = self.router.route(
-                    &wildcard_path,
-                    handler.clone().route_layer(middleware::from_fn(auth_middleware))
-                );
-            } else {
-                self.router = self.router.route(&wildcard_path, handler.clone());
-            }
-        }
+
+        // 业务路由不做静态注册：兜底处理器在每次请求时对全局配置做
+        // 最长前缀匹配，配置热更新后路由的增删和require_auth翻转
+        // 无需重建Router即可生效。认证/RBAC/nonce同样按请求时匹配到的
+        // 规则决定是否执行（认证在外层先执行，nonce校验可使用认证身份）
+        info!("动态路由已启用，当前配置路由数: {}", config.routes.routes.len());
+        let service_proxy = self.service_proxy.clone();
+        let dynamic = any(move |req: Request<Body>| {
+            let service_proxy = service_proxy.clone();
+            async move { dynamic_route_handler(service_proxy, req).await }
+        })
+        .layer(middleware::from_fn(nonce_gate))
+        .layer(middleware::from_fn(crate::auth::middleware::rbac_middleware))
+        .layer(middleware::from_fn(crate::geo::geo_gate))
+        .layer(middleware::from_fn(auth_gate));
+        self.router = self.router.fallback_service(dynamic);
+
+        // 登录/注册/注销聚合端点：编排user-service与auth-service的gRPC调用，
+        // 静态注册的路由优先于动态兜底路由；gRPC通道与代理的转码路径共用一个池
+        let aggregator = Arc::new(crate::handlers::AuthAggregator::new(
+            self.service_proxy.grpc_pool(),
+        ));
+        let login = aggregator.clone();
+        let register = aggregator.clone();
+        let logout = aggregator;
+        self.router = self.router
+            .route(
+                "/api/auth/login",
+                axum::routing::post(move |payload| async move { login.login(payload).await }),
+            )
+            .route(
+                "/api/auth/register",
+                axum::routing::post(move |payload| async move { register.register(payload).await }),
+            )
+            .route(
+                "/api/auth/logout",
+                axum::routing::post(move |headers: axum::http::HeaderMap| async move {
+                    logout.logout(headers).await
+                }),
+            );
+
+        // 高价值操作先从该端点获取一次性nonce（需认证）
+        self.router = self.router.route(
+            "/api/nonce",
+            get(crate::nonce::issue_nonce_handler)
+                .route_layer(middleware::from_fn(auth_middleware)),
+        );
         
-        // 添加健康检查和指标端点
+        // 添加实例权重管理端点（发版时将权重设为0摘除实例，恢复权重重新接流）
+        let discovery = self.service_proxy.service_discovery();
+        let query_discovery = discovery.clone();
+        self.router = self.router
+            .route(
+                "/admin/instances/weight",
+                axum::routing::put(move |Json(payload): Json<InstanceWeight>| {
+                    let discovery = discovery.clone();
+                    async move {
+                        discovery
+                            .set_instance_weight(&payload.instance, payload.weight)
+                            .await;
+                        (StatusCode::OK, Json(json!({
+                            "instance": payload.instance,
+                            "weight": payload.weight,
+                        })))
+                    }
+                })
+                .get(move || {
+                    let discovery = query_discovery.clone();
+                    async move { Json(discovery.get_instance_weights().await) }
+                }),
+            );
+
+        // 熔断器巡检端点：输出每个服务端点熔断器的状态、失败次数
+        // 和距半开的剩余秒数，仅admin角色可访问
+        let breaker_registry = self.service_proxy.circuit_breakers();
+        self.router = self.router.route(
+            "/admin/circuit-breakers",
+            get(move || {
+                let registry = breaker_registry.clone();
+                async move { Json(registry.snapshot()) }
+            })
+            .route_layer(middleware::from_fn(crate::auth::middleware::require_admin))
+            .route_layer(middleware::from_fn(auth_middleware)),
+        );
+
+        // 路由与API Key管理端点（仅admin角色），直接改内存配置即时生效
+        self.router = self.router.merge(crate::admin::admin_router());
+
+        // 添加健康检查、就绪检查和指标端点
         self.router = self.router
             .route("/health", get(health_check))
+            .route("/ready", get(readiness_check))
             .route(&config.metrics_endpoint, get(crate::metrics::get_metrics_handler));
-        
+
         Ok(self.router.with_state(()))
     }
 }
@@ -90,42 +134,208 @@ async fn health_check() -> impl IntoResponse {
     (StatusCode::OK, Json(json!({ "status": "ok" })))
 }
 
-/// 路由注册器 - 用于动态更新路由
-pub struct RouteRegistry {
-    routes: Arc<tokio::sync::RwLock<HashMap<String, Router>>>,
+/// 网关就绪标记：启动依赖门控完成（依赖全部健康或等待超时降级）后置位
+static READY: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
+/// 标记网关已就绪
+pub fn set_ready() {
+    READY.store(true, std::sync::atomic::Ordering::Release);
 }
 
-impl RouteRegistry {
-    /// 创建新的路由注册器
-    pub fn new() -> Self {
-        Self {
-            routes: Arc::new(tokio::sync::RwLock::new(HashMap::new())),
+/// 就绪检查处理函数：依赖门控完成前返回503，
+/// 供编排系统（如k8s readiness probe）在依赖就绪前不向网关导流
+async fn readiness_check() -> impl IntoResponse {
+    if READY.load(std::sync::atomic::Ordering::Acquire) {
+        (StatusCode::OK, Json(json!({ "status": "ready" })))
+    } else {
+        (StatusCode::SERVICE_UNAVAILABLE, Json(json!({ "status": "not_ready" })))
+    }
+}
+
+/// 实例权重设置请求体
+#[derive(serde::Deserialize)]
+struct InstanceWeight {
+    /// 实例URL，如 http://10.0.0.1:8080
+    instance: String,
+    /// 权重，0表示摘除
+    weight: u32,
+}
+
+/// 动态路由兜底处理器
+///
+/// 每次请求时对全局配置做最长前缀匹配，未命中任何规则返回404，
+/// 方法不在允许列表中返回405，否则转发到匹配规则的目标服务
+async fn dynamic_route_handler(
+    service_proxy: Arc<ServiceProxy>,
+    req: Request<Body>,
+) -> axum::response::Response {
+    let (service_type, methods) = {
+        let config = CONFIG.read().await;
+        match config.routes.match_route(req.uri().path()) {
+            Some(rule) => (rule.service_type.clone(), rule.methods.clone()),
+            None => {
+                return (
+                    StatusCode::NOT_FOUND,
+                    Json(json!({
+                        "error": StatusCode::NOT_FOUND.as_u16(),
+                        "message": "未找到匹配的路由",
+                    })),
+                )
+                    .into_response();
+            }
         }
+    };
+
+    // 检查请求方法是否在允许列表中
+    if !method_allowed(&methods, req.method()) {
+        return method_not_allowed_response(&methods);
     }
-    
-    /// 注册路由
-    pub async fn register_route(&self, path: &str, router: Router) {
-        let mut routes = self.routes.write().await;
-        routes.insert(path.to_string(), router);
-        info!("路由已注册: {}", path);
+
+    // 将请求转发到目标服务
+    service_proxy.forward_request(req, &service_type).await
+}
+
+/// 匹配到的路由要求认证时执行认证，否则直接放行
+async fn auth_gate(
+    request: Request<Body>,
+    next: middleware::Next,
+) -> Result<axum::response::Response, common::error::Error> {
+    let require_auth = {
+        let config = CONFIG.read().await;
+        config
+            .routes
+            .match_route(request.uri().path())
+            .map(|r| r.require_auth)
+            .unwrap_or(false)
+    };
+
+    if require_auth {
+        auth_middleware(request, next).await
+    } else {
+        Ok(next.run(request).await)
     }
-    
-    /// 移除路由
-    pub async fn remove_route(&self, path: &str) {
-        let mut routes = self.routes.write().await;
-        routes.remove(path);
-        info!("路由已移除: {}", path);
+}
+
+/// 匹配到的路由要求防重放nonce时执行校验，否则直接放行
+async fn nonce_gate(request: Request<Body>, next: middleware::Next) -> axum::response::Response {
+    let require_nonce = {
+        let config = CONFIG.read().await;
+        config
+            .routes
+            .match_route(request.uri().path())
+            .map(|r| r.require_nonce)
+            .unwrap_or(false)
+    };
+
+    if require_nonce {
+        crate::nonce::nonce_middleware(request, next).await
+    } else {
+        next.run(request).await
     }
-    
-    /// 获取路由
-    pub async fn get_route(&self, path: &str) -> Option<Router> {
-        let routes = self.routes.read().await;
-        routes.get(path).cloned()
+}
+
+/// 检查请求方法是否在路由允许列表中（空列表表示全部允许）
+fn method_allowed(methods: &[String], method: &Method) -> bool {
+    methods.is_empty() || methods.iter().any(|m| m.eq_ignore_ascii_case(method.as_str()))
+}
+
+/// 构建405响应，Allow头中列出允许的方法
+fn method_not_allowed_response(methods: &[String]) -> axum::response::Response {
+    let allow = methods
+        .iter()
+        .map(|m| m.to_uppercase())
+        .collect::<Vec<_>>()
+        .join(", ");
+    let body = Json(json!({
+        "error": StatusCode::METHOD_NOT_ALLOWED.as_u16(),
+        "message": "请求方法不允许",
+    }));
+    let mut response = (StatusCode::METHOD_NOT_ALLOWED, body).into_response();
+    if let Ok(value) = HeaderValue::from_str(&allow) {
+        response.headers_mut().insert(header::ALLOW, value);
     }
-    
-    /// 获取所有路由路径
-    pub async fn get_all_routes(&self) -> Vec<String> {
-        let routes = self.routes.read().await;
-        routes.keys().cloned().collect()
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tower::ServiceExt;
+
+    #[test]
+    fn test_method_allowed() {
+        // 空列表表示全部允许
+        assert!(method_allowed(&[], &Method::GET));
+        // 大小写不敏感
+        let methods = vec!["post".to_string()];
+        assert!(method_allowed(&methods, &Method::POST));
+        assert!(!method_allowed(&methods, &Method::GET));
+    }
+
+    #[tokio::test]
+    async fn test_readiness_flips_after_startup_gate() {
+        let app = Router::new().route("/ready", get(readiness_check));
+
+        // 依赖门控完成前不就绪
+        let response = app
+            .clone()
+            .oneshot(Request::builder().uri("/ready").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        // 门控完成（依赖健康或超时降级）后就绪
+        set_ready();
+        let response = app
+            .oneshot(Request::builder().uri("/ready").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
     }
-} 
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_post_only_route_rejects_get() {
+        let methods = vec!["POST".to_string()];
+        let cloned_methods = methods.clone();
+        let app = Router::new().route(
+            "/api/test",
+            any(move |req: Request<Body>| {
+                let methods = cloned_methods.clone();
+                async move {
+                    if !method_allowed(&methods, req.method()) {
+                        return method_not_allowed_response(&methods);
+                    }
+                    (StatusCode::OK, "ok").into_response()
+                }
+            }),
+        );
+
+        // GET应被拒绝并带有Allow头
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method(Method::GET)
+                    .uri("/api/test")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(response.headers().get(header::ALLOW).unwrap(), "POST");
+
+        // POST正常通过
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(Method::POST)
+                    .uri("/api/test")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
@@ -1,15 +1,228 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use once_cell::sync::OnceCell;
+use opentelemetry::global;
+use opentelemetry::propagation::{Extractor, Injector};
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::{Sampler, SdkTracerProvider};
+use opentelemetry_sdk::Resource;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
 use tracing_subscriber::{layer::SubscriberExt, EnvFilter};
 use tracing_subscriber::fmt::Layer as FmtLayer;
 use tracing_subscriber::util::SubscriberInitExt;
 use axum::{
-    http::{Request, HeaderMap},
+    http::{HeaderMap, HeaderName, HeaderValue, Request},
     body::Body,
     middleware::Next,
-    response::Response,
+    response::{IntoResponse, Response},
 };
-use tracing::{info, info_span};
+use tower::{Layer, Service};
+use tracing::{info, info_span, Instrument};
 use crate::config::CONFIG;
 
+/// 已初始化的TracerProvider，优雅关闭时冲刷并停止导出
+static TRACER_PROVIDER: OnceCell<SdkTracerProvider> = OnceCell::new();
+
+/// 请求关联ID，由[`RequestIdLayer`]写入请求扩展，
+/// 供后续中间件和代理读取
+#[derive(Clone, Debug)]
+pub struct RequestId(pub String);
+
+/// 请求关联ID中间件层
+///
+/// 上游带了`X-Request-ID`则沿用，否则生成新的UUIDv7（时间有序，按日志排序即按到达顺序）；
+/// 写入请求头、请求扩展，并回显到响应头，
+/// 使一条请求在auth→user→friend的跨服务日志里可以串起来。
+#[derive(Clone, Copy)]
+pub struct RequestIdLayer;
+
+impl<S> Layer<S> for RequestIdLayer {
+    type Service = RequestIdService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RequestIdService { inner }
+    }
+}
+
+/// 请求关联ID中间件
+#[derive(Clone)]
+pub struct RequestIdService<S> {
+    inner: S,
+}
+
+impl<S> Service<Request<Body>> for RequestIdService<S>
+where
+    S: Service<Request<Body>, Response = Response> + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: Request<Body>) -> Self::Future {
+        let request_id = req
+            .headers()
+            .get("x-request-id")
+            .and_then(|v| v.to_str().ok())
+            .filter(|v| !v.is_empty())
+            .map(|v| v.to_string())
+            .unwrap_or_else(|| uuid::Uuid::now_v7().to_string());
+
+        if let Ok(value) = HeaderValue::from_str(&request_id) {
+            req.headers_mut().insert("x-request-id", value);
+        }
+        req.extensions_mut().insert(RequestId(request_id.clone()));
+
+        let fut = self.inner.call(req);
+        Box::pin(async move {
+            let mut response = fut.await?;
+            // 代理转发路径已自行回写，这里只补缺失的情况（如限流/认证短路响应）
+            if !response.headers().contains_key("x-request-id") {
+                if let Ok(value) = HeaderValue::from_str(&request_id) {
+                    response.headers_mut().insert("x-request-id", value);
+                }
+            }
+            Ok(response)
+        })
+    }
+}
+
+/// 请求体日志中间件层（调试工具）
+///
+/// 有效日志级别包含trace时，缓冲整个请求体（chunked请求体同样被收齐），
+/// 以trace级别记录前max_log_bytes字节与请求头（sanitize_headers中的头
+/// 打码为[REDACTED]），再把字节原样注入回请求交给下游处理。
+/// trace未启用时直接透传，不缓冲、零开销；因此该层只影响开了
+/// RUST_LOG=trace的调试环境，不要在生产开启trace。
+#[derive(Clone)]
+pub struct BodyLogLayer {
+    max_log_bytes: usize,
+    sanitize_headers: std::sync::Arc<Vec<String>>,
+}
+
+impl BodyLogLayer {
+    pub fn new(max_log_bytes: usize, sanitize_headers: Vec<String>) -> Self {
+        Self {
+            max_log_bytes,
+            // 预先统一为小写，匹配时大小写不敏感
+            sanitize_headers: std::sync::Arc::new(
+                sanitize_headers.iter().map(|h| h.to_ascii_lowercase()).collect(),
+            ),
+        }
+    }
+
+    /// 从网关配置构建
+    pub async fn from_gateway_config() -> Self {
+        let config = CONFIG.read().await;
+        Self::new(
+            config.tracing.max_log_bytes,
+            config.tracing.sanitize_headers.clone(),
+        )
+    }
+}
+
+impl<S> Layer<S> for BodyLogLayer {
+    type Service = BodyLogService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        BodyLogService {
+            inner,
+            max_log_bytes: self.max_log_bytes,
+            sanitize_headers: self.sanitize_headers.clone(),
+        }
+    }
+}
+
+/// 请求体日志中间件
+#[derive(Clone)]
+pub struct BodyLogService<S> {
+    inner: S,
+    max_log_bytes: usize,
+    sanitize_headers: std::sync::Arc<Vec<String>>,
+}
+
+impl<S> Service<Request<Body>> for BodyLogService<S>
+where
+    S: Service<Request<Body>, Response = Response> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        // trace未启用时不碰请求体，直接透传
+        if !tracing::enabled!(tracing::Level::TRACE) {
+            return Box::pin(self.inner.call(req));
+        }
+
+        // 把poll_ready过的inner换出来供异步块使用（标准的Clone+replace模式）
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let max_log_bytes = self.max_log_bytes;
+        let sanitize_headers = self.sanitize_headers.clone();
+
+        Box::pin(async move {
+            let (parts, body) = req.into_parts();
+            let bytes = match axum::body::to_bytes(body, usize::MAX).await {
+                Ok(bytes) => bytes,
+                Err(e) => {
+                    tracing::warn!("请求体日志：读取请求体失败: {}", e);
+                    return Ok((
+                        axum::http::StatusCode::BAD_REQUEST,
+                        "无法读取请求体",
+                    )
+                        .into_response());
+                }
+            };
+
+            let shown = &bytes[..bytes.len().min(max_log_bytes)];
+            tracing::trace!(
+                "请求体: {} {} 头={:?} 体[{}/{}字节]={}",
+                parts.method,
+                parts.uri,
+                sanitized_headers(&parts.headers, &sanitize_headers),
+                shown.len(),
+                bytes.len(),
+                String::from_utf8_lossy(shown)
+            );
+
+            // 字节原样注入回请求
+            inner.call(Request::from_parts(parts, Body::from(bytes))).await
+        })
+    }
+}
+
+/// 生成打码后的请求头列表，sanitize列表（小写）中的头值替换为[REDACTED]
+fn sanitized_headers(headers: &HeaderMap, sanitize: &[String]) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let name = name.as_str().to_string();
+            let value = if sanitize.contains(&name.to_ascii_lowercase()) {
+                "[REDACTED]".to_string()
+            } else {
+                String::from_utf8_lossy(value.as_bytes()).to_string()
+            };
+            (name, value)
+        })
+        .collect()
+}
+
 /// 初始化链路追踪
 pub async fn init_tracer() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     // 读取配置
@@ -28,25 +241,56 @@ pub async fn init_tracer() -> Result<(), Box<dyn std::error::Error + Send + Sync
         return Ok(());
     }
     
-    // 如果启用OpenTelemetry，我们在这里简化实现
-    // 由于版本兼容性问题，我们暂时只使用标准日志
-    info!("由于OpenTelemetry版本兼容性问题，暂时只使用标准日志");
-    
-    // 使用标准格式输出
-    let fmt_layer = FmtLayer::new();
-    
-    // 初始化订阅者
+    // W3C TraceContext作为全局传播格式（traceparent/tracestate）
+    global::set_text_map_propagator(TraceContextPropagator::new());
+
+    // OTLP gRPC导出器，端点未配置时使用SDK默认（localhost:4317）
+    let mut exporter = opentelemetry_otlp::SpanExporter::builder().with_tonic();
+    if let Some(endpoint) = &config.tracing.otlp_endpoint {
+        exporter = exporter.with_endpoint(endpoint.clone());
+    }
+    let exporter = exporter.build()?;
+
+    // 父span已采样则跟随，根span按配置的采样率抽样
+    let provider = SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(
+            config.tracing.sampling_ratio,
+        ))))
+        .with_resource(Resource::builder().with_service_name("api-gateway").build())
+        .build();
+
+    let otel_layer = tracing_opentelemetry::layer().with_tracer(provider.tracer("api-gateway"));
+    let _ = TRACER_PROVIDER.set(provider.clone());
+    global::set_tracer_provider(provider);
+
     tracing_subscriber::registry()
         .with(EnvFilter::from_default_env())
-        .with(fmt_layer)
+        .with(FmtLayer::new())
+        .with(otel_layer)
         .init();
-    
-    info!("已初始化日志系统");
-    
+
+    info!(
+        "已初始化日志系统并启用OpenTelemetry链路追踪（OTLP导出，采样率{}）",
+        config.tracing.sampling_ratio
+    );
+
     Ok(())
 }
 
+/// 关闭OpenTelemetry导出器，冲刷尚未上报的span（优雅关闭时调用）
+pub fn shutdown_tracer() {
+    if let Some(provider) = TRACER_PROVIDER.get() {
+        if let Err(e) = provider.shutdown() {
+            tracing::warn!("OpenTelemetry导出器关闭失败: {:?}", e);
+        }
+    }
+}
+
 /// 链路追踪中间件
+///
+/// 为每个代理请求创建一个span；上游带了合法的`traceparent`时作为父
+/// 上下文延续链路，否则开启新trace。代理层注入出站头时从当前span取上下文。
 pub async fn trace_middleware(req: Request<Body>, next: Next) -> Response {
     // 创建请求跟踪span
     let path = req.uri().path().to_string();
@@ -55,32 +299,229 @@ pub async fn trace_middleware(req: Request<Body>, next: Next) -> Response {
         "http_request",
         path = %path,
         method = %method,
+        // RequestIdLayer在最外层，此处请求头里必然已有关联ID
+        request_id = ?req.headers().get("x-request-id").and_then(|v| v.to_str().ok()),
         http.target = %req.uri().path(),
         http.host = ?req.uri().host(),
         http.user_agent = ?req.headers().get("user-agent").and_then(|v| v.to_str().ok()),
+        http.status_code = tracing::field::Empty,
     );
-    
+
+    // 把上游的trace上下文作为父上下文（W3C traceparent/tracestate）
+    let parent_cx = global::get_text_map_propagator(|p| p.extract(&HeaderExtractor(req.headers())));
+    span.set_parent(parent_cx);
+
     // 在span上下文中执行请求
-    let _enter = span.enter();
-    
-    // 继续执行请求
-    let response = next.run(req).await;
-    
+    let response = next.run(req).instrument(span.clone()).await;
+
     // 记录响应状态码
-    span.record("http.status_code", &response.status().as_u16());
-    
+    span.record("http.status_code", response.status().as_u16());
+
     response
 }
 
-/// 从请求头中提取跟踪上下文
-fn extract_trace_context(headers: &HeaderMap) -> Option<(String, String)> {
-    let traceparent = headers.get("traceparent").and_then(|v| v.to_str().ok())?;
-    
-    // 解析traceparent头 (格式: 00-<trace-id>-<parent-id>-<trace-flags>)
-    let parts: Vec<&str> = traceparent.split('-').collect();
-    if parts.len() != 4 {
-        return None;
+/// 从入站请求头读取trace上下文的传播适配器
+pub struct HeaderExtractor<'a>(pub &'a HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|v| v.to_str().ok())
     }
-    
-    Some((parts[1].to_string(), parts[2].to_string()))
-} 
\ No newline at end of file
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|k| k.as_str()).collect()
+    }
+}
+
+/// 向出站请求头写入trace上下文的传播适配器
+pub struct HeaderInjector<'a>(pub &'a mut HeaderMap);
+
+impl Injector for HeaderInjector<'_> {
+    fn set(&mut self, key: &str, value: String) {
+        if let (Ok(name), Ok(value)) = (
+            HeaderName::from_bytes(key.as_bytes()),
+            HeaderValue::from_str(&value),
+        ) {
+            self.0.insert(name, value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{routing::get, Router};
+    use tower::ServiceExt;
+
+    /// 读取请求扩展里RequestId的处理函数
+    async fn echo_request_id(req: Request<Body>) -> String {
+        req.extensions()
+            .get::<RequestId>()
+            .map(|id| id.0.clone())
+            .unwrap_or_default()
+    }
+
+    #[tokio::test]
+    async fn test_generates_request_id_and_echoes_in_response() {
+        let app = Router::new()
+            .route("/", get(echo_request_id))
+            .layer(RequestIdLayer);
+
+        let resp = app
+            .oneshot(Request::builder().uri("/").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        let header_id = resp
+            .headers()
+            .get("x-request-id")
+            .and_then(|v| v.to_str().ok())
+            .expect("响应应带有x-request-id")
+            .to_string();
+        assert!(uuid::Uuid::parse_str(&header_id).is_ok());
+
+        // 扩展里的ID与响应头一致
+        let body = axum::body::to_bytes(resp.into_body(), 1024).await.unwrap();
+        assert_eq!(String::from_utf8_lossy(&body), header_id);
+    }
+
+    #[tokio::test]
+    async fn test_preserves_upstream_request_id() {
+        let app = Router::new()
+            .route("/", get(echo_request_id))
+            .layer(RequestIdLayer);
+
+        let resp = app
+            .oneshot(
+                Request::builder()
+                    .uri("/")
+                    .header("x-request-id", "upstream-id-42")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            resp.headers().get("x-request-id").unwrap(),
+            "upstream-id-42"
+        );
+    }
+
+    #[test]
+    fn test_sanitized_headers_redact_configured_values() {
+        let mut headers = HeaderMap::new();
+        headers.insert("authorization", "Bearer secret".parse().unwrap());
+        headers.insert("x-api-key", "key-123".parse().unwrap());
+        headers.insert("content-type", "application/json".parse().unwrap());
+
+        // 列表大小写不敏感（BodyLogLayer::new已统一为小写）
+        let sanitize = vec!["authorization".to_string(), "x-api-key".to_string()];
+        let sanitized = sanitized_headers(&headers, &sanitize);
+
+        let value = |name: &str| {
+            sanitized
+                .iter()
+                .find(|(n, _)| n == name)
+                .map(|(_, v)| v.clone())
+                .unwrap()
+        };
+        assert_eq!(value("authorization"), "[REDACTED]");
+        assert_eq!(value("x-api-key"), "[REDACTED]");
+        assert_eq!(value("content-type"), "application/json");
+    }
+
+    #[tokio::test]
+    async fn test_body_log_reinjects_chunked_body() {
+        // trace级别生效时走缓冲+回注路径
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::TRACE)
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let app = Router::new()
+            .route(
+                "/echo",
+                axum::routing::post(|body: axum::body::Bytes| async move { body }),
+            )
+            .layer(BodyLogLayer::new(8, vec!["authorization".to_string()]));
+
+        // chunked（长度未知）的请求体在日志截断后仍应完整到达下游
+        let chunks = vec![Ok::<_, std::convert::Infallible>("hello "), Ok("chunked world")];
+        let body = Body::from_stream(futures::stream::iter(chunks));
+        let resp = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/echo")
+                    .header("authorization", "Bearer secret")
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let echoed = axum::body::to_bytes(resp.into_body(), 1024).await.unwrap();
+        assert_eq!(&echoed[..], b"hello chunked world");
+    }
+
+    #[test]
+    fn test_header_extractor_honors_incoming_traceparent() {
+        use opentelemetry::propagation::TextMapPropagator;
+        use opentelemetry::trace::TraceContextExt;
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "traceparent",
+            "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01"
+                .parse()
+                .unwrap(),
+        );
+
+        let propagator = TraceContextPropagator::new();
+        let cx = propagator.extract(&HeaderExtractor(&headers));
+        let span_context = cx.span().span_context().clone();
+        assert!(span_context.is_valid());
+        assert_eq!(
+            span_context.trace_id().to_string(),
+            "0af7651916cd43dd8448eb211c80319c"
+        );
+        assert!(span_context.is_sampled());
+
+        // 注入回出站头应还原同一trace
+        let mut outgoing = HeaderMap::new();
+        propagator.inject_context(&cx, &mut HeaderInjector(&mut outgoing));
+        let traceparent = outgoing.get("traceparent").unwrap().to_str().unwrap();
+        assert!(traceparent.contains("0af7651916cd43dd8448eb211c80319c"));
+    }
+
+    #[tokio::test]
+    async fn test_body_log_noop_without_trace_level() {
+        // 未启用trace时中间件直接透传
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::INFO)
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let app = Router::new()
+            .route(
+                "/echo",
+                axum::routing::post(|body: axum::body::Bytes| async move { body }),
+            )
+            .layer(BodyLogLayer::new(8, vec![]));
+
+        let resp = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/echo")
+                    .body(Body::from("passthrough"))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let echoed = axum::body::to_bytes(resp.into_body(), 1024).await.unwrap();
+        assert_eq!(&echoed[..], b"passthrough");
+    }
+}
@@ -2,15 +2,14 @@ use anyhow::Result;
 use common::config::DynamicConfig;
 use common::service_registry::ServiceRegistry;
 use clap::Parser;
+use sqlx::postgres::PgPoolOptions;
 use std::net::SocketAddr;
 use std::sync::Arc;
 use tonic::transport::Server;
-use tracing::{info, warn, error, Level};
-use tracing_subscriber::FmtSubscriber;
+use tracing::{info, warn, error};
 use tokio::signal;
 use tokio::sync::oneshot;
 use axum::{Router, routing::get};
-use axum_server;
 
 mod service;
 mod repository;
@@ -38,13 +37,7 @@ struct Args {
 async fn main() -> Result<()> {
     // 初始化命令行参数
     let args = Args::parse();
-    
-    // 初始化日志
-    let subscriber = FmtSubscriber::builder()
-        .with_max_level(Level::INFO)
-        .finish();
-    tracing::subscriber::set_global_default(subscriber)?;
-    
+
     // 配置文件路径
     let mut config_paths = Vec::new();
     
@@ -73,11 +66,15 @@ async fn main() -> Result<()> {
         args.refresh
     )?);
     
-    // 启动配置监控线程
-    dynamic_config.clone().start_refresh_task();
-    
     // 获取初始配置
     let config = dynamic_config.get_config();
+
+    // 根据配置初始化日志，guard需持有到进程退出
+    let _log_guard = common::logging::init(&config.log);
+
+    // 启动配置监控线程
+    dynamic_config.clone().start_refresh_task();
+
     let host = &config.server.host;
     let port = config.server.port;
     let addr = format!("{}:{}", host, port).parse::<SocketAddr>()?;
@@ -85,11 +82,18 @@ async fn main() -> Result<()> {
     // 初始化Redis连接池
     let redis_client = redis::Client::open(config.redis.url())?;
     let redis_conn = redis_client.get_multiplexed_async_connection().await?;
-    
-    // 初始化认证服务
+
+    // 初始化数据库连接池（审计日志持久化）
+    let db_pool = PgPoolOptions::new()
+        .max_connections(10)
+        .connect(&config.database.url())
+        .await?;
+
+    // 初始化认证服务（订阅配置变更，jwt配置热更新后立即生效）
     let auth_service = AuthServiceImpl::new(
-        (*config).clone(),
+        dynamic_config.subscribe(),
         redis_conn,
+        db_pool,
     );
     
     // 创建HTTP服务器用于健康检查
@@ -97,15 +101,34 @@ async fn main() -> Result<()> {
     let health_service = start_health_service(host, health_port).await?;
     
     // 创建并注册到Consul
+    // CONSUL_CHECK_MODE=ttl 时改用TTL心跳上报，
+    // 适用于Consul agent无法回访健康端点的网络环境（NAT、sidecar）
     let service_registry = ServiceRegistry::from_env();
-    let service_id = service_registry.register_service(
-        "auth-service",
-        host,
-        health_port as u32, // 显式转换为u32类型
-        vec!["auth".to_string(), "api".to_string()],
-        "/health",
-        "15s",
-    ).await?;
+    let check_mode = std::env::var("CONSUL_CHECK_MODE").unwrap_or_else(|_| "http".to_string());
+    let service_id = if check_mode.eq_ignore_ascii_case("ttl") {
+        let ttl_secs = std::env::var("CONSUL_TTL_SECS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(15);
+        let id = service_registry.register_service_with_ttl(
+            "auth-service",
+            host,
+            health_port as u32, // 显式转换为u32类型
+            vec!["auth".to_string(), "api".to_string()],
+            ttl_secs,
+        ).await?;
+        service_registry.start_ttl_heartbeat();
+        id
+    } else {
+        service_registry.register_service(
+            "auth-service",
+            host,
+            health_port as u32, // 显式转换为u32类型
+            vec!["auth".to_string(), "api".to_string()],
+            "/health",
+            "15s",
+        ).await?
+    };
     
     info!("认证服务已注册到Consul, 服务ID: {}", service_id);
     
@@ -117,7 +140,14 @@ async fn main() -> Result<()> {
     info!("认证服务启动，监听地址: {}", addr);
     
     // 创建服务器并运行
-    let server = Server::builder()
+    let mut builder = Server::builder();
+    // 配置了server.tls时启用mTLS
+    if let Some(tls) = &config.server.tls {
+        builder = builder.tls_config(common::tls::server_tls_config(tls)?)?;
+    }
+    let server = builder
+        // 每个请求包一层带request_id的span，日志可跨服务关联
+        .layer(common::logging::RequestIdSpanLayer)
         .add_service(AuthServiceServer::new(auth_service))
         .serve_with_shutdown(addr, async {
             let _ = shutdown_rx.await;
@@ -203,7 +233,7 @@ async fn shutdown_signal(tx: oneshot::Sender<()>, service_registry: ServiceRegis
     }
     
     // 发送关闭信号
-    if let Err(_) = tx.send(()) {
+    if tx.send(()).is_err() {
         warn!("无法发送关闭信号，接收端可能已关闭");
     }
     
@@ -0,0 +1,116 @@
+use anyhow::Result;
+use chrono::NaiveDateTime;
+use common::utils::GeoInfo;
+use sqlx::PgPool;
+use tracing::debug;
+use uuid::Uuid;
+
+/// 审计事件类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditEventType {
+    Login,
+    Logout,
+    TokenRefresh,
+    /// 管理端批量失效令牌时使用
+    TokenInvalidate,
+}
+
+impl AuditEventType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditEventType::Login => "LOGIN",
+            AuditEventType::Logout => "LOGOUT",
+            AuditEventType::TokenRefresh => "TOKEN_REFRESH",
+            AuditEventType::TokenInvalidate => "TOKEN_INVALIDATE",
+        }
+    }
+}
+
+/// 审计日志条目
+#[derive(Debug)]
+pub struct AuditLogEntry {
+    pub id: String,
+    pub user_id: String,
+    pub event_type: String,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+/// 审计日志仓库，认证相关事件持久化到PostgreSQL
+pub struct AuditRepository {
+    pool: PgPool,
+}
+
+impl AuditRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// 记录一条审计事件，geo为按IP解析出的地理位置（解析失败时为None）
+    pub async fn log(
+        &self,
+        user_id: &str,
+        event_type: AuditEventType,
+        ip_address: Option<&str>,
+        user_agent: Option<&str>,
+        geo: Option<&GeoInfo>,
+    ) -> Result<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO audit_log (id, user_id, event_type, ip_address, user_agent, country, region, city)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            "#,
+            Uuid::new_v4().to_string(),
+            user_id,
+            event_type.as_str(),
+            ip_address,
+            user_agent,
+            geo.map(|g| g.country.as_str()),
+            geo.map(|g| g.region.as_str()),
+            geo.map(|g| g.city.as_str())
+        )
+        .execute(&self.pool)
+        .await?;
+
+        debug!("审计日志已记录: user_id={}, event={}", user_id, event_type.as_str());
+        Ok(())
+    }
+
+    /// 分页查询用户的审计日志，按时间倒序
+    pub async fn get_audit_log(
+        &self,
+        user_id: &str,
+        page: i64,
+        page_size: i64,
+    ) -> Result<Vec<AuditLogEntry>> {
+        let offset = (page - 1) * page_size;
+
+        let rows = sqlx::query!(
+            r#"
+            SELECT id, user_id, event_type, ip_address, user_agent, created_at
+            FROM audit_log
+            WHERE user_id = $1
+            ORDER BY created_at DESC
+            LIMIT $2 OFFSET $3
+            "#,
+            user_id,
+            page_size,
+            offset
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| AuditLogEntry {
+                id: row.id,
+                user_id: row.user_id,
+                event_type: row.event_type,
+                ip_address: row.ip_address,
+                user_agent: row.user_agent,
+                created_at: row.created_at,
+            })
+            .collect())
+    }
+}
@@ -1 +1,3 @@
-pub mod token_repository; 
\ No newline at end of file
+pub mod token_repository;
+pub mod audit_repository;
+pub mod throttle_repository;
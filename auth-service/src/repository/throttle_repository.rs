@@ -0,0 +1,140 @@
+use common::{Error, Result};
+use redis::{aio::MultiplexedConnection, AsyncCommands};
+use tracing::{debug, error};
+use uuid::Uuid;
+
+/// 认证失败节流仓库
+///
+/// 按主体（"user:{id}"或"ip:{addr}"）在Redis的有序集合里维护
+/// 滑动窗口失败计数：成员是带UUID后缀的唯一值，分数是毫秒时间戳，
+/// 每次操作先清除窗口外的旧记录。键随窗口长度设置过期时间，
+/// 不再失败的主体计数自动消失。
+pub struct ThrottleRepository {
+    redis: MultiplexedConnection,
+}
+
+impl ThrottleRepository {
+    pub fn new(redis: MultiplexedConnection) -> Self {
+        Self { redis }
+    }
+
+    /// 主体的计数键
+    fn key(subject: &str) -> String {
+        format!("auth_failures:{}", subject)
+    }
+
+    /// 当前毫秒时间戳
+    fn now_millis() -> i64 {
+        chrono::Utc::now().timestamp_millis()
+    }
+
+    /// 记录一次认证失败，返回窗口内的失败次数
+    pub async fn record_failure(&self, subject: &str, window_secs: u64) -> Result<u64> {
+        let mut conn = self.redis.clone();
+        let key = Self::key(subject);
+        let now = Self::now_millis();
+        let window_start = now - (window_secs as i64) * 1000;
+
+        // 清除窗口外的旧记录，写入本次失败，并顺延键的过期时间
+        let result: std::result::Result<((), (), u64, ()), _> = redis::pipe()
+            .zrembyscore(&key, 0, window_start)
+            .zadd(&key, format!("{}:{}", now, Uuid::new_v4()), now)
+            .zcard(&key)
+            .expire(&key, window_secs as i64)
+            .query_async(&mut conn)
+            .await;
+
+        match result {
+            Ok((_, _, count, _)) => {
+                debug!("主体 {} 窗口内失败 {} 次", subject, count);
+                Ok(count)
+            }
+            Err(err) => {
+                error!("记录认证失败时发生Redis错误: {}", err);
+                Err(Error::Redis(err))
+            }
+        }
+    }
+
+    /// 查询主体在窗口内的失败次数
+    pub async fn failure_count(&self, subject: &str, window_secs: u64) -> Result<u64> {
+        let mut conn = self.redis.clone();
+        let key = Self::key(subject);
+        let window_start = Self::now_millis() - (window_secs as i64) * 1000;
+
+        // 先清除窗口外的旧记录再计数
+        let result: std::result::Result<((), u64), _> = redis::pipe()
+            .zrembyscore(&key, 0, window_start)
+            .zcard(&key)
+            .query_async(&mut conn)
+            .await;
+
+        match result {
+            Ok((_, count)) => Ok(count),
+            Err(err) => {
+                error!("查询认证失败次数时发生Redis错误: {}", err);
+                Err(Error::Redis(err))
+            }
+        }
+    }
+
+    /// 主体是否已达到失败次数上限
+    pub async fn is_throttled(
+        &self,
+        subject: &str,
+        max_attempts: u32,
+        window_secs: u64,
+    ) -> Result<bool> {
+        Ok(self.failure_count(subject, window_secs).await? >= max_attempts as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    async fn test_repo() -> ThrottleRepository {
+        let client = redis::Client::open("redis://127.0.0.1:6379").unwrap();
+        ThrottleRepository::new(client.get_multiplexed_async_connection().await.unwrap())
+    }
+
+    #[tokio::test]
+    #[ignore = "需要本地Redis (redis://127.0.0.1:6379)"]
+    async fn test_threshold_reached_after_repeated_failures() {
+        let repo = test_repo().await;
+        let subject = format!("user:{}", Uuid::new_v4());
+
+        // 阈值之内不节流
+        for i in 1..=3 {
+            assert_eq!(repo.record_failure(&subject, 60).await.unwrap(), i);
+        }
+        assert!(!repo.is_throttled(&subject, 4, 60).await.unwrap());
+
+        // 达到阈值后节流
+        repo.record_failure(&subject, 60).await.unwrap();
+        assert!(repo.is_throttled(&subject, 4, 60).await.unwrap());
+
+        // 其他主体不受影响
+        let other = format!("ip:{}", Uuid::new_v4());
+        assert!(!repo.is_throttled(&other, 4, 60).await.unwrap());
+    }
+
+    #[tokio::test]
+    #[ignore = "需要本地Redis (redis://127.0.0.1:6379)"]
+    async fn test_counter_clears_after_window() {
+        let repo = test_repo().await;
+        let subject = format!("ip:{}", Uuid::new_v4());
+
+        // 1秒窗口内打满阈值
+        for _ in 0..3 {
+            repo.record_failure(&subject, 1).await.unwrap();
+        }
+        assert!(repo.is_throttled(&subject, 3, 1).await.unwrap());
+
+        // 窗口滑过后计数清零，节流解除
+        tokio::time::sleep(Duration::from_millis(1100)).await;
+        assert_eq!(repo.failure_count(&subject, 1).await.unwrap(), 0);
+        assert!(!repo.is_throttled(&subject, 3, 1).await.unwrap());
+    }
+}
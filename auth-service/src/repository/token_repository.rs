@@ -18,46 +18,88 @@ impl TokenRepository {
     }
     
     /// 存储访问令牌
-    pub async fn store_access_token(&self, user_id: &str, token: &str, expires_in: i64) -> Result<()> {
-        // 存储访问令牌，键为 access_token:{token}，值为用户ID
+    ///
+    /// 按JWT的jti声明而非整串令牌键控：键为 access_token:{jti}，
+    /// 值为用户ID。jti是定长UUID，比动辄数百字节的JWT省内存，
+    /// 吊销检查也是单次O(1)查找
+    pub async fn store_access_token(&self, user_id: &str, jti: &str, expires_in: i64) -> Result<()> {
         let mut conn = self.redis.clone();
-        let token_key = format!("access_token:{}", token);
+        let token_key = format!("access_token:{}", jti);
         let user_tokens_key = format!("user_tokens:{}", user_id);
-        
-        // 设置令牌 -> 用户ID 的映射，带过期时间
+
+        // 设置jti -> 用户ID 的映射，带过期时间
         if let Err(err) = conn.set_ex::<_, _, ()>(&token_key, user_id, expires_in as u64).await {
             error!("存储访问令牌失败: {}", err);
             return Err(Error::Redis(err));
         }
-        
+
         // 添加到用户的令牌集合中，便于查询和注销
-        match conn.sadd::<_, _, i32>(&user_tokens_key, &token).await {
+        match conn.sadd::<_, _, i32>(&user_tokens_key, jti).await {
             Ok(_) => debug!("将令牌添加到用户集合成功"),
             Err(err) => error!("将令牌添加到用户集合失败: {}", err),
         }
-        
+
         Ok(())
     }
     
-    /// 存储刷新令牌
-    pub async fn store_refresh_token(&self, user_id: &str, token: &str, expires_in: i64) -> Result<()> {
-        // 存储刷新令牌，键为 refresh_token:{token}，值为用户ID
+    /// 存储刷新令牌，并登记到所属的令牌族
+    ///
+    /// 除 refresh_token:{token} -> 用户ID 外，额外维护几份数据：
+    /// - refresh_token_family:{token} -> family_id，轮换后保留，用于识别旧令牌被重用
+    /// - refresh_family:{family_id}：该族签发过的全部刷新令牌集合，供整族吊销
+    /// - refresh_family_user:{family_id} -> 用户ID，重用检测时据此吊销该用户的全部令牌
+    /// - user_refresh_tokens:{user_id}：该用户的刷新令牌集合（与访问令牌
+    ///   的user_tokens集合对应），供按用户批量注销
+    pub async fn store_refresh_token(&self, user_id: &str, token: &str, family_id: &str, expires_in: i64) -> Result<()> {
         let mut conn = self.redis.clone();
         let token_key = format!("refresh_token:{}", token);
-        
+        let token_family_key = format!("refresh_token_family:{}", token);
+        let family_key = format!("refresh_family:{}", family_id);
+        let family_user_key = format!("refresh_family_user:{}", family_id);
+        let user_refresh_key = format!("user_refresh_tokens:{}", user_id);
+
         // 设置令牌 -> 用户ID 的映射，带过期时间
         if let Err(err) = conn.set_ex::<_, _, ()>(&token_key, user_id, expires_in as u64).await {
             error!("存储刷新令牌失败: {}", err);
             return Err(Error::Redis(err));
         }
-        
+
+        // 令牌 -> 令牌族 的映射，轮换失效后仍然保留到自然过期
+        if let Err(err) = conn.set_ex::<_, _, ()>(&token_family_key, family_id, expires_in as u64).await {
+            error!("存储刷新令牌族映射失败: {}", err);
+            return Err(Error::Redis(err));
+        }
+
+        // 登记到令牌族集合，集合有效期随最新一次签发顺延
+        match conn.sadd::<_, _, i32>(&family_key, token).await {
+            Ok(_) => debug!("将刷新令牌登记到令牌族成功"),
+            Err(err) => error!("将刷新令牌登记到令牌族失败: {}", err),
+        }
+        if let Err(err) = conn.expire::<_, i32>(&family_key, expires_in).await {
+            error!("设置令牌族过期时间失败: {}", err);
+        }
+
+        // 令牌族 -> 用户 的映射，有效期随最新一次签发顺延
+        if let Err(err) = conn.set_ex::<_, _, ()>(&family_user_key, user_id, expires_in as u64).await {
+            error!("存储令牌族归属用户失败: {}", err);
+        }
+
+        // 登记到用户的刷新令牌集合，供"全部设备退出"等批量注销使用
+        match conn.sadd::<_, _, i32>(&user_refresh_key, token).await {
+            Ok(_) => debug!("将刷新令牌添加到用户集合成功"),
+            Err(err) => error!("将刷新令牌添加到用户集合失败: {}", err),
+        }
+        if let Err(err) = conn.expire::<_, i32>(&user_refresh_key, expires_in).await {
+            error!("设置用户刷新令牌集合过期时间失败: {}", err);
+        }
+
         Ok(())
     }
     
-    /// 验证访问令牌
-    pub async fn validate_access_token(&self, token: &str) -> Result<Option<String>> {
+    /// 按jti验证访问令牌（调用方先解码JWT取出jti声明）
+    pub async fn validate_access_token(&self, jti: &str) -> Result<Option<String>> {
         let mut conn = self.redis.clone();
-        let token_key = format!("access_token:{}", token);
+        let token_key = format!("access_token:{}", jti);
         
         match conn.get::<_, Option<String>>(&token_key).await {
             Ok(Some(user_id)) => {
@@ -75,31 +117,111 @@ impl TokenRepository {
         }
     }
     
-    /// 验证刷新令牌
-    pub async fn validate_refresh_token(&self, token: &str) -> Result<Option<String>> {
+    /// 原子地校验并消费刷新令牌（轮换时调用）
+    ///
+    /// GETDEL保证校验与失效是同一个操作：同一令牌的两次并发轮换
+    /// 只有一次能拿到用户ID，另一次走重用检测路径。
+    /// 令牌族映射保留到自然过期，供后续识别旧令牌重用
+    pub async fn consume_refresh_token(&self, token: &str) -> Result<Option<String>> {
         let mut conn = self.redis.clone();
         let token_key = format!("refresh_token:{}", token);
-        
-        match conn.get::<_, Option<String>>(&token_key).await {
+
+        match redis::cmd("GETDEL")
+            .arg(&token_key)
+            .query_async::<Option<String>>(&mut conn)
+            .await
+        {
             Ok(Some(user_id)) => {
-                debug!("刷新令牌有效，用户ID: {}", user_id);
+                debug!("刷新令牌已消费，用户ID: {}", user_id);
                 Ok(Some(user_id))
-            },
+            }
             Ok(None) => {
-                debug!("刷新令牌不存在或已过期");
+                debug!("刷新令牌不存在或已被消费");
                 Ok(None)
-            },
+            }
             Err(err) => {
-                error!("验证刷新令牌时发生Redis错误: {}", err);
+                error!("消费刷新令牌时发生Redis错误: {}", err);
                 Err(Error::Redis(err))
             }
         }
     }
-    
-    /// 使令牌失效
-    pub async fn invalidate_token(&self, token: &str) -> Result<bool> {
+
+    /// 查询令牌族归属的用户
+    pub async fn refresh_family_user(&self, family_id: &str) -> Result<Option<String>> {
+        let mut conn = self.redis.clone();
+        let family_user_key = format!("refresh_family_user:{}", family_id);
+
+        match conn.get::<_, Option<String>>(&family_user_key).await {
+            Ok(user_id) => Ok(user_id),
+            Err(err) => {
+                error!("查询令牌族归属用户时发生Redis错误: {}", err);
+                Err(Error::Redis(err))
+            }
+        }
+    }
+
+    /// 查询刷新令牌所属的令牌族
+    ///
+    /// 该映射在令牌轮换失效后仍然存在：令牌本身已不可用但族映射还在，
+    /// 即说明这是一次旧令牌重用，调用方应按令牌被盗处理
+    pub async fn refresh_token_family(&self, token: &str) -> Result<Option<String>> {
+        let mut conn = self.redis.clone();
+        let token_family_key = format!("refresh_token_family:{}", token);
+
+        match conn.get::<_, Option<String>>(&token_family_key).await {
+            Ok(family_id) => Ok(family_id),
+            Err(err) => {
+                error!("查询刷新令牌族时发生Redis错误: {}", err);
+                Err(Error::Redis(err))
+            }
+        }
+    }
+
+    /// 吊销整个刷新令牌族（检测到旧令牌重用时调用）
+    pub async fn invalidate_refresh_family(&self, family_id: &str) -> Result<i32> {
+        let mut conn = self.redis.clone();
+        let family_key = format!("refresh_family:{}", family_id);
+
+        // 取出该族签发过的全部刷新令牌
+        let tokens: Vec<String> = match conn.smembers(&family_key).await {
+            Ok(tokens) => tokens,
+            Err(err) => {
+                error!("获取令牌族集合失败: {}", err);
+                return Err(Error::Redis(err));
+            }
+        };
+
+        let mut invalidated_count = 0;
+
+        for token in tokens {
+            let token_key = format!("refresh_token:{}", token);
+            let token_family_key = format!("refresh_token_family:{}", token);
+            match conn.del::<_, i32>(&token_key).await {
+                Ok(1) => {
+                    invalidated_count += 1;
+                    debug!("刷新令牌 {} 已失效", token);
+                }
+                Ok(_) => debug!("刷新令牌 {} 不存在或已失效", token),
+                Err(err) => error!("使刷新令牌 {} 失效时发生Redis错误: {}", token, err),
+            }
+            if let Err(err) = conn.del::<_, i32>(&token_family_key).await {
+                error!("删除刷新令牌族映射失败: {}", err);
+            }
+        }
+
+        // 删除令牌族集合本身
+        match conn.del::<_, i32>(&family_key).await {
+            Ok(_) => debug!("令牌族集合已删除"),
+            Err(err) => error!("删除令牌族集合失败: {}", err),
+        }
+
+        Ok(invalidated_count)
+    }
+
+    /// 按jti使访问令牌失效
+    pub async fn invalidate_token(&self, jti: &str) -> Result<bool> {
         let mut conn = self.redis.clone();
-        let access_token_key = format!("access_token:{}", token);
+        let access_token_key = format!("access_token:{}", jti);
         
         // 首先获取用户ID
         let user_id: Option<String> = match conn.get(&access_token_key).await {
@@ -113,7 +235,7 @@ impl TokenRepository {
         if let Some(user_id) = user_id {
             // 从用户的令牌集合中移除
             let user_tokens_key = format!("user_tokens:{}", user_id);
-            match conn.srem::<_, _, i32>(&user_tokens_key, token).await {
+            match conn.srem::<_, _, i32>(&user_tokens_key, jti).await {
                 Ok(_) => debug!("从用户集合中移除令牌成功"),
                 Err(err) => error!("从用户集合中移除令牌失败: {}", err),
             }
@@ -136,42 +258,184 @@ impl TokenRepository {
         }
     }
     
-    /// 使用户的所有令牌失效
-    #[warn(dead_code)]
+    /// 使用户的所有令牌失效（访问令牌与刷新令牌），返回失效数量
+    ///
+    /// 供"全部设备退出"和账号被盗处置流程调用：两个集合中登记的
+    /// 令牌全部删除后，用户现有会话既无法继续访问也无法再刷新
     pub async fn invalidate_user_tokens(&self, user_id: &str) -> Result<i32> {
         let mut conn = self.redis.clone();
         let user_tokens_key = format!("user_tokens:{}", user_id);
-        
-        // 获取用户的所有令牌
-        let tokens: Vec<String> = match conn.smembers(&user_tokens_key).await {
-            Ok(tokens) => tokens,
-            Err(err) => {
-                error!("获取用户令牌集合失败: {}", err);
-                return Err(Error::Redis(err));
-            }
-        };
-        
+        let user_refresh_key = format!("user_refresh_tokens:{}", user_id);
+
         let mut invalidated_count = 0;
-        
-        // 逐个删除令牌
-        for token in tokens {
-            let token_key = format!("access_token:{}", token);
-            match conn.del::<_, i32>(&token_key).await {
-                Ok(1) => {
-                    invalidated_count += 1;
-                    debug!("令牌 {} 已失效", token);
-                },
-                Ok(_) => debug!("令牌 {} 不存在或已失效", token),
-                Err(err) => error!("使令牌 {} 失效时发生Redis错误: {}", token, err),
+
+        // 逐个删除访问令牌和刷新令牌
+        for (set_key, prefix) in [
+            (&user_tokens_key, "access_token"),
+            (&user_refresh_key, "refresh_token"),
+        ] {
+            let tokens: Vec<String> = match conn.smembers(set_key).await {
+                Ok(tokens) => tokens,
+                Err(err) => {
+                    error!("获取用户令牌集合失败: {}", err);
+                    return Err(Error::Redis(err));
+                }
+            };
+
+            for token in tokens {
+                let token_key = format!("{}:{}", prefix, token);
+                match conn.del::<_, i32>(&token_key).await {
+                    Ok(1) => {
+                        invalidated_count += 1;
+                        debug!("令牌 {} 已失效", token);
+                    },
+                    Ok(_) => debug!("令牌 {} 不存在或已失效", token),
+                    Err(err) => error!("使令牌 {} 失效时发生Redis错误: {}", token, err),
+                }
+            }
+
+            // 清空该令牌集合
+            match conn.del::<_, i32>(set_key).await {
+                Ok(_) => debug!("用户令牌集合已清空"),
+                Err(err) => error!("清空用户令牌集合失败: {}", err),
             }
         }
-        
-        // 清空用户的令牌集合
-        match conn.del::<_, i32>(&user_tokens_key).await {
-            Ok(_) => debug!("用户令牌集合已清空"),
-            Err(err) => error!("清空用户令牌集合失败: {}", err),
-        }
-        
+
         Ok(invalidated_count)
     }
-} 
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    async fn test_repo() -> TokenRepository {
+        let client = redis::Client::open("redis://127.0.0.1:6379").unwrap();
+        TokenRepository::new(client.get_multiplexed_async_connection().await.unwrap())
+    }
+
+    #[tokio::test]
+    #[ignore = "需要本地Redis (redis://127.0.0.1:6379)"]
+    async fn test_rotation_consumes_token_exactly_once() {
+        let repo = test_repo().await;
+        let user_id = Uuid::new_v4().to_string();
+        let token = Uuid::new_v4().to_string();
+        let family_id = Uuid::new_v4().to_string();
+
+        repo.store_refresh_token(&user_id, &token, &family_id, 60)
+            .await
+            .unwrap();
+
+        // 首次消费返回用户ID并使令牌失效
+        assert_eq!(
+            repo.consume_refresh_token(&token).await.unwrap(),
+            Some(user_id.clone())
+        );
+        // 再次消费失败，但族映射仍可识别该令牌
+        assert_eq!(repo.consume_refresh_token(&token).await.unwrap(), None);
+        assert_eq!(
+            repo.refresh_token_family(&token).await.unwrap(),
+            Some(family_id.clone())
+        );
+
+        repo.invalidate_refresh_family(&family_id).await.unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore = "需要本地Redis (redis://127.0.0.1:6379)"]
+    async fn test_reuse_detection_revokes_family_and_user_tokens() {
+        let repo = test_repo().await;
+        let user_id = Uuid::new_v4().to_string();
+        let family_id = Uuid::new_v4().to_string();
+        let old_token = Uuid::new_v4().to_string();
+        let new_token = Uuid::new_v4().to_string();
+        let access_token = Uuid::new_v4().to_string();
+
+        // 模拟一次正常轮换：old_token已消费，new_token在同一族中有效
+        repo.store_refresh_token(&user_id, &old_token, &family_id, 60)
+            .await
+            .unwrap();
+        repo.store_access_token(&user_id, &access_token, 60)
+            .await
+            .unwrap();
+        assert!(repo.consume_refresh_token(&old_token).await.unwrap().is_some());
+        repo.store_refresh_token(&user_id, &new_token, &family_id, 60)
+            .await
+            .unwrap();
+
+        // old_token被重用：族映射暴露其归属，按被盗处理整族吊销
+        assert_eq!(repo.consume_refresh_token(&old_token).await.unwrap(), None);
+        let family = repo.refresh_token_family(&old_token).await.unwrap().unwrap();
+        assert_eq!(family, family_id);
+        assert_eq!(
+            repo.refresh_family_user(&family).await.unwrap(),
+            Some(user_id.clone())
+        );
+        repo.invalidate_refresh_family(&family).await.unwrap();
+        repo.invalidate_user_tokens(&user_id).await.unwrap();
+
+        // 族内尚未使用的new_token和用户的访问令牌全部失效
+        assert_eq!(repo.consume_refresh_token(&new_token).await.unwrap(), None);
+        assert_eq!(repo.validate_access_token(&access_token).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    #[ignore = "需要本地Redis (redis://127.0.0.1:6379)"]
+    async fn test_invalidate_user_tokens_purges_access_and_refresh() {
+        let repo = test_repo().await;
+        let user_id = Uuid::new_v4().to_string();
+
+        // 同一用户的多个会话：两个访问令牌 + 两个不同族的刷新令牌
+        let access_tokens: Vec<String> =
+            (0..2).map(|_| Uuid::new_v4().to_string()).collect();
+        let refresh_tokens: Vec<String> =
+            (0..2).map(|_| Uuid::new_v4().to_string()).collect();
+        for token in &access_tokens {
+            repo.store_access_token(&user_id, token, 60).await.unwrap();
+        }
+        for token in &refresh_tokens {
+            let family_id = Uuid::new_v4().to_string();
+            repo.store_refresh_token(&user_id, token, &family_id, 60)
+                .await
+                .unwrap();
+        }
+
+        // 批量注销返回全部令牌的数量
+        let revoked = repo.invalidate_user_tokens(&user_id).await.unwrap();
+        assert_eq!(revoked, 4);
+
+        // 访问令牌与刷新令牌全部失效
+        for token in &access_tokens {
+            assert_eq!(repo.validate_access_token(token).await.unwrap(), None);
+        }
+        for token in &refresh_tokens {
+            assert_eq!(repo.consume_refresh_token(token).await.unwrap(), None);
+        }
+
+        // 再次注销无可失效的令牌
+        assert_eq!(repo.invalidate_user_tokens(&user_id).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    #[ignore = "需要本地Redis (redis://127.0.0.1:6379)"]
+    async fn test_validate_and_revoke_access_token_by_jti() {
+        let repo = test_repo().await;
+        let user_id = Uuid::new_v4().to_string();
+        let jti = Uuid::new_v4().to_string();
+
+        // 按jti登记后可验证通过
+        repo.store_access_token(&user_id, &jti, 60).await.unwrap();
+        assert_eq!(
+            repo.validate_access_token(&jti).await.unwrap(),
+            Some(user_id.clone())
+        );
+
+        // 按jti吊销后验证被拒绝
+        assert!(repo.invalidate_token(&jti).await.unwrap());
+        assert_eq!(repo.validate_access_token(&jti).await.unwrap(), None);
+
+        // 已吊销的jti再次吊销返回false
+        assert!(!repo.invalidate_token(&jti).await.unwrap());
+    }
+}
\ No newline at end of file
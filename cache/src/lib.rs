@@ -33,6 +33,18 @@ pub trait Cache: Sync + Send + Debug {
     /// it returns the current send sequence and the max send sequence
     async fn get_send_seq(&self, user_id: &str) -> Result<(i64, i64), Error>;
 
+    /// set the read sequence for the conversation,
+    /// only moves forward: a smaller value than the stored one is ignored
+    async fn set_read_seq(
+        &self,
+        user_id: &str,
+        conversation_id: &str,
+        last_read_seq: i64,
+    ) -> Result<(), Error>;
+
+    /// query the read sequence for the conversation, 0 if never marked
+    async fn get_read_seq(&self, user_id: &str, conversation_id: &str) -> Result<i64, Error>;
+
     /// increase receive sequence by user id
     async fn increase_seq(&self, user_id: &str) -> Result<(i64, i64, bool), Error>;
 
@@ -1,3 +1,19 @@
+/// 所有proto中google.protobuf.Timestamp类型的字段名。
+/// prost_types::Timestamp不支持serde，这些字段统一走自定义适配，
+/// JSON侧表示为RFC3339字符串
+const TIMESTAMP_FIELDS: &[&str] = &[
+    "created_at",
+    "updated_at",
+    "friendship_created_at",
+    "announcement_updated_at",
+    "joined_at",
+    "muted_until",
+    "sent_at",
+    "last_active",
+    "read_at",
+    "last_seen",
+];
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     // 告诉Cargo如果proto文件发生变化，就重新运行此构建脚本
     println!("cargo:rerun-if-changed=proto/");
@@ -13,9 +29,20 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     // 编译所有proto文件
     // 使用tonic_build的configure方法来自定义生成的代码
     // 在tonic-build 0.13.0版本中，应该使用compile_protos方法
-    tonic_build::configure()
+    // 网关的JSON↔gRPC转码需要消息类型支持serde，
+    // 缺省字段按prost默认值处理
+    let mut builder = tonic_build::configure()
         .build_client(true)  // 生成客户端代码
         .build_server(true)  // 生成服务器代码
+        .type_attribute(".", "#[derive(serde::Serialize, serde::Deserialize)]")
+        .message_attribute(".", "#[serde(default)]");
+    for field in TIMESTAMP_FIELDS {
+        builder = builder.field_attribute(
+            field,
+            "#[serde(with = \"crate::proto::timestamp_serde\")]",
+        );
+    }
+    builder
         .compile(
             // 指定要编译的所有proto文件
             &[
@@ -26,6 +53,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 "proto/private_message.proto",
                 "proto/group_message.proto",
                 "proto/message_gateway.proto",
+                "proto/grpc_health.proto",
             ],
             // 指定proto文件的搜索路径，用于解析import语句
             &["proto"],
@@ -0,0 +1,188 @@
+use chrono::{Duration, Utc};
+use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::config::JwtConfig;
+use crate::Result;
+
+/// JWT负载的规范定义，签发与校验两侧共用
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Claims {
+    /// 用户ID
+    pub sub: String,
+    pub username: String,
+    /// 用户角色，网关按路由配置的required_roles校验
+    #[serde(default)]
+    pub roles: Vec<String>,
+    /// 令牌唯一标识（UUID），吊销名单按此键控而非整串JWT
+    #[serde(default)]
+    pub jti: String,
+    /// 过期时间（Unix秒）
+    pub exp: usize,
+    /// 签发时间（Unix秒）
+    pub iat: usize,
+}
+
+/// JWT校验器
+///
+/// 密钥来自`jwt.secret`配置，构造后不再读取任何全局状态，
+/// 配置热更新时用最新的[`JwtConfig`]重建即可。
+pub struct JwtValidator {
+    decoding_key: DecodingKey,
+    validation: Validation,
+}
+
+impl JwtValidator {
+    pub fn new(config: &JwtConfig) -> Self {
+        Self::from_secret(&config.secret)
+    }
+
+    pub fn from_secret(secret: &str) -> Self {
+        Self {
+            decoding_key: DecodingKey::from_secret(secret.as_bytes()),
+            validation: Validation::default(),
+        }
+    }
+
+    /// 校验令牌签名与有效期，返回其负载
+    pub fn validate(&self, token: &str) -> Result<Claims> {
+        let token_data = decode::<Claims>(token, &self.decoding_key, &self.validation)?;
+        Ok(token_data.claims)
+    }
+}
+
+/// 用配置中的密钥和有效期签发访问令牌
+///
+/// 返回(令牌, jti)：jti为本次签发的唯一标识，调用方以它为键
+/// 在Redis中登记令牌状态，吊销时按jti删除即可，无需存整串JWT
+pub fn generate_jwt(
+    user_id: &Uuid,
+    username: &str,
+    roles: &[String],
+    config: &JwtConfig,
+) -> Result<(String, String)> {
+    let now = Utc::now();
+    let expiration = now
+        .checked_add_signed(Duration::seconds(config.expiration as i64))
+        .expect("有效的时间戳")
+        .timestamp() as usize;
+
+    let jti = Uuid::new_v4().to_string();
+    let claims = Claims {
+        sub: user_id.to_string(),
+        username: username.to_string(),
+        roles: roles.to_vec(),
+        jti: jti.clone(),
+        exp: expiration,
+        iat: now.timestamp() as usize,
+    };
+
+    let token = encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(config.secret.as_bytes()),
+    )?;
+
+    Ok((token, jti))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_config() -> JwtConfig {
+        JwtConfig {
+            secret: "test_secret".to_string(),
+            expiration: 3600,
+        }
+    }
+
+    #[test]
+    fn test_roundtrip_preserves_claims() {
+        let config = test_config();
+        let user_id = Uuid::new_v4();
+        let roles = vec!["admin".to_string(), "user".to_string()];
+        let (token, jti) = generate_jwt(&user_id, "alice", &roles, &config).unwrap();
+
+        let claims = JwtValidator::new(&config).validate(&token).unwrap();
+        assert_eq!(claims.sub, user_id.to_string());
+        assert_eq!(claims.username, "alice");
+        assert_eq!(claims.roles, roles);
+        assert!(claims.exp > claims.iat);
+
+        // jti是合法UUID，且写入了令牌负载
+        assert!(Uuid::parse_str(&jti).is_ok());
+        assert_eq!(claims.jti, jti);
+
+        // 每次签发的jti互不相同
+        let (_, other_jti) = generate_jwt(&user_id, "alice", &roles, &config).unwrap();
+        assert_ne!(jti, other_jti);
+    }
+
+    #[test]
+    fn test_token_without_roles_claim_still_validates() {
+        let config = test_config();
+        let now = Utc::now().timestamp() as usize;
+        // 手工构造不含roles字段的负载，模拟升级前签发的存量令牌
+        let payload = serde_json::json!({
+            "sub": Uuid::new_v4().to_string(),
+            "username": "alice",
+            "jti": Uuid::new_v4().to_string(),
+            "exp": now + 3600,
+            "iat": now,
+        });
+        let token = encode(
+            &Header::default(),
+            &payload,
+            &EncodingKey::from_secret(config.secret.as_bytes()),
+        )
+        .unwrap();
+
+        let claims = JwtValidator::new(&config).validate(&token).unwrap();
+        assert!(claims.roles.is_empty());
+    }
+
+    #[test]
+    fn test_expired_token_is_rejected() {
+        let config = test_config();
+        let now = Utc::now().timestamp() as usize;
+        // 过期时间远超默认leeway（60秒），确保被判定为过期
+        let claims = Claims {
+            sub: Uuid::new_v4().to_string(),
+            username: "alice".to_string(),
+            roles: vec![],
+            jti: Uuid::new_v4().to_string(),
+            exp: now - 600,
+            iat: now - 4200,
+        };
+        let token = encode(
+            &Header::default(),
+            &claims,
+            &EncodingKey::from_secret(config.secret.as_bytes()),
+        )
+        .unwrap();
+
+        assert!(JwtValidator::new(&config).validate(&token).is_err());
+    }
+
+    #[test]
+    fn test_tampered_signature_is_rejected() {
+        let config = test_config();
+        let (token, _) = generate_jwt(&Uuid::new_v4(), "alice", &[], &config).unwrap();
+
+        // 篡改签名段最后一个字符
+        let mut tampered = token.clone();
+        let last = tampered.pop().unwrap();
+        tampered.push(if last == 'A' { 'B' } else { 'A' });
+        assert!(JwtValidator::new(&config).validate(&tampered).is_err());
+
+        // 用其他密钥签发的令牌同样被拒绝
+        let other = JwtConfig {
+            secret: "other_secret".to_string(),
+            expiration: 3600,
+        };
+        let (foreign, _) = generate_jwt(&Uuid::new_v4(), "alice", &[], &other).unwrap();
+        assert!(JwtValidator::new(&config).validate(&foreign).is_err());
+    }
+}
@@ -3,11 +3,11 @@ use dotenv::dotenv;
 use serde::{Deserialize, Serialize};
 use std::path::Path;
 use std::sync::{Arc, RwLock};
-use std::thread;
 use std::time::Duration;
+use tokio::sync::watch;
 use tracing::{error, info, warn};
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, PartialEq)]
 pub struct PostgresConfig {
     pub host: String,
     pub port: u16,
@@ -16,7 +16,7 @@ pub struct PostgresConfig {
     pub database: String,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, PartialEq)]
 pub struct MongodbConfig {
     pub host: String,
     pub port: u16,
@@ -26,13 +26,13 @@ pub struct MongodbConfig {
     pub clean: MongodbCleanConfig,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, PartialEq)]
 pub struct MongodbCleanConfig {
     pub period: u64,
     pub except_types: Vec<String>,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, PartialEq)]
 pub struct DatabaseConfig {
     pub postgres: PostgresConfig,
     pub mongodb: MongodbConfig,
@@ -52,7 +52,7 @@ impl DatabaseConfig {
     }
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
 pub struct RedisConfig {
     pub host: String,
     pub port: u16,
@@ -65,7 +65,7 @@ impl RedisConfig {
     }
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, PartialEq)]
 pub struct KafkaProducerConfig {
     pub timeout: u64,
     pub acks: String,
@@ -73,13 +73,13 @@ pub struct KafkaProducerConfig {
     pub retry_interval: u64,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, PartialEq)]
 pub struct KafkaConsumerConfig {
     pub auto_offset_reset: String,
     pub session_timeout: u64,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, PartialEq)]
 pub struct KafkaConfig {
     pub hosts: Vec<String>,
     pub topic: String,
@@ -89,13 +89,13 @@ pub struct KafkaConfig {
     pub consumer: KafkaConsumerConfig,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, PartialEq)]
 pub struct JwtConfig {
     pub secret: String,
     pub expiration: u64,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, PartialEq)]
 pub struct Oauth2Provider {
     pub client_id: String,
     pub client_secret: String,
@@ -106,18 +106,21 @@ pub struct Oauth2Provider {
     pub email_url: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, PartialEq)]
 pub struct Oauth2Config {
     pub google: Oauth2Provider,
     pub github: Oauth2Provider,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, PartialEq)]
 pub struct ServerConfig {
     pub host: String,
     pub port: u16,
     pub ws_lb_strategy: String,
     pub oauth2: Oauth2Config,
+    /// gRPC服务端TLS配置，未配置时使用明文
+    #[serde(default)]
+    pub tls: Option<TlsConfig>,
 }
 
 impl ServerConfig {
@@ -134,11 +137,38 @@ impl ServerConfig {
             port,
             ws_lb_strategy: self.ws_lb_strategy.clone(),
             oauth2: self.oauth2.clone(),
+            tls: self.tls.clone(),
         }
     }
 }
 
-#[derive(Debug, Deserialize, Clone)]
+/// 认证服务配置
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct AuthConfig {
+    /// 滑动窗口内同一主体（用户或IP）允许的认证失败次数上限，
+    /// 超过后返回resource_exhausted（防暴力破解）
+    pub max_failed_attempts: u32,
+    /// 认证失败计数的滑动窗口长度（秒），计数到期自动清零
+    pub failed_attempt_window_secs: u64,
+}
+
+/// 好友服务配置
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct FriendConfig {
+    /// 单个用户未处理的出站好友请求数量上限（防批量骚扰）
+    pub max_pending_requests: u32,
+    /// 好友请求的有效天数，超时未处理的PENDING请求会被定时清理
+    pub request_ttl_days: u32,
+}
+
+/// 群组服务配置
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct GroupConfig {
+    /// 新建群组的成员数上限默认值，写入groups.max_members
+    pub max_members: u32,
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
 pub struct ServiceCenterConfig {
     pub host: String,
     pub port: u16,
@@ -146,13 +176,21 @@ pub struct ServiceCenterConfig {
     pub protocol: String,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, PartialEq)]
 pub struct WebsocketConfig {
     pub protocol: String,
     pub host: String,
     pub port: u16,
     pub name: String,
     pub tags: Vec<String>,
+    /// 心跳间隔：连接空闲超过该秒数后发送Ping
+    pub heartbeat_interval_secs: u64,
+    /// 心跳超时：超过该秒数未收到任何客户端帧则关闭连接
+    pub heartbeat_timeout_secs: u64,
+    /// 是否启用出站消息压缩
+    pub compression: bool,
+    /// 压缩阈值（字节）：仅压缩超过该大小的消息
+    pub compress_threshold_bytes: usize,
 }
 
 impl WebsocketConfig {
@@ -176,13 +214,29 @@ impl WebsocketConfig {
     }
 }
 
-#[derive(Debug, Deserialize, Clone)]
+/// gRPC双向TLS配置
+///
+/// 所有路径指向PEM文件。由`crate::tls`中的辅助函数加载并转换为
+/// tonic的服务端/客户端TLS配置。
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct TlsConfig {
+    /// 本端证书
+    pub cert_path: String,
+    /// 本端私钥
+    pub key_path: String,
+    /// 信任的CA证书，用于校验对端
+    pub ca_path: String,
+    /// 服务端是否要求并校验客户端证书（双向TLS）
+    pub client_auth: bool,
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
 pub struct GrpcHealthCheckConfig {
     pub grpc_use_tls: bool,
     pub interval: u64,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, PartialEq)]
 pub struct RpcServiceConfig {
     pub protocol: String,
     pub host: String,
@@ -190,6 +244,9 @@ pub struct RpcServiceConfig {
     pub name: String,
     pub tags: Vec<String>,
     pub grpc_health_check: Option<GrpcHealthCheckConfig>,
+    /// 该rpc端点的TLS配置，未配置时使用明文
+    #[serde(default)]
+    pub tls: Option<TlsConfig>,
 }
 
 impl RpcServiceConfig {
@@ -209,7 +266,7 @@ impl RpcServiceConfig {
     }
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, PartialEq)]
 pub struct RpcConfig {
     pub health_check: bool,
     pub ws: RpcServiceConfig,
@@ -218,7 +275,7 @@ pub struct RpcConfig {
     pub pusher: RpcServiceConfig,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, PartialEq)]
 pub struct MailConfig {
     pub server: String,
     pub account: String,
@@ -227,10 +284,12 @@ pub struct MailConfig {
     pub temp_file: String,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, PartialEq)]
 pub struct LogConfig {
     pub level: String,
     pub output: String,
+    /// 错误响应详细程度：minimal仅返回通用消息与错误ID，full返回完整错误细节
+    pub error_verbosity: String,
 }
 
 impl LogConfig {
@@ -246,7 +305,7 @@ impl LogConfig {
     }
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, PartialEq)]
 pub struct AppConfig {
     pub component: Component,
     pub log: LogConfig,
@@ -260,9 +319,15 @@ pub struct AppConfig {
     pub jwt: JwtConfig,
     pub oss: OssConfig,
     pub mail: MailConfig,
+    /// 出站Webhook配置，未配置时禁用
+    #[serde(default)]
+    pub webhook: crate::webhook::WebhookConfig,
+    pub friend: FriendConfig,
+    pub group: GroupConfig,
+    pub auth: AuthConfig,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
 pub struct OssConfig {
     pub endpoint: String,
     pub access_key: String,
@@ -270,9 +335,17 @@ pub struct OssConfig {
     pub bucket: String,
     pub avatar_bucket: String,
     pub region: String,
+    /// 头像上传大小上限（字节）
+    #[serde(default = "default_max_avatar_bytes")]
+    pub max_avatar_bytes: usize,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+/// 头像上传大小上限的默认值：5MB
+fn default_max_avatar_bytes() -> usize {
+    5 * 1024 * 1024
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
 #[serde(rename_all = "lowercase")]
 pub enum Component {
     Api,
@@ -288,8 +361,21 @@ pub struct DynamicConfig {
     current: RwLock<Arc<AppConfig>>,
     config_paths: Vec<String>,
     refresh_interval: Duration,
+    // 配置变更通知通道，仅在配置真正变化时发布
+    sender: watch::Sender<Arc<AppConfig>>,
 }
 
+/// 兼容期内仍支持的旧环境变量名及其对应的配置键（已废弃，使用APP__双下划线形式）
+const LEGACY_ENV_OVERRIDES: &[(&str, &str)] = &[
+    ("DATABASE_POSTGRES_HOST", "database.postgres.host"),
+    ("DATABASE_POSTGRES_PORT", "database.postgres.port"),
+    ("DATABASE_POSTGRES_USER", "database.postgres.user"),
+    ("DATABASE_POSTGRES_PASSWORD", "database.postgres.password"),
+    ("DATABASE_POSTGRES_DATABASE", "database.postgres.database"),
+    ("REDIS_HOST", "redis.host"),
+    ("REDIS_PORT", "redis.port"),
+];
+
 impl AppConfig {
     // 创建一个新的AppConfig实例
     pub fn new() -> Result<Self, ConfigError> {
@@ -309,6 +395,7 @@ impl AppConfig {
             .set_default("component", "all")?
             .set_default("log.level", "debug")?
             .set_default("log.output", "console")?
+            .set_default("log.error_verbosity", "minimal")?
             .set_default("database.postgres.host", "127.0.0.1")?
             .set_default("database.postgres.port", 5432)?
             .set_default("database.postgres.user", "kelisi")?
@@ -335,6 +422,15 @@ impl AppConfig {
                 "websocket.tags",
                 vec!["websocket".to_string(), "grpc".to_string()],
             )?
+            .set_default("websocket.heartbeat_interval_secs", 30)?
+            .set_default("websocket.heartbeat_timeout_secs", 90)?
+            .set_default("websocket.compression", false)?
+            .set_default("websocket.compress_threshold_bytes", 1024)?
+            .set_default("friend.max_pending_requests", 50)?
+            .set_default("friend.request_ttl_days", 7)?
+            .set_default("group.max_members", 500)?
+            .set_default("auth.max_failed_attempts", 10)?
+            .set_default("auth.failed_attempt_window_secs", 60)?
             .set_default("rpc.health_check", false)?
             .set_default("rpc.ws.protocol", "http")?
             .set_default("rpc.ws.host", "127.0.0.1")?
@@ -386,6 +482,7 @@ impl AppConfig {
             .set_default("oss.bucket", "rustIM")?
             .set_default("oss.avatar_bucket", "rustIM-avatar")?
             .set_default("oss.region", "us-east-1")?
+            .set_default("oss.max_avatar_bytes", 5 * 1024 * 1024)?
             .set_default("mail.server", "smtp.qq.com")?
             .set_default("mail.account", "17788889999@qq.com")?
             .set_default("mail.password", "iejtiohyreybgdf")?
@@ -433,13 +530,27 @@ impl AppConfig {
         }
 
         // 4. 读取环境变量 (最高优先级)
-        builder = builder.add_source(config::Environment::default().separator("_"));
+        // 旧方案Environment::default().separator("_")会按每个下划线拆分变量名，
+        // 含下划线的配置键（seq_step、access_key、avatar_bucket等）无法从环境覆盖。
+        // 改为APP前缀+双下划线：APP__DATABASE__POSTGRES__HOST -> database.postgres.host。
+        // 兼容期内仍读取旧的常用数据库/Redis变量名，新形式存在时以新形式为准。
+        for (legacy_name, key) in LEGACY_ENV_OVERRIDES {
+            let new_name = format!("APP__{}", key.replace('.', "__").to_uppercase());
+            if std::env::var(&new_name).is_ok() {
+                continue;
+            }
+            if let Ok(value) = std::env::var(legacy_name) {
+                warn!("环境变量{}已废弃，请改用{}", legacy_name, new_name);
+                builder = builder.set_override(*key, value)?;
+            }
+        }
+        builder = builder.add_source(config::Environment::with_prefix("APP").separator("__"));
 
         // 构建配置
         let config = builder.build()?;
 
         // 转换为AppConfig结构体
-        Ok(config.try_deserialize()?)
+        config.try_deserialize()
     }
 }
 
@@ -450,12 +561,20 @@ impl DynamicConfig {
         refresh_interval_secs: u64,
     ) -> Result<Self, ConfigError> {
         let interval = Duration::from_secs(refresh_interval_secs);
-        let config = AppConfig::new()?;
+        // 初始配置与刷新逻辑一致：优先从配置路径加载，均不存在时回退到环境变量
+        let config = Arc::new(
+            match config_paths.iter().find(|p| Path::new(p).exists()) {
+                Some(path) => AppConfig::from_file(Some(path))?,
+                None => AppConfig::new()?,
+            },
+        );
+        let (sender, _) = watch::channel(config.clone());
 
         Ok(DynamicConfig {
-            current: RwLock::new(Arc::new(config)),
+            current: RwLock::new(config),
             config_paths,
             refresh_interval: interval,
+            sender,
         })
     }
 
@@ -464,21 +583,29 @@ impl DynamicConfig {
         self.current.read().unwrap().clone()
     }
 
-    // 启动配置监控线程
+    // 订阅配置变更，接收端总是能读到最新配置
+    pub fn subscribe(&self) -> watch::Receiver<Arc<AppConfig>> {
+        self.sender.subscribe()
+    }
+
+    // 启动配置监控任务
     pub fn start_refresh_task(self: Arc<Self>) {
         let dynamic_config = self.clone();
 
-        thread::spawn(move || {
+        tokio::spawn(async move {
             info!(
-                "配置监控线程启动，刷新间隔: {:?}",
+                "配置监控任务启动，刷新间隔: {:?}",
                 dynamic_config.refresh_interval
             );
 
+            let mut interval = tokio::time::interval(dynamic_config.refresh_interval);
+            // 第一次tick立即完成，跳过以避免启动时多余的刷新
+            interval.tick().await;
+
             loop {
-                thread::sleep(dynamic_config.refresh_interval);
-                match dynamic_config.refresh_config() {
-                    Ok(_) => info!("配置已更新"),
-                    Err(e) => error!("刷新配置失败: {}", e),
+                interval.tick().await;
+                if let Err(e) = dynamic_config.refresh_config() {
+                    error!("刷新配置失败: {}", e);
                 }
             }
         });
@@ -495,8 +622,7 @@ impl DynamicConfig {
             match AppConfig::from_file(Some(path)) {
                 Ok(new_config) => {
                     // 更新当前配置
-                    let mut current = self.current.write().unwrap();
-                    *current = Arc::new(new_config);
+                    self.apply_config(new_config);
                     info!("已从文件 {} 加载新配置", path);
                     return Ok(());
                 }
@@ -509,8 +635,7 @@ impl DynamicConfig {
         // 如果所有路径都失败，尝试从环境变量加载
         match AppConfig::new() {
             Ok(new_config) => {
-                let mut current = self.current.write().unwrap();
-                *current = Arc::new(new_config);
+                self.apply_config(new_config);
                 info!("已从环境变量加载新配置");
                 Ok(())
             }
@@ -520,6 +645,29 @@ impl DynamicConfig {
             }
         }
     }
+
+    // 替换当前配置并通知订阅者，配置未变化时不发布
+    fn apply_config(&self, new_config: AppConfig) {
+        let new_level = new_config.log.level();
+        let mut current = self.current.write().unwrap();
+
+        if **current == new_config {
+            return;
+        }
+
+        let level_changed = current.log.level() != new_level;
+        let new_config = Arc::new(new_config);
+        *current = new_config.clone();
+        drop(current);
+
+        // 通知订阅者，没有接收端时send会失败，忽略即可
+        let _ = self.sender.send(new_config);
+        info!("配置已更新，已通知订阅者");
+
+        if level_changed {
+            crate::logging::set_level(new_level);
+        }
+    }
 }
 
 // 辅助函数，用于构建URL字符串
@@ -537,16 +685,73 @@ mod tests {
 
     #[test]
     fn test_load() {
-        let config = match AppConfig::from_file(Some("./config/config.yaml")) {
+        let config = match AppConfig::from_file(Some("../config/config.yaml")) {
             Ok(config) => config,
             Err(err) => {
                 panic!("load config error: {:?}", err);
             }
         };
         println!("{:?}", config);
-        assert_eq!(config.database.postgres.host, "localhost");
+        assert_eq!(config.database.postgres.host, "127.0.0.1");
         assert_eq!(config.database.postgres.port, 5432);
         assert_eq!(config.database.postgres.user, "kelisi");
         assert_eq!(config.database.postgres.password, "123456");
     }
+
+    #[test]
+    fn test_env_override() {
+        // 新形式：APP前缀+双下划线，含下划线的字段名不再被错误拆分
+        std::env::set_var("APP__REDIS__SEQ_STEP", "500");
+        std::env::set_var("APP__OSS__ACCESS_KEY", "env_access_key");
+        std::env::set_var("APP__SERVICE_CENTER__TIMEOUT", "9000");
+        // 旧形式兼容：无前缀的常用数据库/Redis变量名
+        std::env::set_var("REDIS_HOST", "10.1.2.3");
+        // 新旧同时存在时新形式优先
+        std::env::set_var("REDIS_PORT", "6380");
+        std::env::set_var("APP__REDIS__PORT", "6390");
+
+        let config = AppConfig::from_file(Some("../config/config.yaml")).expect("load config");
+        assert_eq!(config.redis.seq_step, 500);
+        assert_eq!(config.oss.access_key, "env_access_key");
+        assert_eq!(config.service_center.timeout, 9000);
+        assert_eq!(config.redis.host, "10.1.2.3");
+        assert_eq!(config.redis.port, 6390);
+
+        for name in [
+            "APP__REDIS__SEQ_STEP",
+            "APP__OSS__ACCESS_KEY",
+            "APP__SERVICE_CENTER__TIMEOUT",
+            "REDIS_HOST",
+            "REDIS_PORT",
+            "APP__REDIS__PORT",
+        ] {
+            std::env::remove_var(name);
+        }
+    }
+
+    /// 刷新任务基于tokio定时器而非OS线程，暂停时钟快进即可触发刷新，
+    /// 测试无需真实等待刷新间隔
+    #[tokio::test(start_paused = true)]
+    async fn test_refresh_task_picks_up_file_changes_with_paused_clock() {
+        // 以完整的样例配置为底本，测试只改动group.max_members
+        let base = std::fs::read_to_string("../config/config.yaml").unwrap();
+        let path = std::env::temp_dir()
+            .join(format!("dynamic-config-test-{}.yaml", std::process::id()));
+        std::fs::write(&path, &base).unwrap();
+
+        let dynamic = Arc::new(
+            DynamicConfig::new(vec![path.to_string_lossy().into_owned()], 60).unwrap(),
+        );
+        assert_eq!(dynamic.get_config().group.max_members, 500);
+        let mut rx = dynamic.subscribe();
+        dynamic.clone().start_refresh_task();
+
+        // 修改配置文件后快进时钟触发下一次tick，订阅端应收到新配置
+        std::fs::write(&path, format!("{}\ngroup:\n  max_members: 999\n", base)).unwrap();
+        tokio::time::advance(Duration::from_secs(61)).await;
+        rx.changed().await.unwrap();
+        assert_eq!(dynamic.get_config().group.max_members, 999);
+
+        std::fs::remove_file(&path).ok();
+    }
 }
@@ -1,3 +1,5 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
 use aws_sdk_s3::error::SdkError;
 use axum::http::StatusCode;
 use axum::Json;
@@ -5,6 +7,42 @@ use axum::response::{IntoResponse, Response};
 use serde::de::StdError;
 use serde_json::json;
 use thiserror::Error;
+use uuid::Uuid;
+
+/// 错误响应详细程度，控制[`IntoResponse`]是否向客户端暴露内部错误细节
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorVerbosity {
+    /// 仅返回通用消息与错误ID，完整错误通过该ID在日志中检索（生产环境）
+    Minimal,
+    /// 直接返回完整错误细节（开发环境）
+    Full,
+}
+
+impl ErrorVerbosity {
+    /// 从配置取值`log.error_verbosity`解析，未知取值按minimal处理以免误泄露细节
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "full" => ErrorVerbosity::Full,
+            _ => ErrorVerbosity::Minimal,
+        }
+    }
+}
+
+// 全局错误响应详细程度，服务启动时由logging::init根据配置设置
+static ERROR_VERBOSITY_FULL: AtomicBool = AtomicBool::new(false);
+
+/// 设置全局错误响应详细程度
+pub fn set_error_verbosity(verbosity: ErrorVerbosity) {
+    ERROR_VERBOSITY_FULL.store(verbosity == ErrorVerbosity::Full, Ordering::Relaxed);
+}
+
+fn error_verbosity() -> ErrorVerbosity {
+    if ERROR_VERBOSITY_FULL.load(Ordering::Relaxed) {
+        ErrorVerbosity::Full
+    } else {
+        ErrorVerbosity::Minimal
+    }
+}
 
 #[derive(Debug, Error)]
 pub enum Error {
@@ -68,6 +106,12 @@ pub enum Error {
     #[error("gRPC状态错误: {0}")]
     TonicStatus(#[from] tonic::Status),
 
+    #[error("请求过于频繁，请稍后重试")]
+    RateLimited { retry_after_secs: u64 },
+
+    #[error("服务暂时不可用: {0}")]
+    ServiceUnavailable(String),
+
     #[error("对象存储服务错误")]
     OSSError,
     
@@ -102,6 +146,7 @@ impl From<Error> for tonic::Status {
             Error::Authentication(msg) => tonic::Status::unauthenticated(msg),
             Error::Authorization(msg) => tonic::Status::permission_denied(msg),
             Error::BadRequest(msg) => tonic::Status::invalid_argument(msg),
+            Error::ServiceUnavailable(msg) => tonic::Status::unavailable(msg),
             _ => tonic::Status::internal(error.to_string()),
         }
     }
@@ -111,7 +156,7 @@ impl<E> From<SdkError<E>> for Error
 where
     E: StdError + 'static,
 {
-    fn from(err: SdkError<E>) -> Self {
+    fn from(_err: SdkError<E>) -> Self {
         Error::OSSError
     }
 }
@@ -125,15 +170,30 @@ impl From<Error> for axum::http::StatusCode {
             Error::Authentication(_) => StatusCode::UNAUTHORIZED,
             Error::Authorization(_) => StatusCode::FORBIDDEN,
             Error::BadRequest(_) => StatusCode::BAD_REQUEST,
+            Error::ServiceUnavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
             _ => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
 }
 
-impl IntoResponse for Error {
-    fn into_response(self) -> Response {
+impl Error {
+    // 按指定详细程度渲染为HTTP响应，into_response使用全局配置的详细程度
+    fn into_response_with(self, verbosity: ErrorVerbosity) -> Response {
         let (status, message) = match self {
-            Error::Unauthorized(_) => (StatusCode::UNAUTHORIZED, "未授权访问".to_string()),
+            // 限流响应需要额外的Retry-After头，单独处理
+            Error::RateLimited { retry_after_secs } => {
+                let mut headers = axum::http::HeaderMap::new();
+                if retry_after_secs > 0 {
+                    headers.insert("Retry-After", axum::http::HeaderValue::from(retry_after_secs));
+                }
+                let json = Json(json!({
+                    "error": 429,
+                    "message": "请求过于频繁，请稍后重试",
+                    "retry_after": retry_after_secs,
+                }));
+                return (StatusCode::TOO_MANY_REQUESTS, headers, json).into_response();
+            }
+            Error::Unauthorized => (StatusCode::UNAUTHORIZED, "未授权访问".to_string()),
             Error::TokenExpired => (StatusCode::UNAUTHORIZED, "Token已过期".to_string()),
             Error::InvalidToken => (StatusCode::UNAUTHORIZED, "Token无效".to_string()),
             Error::InvalidIssuer => (StatusCode::UNAUTHORIZED, "签发者无效".to_string()),
@@ -141,8 +201,37 @@ impl IntoResponse for Error {
             Error::InvalidApiKey => (StatusCode::UNAUTHORIZED, "API Key无效".to_string()),
             Error::ApiKeyExpired => (StatusCode::UNAUTHORIZED, "API Key已过期".to_string()),
             Error::OAuth2Error(msg) => (StatusCode::UNAUTHORIZED, msg),
-            Error::Internal(_) => (StatusCode::INTERNAL_SERVER_ERROR, "内部认证错误".to_string()),
-            _ => todo!(),
+            Error::Authentication(msg) => (StatusCode::UNAUTHORIZED, msg),
+            Error::Authorization(msg) => (StatusCode::FORBIDDEN, msg),
+            Error::NotFound(msg) => (StatusCode::NOT_FOUND, msg),
+            Error::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg),
+            Error::ServiceUnavailable(msg) => (StatusCode::SERVICE_UNAVAILABLE, msg),
+            // 内部/基础设施类错误：完整细节连同错误ID写入日志；
+            // minimal下客户端仅收到通用消息与用于关联日志的错误ID，full下返回细节
+            error @ (Error::Internal(_)
+            | Error::Database(_)
+            | Error::Redis(_)
+            | Error::IO(_)
+            | Error::Json(_)
+            | Error::Jwt(_)
+            | Error::Tonic(_)
+            | Error::TonicStatus(_)
+            | Error::OSSError
+            | Error::BroadCastError(_)) => {
+                let error_id = Uuid::new_v4().to_string();
+                let detail = error.to_string();
+                tracing::error!(error_id = %error_id, "内部错误: {}", detail);
+                let message = match verbosity {
+                    ErrorVerbosity::Full => detail,
+                    ErrorVerbosity::Minimal => "内部服务错误".to_string(),
+                };
+                let json = Json(json!({
+                    "error": 500,
+                    "message": message,
+                    "error_id": error_id,
+                }));
+                return (StatusCode::INTERNAL_SERVER_ERROR, json).into_response();
+            }
         };
 
         let json = Json(json!({
@@ -152,4 +241,188 @@ impl IntoResponse for Error {
 
         (status, json).into_response()
     }
-} 
\ No newline at end of file
+}
+
+impl IntoResponse for Error {
+    fn into_response(self) -> Response {
+        self.into_response_with(error_verbosity())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 将Error转换为HTTP响应并提取状态码与JSON消息
+    async fn render(error: Error) -> (StatusCode, serde_json::Value) {
+        let response = error.into_response();
+        let status = response.status();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        (status, body)
+    }
+
+    #[tokio::test]
+    async fn test_not_found_maps_to_404() {
+        let (status, body) = render(Error::NotFound("用户不存在".to_string())).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["error"], 404);
+        assert_eq!(body["message"], "用户不存在");
+    }
+
+    #[tokio::test]
+    async fn test_bad_request_maps_to_400() {
+        let (status, body) = render(Error::BadRequest("参数无效".to_string())).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], 400);
+        assert_eq!(body["message"], "参数无效");
+    }
+
+    #[tokio::test]
+    async fn test_authorization_maps_to_403() {
+        let (status, body) = render(Error::Authorization("无权访问该资源".to_string())).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(body["message"], "无权访问该资源");
+
+        let (status, body) = render(Error::InsufficientPermissions).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(body["message"], "没有足够的权限");
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_maps_to_429_with_retry_after() {
+        let response = Error::RateLimited { retry_after_secs: 7 }.into_response();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers().get("Retry-After").unwrap(), "7");
+
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["error"], 429);
+        assert_eq!(body["message"], "请求过于频繁，请稍后重试");
+        assert_eq!(body["retry_after"], 7);
+
+        // 等待时间为0时不发送Retry-After头
+        let response = Error::RateLimited { retry_after_secs: 0 }.into_response();
+        assert!(response.headers().get("Retry-After").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_infrastructure_errors_map_to_500_without_detail() {
+        let errors: Vec<Error> = vec![
+            Error::Database(sqlx::Error::RowNotFound),
+            Error::IO(std::io::Error::other("disk full")),
+            Error::Json(serde_json::from_str::<serde_json::Value>("{").unwrap_err()),
+            Error::TonicStatus(tonic::Status::internal("upstream failed")),
+            Error::OSSError,
+            Error::BroadCastError("channel closed".to_string()),
+        ];
+        for error in errors {
+            let (status, body) = render(error).await;
+            assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+            assert_eq!(body["error"], 500);
+            // 默认minimal，不泄露内部错误细节
+            assert_eq!(body["message"], "内部服务错误");
+        }
+    }
+
+    // 按指定详细程度渲染，绕过全局配置以便并发测试互不干扰
+    async fn render_with(
+        error: Error,
+        verbosity: ErrorVerbosity,
+    ) -> (StatusCode, serde_json::Value) {
+        let response = error.into_response_with(verbosity);
+        let status = response.status();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        (status, body)
+    }
+
+    #[test]
+    fn test_error_verbosity_from_name() {
+        assert_eq!(ErrorVerbosity::from_name("full"), ErrorVerbosity::Full);
+        assert_eq!(ErrorVerbosity::from_name("minimal"), ErrorVerbosity::Minimal);
+        // 未知取值按minimal处理
+        assert_eq!(ErrorVerbosity::from_name("verbose"), ErrorVerbosity::Minimal);
+    }
+
+    #[tokio::test]
+    async fn test_minimal_verbosity_returns_generic_message_with_error_id() {
+        let (status, body) = render_with(
+            Error::Internal("数据库连接池耗尽".to_string()),
+            ErrorVerbosity::Minimal,
+        )
+        .await;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(body["message"], "内部服务错误");
+        // 错误ID可用于在日志中检索完整错误
+        let error_id = body["error_id"].as_str().unwrap();
+        assert!(Uuid::parse_str(error_id).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_full_verbosity_includes_detail() {
+        let (status, body) = render_with(
+            Error::Internal("数据库连接池耗尽".to_string()),
+            ErrorVerbosity::Full,
+        )
+        .await;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(body["message"], "内部服务错误: 数据库连接池耗尽");
+        // full模式同样带错误ID，方便与日志关联
+        assert!(body["error_id"].as_str().is_some());
+    }
+
+    // 捕获日志输出到内存缓冲区的写入器
+    #[derive(Clone, Default)]
+    struct LogBuffer(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl std::io::Write for LogBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl<'a> tracing_subscriber::fmt::MakeWriter<'a> for LogBuffer {
+        type Writer = LogBuffer;
+
+        fn make_writer(&'a self) -> Self::Writer {
+            self.clone()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_error_id_correlates_response_with_logs() {
+        let buffer = LogBuffer::default();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer(buffer.clone())
+            .with_ansi(false)
+            .finish();
+
+        let response = tracing::subscriber::with_default(subscriber, || {
+            Error::Internal("数据库连接池耗尽".to_string())
+                .into_response_with(ErrorVerbosity::Minimal)
+        });
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        let error_id = body["error_id"].as_str().unwrap();
+
+        // 日志中包含错误ID与完整错误细节，即使响应体里没有细节
+        let logs = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        assert!(logs.contains(error_id));
+        assert!(logs.contains("数据库连接池耗尽"));
+        assert_eq!(body["message"], "内部服务错误");
+    }
+}
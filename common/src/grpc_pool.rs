@@ -0,0 +1,333 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+use tonic::transport::{Channel, Endpoint};
+use tracing::{info, warn};
+
+use crate::proto::health::health_check_response::ServingStatus;
+use crate::proto::health::health_client::HealthClient;
+use crate::proto::health::HealthCheckRequest;
+use crate::{Error, Result};
+
+/// 熔断器状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakerState {
+    /// 关闭 - 请求正常通过
+    Closed,
+    /// 开启 - 快速失败，不发送请求
+    Open,
+    /// 半开 - 放行探测请求以确认端点是否恢复
+    HalfOpen,
+}
+
+/// 熔断器内部可变状态，整体加锁避免多把锁之间的状态不一致
+struct BreakerInner {
+    state: BreakerState,
+    failure_count: u64,
+    last_failure: Instant,
+}
+
+/// 端点级熔断器
+///
+/// api-gateway按"服务名+端点"维护自己的熔断注册表，但common不能
+/// 反向依赖网关，这里内置一份语义一致的轻量实现（关闭/开启/半开
+/// 三态，连续失败达到阈值后开启，重置时间过后放行探测请求），
+/// 供各服务的出站gRPC调用复用。
+pub struct CircuitBreaker {
+    inner: Mutex<BreakerInner>,
+    failure_threshold: u64,
+    reset_timeout: Duration,
+    /// 目标端点URL，用于日志
+    target: String,
+}
+
+impl CircuitBreaker {
+    /// 创建新的熔断器
+    pub fn new(target: &str, failure_threshold: u64, reset_timeout: Duration) -> Self {
+        Self {
+            inner: Mutex::new(BreakerInner {
+                state: BreakerState::Closed,
+                failure_count: 0,
+                last_failure: Instant::now(),
+            }),
+            failure_threshold,
+            reset_timeout,
+            target: target.to_string(),
+        }
+    }
+
+    /// 当前熔断器状态
+    pub fn state(&self) -> BreakerState {
+        self.inner.lock().unwrap().state
+    }
+
+    /// 检查是否放行请求，必要时进行状态转换（开启→半开）
+    pub fn check(&self) -> bool {
+        let mut inner = self.inner.lock().unwrap();
+        match inner.state {
+            BreakerState::Closed => true,
+            BreakerState::Open => {
+                if inner.last_failure.elapsed() >= self.reset_timeout {
+                    // 重置时间已过，放行一个探测请求
+                    inner.state = BreakerState::HalfOpen;
+                    info!("端点 {} 熔断器切换为半开状态，尝试探测恢复", self.target);
+                    true
+                } else {
+                    false
+                }
+            }
+            // 半开状态已有在途探测，其余请求继续快速失败
+            BreakerState::HalfOpen => false,
+        }
+    }
+
+    /// 记录请求成功
+    pub fn record_success(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.state != BreakerState::Closed {
+            info!("端点 {} 熔断器已关闭，服务恢复正常", self.target);
+        }
+        inner.state = BreakerState::Closed;
+        inner.failure_count = 0;
+    }
+
+    /// 记录请求失败
+    pub fn record_failure(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.last_failure = Instant::now();
+        match inner.state {
+            BreakerState::Closed => {
+                inner.failure_count += 1;
+                if inner.failure_count >= self.failure_threshold {
+                    inner.state = BreakerState::Open;
+                    warn!(
+                        "端点 {} 熔断器已打开，连续失败 {} 次",
+                        self.target, inner.failure_count
+                    );
+                }
+            }
+            BreakerState::HalfOpen => {
+                // 探测失败，重新开启
+                inner.state = BreakerState::Open;
+                warn!("端点 {} 熔断器从半开状态重新打开，端点仍不可用", self.target);
+            }
+            BreakerState::Open => {}
+        }
+    }
+}
+
+/// 池中的单个通道条目：通道与对应端点的熔断器
+pub struct ChannelEntry {
+    channel: Channel,
+    breaker: CircuitBreaker,
+}
+
+impl ChannelEntry {
+    /// 共享通道（tonic的Channel克隆后复用底层连接）
+    pub fn channel(&self) -> Channel {
+        self.channel.clone()
+    }
+
+    /// 端点熔断器
+    pub fn breaker(&self) -> &CircuitBreaker {
+        &self.breaker
+    }
+}
+
+/// 带熔断的gRPC通道池
+///
+/// 按目标URL缓存惰性建立的Channel并保持存活，避免每次调用重新
+/// 建连。取通道前检查端点熔断器，调用方通过[`record_success`]/
+/// [`record_failure`]回报结果驱动熔断状态；[`health_check_all`]
+/// 按标准gRPC健康检查协议逐端点探测并更新对应熔断器。
+///
+/// [`record_success`]: GrpcChannelPool::record_success
+/// [`record_failure`]: GrpcChannelPool::record_failure
+/// [`health_check_all`]: GrpcChannelPool::health_check_all
+pub struct GrpcChannelPool {
+    entries: RwLock<HashMap<String, Arc<ChannelEntry>>>,
+    failure_threshold: u64,
+    reset_timeout: Duration,
+    connect_timeout: Duration,
+    request_timeout: Duration,
+}
+
+impl Default for GrpcChannelPool {
+    fn default() -> Self {
+        Self::new(5, Duration::from_secs(30))
+    }
+}
+
+impl GrpcChannelPool {
+    /// 创建新的通道池
+    pub fn new(failure_threshold: u64, reset_timeout: Duration) -> Self {
+        Self {
+            entries: RwLock::new(HashMap::new()),
+            failure_threshold,
+            reset_timeout,
+            connect_timeout: Duration::from_secs(5),
+            request_timeout: Duration::from_secs(30),
+        }
+    }
+
+    /// 获取或惰性创建目标端点的通道条目
+    fn get_or_create(&self, url: &str) -> Result<Arc<ChannelEntry>> {
+        if let Some(entry) = self.entries.read().unwrap().get(url) {
+            return Ok(entry.clone());
+        }
+
+        // connect_lazy在首次请求时才真正建连，失败由熔断器记录
+        let channel = Endpoint::new(url.to_string())?
+            .connect_timeout(self.connect_timeout)
+            .timeout(self.request_timeout)
+            .concurrency_limit(100)
+            .connect_lazy();
+
+        let mut entries = self.entries.write().unwrap();
+        // 双重检查，避免并发创建时覆盖已有条目（及其熔断状态）
+        if let Some(entry) = entries.get(url) {
+            return Ok(entry.clone());
+        }
+
+        let entry = Arc::new(ChannelEntry {
+            channel,
+            breaker: CircuitBreaker::new(url, self.failure_threshold, self.reset_timeout),
+        });
+        entries.insert(url.to_string(), entry.clone());
+        info!("gRPC通道池新建端点通道: {}", url);
+        Ok(entry)
+    }
+
+    /// 获取目标端点的共享通道；端点熔断中时快速失败
+    pub fn get_channel(&self, url: &str) -> Result<Channel> {
+        let entry = self.get_or_create(url)?;
+        if !entry.breaker.check() {
+            return Err(Error::ServiceUnavailable(format!("端点 {} 熔断中", url)));
+        }
+        Ok(entry.channel())
+    }
+
+    /// 记录端点调用成功
+    pub fn record_success(&self, url: &str) {
+        if let Some(entry) = self.entries.read().unwrap().get(url) {
+            entry.breaker.record_success();
+        }
+    }
+
+    /// 记录端点调用失败
+    pub fn record_failure(&self, url: &str) {
+        if let Some(entry) = self.entries.read().unwrap().get(url) {
+            entry.breaker.record_failure();
+        }
+    }
+
+    /// 按标准gRPC健康检查协议探测池中所有端点并更新熔断器，
+    /// 返回各端点的探测结果（URL，是否健康）
+    pub async fn health_check_all(&self) -> Vec<(String, bool)> {
+        // 先拷贝条目快照再逐个探测，避免跨await持有锁
+        let snapshot: Vec<(String, Arc<ChannelEntry>)> = self
+            .entries
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(url, entry)| (url.clone(), entry.clone()))
+            .collect();
+
+        let mut results = Vec::with_capacity(snapshot.len());
+        for (url, entry) in snapshot {
+            let healthy = Self::check_endpoint(entry.channel(), self.connect_timeout).await;
+            if healthy {
+                entry.breaker.record_success();
+            } else {
+                warn!("端点 {} 健康检查失败", url);
+                entry.breaker.record_failure();
+            }
+            results.push((url, healthy));
+        }
+        results
+    }
+
+    /// 探测单个端点：Check返回SERVING视为健康，超时或错误视为不健康
+    async fn check_endpoint(channel: Channel, timeout: Duration) -> bool {
+        let mut client = HealthClient::new(channel);
+        let request = HealthCheckRequest {
+            // 空服务名表示检查整个服务器
+            service: String::new(),
+        };
+        match tokio::time::timeout(timeout, client.check(request)).await {
+            Ok(Ok(response)) => response.into_inner().status() == ServingStatus::Serving,
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_channel_reused_for_same_url() {
+        let pool = GrpcChannelPool::default();
+
+        pool.get_channel("http://127.0.0.1:50051").unwrap();
+        pool.get_channel("http://127.0.0.1:50051").unwrap();
+        pool.get_channel("http://127.0.0.1:50052").unwrap();
+
+        // 同一URL复用条目，不同URL各自建条目
+        assert_eq!(pool.entries.read().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_open_breaker_rejects_get_channel() {
+        let pool = GrpcChannelPool::new(2, Duration::from_secs(60));
+        let url = "http://127.0.0.1:50051";
+
+        pool.get_channel(url).unwrap();
+        pool.record_failure(url);
+        assert!(pool.get_channel(url).is_ok());
+        pool.record_failure(url);
+
+        // 达到阈值后熔断，取通道快速失败且不影响其他端点
+        assert!(matches!(
+            pool.get_channel(url),
+            Err(Error::ServiceUnavailable(_))
+        ));
+        assert!(pool.get_channel("http://127.0.0.1:50052").is_ok());
+    }
+
+    #[test]
+    fn test_breaker_half_open_probe_then_recover() {
+        let breaker = CircuitBreaker::new("http://127.0.0.1:50051", 1, Duration::from_millis(0));
+        breaker.record_failure();
+        assert_eq!(breaker.state(), BreakerState::Open);
+
+        // 重置时间为0：下一次check即转半开并放行探测
+        assert!(breaker.check());
+        assert_eq!(breaker.state(), BreakerState::HalfOpen);
+        // 探测完成前其余请求继续快速失败
+        assert!(!breaker.check());
+
+        // 探测成功关闭熔断器
+        breaker.record_success();
+        assert_eq!(breaker.state(), BreakerState::Closed);
+        assert!(breaker.check());
+    }
+
+    #[tokio::test]
+    async fn test_health_check_marks_unreachable_endpoint_failed() {
+        let pool = GrpcChannelPool::new(1, Duration::from_secs(60));
+        // 端口1无服务监听，探测应失败
+        let url = "http://127.0.0.1:1";
+        pool.get_channel(url).unwrap();
+
+        let results = pool.health_check_all().await;
+        assert_eq!(results, vec![(url.to_string(), false)]);
+
+        // 阈值为1：一次失败即熔断
+        assert!(matches!(
+            pool.get_channel(url),
+            Err(Error::ServiceUnavailable(_))
+        ));
+    }
+}
@@ -1,11 +1,19 @@
+// Error中内嵌tonic::Status导致Err较大，属于设计取舍
+#![allow(clippy::result_large_err)]
+
+pub mod auth;
 pub mod config;
 pub mod error;
+pub mod grpc_pool;
+pub mod logging;
 pub mod models;
 pub mod proto;
 pub mod utils;
 pub mod service_registry;
 pub mod message;
+pub mod tls;
 pub mod types;
+pub mod webhook;
 
 pub use error::Error;
 pub type Result<T> = std::result::Result<T, Error>; 
\ No newline at end of file
@@ -0,0 +1,131 @@
+use std::sync::OnceLock;
+use std::task::{Context, Poll};
+
+use tonic::codegen::http;
+use tower::{Layer, Service};
+use tracing::info;
+use tracing::instrument::Instrumented;
+use tracing::Instrument;
+use uuid::Uuid;
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{reload, Registry};
+
+use crate::config::LogConfig;
+
+// 全局日志级别的reload句柄，配置热更新时用于动态调整级别
+static RELOAD_HANDLE: OnceLock<reload::Handle<LevelFilter, Registry>> = OnceLock::new();
+
+/// 根据配置初始化全局日志订阅器
+///
+/// - 日志级别来自`log.level`，并支持运行时通过[`set_level`]动态调整
+/// - `log.output`为`"console"`时输出到控制台；为`"file"`时写入`./logs`目录，
+///   其他值视为日志目录路径。文件输出按天滚动，使用非阻塞写入器
+/// - `log.error_verbosity`控制HTTP错误响应的详细程度（见[`crate::error::ErrorVerbosity`]）
+///
+/// 返回非阻塞写入器的guard（控制台输出时为None），main需持有它直到进程退出，
+/// 否则缓冲中的日志可能丢失
+pub fn init(config: &LogConfig) -> Option<WorkerGuard> {
+    // 错误响应详细程度随日志配置一同生效，minimal下HTTP响应只带错误ID
+    crate::error::set_error_verbosity(crate::error::ErrorVerbosity::from_name(
+        &config.error_verbosity,
+    ));
+
+    let (filter, handle) = reload::Layer::new(LevelFilter::from_level(config.level()));
+    let _ = RELOAD_HANDLE.set(handle);
+
+    match config.output.as_str() {
+        "console" | "" => {
+            tracing_subscriber::registry()
+                .with(filter)
+                .with(tracing_subscriber::fmt::layer())
+                .init();
+            None
+        }
+        output => {
+            // "file"使用默认目录，其他值作为目录路径
+            let dir = if output == "file" { "./logs" } else { output };
+            let prefix = std::env::current_exe()
+                .ok()
+                .and_then(|p| p.file_name().map(|n| n.to_string_lossy().into_owned()))
+                .unwrap_or_else(|| "app".to_string());
+
+            let appender = tracing_appender::rolling::daily(dir, format!("{}.log", prefix));
+            let (writer, guard) = tracing_appender::non_blocking(appender);
+
+            tracing_subscriber::registry()
+                .with(filter)
+                .with(
+                    tracing_subscriber::fmt::layer()
+                        .with_ansi(false)
+                        .with_writer(writer),
+                )
+                .init();
+            Some(guard)
+        }
+    }
+}
+
+/// 为每个gRPC请求创建带request_id字段的span的tower层
+///
+/// request_id取元数据里的`x-request-id`（由网关注入），缺失时本地生成。
+/// 挂在`Server::builder().layer(...)`上后，请求处理期间的所有日志
+/// 都会带上request_id，跨服务排查时按该字段即可把一条链路串起来。
+#[derive(Clone, Copy, Default)]
+pub struct RequestIdSpanLayer;
+
+impl<S> Layer<S> for RequestIdSpanLayer {
+    type Service = RequestIdSpanService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RequestIdSpanService { inner }
+    }
+}
+
+/// gRPC请求span中间件，见[`RequestIdSpanLayer`]
+#[derive(Clone)]
+pub struct RequestIdSpanService<S> {
+    inner: S,
+}
+
+impl<S, B> Service<http::Request<B>> for RequestIdSpanService<S>
+where
+    S: Service<http::Request<B>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Instrumented<S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        let request_id = req
+            .headers()
+            .get("x-request-id")
+            .and_then(|v| v.to_str().ok())
+            .filter(|v| !v.is_empty())
+            .map(|v| v.to_string())
+            .unwrap_or_else(|| Uuid::now_v7().to_string());
+
+        let span = tracing::info_span!(
+            "grpc_request",
+            path = %req.uri().path(),
+            request_id = %request_id,
+        );
+        self.inner.call(req).instrument(span)
+    }
+}
+
+/// 运行时调整全局日志级别（配置热更新时由DynamicConfig调用）
+pub fn set_level(level: tracing::Level) {
+    if let Some(handle) = RELOAD_HANDLE.get() {
+        match handle.modify(|filter| *filter = LevelFilter::from_level(level)) {
+            Ok(_) => info!("日志级别已调整为: {}", level),
+            Err(e) => tracing::warn!("调整日志级别失败: {}", e),
+        }
+    }
+}
@@ -57,6 +57,9 @@ pub struct Msg {
     /// / send sequence
     #[prost(int64, tag = "20")]
     pub send_seq: i64,
+    /// / server-assigned sequence within the conversation
+    #[prost(int64, tag = "21")]
+    pub server_seq: i64,
 }
 #[derive(serde::Serialize, serde::Deserialize)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -701,6 +704,77 @@ pub struct SaveMaxSeqRequest {
     #[prost(string, tag = "1")]
     pub user_id: ::prost::alloc::string::String,
 }
+/// / mark messages as read up to the given sequence
+#[derive(serde::Serialize, serde::Deserialize)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MarkReadRequest {
+    #[prost(string, tag = "1")]
+    pub user_id: ::prost::alloc::string::String,
+    /// / peer user id for single chat, group id for group chat
+    #[prost(string, tag = "2")]
+    pub conversation_id: ::prost::alloc::string::String,
+    /// / 0: single chat, 1: group chat
+    #[prost(int32, tag = "3")]
+    pub conversation_type: i32,
+    #[prost(int64, tag = "4")]
+    pub last_read_seq: i64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MarkReadResponse {}
+#[derive(serde::Serialize, serde::Deserialize)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetUnreadCountRequest {
+    #[prost(string, tag = "1")]
+    pub user_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub conversation_id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetUnreadCountResponse {
+    #[prost(int64, tag = "1")]
+    pub unread_count: i64,
+}
+/// / query the most recent conversations for a user
+#[derive(serde::Serialize, serde::Deserialize)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetConversationsRequest {
+    #[prost(string, tag = "1")]
+    pub user_id: ::prost::alloc::string::String,
+    /// / max conversations to return, the server caps it
+    #[prost(int32, tag = "2")]
+    pub limit: i32,
+    /// / only return conversations whose latest message is older than this
+    /// / timestamp (ms), 0 means start from the most recent one
+    #[prost(int64, tag = "3")]
+    pub before_timestamp: i64,
+}
+/// / one conversation with its latest message and unread count
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ConversationSummary {
+    /// / peer user id for single chat, group id for group chat
+    #[prost(string, tag = "1")]
+    pub conversation_id: ::prost::alloc::string::String,
+    /// / 0: single chat, 1: group chat
+    #[prost(int32, tag = "2")]
+    pub conversation_type: i32,
+    /// / latest message in the conversation
+    #[prost(message, optional, tag = "3")]
+    pub last_msg: ::core::option::Option<Msg>,
+    #[prost(int64, tag = "4")]
+    pub unread_count: i64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetConversationsResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub conversations: ::prost::alloc::vec::Vec<ConversationSummary>,
+}
 /// / user platform which login the system
 #[derive(
     serde::Serialize,
@@ -1144,6 +1218,43 @@ pub mod msg_service_client {
                 .insert(GrpcMethod::new("message.MsgService", "SendGroupMsgToUser"));
             self.inner.unary(req, path, codec).await
         }
+        /// mark messages as read up to the given sequence
+        pub async fn mark_read(
+            &mut self,
+            request: impl tonic::IntoRequest<super::MarkReadRequest>,
+        ) -> std::result::Result<tonic::Response<super::MarkReadResponse>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/message.MsgService/MarkRead");
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("message.MsgService", "MarkRead"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// query unread message count for the conversation
+        pub async fn get_unread_count(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GetUnreadCountRequest>,
+        ) -> std::result::Result<tonic::Response<super::GetUnreadCountResponse>, tonic::Status>
+        {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/message.MsgService/GetUnreadCount");
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("message.MsgService", "GetUnreadCount"));
+            self.inner.unary(req, path, codec).await
+        }
     }
 }
 /// Generated client implementations.
@@ -1249,6 +1360,26 @@ pub mod chat_service_client {
                 .insert(GrpcMethod::new("message.ChatService", "SendMsg"));
             self.inner.unary(req, path, codec).await
         }
+        /// query the most recent conversations for a user
+        pub async fn get_conversations(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GetConversationsRequest>,
+        ) -> std::result::Result<tonic::Response<super::GetConversationsResponse>, tonic::Status>
+        {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path =
+                http::uri::PathAndQuery::from_static("/message.ChatService/GetConversations");
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("message.ChatService", "GetConversations"));
+            self.inner.unary(req, path, codec).await
+        }
     }
 }
 /// Generated server implementations.
@@ -1273,6 +1404,16 @@ pub mod msg_service_server {
             &self,
             request: tonic::Request<super::SendGroupMsgRequest>,
         ) -> std::result::Result<tonic::Response<super::SendMsgResponse>, tonic::Status>;
+        /// mark messages as read up to the given sequence
+        async fn mark_read(
+            &self,
+            request: tonic::Request<super::MarkReadRequest>,
+        ) -> std::result::Result<tonic::Response<super::MarkReadResponse>, tonic::Status>;
+        /// query unread message count for the conversation
+        async fn get_unread_count(
+            &self,
+            request: tonic::Request<super::GetUnreadCountRequest>,
+        ) -> std::result::Result<tonic::Response<super::GetUnreadCountResponse>, tonic::Status>;
     }
     #[derive(Debug)]
     pub struct MsgServiceServer<T: MsgService> {
@@ -1472,6 +1613,87 @@ pub mod msg_service_server {
                     };
                     Box::pin(fut)
                 }
+                "/message.MsgService/MarkRead" => {
+                    #[allow(non_camel_case_types)]
+                    struct MarkReadSvc<T: MsgService>(pub Arc<T>);
+                    impl<T: MsgService> tonic::server::UnaryService<super::MarkReadRequest> for MarkReadSvc<T> {
+                        type Response = super::MarkReadResponse;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::MarkReadRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut =
+                                async move { <T as MsgService>::mark_read(&inner, request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = MarkReadSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/message.MsgService/GetUnreadCount" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetUnreadCountSvc<T: MsgService>(pub Arc<T>);
+                    impl<T: MsgService> tonic::server::UnaryService<super::GetUnreadCountRequest>
+                        for GetUnreadCountSvc<T>
+                    {
+                        type Response = super::GetUnreadCountResponse;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetUnreadCountRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as MsgService>::get_unread_count(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetUnreadCountSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 _ => Box::pin(async move {
                     Ok(http::Response::builder()
                         .status(200)
@@ -1520,6 +1742,11 @@ pub mod chat_service_server {
             &self,
             request: tonic::Request<super::SendMsgRequest>,
         ) -> std::result::Result<tonic::Response<super::MsgResponse>, tonic::Status>;
+        /// query the most recent conversations for a user
+        async fn get_conversations(
+            &self,
+            request: tonic::Request<super::GetConversationsRequest>,
+        ) -> std::result::Result<tonic::Response<super::GetConversationsResponse>, tonic::Status>;
     }
     /// / chat service, receive message then generate message id and send message to
     /// / mq; response operation result;
@@ -1638,6 +1865,49 @@ pub mod chat_service_server {
                     };
                     Box::pin(fut)
                 }
+                "/message.ChatService/GetConversations" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetConversationsSvc<T: ChatService>(pub Arc<T>);
+                    impl<T: ChatService>
+                        tonic::server::UnaryService<super::GetConversationsRequest>
+                        for GetConversationsSvc<T>
+                    {
+                        type Response = super::GetConversationsResponse;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetConversationsRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as ChatService>::get_conversations(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetConversationsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 _ => Box::pin(async move {
                     Ok(http::Response::builder()
                         .status(200)
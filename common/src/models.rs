@@ -94,13 +94,8 @@ pub enum MessageContentType {
 }
 
 // 身份认证相关模型
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Claims {
-    pub sub: String,     // 用户ID
-    pub username: String,
-    pub exp: usize,      // 过期时间
-    pub iat: usize,      // 签发时间
-}
+// JWT负载的规范定义已移至auth模块，这里保留别名兼容旧引用
+pub use crate::auth::Claims;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TokenPair {
@@ -25,4 +25,43 @@ pub mod group_message {
 
 pub mod message_gateway {
     tonic::include_proto!("message_gateway");
-} 
\ No newline at end of file
+} 
+pub mod health {
+    tonic::include_proto!("grpc.health.v1");
+}
+
+/// Timestamp字段的serde适配：JSON侧表示为RFC3339字符串
+/// （prost把proto3的消息字段一律生成为Option，这里按Option处理）
+pub mod timestamp_serde {
+    use prost_types::Timestamp;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        value: &Option<Timestamp>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match value {
+            Some(ts) => chrono::DateTime::from_timestamp(ts.seconds, ts.nanos.max(0) as u32)
+                .ok_or_else(|| serde::ser::Error::custom("时间戳超出可表示范围"))?
+                .to_rfc3339()
+                .serialize(serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Timestamp>, D::Error> {
+        match Option::<String>::deserialize(deserializer)? {
+            Some(text) => {
+                let datetime = chrono::DateTime::parse_from_rfc3339(&text)
+                    .map_err(serde::de::Error::custom)?;
+                Ok(Some(Timestamp {
+                    seconds: datetime.timestamp(),
+                    nanos: datetime.timestamp_subsec_nanos() as i32,
+                }))
+            }
+            None => Ok(None),
+        }
+    }
+}
@@ -1,8 +1,11 @@
+use std::collections::HashMap;
 use std::time::Duration;
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use reqwest::Client;
-use tracing::info;
+use tokio::sync::watch;
+use tracing::{error, info, warn};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, RwLock};
 
 /// Consul服务信息结构
@@ -23,12 +26,66 @@ struct ConsulService {
 #[derive(Debug, Serialize, Deserialize)]
 struct ConsulServicesResponse(Vec<ConsulService>);
 
+/// 健康检查方式
+#[derive(Debug, Clone)]
+enum CheckMode {
+    /// Consul agent回访HTTP健康端点
+    Http { path: String, interval: String },
+    /// TTL检查：服务主动上报心跳，适用于agent无法回访的网络环境
+    Ttl { ttl_secs: u64 },
+}
+
+/// 注册参数快照，用于后台任务在Consul丢失注册后重新注册
+#[derive(Debug, Clone)]
+struct RegistrationInfo {
+    service_name: String,
+    host: String,
+    port: u32,
+    tags: Vec<String>,
+    check: CheckMode,
+}
+
+/// TTL心跳连续失败该次数后升级为error日志并尝试重新注册
+const TTL_HEARTBEAT_FAILURE_THRESHOLD: u32 = 3;
+
+/// Consul阻塞查询的最长等待时间
+const WATCH_WAIT: &str = "30s";
+
+/// Consul未返回索引（不支持阻塞查询）时的退化轮询间隔
+const WATCH_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// watch查询失败后的重试间隔
+const WATCH_RETRY_INTERVAL: Duration = Duration::from_secs(2);
+
+/// 未在ServiceMeta中设置weight的实例默认权重
+pub const DEFAULT_ENDPOINT_WEIGHT: u32 = 100;
+
+/// 服务实例端点
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServiceEndpoint {
+    pub url: String,
+    /// 来自ServiceMeta的weight，未设置时为默认权重
+    pub weight: u32,
+}
+
 /// 服务注册管理器
 #[derive(Clone)]
 pub struct ServiceRegistry {
     http_client: Client,
+    /// watch专用客户端：阻塞查询最长等待30s，超时需大于等待时间
+    watch_client: Client,
     consul_url: String,
     service_id: Arc<RwLock<Option<String>>>,
+    /// 活跃的服务watch（服务名 -> 端点列表接收端）
+    watches: Arc<RwLock<HashMap<String, watch::Receiver<Vec<ServiceEndpoint>>>>>,
+    /// 最近一次注册的参数，供重注册任务复用
+    registration: Arc<RwLock<Option<RegistrationInfo>>>,
+    /// 已主动注销：置位后重注册任务不得再注册
+    deregistered: Arc<AtomicBool>,
+    /// 注册失败时的最大重试次数
+    register_max_retries: u32,
+    /// 注册重试的初始退避时间，之后指数增长
+    register_retry_base_delay: Duration,
 }
 
 impl ServiceRegistry {
@@ -38,22 +95,43 @@ impl ServiceRegistry {
             .timeout(Duration::from_secs(5))
             .build()
             .unwrap_or_else(|_| Client::new());
-            
+
+        let watch_client = Client::builder()
+            .timeout(Duration::from_secs(40))
+            .build()
+            .unwrap_or_else(|_| Client::new());
+
         Self {
             http_client,
+            watch_client,
             consul_url: consul_url.to_string(),
             service_id: Arc::new(RwLock::new(None)),
+            watches: Arc::new(RwLock::new(HashMap::new())),
+            registration: Arc::new(RwLock::new(None)),
+            deregistered: Arc::new(AtomicBool::new(false)),
+            register_max_retries: 5,
+            register_retry_base_delay: Duration::from_millis(500),
         }
     }
-    
+
     /// 从环境变量创建服务注册管理器
     pub fn from_env() -> Self {
         let consul_url = std::env::var("CONSUL_URL")
             .unwrap_or_else(|_| "http://localhost:8500".to_string());
         Self::new(&consul_url)
     }
-    
+
+    /// 配置注册失败时的重试策略（重试次数与初始退避时间，退避按指数增长）
+    pub fn with_retry_policy(mut self, max_retries: u32, base_delay: Duration) -> Self {
+        self.register_max_retries = max_retries;
+        self.register_retry_base_delay = base_delay;
+        self
+    }
+
     /// 注册服务到Consul
+    ///
+    /// Consul短暂不可用时按指数退避重试（见`with_retry_policy`），
+    /// 全部重试失败才返回错误。
     pub async fn register_service(
         &self,
         service_name: &str,
@@ -63,51 +141,268 @@ impl ServiceRegistry {
         health_check_path: &str,
         health_check_interval: &str,
     ) -> Result<String> {
+        let info = RegistrationInfo {
+            service_name: service_name.to_string(),
+            host: host.to_string(),
+            port,
+            tags,
+            check: CheckMode::Http {
+                path: health_check_path.to_string(),
+                interval: health_check_interval.to_string(),
+            },
+        };
+        self.register_with_retry(info).await
+    }
+
+    /// 以TTL检查方式注册服务到Consul
+    ///
+    /// 适用于Consul agent无法回访健康端点的网络环境（NAT、sidecar）。
+    /// 注册后需调用`start_ttl_heartbeat`启动心跳上报，否则检查会超时转为critical。
+    pub async fn register_service_with_ttl(
+        &self,
+        service_name: &str,
+        host: &str,
+        port: u32,
+        tags: Vec<String>,
+        ttl_secs: u64,
+    ) -> Result<String> {
+        let info = RegistrationInfo {
+            service_name: service_name.to_string(),
+            host: host.to_string(),
+            port,
+            tags,
+            check: CheckMode::Ttl { ttl_secs },
+        };
+        self.register_with_retry(info).await
+    }
+
+    /// 带指数退避重试的注册，成功后更新内部状态
+    async fn register_with_retry(&self, info: RegistrationInfo) -> Result<String> {
+        let mut delay = self.register_retry_base_delay;
+        let mut attempt = 0;
+        let service_id = loop {
+            match self.register_once(&info).await {
+                Ok(id) => break id,
+                Err(e) if attempt < self.register_max_retries => {
+                    attempt += 1;
+                    warn!(
+                        "服务 {} 注册失败（第{}次重试，{}ms后重试）: {}",
+                        info.service_name, attempt, delay.as_millis(), e
+                    );
+                    tokio::time::sleep(delay).await;
+                    delay *= 2;
+                }
+                Err(e) => return Err(e),
+            }
+        };
+
+        // 保存注册参数并清除注销标记，供重注册任务使用
+        self.deregistered.store(false, Ordering::SeqCst);
+        if let Ok(mut reg) = self.registration.write() {
+            *reg = Some(info);
+        }
+        if let Ok(mut id) = self.service_id.write() {
+            *id = Some(service_id.clone());
+        }
+
+        Ok(service_id)
+    }
+
+    /// 单次注册请求（不重试、不更新内部状态）
+    async fn register_once(&self, info: &RegistrationInfo) -> Result<String> {
         // 生成唯一服务ID
-        let service_id = format!("{}-{}-{}", service_name, host, port);
-        
+        let service_id = format!("{}-{}-{}", info.service_name, info.host, info.port);
+
+        // 按检查方式构建Check定义
+        let check = match &info.check {
+            CheckMode::Http { path, interval } => serde_json::json!({
+                "HTTP": format!("http://{}:{}{}", info.host, info.port, path),
+                "Interval": interval,
+                "Timeout": "5s",
+                "DeregisterCriticalServiceAfter": "30s",
+            }),
+            CheckMode::Ttl { ttl_secs } => serde_json::json!({
+                "TTL": format!("{}s", ttl_secs),
+                "DeregisterCriticalServiceAfter": "30s",
+            }),
+        };
+
         // 构建注册请求体
         let register_payload = serde_json::json!({
             "ID": service_id,
-            "Name": service_name,
-            "Tags": tags,
-            "Address": host,
-            "Port": port,
-            "Check": {
-                "HTTP": format!("http://{}:{}{}", host, port, health_check_path),
-                "Interval": health_check_interval,
-                "Timeout": "5s",
-                "DeregisterCriticalServiceAfter": "30s",
-            }
+            "Name": info.service_name,
+            "Tags": info.tags,
+            "Address": info.host,
+            "Port": info.port,
+            "Check": check,
         });
-        
+
         let url = format!("{}/v1/agent/service/register", self.consul_url);
-        
-        info!("注册服务 {} 到 Consul: {}", service_name, url);
-        
+
+        info!("注册服务 {} 到 Consul: {}", info.service_name, url);
+
         let response = self.http_client.put(&url)
             .json(&register_payload)
             .send()
             .await?;
-        
+
         if !response.status().is_success() {
             let status = response.status();
             let body = response.text().await.unwrap_or_default();
             return Err(anyhow::anyhow!("服务注册失败: 状态码 {}, 消息: {}", status, body));
         }
-        
-        info!("服务 {} 已成功注册到Consul, 服务ID: {}", service_name, service_id);
-        
-        // 使用RwLock更新service_id
-        if let Ok(mut id) = self.service_id.write() {
-            *id = Some(service_id.clone());
-        }
-        
+
+        info!("服务 {} 已成功注册到Consul, 服务ID: {}", info.service_name, service_id);
+
         Ok(service_id)
     }
-    
+
+    /// 启动后台重注册任务
+    ///
+    /// 按`check_interval`轮询`/v1/agent/service/{id}`，若Consul丢失了注册
+    /// （例如Consul重启后），则用最近一次的注册参数重新注册。
+    /// 调用`deregister_service`后任务自动退出，不会把服务重新注册回去；
+    /// 也可通过返回的句柄`abort()`在优雅关闭时取消任务。
+    pub fn start_reregistration_task(&self, check_interval: Duration) -> tokio::task::JoinHandle<()> {
+        let registry = self.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(check_interval).await;
+
+                // 已主动注销则退出，避免把服务重新注册回去
+                if registry.deregistered.load(Ordering::SeqCst) {
+                    info!("服务已注销，重注册任务退出");
+                    return;
+                }
+
+                let (service_id, info) = {
+                    let id = registry.service_id.read().ok().and_then(|id| id.clone());
+                    let reg = registry.registration.read().ok().and_then(|r| r.clone());
+                    match (id, reg) {
+                        (Some(id), Some(reg)) => (id, reg),
+                        _ => continue,
+                    }
+                };
+
+                match registry.is_registered(&service_id).await {
+                    Ok(true) => {}
+                    Ok(false) => {
+                        warn!("Consul丢失服务 {} 的注册，正在重新注册", service_id);
+                        // 重注册前再次确认未被注销，避免与deregister竞争
+                        if registry.deregistered.load(Ordering::SeqCst) {
+                            return;
+                        }
+                        if let Err(e) = registry.register_once(&info).await {
+                            warn!("服务 {} 重新注册失败，下轮重试: {}", service_id, e);
+                        }
+                    }
+                    Err(e) => {
+                        warn!("查询服务 {} 注册状态失败: {}", service_id, e);
+                    }
+                }
+            }
+        })
+    }
+
+    /// 启动TTL心跳上报任务
+    ///
+    /// 每ttl/2向Consul上报一次`/v1/agent/check/pass/service:{id}`。
+    /// 连续失败达到阈值后升级为error日志并尝试重新注册（Consul可能已丢失该服务）。
+    /// 调用`deregister_service`后任务自动退出，也可通过返回的句柄`abort()`取消。
+    pub fn start_ttl_heartbeat(&self) -> tokio::task::JoinHandle<()> {
+        let registry = self.clone();
+        tokio::spawn(async move {
+            // 心跳间隔取TTL的一半，非TTL注册直接退出
+            let interval = {
+                let reg = registry.registration.read().ok().and_then(|r| r.clone());
+                match reg {
+                    Some(RegistrationInfo {
+                        check: CheckMode::Ttl { ttl_secs },
+                        ..
+                    }) => Duration::from_secs((ttl_secs / 2).max(1)),
+                    _ => {
+                        warn!("服务未以TTL方式注册，心跳任务退出");
+                        return;
+                    }
+                }
+            };
+
+            let mut consecutive_failures: u32 = 0;
+            loop {
+                tokio::time::sleep(interval).await;
+
+                if registry.deregistered.load(Ordering::SeqCst) {
+                    info!("服务已注销，TTL心跳任务退出");
+                    return;
+                }
+
+                let (service_id, info) = {
+                    let id = registry.service_id.read().ok().and_then(|id| id.clone());
+                    let reg = registry.registration.read().ok().and_then(|r| r.clone());
+                    match (id, reg) {
+                        (Some(id), Some(reg)) => (id, reg),
+                        _ => continue,
+                    }
+                };
+
+                match registry.pass_ttl_check(&service_id).await {
+                    Ok(()) => {
+                        consecutive_failures = 0;
+                    }
+                    Err(e) => {
+                        consecutive_failures += 1;
+                        if consecutive_failures >= TTL_HEARTBEAT_FAILURE_THRESHOLD {
+                            error!(
+                                "TTL心跳连续失败{}次，尝试重新注册服务 {}: {}",
+                                consecutive_failures, service_id, e
+                            );
+                            if let Err(e) = registry.register_once(&info).await {
+                                error!("服务 {} 重新注册失败，下轮重试: {}", service_id, e);
+                            }
+                        } else {
+                            warn!("TTL心跳上报失败（第{}次）: {}", consecutive_failures, e);
+                        }
+                    }
+                }
+            }
+        })
+    }
+
+    /// 向Consul上报一次TTL检查通过
+    async fn pass_ttl_check(&self, service_id: &str) -> Result<()> {
+        let url = format!(
+            "{}/v1/agent/check/pass/service:{}",
+            self.consul_url, service_id
+        );
+        let response = self.http_client.put(&url).send().await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(anyhow::anyhow!("TTL心跳上报失败: 状态码 {}, 消息: {}", status, body));
+        }
+        Ok(())
+    }
+
+    /// 查询Consul是否仍持有该服务的注册
+    async fn is_registered(&self, service_id: &str) -> Result<bool> {
+        let url = format!("{}/v1/agent/service/{}", self.consul_url, service_id);
+        let response = self.http_client.get(&url).send().await?;
+
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(false);
+        }
+        if !response.status().is_success() {
+            return Err(anyhow::anyhow!("Consul API请求失败: {}", response.status()));
+        }
+        Ok(true)
+    }
+
     /// 从Consul注销服务
     pub async fn deregister_service(&self) -> Result<()> {
+        // 先置位注销标记，确保重注册任务不会把服务注册回去
+        self.deregistered.store(true, Ordering::SeqCst);
+
         let service_id = match self.service_id.read() {
             Ok(id) => match &*id {
                 Some(id) => id.clone(),
@@ -134,10 +429,164 @@ impl ServiceRegistry {
         Ok(())
     }
     
+    /// 建立对某服务的watch
+    ///
+    /// 首次调用做一次即时查询取得初始端点，随后由后台任务用Consul阻塞查询
+    /// （index + wait）保持端点列表实时，并在变更时通过watch通道通知订阅方。
+    /// 重复调用返回同一watch的接收端。
+    pub async fn watch_service(
+        &self,
+        service_name: &str,
+    ) -> Result<watch::Receiver<Vec<ServiceEndpoint>>> {
+        if let Ok(watches) = self.watches.read() {
+            if let Some(rx) = watches.get(service_name) {
+                return Ok(rx.clone());
+            }
+        }
+
+        // 首次即时查询，取得初始端点与Consul索引
+        let (endpoints, mut index) = self.fetch_endpoints(service_name, None).await?;
+        let (tx, rx) = watch::channel(endpoints);
+
+        if let Ok(mut watches) = self.watches.write() {
+            // 并发调用时可能已有其他调用方建立了watch，复用其接收端
+            if let Some(existing) = watches.get(service_name) {
+                return Ok(existing.clone());
+            }
+            watches.insert(service_name.to_string(), rx.clone());
+        }
+
+        info!("已建立服务 {} 的watch", service_name);
+
+        let registry = self.clone();
+        let name = service_name.to_string();
+        tokio::spawn(async move {
+            loop {
+                // 服务端不支持阻塞查询（未返回索引）时退化为定期轮询
+                if index.is_none() {
+                    tokio::time::sleep(WATCH_POLL_INTERVAL).await;
+                }
+
+                match registry.fetch_endpoints(&name, index).await {
+                    Ok((endpoints, new_index)) => {
+                        index = new_index;
+                        tx.send_if_modified(|current| {
+                            if *current != endpoints {
+                                info!(
+                                    "服务 {} 实例列表变更，当前 {} 个实例",
+                                    name,
+                                    endpoints.len()
+                                );
+                                *current = endpoints;
+                                true
+                            } else {
+                                false
+                            }
+                        });
+                    }
+                    Err(e) => {
+                        warn!("服务 {} 的watch查询失败，稍后重试: {}", name, e);
+                        tokio::time::sleep(WATCH_RETRY_INTERVAL).await;
+                    }
+                }
+            }
+        });
+
+        Ok(rx)
+    }
+
+    /// 读取活跃watch缓存的端点，未建立watch时返回None
+    pub fn watched_endpoints(&self, service_name: &str) -> Option<Vec<ServiceEndpoint>> {
+        self.watches
+            .read()
+            .ok()?
+            .get(service_name)
+            .map(|rx| rx.borrow().clone())
+    }
+
+    /// 查询服务的健康端点
+    ///
+    /// 带index时使用Consul阻塞查询，直到列表变更或等待超时才返回。
+    /// 返回端点列表与响应的Consul索引（用于下一次阻塞查询）。
+    async fn fetch_endpoints(
+        &self,
+        service_name: &str,
+        index: Option<u64>,
+    ) -> Result<(Vec<ServiceEndpoint>, Option<u64>)> {
+        let url = format!("{}/v1/health/service/{}", self.consul_url, service_name);
+
+        let mut request = self.watch_client.get(&url).query(&[("passing", "true")]);
+        if let Some(index) = index {
+            request = request.query(&[
+                ("index", index.to_string()),
+                ("wait", WATCH_WAIT.to_string()),
+            ]);
+        }
+
+        let response = request.send().await?;
+        if !response.status().is_success() {
+            return Err(anyhow::anyhow!("Consul API请求失败: {}", response.status()));
+        }
+
+        let new_index = response
+            .headers()
+            .get("X-Consul-Index")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<u64>().ok());
+
+        let entries: Vec<serde_json::Value> = response.json().await?;
+        let mut endpoints = Vec::with_capacity(entries.len());
+        for entry in entries {
+            let Some(service) = entry.get("Service") else {
+                continue;
+            };
+            let (Some(address), Some(port)) = (
+                service.get("Address").and_then(|a| a.as_str()),
+                service.get("Port").and_then(|p| p.as_u64()),
+            ) else {
+                continue;
+            };
+
+            // Service.Address为空时退回节点地址
+            let host = if address.is_empty() {
+                match entry
+                    .get("Node")
+                    .and_then(|n| n.get("Address"))
+                    .and_then(|a| a.as_str())
+                {
+                    Some(addr) => addr,
+                    None => continue,
+                }
+            } else {
+                address
+            };
+
+            // 解析ServiceMeta中的weight，未设置或非法时用默认权重
+            let weight = service
+                .get("Meta")
+                .and_then(|m| m.get("weight"))
+                .and_then(|w| w.as_str())
+                .and_then(|w| w.parse::<u32>().ok())
+                .unwrap_or(DEFAULT_ENDPOINT_WEIGHT);
+
+            endpoints.push(ServiceEndpoint {
+                url: format!("http://{}:{}", host, port),
+                weight,
+            });
+        }
+
+        Ok((endpoints, new_index))
+    }
+
     /// 发现服务实例
     pub async fn discover_service(&self, service_name: &str) -> Result<Vec<String>> {
+        // 已建立watch时直接读取实时缓存，避免每次调用都请求Consul
+        if let Some(endpoints) = self.watched_endpoints(service_name) {
+            return Ok(endpoints.into_iter().map(|e| e.url).collect());
+        }
+
         let url = format!("{}/v1/health/service/{}", self.consul_url, service_name);
-        
+
         info!("从Consul查询服务: {}", service_name);
         
         let response = self.http_client.get(&url)
@@ -164,4 +613,146 @@ impl ServiceRegistry {
         
         Ok(service_urls)
     }
-} 
\ No newline at end of file
+
+    /// 等待依赖服务在Consul中出现健康实例
+    ///
+    /// 每隔poll_interval轮询一次，直到所有services都有健康实例时返回Ok；
+    /// 超时返回错误，错误信息列出仍不健康的服务。
+    /// 供网关和服务间客户端在启动阶段做依赖就绪门控。
+    pub async fn wait_for_services(
+        &self,
+        services: &[String],
+        timeout: Duration,
+        poll_interval: Duration,
+    ) -> Result<()> {
+        if services.is_empty() {
+            return Ok(());
+        }
+
+        let deadline = tokio::time::Instant::now() + timeout;
+        let mut pending: Vec<String> = services.to_vec();
+
+        loop {
+            let mut still_pending = Vec::new();
+            for name in &pending {
+                match self.discover_service(name).await {
+                    Ok(instances) if !instances.is_empty() => {
+                        info!("依赖服务 {} 已就绪", name);
+                    }
+                    _ => still_pending.push(name.clone()),
+                }
+            }
+            pending = still_pending;
+
+            if pending.is_empty() {
+                return Ok(());
+            }
+
+            let now = tokio::time::Instant::now();
+            if now >= deadline {
+                return Err(anyhow::anyhow!(
+                    "等待依赖服务就绪超时: {}",
+                    pending.join(", ")
+                ));
+            }
+            info!("等待依赖服务就绪: {}", pending.join(", "));
+            tokio::time::sleep(poll_interval.min(deadline - now)).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::routing::get;
+    use axum::{Json, Router};
+    use serde_json::json;
+    use std::time::Instant;
+
+    /// 启动模拟Consul：healthy为false时健康实例列表为空
+    async fn start_mock_consul(healthy: Arc<AtomicBool>) -> String {
+        let app = Router::new().route(
+            "/v1/health/service/{name}",
+            get(move || {
+                let healthy = healthy.clone();
+                async move {
+                    if healthy.load(Ordering::SeqCst) {
+                        Json(json!([{
+                            "ServiceID": "user-service-1",
+                            "ServiceName": "user-service",
+                            "ServiceAddress": "127.0.0.1",
+                            "ServicePort": 8080
+                        }]))
+                    } else {
+                        Json(json!([]))
+                    }
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        url
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_services_blocks_until_healthy() {
+        let healthy = Arc::new(AtomicBool::new(false));
+        let consul_url = start_mock_consul(healthy.clone()).await;
+        let registry = ServiceRegistry::new(&consul_url);
+
+        // 200ms后依赖变为健康
+        let flip = healthy.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            flip.store(true, Ordering::SeqCst);
+        });
+
+        let start = Instant::now();
+        registry
+            .wait_for_services(
+                &["user-service".to_string()],
+                Duration::from_secs(5),
+                Duration::from_millis(50),
+            )
+            .await
+            .unwrap();
+
+        // 在依赖健康前一直等待，健康后很快返回
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(150), "等待时间过短: {:?}", elapsed);
+        assert!(elapsed < Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_services_times_out_when_still_unhealthy() {
+        let healthy = Arc::new(AtomicBool::new(false));
+        let consul_url = start_mock_consul(healthy).await;
+        let registry = ServiceRegistry::new(&consul_url);
+
+        let start = Instant::now();
+        let err = registry
+            .wait_for_services(
+                &["user-service".to_string()],
+                Duration::from_millis(300),
+                Duration::from_millis(50),
+            )
+            .await
+            .unwrap_err();
+
+        assert!(start.elapsed() >= Duration::from_millis(300));
+        assert!(err.to_string().contains("user-service"), "错误应列出未就绪的服务: {}", err);
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_services_empty_list_returns_immediately() {
+        // 不依赖任何服务时无需访问Consul
+        let registry = ServiceRegistry::new("http://127.0.0.1:1");
+        registry
+            .wait_for_services(&[], Duration::from_millis(100), Duration::from_millis(10))
+            .await
+            .unwrap();
+    }
+}
@@ -0,0 +1,76 @@
+//! gRPC传输层TLS辅助
+//!
+//! 按[`TlsConfig`]中的PEM文件路径构造tonic的服务端/客户端TLS配置，
+//! 供各服务二进制复用，避免在每个main.rs里重复读证书的样板代码。
+
+use tonic::transport::{Certificate, ClientTlsConfig, Identity, ServerTlsConfig};
+
+use crate::config::TlsConfig;
+use crate::Result;
+
+/// 加载本端证书与私钥
+fn load_identity(cfg: &TlsConfig) -> Result<Identity> {
+    let cert = std::fs::read(&cfg.cert_path)?;
+    let key = std::fs::read(&cfg.key_path)?;
+    Ok(Identity::from_pem(cert, key))
+}
+
+/// 加载信任的CA证书
+fn load_ca(cfg: &TlsConfig) -> Result<Certificate> {
+    Ok(Certificate::from_pem(std::fs::read(&cfg.ca_path)?))
+}
+
+/// 服务端TLS配置：出示本端证书，`client_auth`开启时强制校验客户端证书
+pub fn server_tls_config(cfg: &TlsConfig) -> Result<ServerTlsConfig> {
+    let mut tls = ServerTlsConfig::new().identity(load_identity(cfg)?);
+    if cfg.client_auth {
+        tls = tls.client_ca_root(load_ca(cfg)?);
+    }
+    Ok(tls)
+}
+
+/// 客户端TLS配置：用CA校验服务端，同时出示本端证书以支持双向TLS
+pub fn client_tls_config(cfg: &TlsConfig) -> Result<ClientTlsConfig> {
+    Ok(ClientTlsConfig::new()
+        .ca_certificate(load_ca(cfg)?)
+        .identity(load_identity(cfg)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config_in(dir: &std::path::Path, client_auth: bool) -> TlsConfig {
+        let path = |name: &str| dir.join(name).to_string_lossy().into_owned();
+        TlsConfig {
+            cert_path: path("cert.pem"),
+            key_path: path("key.pem"),
+            ca_path: path("ca.pem"),
+            client_auth,
+        }
+    }
+
+    #[test]
+    fn test_configs_load_from_pem_files() {
+        // Identity/Certificate只保存PEM字节，到握手时才解析，
+        // 这里用占位内容即可验证文件装配路径
+        let dir = std::env::temp_dir().join(format!("tls_test_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for name in ["cert.pem", "key.pem", "ca.pem"] {
+            std::fs::write(dir.join(name), "-----BEGIN TEST-----").unwrap();
+        }
+
+        let cfg = config_in(&dir, true);
+        assert!(server_tls_config(&cfg).is_ok());
+        assert!(client_tls_config(&cfg).is_ok());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_missing_pem_file_is_an_error() {
+        let cfg = config_in(std::path::Path::new("/nonexistent"), false);
+        assert!(server_tls_config(&cfg).is_err());
+        assert!(client_tls_config(&cfg).is_err());
+    }
+}
@@ -1,49 +1,6 @@
-use crate::{Error, Result, models::Claims};
-use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
-use std::env;
-use chrono::{Duration, Utc};
-use uuid::Uuid;
-
-// JWT工具函数
-pub fn generate_jwt(user_id: &Uuid, username: &str) -> Result<String> {
-    let expiration = Utc::now()
-        .checked_add_signed(Duration::seconds(
-            env::var("JWT_EXPIRATION")
-                .unwrap_or_else(|_| "86400".to_string())
-                .parse()
-                .unwrap_or(86400),
-        ))
-        .expect("有效的时间戳")
-        .timestamp() as usize;
-
-    let claims = Claims {
-        sub: user_id.to_string(),
-        username: username.to_string(),
-        exp: expiration,
-        iat: Utc::now().timestamp() as usize,
-    };
-
-    let secret = env::var("JWT_SECRET").unwrap_or_else(|_| "default_jwt_secret".to_string());
-    let token = encode(
-        &Header::default(),
-        &claims,
-        &EncodingKey::from_secret(secret.as_bytes()),
-    )?;
-
-    Ok(token)
-}
-
-pub fn validate_jwt(token: &str) -> Result<Claims> {
-    let secret = env::var("JWT_SECRET").unwrap_or_else(|_| "default_jwt_secret".to_string());
-    let validation = Validation::default();
-    let token_data = decode::<Claims>(
-        token,
-        &DecodingKey::from_secret(secret.as_bytes()),
-        &validation,
-    )?;
-
-    Ok(token_data.claims)
-}
+use crate::{Error, Result};
+
+// JWT签发与校验见auth模块（密钥来自jwt配置，不读环境变量）
 
 // 密码哈希工具
 pub fn hash_password(password: &str) -> Result<String> {
@@ -56,4 +13,327 @@ pub fn verify_password(password: &str, hash: &str) -> Result<bool> {
     let is_valid = bcrypt::verify(password, hash)
         .map_err(|e| Error::Internal(format!("密码验证失败: {}", e)))?;
     Ok(is_valid)
-} 
\ No newline at end of file
+}
+
+/// 基于Redis的会话序列号生成器
+///
+/// 每个会话一个Redis键`seq:{conversation_id}`，通过`INCRBY`一次预取
+/// `seq_step`个序号，区间内由本地原子计数器直接发放，耗尽后再取下一个
+/// 区间，把Redis往返从每条消息一次降到每`seq_step`条一次。不同实例
+/// 各自持有不相交的区间，同一会话的序号因此全局唯一且单调递增
+/// （区间内连续；实例重启会弃用区间内剩余的序号，留下空洞）。
+pub struct SequenceGenerator {
+    client: redis::Client,
+    step: u64,
+    /// 各会话当前持有的序号区间，按conversation_id索引
+    blocks: std::sync::Mutex<std::collections::HashMap<String, std::sync::Arc<SeqBlock>>>,
+}
+
+/// 一个已预取的序号区间，`next > end`表示已耗尽
+struct SeqBlock {
+    /// 下一个待发放的序号
+    next: std::sync::atomic::AtomicU64,
+    /// 区间上界（含）
+    end: std::sync::atomic::AtomicU64,
+    /// 区间补充锁，避免并发任务重复INCRBY
+    refill: tokio::sync::Mutex<()>,
+}
+
+impl SequenceGenerator {
+    pub fn new(client: redis::Client, seq_step: i32) -> Self {
+        Self {
+            client,
+            step: seq_step.max(1) as u64,
+            blocks: std::sync::Mutex::new(std::collections::HashMap::new()),
+        }
+    }
+
+    /// 发放conversation_id会话内的下一个序号（从1开始）
+    pub async fn next_seq(&self, conversation_id: &str) -> Result<i64> {
+        use std::sync::atomic::{AtomicU64, Ordering};
+
+        let block = {
+            let mut blocks = self.blocks.lock().unwrap();
+            blocks
+                .entry(conversation_id.to_string())
+                .or_insert_with(|| {
+                    std::sync::Arc::new(SeqBlock {
+                        // next > end，首次发放必然触发预取
+                        next: AtomicU64::new(1),
+                        end: AtomicU64::new(0),
+                        refill: tokio::sync::Mutex::new(()),
+                    })
+                })
+                .clone()
+        };
+
+        loop {
+            let candidate = block.next.fetch_add(1, Ordering::SeqCst);
+            if candidate <= block.end.load(Ordering::SeqCst) {
+                return Ok(candidate as i64);
+            }
+
+            // 区间耗尽：持锁补充，拿到锁后先复查（可能已被并发任务补过）
+            let _guard = block.refill.lock().await;
+            if block.next.load(Ordering::SeqCst) <= block.end.load(Ordering::SeqCst) {
+                continue;
+            }
+
+            let mut conn = self
+                .client
+                .get_multiplexed_async_connection()
+                .await
+                .map_err(Error::Redis)?;
+            let end: u64 = redis::cmd("INCRBY")
+                .arg(format!("seq:{}", conversation_id))
+                .arg(self.step)
+                .query_async(&mut conn)
+                .await
+                .map_err(Error::Redis)?;
+
+            // 必须先写next再写end：end更新前其他任务最多白拿一个候选值后
+            // 重试，不会发出重复序号；顺序反过来会把旧next下的序号发两次
+            block.next.store(end - self.step + 1, Ordering::SeqCst);
+            block.end.store(end, Ordering::SeqCst);
+        }
+    }
+}
+
+/// IP地理位置信息，来自ip2region库
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GeoInfo {
+    pub country: String,
+    /// 省份/州
+    pub region: String,
+    pub city: String,
+}
+
+/// ip2region xdb文件头长度
+const XDB_HEADER_LEN: usize = 256;
+/// 向量索引：256*256个条目，每个条目8字节(起始指针+结束指针)
+const XDB_VECTOR_LEN: usize = 256 * 256 * 8;
+/// 段索引条目长度：起始IP(4) + 结束IP(4) + 数据长度(2) + 数据指针(4)
+const XDB_SEGMENT_LEN: usize = 14;
+
+/// 基于ip2region xdb文件的IP地理位置查询器
+///
+/// 启动时把整个xdb文件（约11MB）读入内存，之后的查询无任何IO：
+/// 先按IP前两段查向量索引定位段索引区间，再在区间内二分查找。
+/// 数据格式为xdb v2（国家|区域|省份|城市|ISP），仅支持IPv4
+pub struct XdbGeoLocator {
+    data: Vec<u8>,
+}
+
+impl XdbGeoLocator {
+    /// 从路径加载xdb文件，文件缺失或过小（连索引区都不完整）时报错
+    pub fn new(path: &str) -> Result<Self> {
+        let data = std::fs::read(path)
+            .map_err(|e| Error::Internal(format!("无法读取xdb文件 {}: {}", path, e)))?;
+        if data.len() < XDB_HEADER_LEN + XDB_VECTOR_LEN {
+            return Err(Error::Internal(format!(
+                "xdb文件 {} 不完整: {}字节",
+                path,
+                data.len()
+            )));
+        }
+        Ok(Self { data })
+    }
+
+    /// 查询IPv4地址的地理位置，未收录或格式非法时返回None
+    pub fn locate(&self, ip: &str) -> Option<GeoInfo> {
+        let ip = u32::from(ip.parse::<std::net::Ipv4Addr>().ok()?);
+
+        // 向量索引按IP的前两段分桶，桶内是段索引的[start, last]指针
+        let vector_at =
+            XDB_HEADER_LEN + ((ip >> 24) as usize * 256 + (ip >> 16 & 0xFF) as usize) * 8;
+        let s_ptr = self.read_u32(vector_at)? as usize;
+        let e_ptr = self.read_u32(vector_at + 4)? as usize;
+        if e_ptr < s_ptr {
+            return None;
+        }
+
+        // 段索引内二分查找覆盖该IP的网段
+        let mut low = 0usize;
+        let mut high = (e_ptr - s_ptr) / XDB_SEGMENT_LEN;
+        while low <= high {
+            let mid = (low + high) / 2;
+            let at = s_ptr + mid * XDB_SEGMENT_LEN;
+            let start_ip = self.read_u32(at)?;
+            let end_ip = self.read_u32(at + 4)?;
+            if ip < start_ip {
+                if mid == 0 {
+                    break;
+                }
+                high = mid - 1;
+            } else if ip > end_ip {
+                low = mid + 1;
+            } else {
+                let data_len = self.read_u16(at + 8)? as usize;
+                let data_ptr = self.read_u32(at + 10)? as usize;
+                let raw = self.data.get(data_ptr..data_ptr + data_len)?;
+                return Self::parse_content(std::str::from_utf8(raw).ok()?);
+            }
+        }
+        None
+    }
+
+    /// 解析xdb内容行：国家|区域|省份|城市|ISP，"0"表示未知
+    fn parse_content(content: &str) -> Option<GeoInfo> {
+        let mut parts = content.split('|');
+        let country = parts.next()?;
+        let _area = parts.next()?;
+        let region = parts.next()?;
+        let city = parts.next()?;
+        let normalize = |s: &str| if s == "0" { String::new() } else { s.to_string() };
+        Some(GeoInfo {
+            country: normalize(country),
+            region: normalize(region),
+            city: normalize(city),
+        })
+    }
+
+    fn read_u32(&self, at: usize) -> Option<u32> {
+        let bytes = self.data.get(at..at + 4)?;
+        Some(u32::from_le_bytes(bytes.try_into().ok()?))
+    }
+
+    fn read_u16(&self, at: usize) -> Option<u16> {
+        let bytes = self.data.get(at..at + 2)?;
+        Some(u16::from_le_bytes(bytes.try_into().ok()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+    use std::sync::Arc;
+    use uuid::Uuid;
+
+    #[tokio::test]
+    #[ignore = "需要本地Redis (redis://127.0.0.1:6379)"]
+    async fn test_concurrent_seq_allocations_are_unique() {
+        let client = redis::Client::open("redis://127.0.0.1:6379").unwrap();
+        // 两个生成器模拟两个并发的msg-server实例，小步长迫使频繁预取
+        let gen_a = Arc::new(SequenceGenerator::new(client.clone(), 7));
+        let gen_b = Arc::new(SequenceGenerator::new(client.clone(), 7));
+        let conversation = format!("itest:{}", Uuid::new_v4());
+
+        async fn allocate(gen: Arc<SequenceGenerator>, conversation: String, n: usize) -> Vec<i64> {
+            let handles: Vec<_> = (0..n)
+                .map(|_| {
+                    let gen = gen.clone();
+                    let conversation = conversation.clone();
+                    tokio::spawn(async move { gen.next_seq(&conversation).await.unwrap() })
+                })
+                .collect();
+            let mut seqs = Vec::with_capacity(n);
+            for handle in handles {
+                seqs.push(handle.await.unwrap());
+            }
+            seqs
+        }
+
+        // 两个实例各并发分配50个，共100个序号不允许重复
+        let (a, b) = tokio::join!(
+            allocate(gen_a.clone(), conversation.clone(), 50),
+            allocate(gen_b, conversation.clone(), 50)
+        );
+        let seqs: HashSet<i64> = a.iter().chain(b.iter()).copied().collect();
+        assert_eq!(seqs.len(), 100, "并发分配的序号出现重复");
+        assert!(seqs.iter().all(|&s| s >= 1));
+
+        // 单个实例顺序分配严格递增
+        let mut prev = 0;
+        for _ in 0..10 {
+            let seq = gen_a.next_seq(&conversation).await.unwrap();
+            assert!(seq > prev, "序号未递增: {} -> {}", prev, seq);
+            prev = seq;
+        }
+
+        let mut conn = client.get_multiplexed_async_connection().await.unwrap();
+        let _: () = redis::cmd("DEL")
+            .arg(format!("seq:{}", conversation))
+            .query_async(&mut conn)
+            .await
+            .unwrap();
+    }
+
+    /// 构造一个最小的xdb文件：两个网段，所有向量索引桶都指向同一段索引区间
+    fn write_test_xdb(segments: &[(u32, u32, &str)]) -> std::path::PathBuf {
+        let mut buf = vec![0u8; XDB_HEADER_LEN + XDB_VECTOR_LEN];
+
+        // 内容区紧跟索引区，段索引区放在内容区之后
+        let mut contents = Vec::new();
+        let mut content_ptrs = Vec::new();
+        for (_, _, content) in segments {
+            content_ptrs.push((buf.len() + contents.len()) as u32);
+            contents.extend_from_slice(content.as_bytes());
+        }
+        buf.extend_from_slice(&contents);
+
+        let seg_start = buf.len() as u32;
+        for (i, (start_ip, end_ip, content)) in segments.iter().enumerate() {
+            buf.extend_from_slice(&start_ip.to_le_bytes());
+            buf.extend_from_slice(&end_ip.to_le_bytes());
+            buf.extend_from_slice(&(content.len() as u16).to_le_bytes());
+            buf.extend_from_slice(&content_ptrs[i].to_le_bytes());
+        }
+        let seg_last = seg_start + ((segments.len() - 1) * XDB_SEGMENT_LEN) as u32;
+
+        // 所有桶共用同一个段索引区间
+        for i in 0..256 * 256 {
+            let at = XDB_HEADER_LEN + i * 8;
+            buf[at..at + 4].copy_from_slice(&seg_start.to_le_bytes());
+            buf[at + 4..at + 8].copy_from_slice(&seg_last.to_le_bytes());
+        }
+
+        let path = std::env::temp_dir().join(format!("ip2region-test-{}.xdb", Uuid::new_v4()));
+        std::fs::write(&path, buf).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_xdb_locate_returns_geo_fields() {
+        let path = write_test_xdb(&[
+            (
+                u32::from(std::net::Ipv4Addr::new(1, 0, 0, 0)),
+                u32::from(std::net::Ipv4Addr::new(1, 0, 0, 255)),
+                "中国|0|广东省|深圳市|电信",
+            ),
+            (
+                u32::from(std::net::Ipv4Addr::new(8, 8, 8, 0)),
+                u32::from(std::net::Ipv4Addr::new(8, 8, 8, 255)),
+                "美国|0|加利福尼亚|0|谷歌",
+            ),
+        ]);
+        let locator = XdbGeoLocator::new(path.to_str().unwrap()).unwrap();
+
+        let geo = locator.locate("1.0.0.66").unwrap();
+        assert_eq!(geo.country, "中国");
+        assert_eq!(geo.region, "广东省");
+        assert_eq!(geo.city, "深圳市");
+
+        // "0"字段归一化为空
+        let geo = locator.locate("8.8.8.8").unwrap();
+        assert_eq!(geo.country, "美国");
+        assert_eq!(geo.city, "");
+
+        // 未收录的IP和非法输入返回None
+        assert!(locator.locate("2.0.0.1").is_none());
+        assert!(locator.locate("not-an-ip").is_none());
+        assert!(locator.locate("::1").is_none());
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_xdb_rejects_missing_or_truncated_file() {
+        assert!(XdbGeoLocator::new("/nonexistent/ip2region.xdb").is_err());
+
+        let path = std::env::temp_dir().join(format!("ip2region-trunc-{}.xdb", Uuid::new_v4()));
+        std::fs::write(&path, vec![0u8; 128]).unwrap();
+        assert!(XdbGeoLocator::new(path.to_str().unwrap()).is_err());
+        std::fs::remove_file(path).unwrap();
+    }
+}
\ No newline at end of file
@@ -0,0 +1,405 @@
+//! 出站Webhook分发器
+//!
+//! 外部集成可按事件类型订阅HTTP回调（用户创建、消息发送等）。
+//! 事件经无界队列由后台任务投递，投递失败按指数退避重试，
+//! 重试耗尽后进入死信队列。请求体使用HMAC-SHA256签名，
+//! 接收方可用订阅密钥校验`X-Webhook-Signature`头验证来源。
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use tokio::sync::mpsc;
+use tracing::{debug, info, warn};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// 签名头：HMAC-SHA256(secret, body)的十六进制
+pub const SIGNATURE_HEADER: &str = "X-Webhook-Signature";
+/// 事件类型头
+pub const EVENT_HEADER: &str = "X-Webhook-Event";
+/// 事件产生时间头（Unix秒）
+pub const TIMESTAMP_HEADER: &str = "X-Webhook-Timestamp";
+
+/// 单个Webhook订阅
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct WebhookSubscription {
+    /// 回调地址
+    pub url: String,
+    /// 签名密钥
+    pub secret: String,
+    /// 订阅的事件类型，空表示订阅全部事件
+    #[serde(default)]
+    pub event_types: Vec<String>,
+}
+
+impl WebhookSubscription {
+    fn matches(&self, event_type: &str) -> bool {
+        self.event_types.is_empty() || self.event_types.iter().any(|t| t == event_type)
+    }
+}
+
+/// Webhook分发配置
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+#[serde(default)]
+pub struct WebhookConfig {
+    pub enabled: bool,
+    /// 投递失败后的最大重试次数
+    pub max_retries: u32,
+    /// 重试的初始退避时间（毫秒），之后指数增长
+    pub retry_base_delay_ms: u64,
+    pub subscriptions: Vec<WebhookSubscription>,
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_retries: 3,
+            retry_base_delay_ms: 200,
+            subscriptions: Vec::new(),
+        }
+    }
+}
+
+/// 分发的事件，payload为任意JSON
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct WebhookEvent {
+    pub event_type: String,
+    /// 事件产生时间（Unix秒）
+    pub timestamp: i64,
+    pub payload: serde_json::Value,
+}
+
+impl WebhookEvent {
+    pub fn new(event_type: impl Into<String>, payload: serde_json::Value) -> Self {
+        Self {
+            event_type: event_type.into(),
+            timestamp: chrono::Utc::now().timestamp(),
+            payload,
+        }
+    }
+}
+
+/// 重试耗尽后进入死信队列的投递记录
+#[derive(Debug, Clone)]
+pub struct DeadLetter {
+    pub url: String,
+    pub event: WebhookEvent,
+    pub attempts: u32,
+    pub last_error: String,
+}
+
+/// Webhook分发器
+///
+/// `dispatch`仅入队不阻塞调用方，实际投递由后台任务完成。
+pub struct WebhookDispatcher {
+    tx: mpsc::UnboundedSender<WebhookEvent>,
+    dead_letters: Arc<Mutex<Vec<DeadLetter>>>,
+}
+
+impl WebhookDispatcher {
+    /// 启动分发器后台任务
+    pub fn start(config: WebhookConfig) -> Arc<Self> {
+        let (tx, mut rx) = mpsc::unbounded_channel::<WebhookEvent>();
+        let dead_letters: Arc<Mutex<Vec<DeadLetter>>> = Arc::new(Mutex::new(Vec::new()));
+
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .unwrap_or_else(|_| reqwest::Client::new());
+
+        let worker_dead_letters = dead_letters.clone();
+        tokio::spawn(async move {
+            info!(
+                "webhook dispatcher started, {} subscription(s)",
+                config.subscriptions.len()
+            );
+            while let Some(event) = rx.recv().await {
+                for sub in config
+                    .subscriptions
+                    .iter()
+                    .filter(|s| s.matches(&event.event_type))
+                {
+                    Self::deliver(&client, &config, sub, &event, &worker_dead_letters).await;
+                }
+            }
+        });
+
+        Arc::new(Self { tx, dead_letters })
+    }
+
+    /// 分发一个事件（异步投递，不阻塞调用方）
+    pub fn dispatch(&self, event: WebhookEvent) {
+        if self.tx.send(event).is_err() {
+            warn!("webhook dispatcher worker has stopped, event dropped");
+        }
+    }
+
+    /// 取出死信队列快照
+    pub fn dead_letters(&self) -> Vec<DeadLetter> {
+        self.dead_letters.lock().map(|d| d.clone()).unwrap_or_default()
+    }
+
+    /// 计算请求体签名（接收方用同样方式校验）
+    pub fn sign(secret: &str, body: &[u8]) -> String {
+        let mut mac =
+            HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC可接受任意长度密钥");
+        mac.update(body);
+        hex::encode(mac.finalize().into_bytes())
+    }
+
+    /// 向单个订阅投递事件，失败按指数退避重试，耗尽进入死信队列
+    async fn deliver(
+        client: &reqwest::Client,
+        config: &WebhookConfig,
+        sub: &WebhookSubscription,
+        event: &WebhookEvent,
+        dead_letters: &Arc<Mutex<Vec<DeadLetter>>>,
+    ) {
+        let body = match serde_json::to_vec(event) {
+            Ok(body) => body,
+            Err(e) => {
+                warn!("failed to serialize webhook event: {}", e);
+                return;
+            }
+        };
+        let signature = Self::sign(&sub.secret, &body);
+
+        let mut delay = Duration::from_millis(config.retry_base_delay_ms);
+        let mut last_error = String::new();
+
+        for attempt in 0..=config.max_retries {
+            if attempt > 0 {
+                tokio::time::sleep(delay).await;
+                delay *= 2;
+            }
+
+            let result = client
+                .post(&sub.url)
+                .header("Content-Type", "application/json")
+                .header(EVENT_HEADER, &event.event_type)
+                .header(TIMESTAMP_HEADER, event.timestamp.to_string())
+                .header(SIGNATURE_HEADER, &signature)
+                .body(body.clone())
+                .send()
+                .await;
+
+            match result {
+                Ok(resp) if resp.status().is_success() => {
+                    debug!(
+                        "webhook {} delivered to {} (attempt {})",
+                        event.event_type,
+                        sub.url,
+                        attempt + 1
+                    );
+                    return;
+                }
+                Ok(resp) => {
+                    last_error = format!("receiver returned status {}", resp.status());
+                }
+                Err(e) => {
+                    last_error = e.to_string();
+                }
+            }
+            warn!(
+                "webhook {} delivery to {} failed (attempt {}): {}",
+                event.event_type,
+                sub.url,
+                attempt + 1,
+                last_error
+            );
+        }
+
+        // 重试耗尽，进入死信队列
+        warn!(
+            "webhook {} to {} exhausted retries, moved to dead letter queue",
+            event.event_type, sub.url
+        );
+        if let Ok(mut dead) = dead_letters.lock() {
+            dead.push(DeadLetter {
+                url: sub.url.clone(),
+                event: event.clone(),
+                attempts: config.max_retries + 1,
+                last_error,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Bytes;
+    use axum::extract::State;
+    use axum::http::{HeaderMap, StatusCode};
+    use axum::routing::post;
+    use axum::Router;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// 记录收到的请求：(签名头, 事件类型头, 请求体)
+    type Received = Arc<Mutex<Vec<(String, String, Vec<u8>)>>>;
+
+    struct TestReceiver {
+        url: String,
+        received: Received,
+        hits: Arc<AtomicUsize>,
+    }
+
+    /// 启动一个测试接收端，前`fail_first`个请求返回500，之后返回200
+    async fn spawn_receiver(fail_first: usize) -> TestReceiver {
+        let received: Received = Arc::new(Mutex::new(Vec::new()));
+        let hits = Arc::new(AtomicUsize::new(0));
+
+        #[derive(Clone)]
+        struct AppState {
+            received: Received,
+            hits: Arc<AtomicUsize>,
+            fail_first: usize,
+        }
+
+        async fn handler(
+            State(state): State<AppState>,
+            headers: HeaderMap,
+            body: Bytes,
+        ) -> StatusCode {
+            let n = state.hits.fetch_add(1, Ordering::SeqCst);
+            if n < state.fail_first {
+                return StatusCode::INTERNAL_SERVER_ERROR;
+            }
+            let signature = headers
+                .get(SIGNATURE_HEADER)
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or_default()
+                .to_string();
+            let event_type = headers
+                .get(EVENT_HEADER)
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or_default()
+                .to_string();
+            state
+                .received
+                .lock()
+                .unwrap()
+                .push((signature, event_type, body.to_vec()));
+            StatusCode::OK
+        }
+
+        let state = AppState {
+            received: received.clone(),
+            hits: hits.clone(),
+            fail_first,
+        };
+        let app = Router::new().route("/hook", post(handler)).with_state(state);
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        TestReceiver {
+            url: format!("http://{}/hook", addr),
+            received,
+            hits,
+        }
+    }
+
+    fn test_config(url: &str, event_types: Vec<String>, max_retries: u32) -> WebhookConfig {
+        WebhookConfig {
+            enabled: true,
+            max_retries,
+            retry_base_delay_ms: 10,
+            subscriptions: vec![WebhookSubscription {
+                url: url.to_string(),
+                secret: "hook_secret".to_string(),
+                event_types,
+            }],
+        }
+    }
+
+    async fn wait_until(mut cond: impl FnMut() -> bool) {
+        for _ in 0..100 {
+            if cond() {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        panic!("condition not met within timeout");
+    }
+
+    #[tokio::test]
+    async fn test_matching_event_posts_signed_payload() {
+        let receiver = spawn_receiver(0).await;
+        let dispatcher = WebhookDispatcher::start(test_config(
+            &receiver.url,
+            vec!["user.created".to_string()],
+            0,
+        ));
+
+        // 不匹配的事件不投递
+        dispatcher.dispatch(WebhookEvent::new(
+            "message.sent",
+            serde_json::json!({"id": "m1"}),
+        ));
+        // 匹配的事件投递且带签名
+        dispatcher.dispatch(WebhookEvent::new(
+            "user.created",
+            serde_json::json!({"user_id": "u1", "username": "alice"}),
+        ));
+
+        let received = receiver.received.clone();
+        wait_until(|| !received.lock().unwrap().is_empty()).await;
+
+        let got = receiver.received.lock().unwrap();
+        assert_eq!(got.len(), 1, "只应投递匹配订阅的事件");
+        let (signature, event_type, body) = &got[0];
+        assert_eq!(event_type, "user.created");
+        assert_eq!(signature, &WebhookDispatcher::sign("hook_secret", body));
+
+        let event: WebhookEvent = serde_json::from_slice(body).unwrap();
+        assert_eq!(event.event_type, "user.created");
+        assert_eq!(event.payload["username"], "alice");
+    }
+
+    #[tokio::test]
+    async fn test_delivery_retries_until_success() {
+        // 前两次返回500，第三次成功
+        let receiver = spawn_receiver(2).await;
+        let dispatcher =
+            WebhookDispatcher::start(test_config(&receiver.url, vec![], 3));
+
+        dispatcher.dispatch(WebhookEvent::new(
+            "user.created",
+            serde_json::json!({"user_id": "u1"}),
+        ));
+
+        let received = receiver.received.clone();
+        wait_until(|| !received.lock().unwrap().is_empty()).await;
+
+        assert_eq!(receiver.hits.load(Ordering::SeqCst), 3);
+        assert!(dispatcher.dead_letters().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_exhausted_retries_go_to_dead_letter_queue() {
+        // 所有请求都失败
+        let receiver = spawn_receiver(usize::MAX).await;
+        let dispatcher =
+            WebhookDispatcher::start(test_config(&receiver.url, vec![], 1));
+
+        dispatcher.dispatch(WebhookEvent::new(
+            "user.created",
+            serde_json::json!({"user_id": "u1"}),
+        ));
+
+        let dispatcher_ref = dispatcher.clone();
+        wait_until(move || !dispatcher_ref.dead_letters().is_empty()).await;
+
+        let dead = dispatcher.dead_letters();
+        assert_eq!(dead.len(), 1);
+        assert_eq!(dead[0].attempts, 2);
+        assert_eq!(dead[0].event.event_type, "user.created");
+    }
+}
@@ -0,0 +1,3 @@
+-- 账号删除（GDPR）：用户软删除标记
+-- deleted_at非空表示账号已删除，档案字段已匿名化
+ALTER TABLE users ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMP NULL;
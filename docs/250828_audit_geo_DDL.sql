@@ -0,0 +1,4 @@
+-- 审计日志增加IP地理位置(来自ip2region xdb库, 解析失败时为NULL)
+ALTER TABLE audit_log ADD COLUMN country VARCHAR(64);
+ALTER TABLE audit_log ADD COLUMN region VARCHAR(64);
+ALTER TABLE audit_log ADD COLUMN city VARCHAR(64);
@@ -0,0 +1,10 @@
+-- 会话列表查询索引(按会话分组取最新一条消息与未读数)
+
+-- 单聊会话: 按接收者+时间倒序扫描
+CREATE INDEX idx_messages_receiver_send_time ON messages (receiver_id, send_time DESC);
+
+-- 群聊会话: 按群组+时间倒序扫描
+CREATE INDEX idx_messages_group_send_time ON messages (group_id, send_time DESC) WHERE group_id IS NOT NULL;
+
+-- 未读数统计: 只覆盖收件箱中的未读消息
+CREATE INDEX idx_msg_rec_box_user_unread ON msg_rec_box (user_id) WHERE is_read = FALSE;
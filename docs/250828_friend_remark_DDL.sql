@@ -0,0 +1,5 @@
+-- 好友备注：每个用户可以给好友设置私有备注名，只有设置方自己可见
+-- 好友关系单行存储双向关系，因此备注按方向拆成两列：
+-- user_remark是user_id侧给friend_id设置的备注，friend_remark反之
+ALTER TABLE friendships ADD COLUMN IF NOT EXISTS user_remark VARCHAR(60) NULL;
+ALTER TABLE friendships ADD COLUMN IF NOT EXISTS friend_remark VARCHAR(60) NULL;
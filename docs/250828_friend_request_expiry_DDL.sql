@@ -0,0 +1,7 @@
+-- 好友请求过期：PENDING请求到期后视为不存在，并由friend-service的
+-- 后台任务每小时删除一次，避免friendships表无限堆积未处理请求。
+-- 过期时间在插入时按friend.request_ttl_days计算，默认值兜底7天
+ALTER TABLE friendships ADD COLUMN IF NOT EXISTS expires_at TIMESTAMP NULL DEFAULT (CURRENT_TIMESTAMP + INTERVAL '7 days');
+
+-- 清理任务按过期时间扫描PENDING行
+CREATE INDEX IF NOT EXISTS idx_friendships_pending_expires_at ON friendships(expires_at) WHERE status = 'PENDING';
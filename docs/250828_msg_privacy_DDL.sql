@@ -0,0 +1,6 @@
+-- 单聊隐私设置：谁可以给我发消息
+-- EVERYONE: 任何人（默认）；FRIENDS: 仅好友；NOBODY: 任何人都不可以
+-- 该设置在消息投递入口处检查，拒绝时对发送方返回通用失败，不暴露隐私配置
+ALTER TABLE users
+    ADD COLUMN IF NOT EXISTS msg_privacy VARCHAR(10) NOT NULL DEFAULT 'EVERYONE'
+    CONSTRAINT check_msg_privacy CHECK (msg_privacy IN ('EVERYONE', 'FRIENDS', 'NOBODY'));
@@ -0,0 +1,48 @@
+-- 消息服务(msg-server)持久化表
+
+-- 用户消息序列号表
+CREATE TABLE sequence
+(
+    user_id    VARCHAR(36) PRIMARY KEY,
+    max_seq    BIGINT    NOT NULL DEFAULT 0,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- 消息历史表
+CREATE TABLE messages
+(
+    server_id    VARCHAR(36) PRIMARY KEY,
+    local_id     VARCHAR(36),
+    send_id      VARCHAR(36) NOT NULL,
+    receiver_id  VARCHAR(36) NOT NULL,
+    group_id     VARCHAR(36),
+    msg_type     INT         NOT NULL,
+    content_type INT         NOT NULL,
+    content      BYTEA,
+    seq          BIGINT      NOT NULL DEFAULT 0,
+    send_seq     BIGINT      NOT NULL DEFAULT 0,
+    platform     INT         NOT NULL DEFAULT 0,
+    send_time    BIGINT      NOT NULL,
+    create_time  TIMESTAMP   NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_messages_send_id ON messages (send_id);
+CREATE INDEX idx_messages_receiver_id ON messages (receiver_id);
+CREATE INDEX idx_messages_send_time ON messages (send_time);
+
+-- 消息收件箱表(按接收者保存消息副本)
+CREATE TABLE msg_rec_box
+(
+    user_id      VARCHAR(36) NOT NULL,
+    seq          BIGINT      NOT NULL,
+    server_id    VARCHAR(36) NOT NULL,
+    send_id      VARCHAR(36) NOT NULL,
+    msg_type     INT         NOT NULL,
+    content_type INT         NOT NULL,
+    content      BYTEA,
+    send_time    BIGINT      NOT NULL,
+    is_read      BOOLEAN     NOT NULL DEFAULT FALSE,
+    PRIMARY KEY (user_id, seq)
+);
+
+CREATE INDEX idx_msg_rec_box_server_id ON msg_rec_box (server_id);
@@ -0,0 +1,9 @@
+-- 用户在线状态：聊天UI展示在线/离开/免打扰/离线
+-- ONLINE: 在线；AWAY: 离开；DND: 免打扰；OFFLINE: 离线（默认）
+-- last_seen记录最近一次状态变更时间，客户端据此展示"最后在线于..."
+ALTER TABLE users
+    ADD COLUMN IF NOT EXISTS status VARCHAR(10) NOT NULL DEFAULT 'OFFLINE'
+    CONSTRAINT check_status CHECK (status IN ('ONLINE', 'AWAY', 'DND', 'OFFLINE'));
+
+ALTER TABLE users
+    ADD COLUMN IF NOT EXISTS last_seen TIMESTAMP;
@@ -88,6 +88,11 @@ CREATE TABLE groups
     description TEXT,
     avatar_url  VARCHAR(255),
     owner_id    VARCHAR(36)  NOT NULL,
+    announcement TEXT, -- 置顶群公告，与description独立
+    announcement_updated_at TIMESTAMP NULL,
+    announcement_updated_by VARCHAR(36),
+    max_members INTEGER      NOT NULL DEFAULT 500, -- 成员数上限，建群时取配置group.max_members
+    is_public   BOOLEAN      NOT NULL DEFAULT TRUE, -- 公开群组可被搜索发现
     created_at  TIMESTAMP    NOT NULL DEFAULT CURRENT_TIMESTAMP,
     updated_at  TIMESTAMP    NOT NULL DEFAULT CURRENT_TIMESTAMP,
     CONSTRAINT fk_owner_id FOREIGN KEY (owner_id) REFERENCES users (id) ON DELETE CASCADE
@@ -95,6 +100,9 @@ CREATE TABLE groups
 
 CREATE INDEX idx_groups_owner_id ON groups (owner_id);
 CREATE INDEX idx_groups_name ON groups (name);
+-- 全文搜索索引，表达式须与search_groups查询中的一致
+CREATE INDEX idx_groups_search ON groups
+    USING GIN (to_tsvector('simple', name || ' ' || COALESCE(description, '')));
 
 -- 创建触发器自动更新updated_at
 CREATE TRIGGER update_groups_modtime
@@ -111,6 +119,7 @@ CREATE TABLE group_members
     user_id   VARCHAR(36) NOT NULL,
     role      VARCHAR(10) NOT NULL DEFAULT 'MEMBER',
     joined_at TIMESTAMP   NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    muted_until TIMESTAMP NULL, -- 免打扰截止时间，NULL表示未静音
     CONSTRAINT check_role CHECK (role IN ('MEMBER', 'ADMIN', 'OWNER')),
     CONSTRAINT unique_membership UNIQUE (group_id, user_id),
     CONSTRAINT fk_group_id FOREIGN KEY (group_id) REFERENCES groups (id) ON DELETE CASCADE,
@@ -120,6 +129,20 @@ CREATE TABLE group_members
 CREATE INDEX idx_group_members_group_id ON group_members (group_id);
 CREATE INDEX idx_group_members_user_id ON group_members (user_id);
 
+-- 入群申请表（批准后申请行删除并写入group_members）
+CREATE TABLE group_join_requests
+(
+    id         VARCHAR(36) PRIMARY KEY,
+    group_id   VARCHAR(36) NOT NULL,
+    user_id    VARCHAR(36) NOT NULL,
+    created_at TIMESTAMP   NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT unique_join_request UNIQUE (group_id, user_id),
+    CONSTRAINT fk_group_id FOREIGN KEY (group_id) REFERENCES groups (id) ON DELETE CASCADE,
+    CONSTRAINT fk_user_id FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_group_join_requests_group_id ON group_join_requests (group_id);
+
 -- 群组消息表
 CREATE TABLE group_messages
 (
@@ -137,4 +160,18 @@ CREATE TABLE group_messages
 
 CREATE INDEX idx_group_messages_group_id ON group_messages (group_id);
 CREATE INDEX idx_group_messages_sender_id ON group_messages (sender_id);
-CREATE INDEX idx_group_messages_sent_at ON group_messages (sent_at);
\ No newline at end of file
+CREATE INDEX idx_group_messages_sent_at ON group_messages (sent_at);
+-- 认证审计日志表
+CREATE TABLE audit_log
+(
+    id         VARCHAR(36) PRIMARY KEY,
+    user_id    VARCHAR(36) NOT NULL,
+    event_type VARCHAR(20) NOT NULL,
+    ip_address VARCHAR(45),
+    user_agent VARCHAR(255),
+    created_at TIMESTAMP   NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT check_event_type CHECK (event_type IN ('LOGIN', 'LOGOUT', 'TOKEN_REFRESH', 'TOKEN_INVALIDATE'))
+);
+
+CREATE INDEX idx_audit_log_user_id ON audit_log (user_id);
+CREATE INDEX idx_audit_log_created_at ON audit_log (created_at);
@@ -47,6 +47,8 @@ pub struct Friend {
     pub username: String,
     pub nickname: Option<String>,
     pub avatar_url: Option<String>,
+    // 查询方给该好友设置的备注，仅设置方可见
+    pub remark: Option<String>,
     pub friendship_created_at: DateTime<Utc>,
 }
 
@@ -59,6 +61,7 @@ impl Friend {
             username: self.username.clone(),
             nickname: self.nickname.clone(),
             avatar_url: self.avatar_url.clone(),
+            remark: self.remark.clone(),
             friendship_created_at: Some(prost_types::Timestamp::from(created_system_time)),
         }
     }
@@ -6,6 +6,27 @@ use common::proto::friend::FriendshipStatus;
 
 use crate::model::friendship::{Friendship, Friend};
 
+// friendships.status在DDL中以字符串存储（check_status约束），
+// 历史数据可能存有数字编码，读取时两种都兼容
+fn status_to_db(status: FriendshipStatus) -> &'static str {
+    match status {
+        FriendshipStatus::Pending => "PENDING",
+        FriendshipStatus::Accepted => "ACCEPTED",
+        FriendshipStatus::Rejected => "REJECTED",
+        FriendshipStatus::Blocked => "BLOCKED",
+    }
+}
+
+fn status_code(status: &str) -> i32 {
+    match status {
+        "PENDING" => FriendshipStatus::Pending as i32,
+        "ACCEPTED" => FriendshipStatus::Accepted as i32,
+        "REJECTED" => FriendshipStatus::Rejected as i32,
+        "BLOCKED" => FriendshipStatus::Blocked as i32,
+        other => other.parse::<i32>().unwrap_or(0),
+    }
+}
+
 pub struct FriendshipRepository {
     pool: PgPool,
 }
@@ -15,26 +36,28 @@ impl FriendshipRepository {
         Self { pool }
     }
     
-    // 创建好友请求
-    pub async fn create_friend_request(&self, user_id: Uuid, friend_id: Uuid) -> Result<Friendship> {
+    // 创建好友请求，ttl_days天内未被处理即过期
+    pub async fn create_friend_request(&self, user_id: Uuid, friend_id: Uuid, ttl_days: i64) -> Result<Friendship> {
         let friendship = Friendship::new(user_id, friend_id);
-        
+
         // 将DateTime<Utc>转换为NaiveDateTime
         let created_at_naive = friendship.created_at.naive_utc();
         let updated_at_naive = friendship.updated_at.naive_utc();
-        
+        let expires_at_naive = (friendship.created_at + chrono::Duration::days(ttl_days)).naive_utc();
+
         let result = sqlx::query!(
             r#"
-            INSERT INTO friendships (id, user_id, friend_id, status, created_at, updated_at)
-            VALUES ($1, $2, $3, $4, $5, $6)
+            INSERT INTO friendships (id, user_id, friend_id, status, created_at, updated_at, expires_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
             RETURNING id, user_id, friend_id, status, created_at, updated_at
             "#,
             friendship.id.to_string(),
             friendship.user_id.to_string(),
             friendship.friend_id.to_string(),
-            friendship.status.to_string(),
+            status_to_db(FriendshipStatus::Pending),
             created_at_naive,
-            updated_at_naive
+            updated_at_naive,
+            expires_at_naive
         )
         .fetch_one(&self.pool)
         .await?;
@@ -43,40 +66,60 @@ impl FriendshipRepository {
             id: Uuid::parse_str(&result.id).unwrap(),
             user_id: Uuid::parse_str(&result.user_id).unwrap(),
             friend_id: Uuid::parse_str(&result.friend_id).unwrap(),
-            status: result.status.parse::<i32>().unwrap_or(0),
+            status: status_code(&result.status),
             created_at: Utc.from_utc_datetime(&result.created_at),
             updated_at: Utc.from_utc_datetime(&result.updated_at),
         })
     }
     
-    // 接受好友请求
-    pub async fn accept_friend_request(&self, user_id: Uuid, friend_id: Uuid) -> Result<Friendship> {
+    // 统计用户未被处理的出站好友请求数量（防骚扰上限用）
+    pub async fn count_pending_outgoing(&self, user_id: Uuid) -> Result<i64> {
+        let total = sqlx::query!(
+            r#"
+            SELECT COUNT(*) as "total!"
+            FROM friendships
+            WHERE user_id = $1 AND status = $2
+            "#,
+            user_id.to_string(),
+            status_to_db(FriendshipStatus::Pending)
+        )
+        .fetch_one(&self.pool)
+        .await?
+        .total;
+
+        Ok(total)
+    }
+
+    // 接受好友请求；请求不存在或已过期（尚未被清理任务删除）时返回None
+    pub async fn accept_friend_request(&self, user_id: Uuid, friend_id: Uuid) -> Result<Option<Friendship>> {
         let now = Utc::now();
         let now_naive = now.naive_utc();
-        
+
         let result = sqlx::query!(
             r#"
             UPDATE friendships
             SET status = $1, updated_at = $2
             WHERE user_id = $3 AND friend_id = $4
+              AND (status <> $5 OR expires_at IS NULL OR expires_at >= $2)
             RETURNING id, user_id, friend_id, status, created_at, updated_at
             "#,
-            (FriendshipStatus::Accepted as i32).to_string(),
+            status_to_db(FriendshipStatus::Accepted),
             now_naive,
+            user_id.to_string(),
             friend_id.to_string(),
-            user_id.to_string()
+            status_to_db(FriendshipStatus::Pending)
         )
-        .fetch_one(&self.pool)
+        .fetch_optional(&self.pool)
         .await?;
-        
-        Ok(Friendship {
+
+        Ok(result.map(|result| Friendship {
             id: Uuid::parse_str(&result.id).unwrap(),
             user_id: Uuid::parse_str(&result.user_id).unwrap(),
             friend_id: Uuid::parse_str(&result.friend_id).unwrap(),
-            status: result.status.parse::<i32>().unwrap_or(0),
+            status: status_code(&result.status),
             created_at: Utc.from_utc_datetime(&result.created_at),
             updated_at: Utc.from_utc_datetime(&result.updated_at),
-        })
+        }))
     }
     
     // 拒绝好友请求
@@ -91,10 +134,10 @@ impl FriendshipRepository {
             WHERE user_id = $3 AND friend_id = $4
             RETURNING id, user_id, friend_id, status, created_at, updated_at
             "#,
-            (FriendshipStatus::Rejected as i32).to_string(),
+            status_to_db(FriendshipStatus::Rejected),
             now_naive,
-            friend_id.to_string(),
-            user_id.to_string()
+            user_id.to_string(),
+            friend_id.to_string()
         )
         .fetch_one(&self.pool)
         .await?;
@@ -103,30 +146,38 @@ impl FriendshipRepository {
             id: Uuid::parse_str(&result.id).unwrap(),
             user_id: Uuid::parse_str(&result.user_id).unwrap(),
             friend_id: Uuid::parse_str(&result.friend_id).unwrap(),
-            status: result.status.parse::<i32>().unwrap_or(0),
+            status: status_code(&result.status),
             created_at: Utc.from_utc_datetime(&result.created_at),
             updated_at: Utc.from_utc_datetime(&result.updated_at),
         })
     }
     
     // 获取好友列表
-    pub async fn get_friend_list(&self, user_id: Uuid) -> Result<Vec<Friend>> {
+    pub async fn get_friend_list(&self, user_id: Uuid, page: i32, page_size: i32) -> Result<(Vec<Friend>, i32)> {
+        // 计算分页
+        let offset = (page - 1) * page_size;
+
         let friends = sqlx::query!(
             r#"
-            SELECT 
-                u.id, 
-                u.username, 
-                u.nickname, 
-                u.avatar_url, 
+            SELECT
+                u.id,
+                u.username,
+                u.nickname,
+                u.avatar_url,
+                CASE WHEN f.user_id = $1 THEN f.user_remark ELSE f.friend_remark END as remark,
                 f.created_at as friendship_created_at
             FROM users u
-            JOIN friendships f ON 
-                (f.friend_id = u.id AND f.user_id = $1) OR 
+            JOIN friendships f ON
+                (f.friend_id = u.id AND f.user_id = $1) OR
                 (f.user_id = u.id AND f.friend_id = $1)
             WHERE f.status = $2
+            ORDER BY u.username
+            LIMIT $3 OFFSET $4
             "#,
             user_id.to_string(),
-            (FriendshipStatus::Accepted as i32).to_string()
+            status_to_db(FriendshipStatus::Accepted),
+            page_size as i64,
+            offset as i64
         )
         .fetch_all(&self.pool)
         .await?;
@@ -138,23 +189,45 @@ impl FriendshipRepository {
                 username: f.username,
                 nickname: f.nickname,
                 avatar_url: f.avatar_url,
+                remark: f.remark,
                 friendship_created_at: Utc.from_utc_datetime(&f.friendship_created_at),
             })
             .collect();
+
+        // 查询总数
+        let total = sqlx::query!(
+            r#"
+            SELECT COUNT(*) as "total!"
+            FROM friendships f
+            WHERE (f.user_id = $1 OR f.friend_id = $1) AND f.status = $2
+            "#,
+            user_id.to_string(),
+            status_to_db(FriendshipStatus::Accepted)
+        )
+        .fetch_one(&self.pool)
+        .await?
+        .total;
         
-        Ok(result)
+        Ok((result, total as i32))
     }
     
     // 获取好友请求列表
-    pub async fn get_friend_requests(&self, user_id: Uuid) -> Result<Vec<Friendship>> {
+    pub async fn get_friend_requests(&self, user_id: Uuid, page: i32, page_size: i32) -> Result<(Vec<Friendship>, i32)> {
+        // 计算分页
+        let offset = (page - 1) * page_size;
+
         let requests = sqlx::query!(
             r#"
             SELECT id, user_id, friend_id, status, created_at, updated_at
             FROM friendships
             WHERE friend_id = $1 AND status = $2
+            ORDER BY created_at DESC
+            LIMIT $3 OFFSET $4
             "#,
             user_id.to_string(),
-            (FriendshipStatus::Pending as i32).to_string()
+            status_to_db(FriendshipStatus::Pending),
+            page_size as i64,
+            offset as i64
         )
         .fetch_all(&self.pool)
         .await?;
@@ -165,15 +238,79 @@ impl FriendshipRepository {
                 id: Uuid::parse_str(&r.id).unwrap(),
                 user_id: Uuid::parse_str(&r.user_id).unwrap(),
                 friend_id: Uuid::parse_str(&r.friend_id).unwrap(),
-                status: r.status.parse::<i32>().unwrap_or(0),
+                status: status_code(&r.status),
                 created_at: Utc.from_utc_datetime(&r.created_at),
                 updated_at: Utc.from_utc_datetime(&r.updated_at),
             })
             .collect();
+
+        // 查询总数
+        let total = sqlx::query!(
+            r#"
+            SELECT COUNT(*) as "total!"
+            FROM friendships
+            WHERE friend_id = $1 AND status = $2
+            "#,
+            user_id.to_string(),
+            status_to_db(FriendshipStatus::Pending)
+        )
+        .fetch_one(&self.pool)
+        .await?
+        .total;
         
-        Ok(result)
+        Ok((result, total as i32))
     }
     
+    // 获取自己发出的、尚未被处理的好友请求列表
+    pub async fn get_sent_friend_requests(&self, user_id: Uuid, page: i32, page_size: i32) -> Result<(Vec<Friendship>, i32)> {
+        // 计算分页
+        let offset = (page - 1) * page_size;
+
+        let requests = sqlx::query!(
+            r#"
+            SELECT id, user_id, friend_id, status, created_at, updated_at
+            FROM friendships
+            WHERE user_id = $1 AND status = $2
+            ORDER BY created_at DESC
+            LIMIT $3 OFFSET $4
+            "#,
+            user_id.to_string(),
+            status_to_db(FriendshipStatus::Pending),
+            page_size as i64,
+            offset as i64
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let result = requests
+            .into_iter()
+            .map(|r| Friendship {
+                id: Uuid::parse_str(&r.id).unwrap(),
+                user_id: Uuid::parse_str(&r.user_id).unwrap(),
+                friend_id: Uuid::parse_str(&r.friend_id).unwrap(),
+                status: status_code(&r.status),
+                created_at: Utc.from_utc_datetime(&r.created_at),
+                updated_at: Utc.from_utc_datetime(&r.updated_at),
+            })
+            .collect();
+
+        // 查询总数
+        let total = sqlx::query!(
+            r#"
+            SELECT COUNT(*) as "total!"
+            FROM friendships
+            WHERE user_id = $1 AND status = $2
+            "#,
+            user_id.to_string(),
+            status_to_db(FriendshipStatus::Pending)
+        )
+        .fetch_one(&self.pool)
+        .await?
+        .total;
+
+        Ok((result, total as i32))
+    }
+
     // 删除好友
     pub async fn delete_friend(&self, user_id: Uuid, friend_id: Uuid) -> Result<bool> {
         let rows_affected = sqlx::query!(
@@ -191,6 +328,94 @@ impl FriendshipRepository {
         Ok(rows_affected > 0)
     }
     
+    // 设置好友备注
+    // 好友关系单行存双向，按调用方所在方向只写自己的备注列，
+    // 另一方的备注不受影响；空备注写为NULL表示清除
+    pub async fn set_friend_remark(&self, user_id: Uuid, friend_id: Uuid, remark: &str) -> Result<bool> {
+        let remark = if remark.is_empty() {
+            None
+        } else {
+            Some(remark.to_string())
+        };
+        let now_naive = Utc::now().naive_utc();
+
+        let rows_affected = sqlx::query!(
+            r#"
+            UPDATE friendships
+            SET user_remark = CASE WHEN user_id = $1 THEN $3 ELSE user_remark END,
+                friend_remark = CASE WHEN friend_id = $1 THEN $3 ELSE friend_remark END,
+                updated_at = $4
+            WHERE ((user_id = $1 AND friend_id = $2) OR (user_id = $2 AND friend_id = $1))
+              AND status = $5
+            "#,
+            user_id.to_string(),
+            friend_id.to_string(),
+            remark,
+            now_naive,
+            status_to_db(FriendshipStatus::Accepted)
+        )
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        Ok(rows_affected > 0)
+    }
+
+    // 设置好友私密别名（note），与set_friend_remark共用同一份按方向存储的字段，
+    // 返回更新后的好友关系与设置方视角的当前别名；关系不存在或未接受时返回None
+    pub async fn set_note(
+        &self,
+        user_id: Uuid,
+        friend_id: Uuid,
+        note: &str,
+    ) -> Result<Option<(Friendship, Option<String>)>> {
+        let note = if note.is_empty() {
+            None
+        } else {
+            Some(note.to_string())
+        };
+        let now_naive = Utc::now().naive_utc();
+
+        let result = sqlx::query!(
+            r#"
+            UPDATE friendships
+            SET user_remark = CASE WHEN user_id = $1 THEN $3 ELSE user_remark END,
+                friend_remark = CASE WHEN friend_id = $1 THEN $3 ELSE friend_remark END,
+                updated_at = $4
+            WHERE ((user_id = $1 AND friend_id = $2) OR (user_id = $2 AND friend_id = $1))
+              AND status = $5
+            RETURNING id, user_id, friend_id, status, created_at, updated_at, user_remark, friend_remark
+            "#,
+            user_id.to_string(),
+            friend_id.to_string(),
+            note,
+            now_naive,
+            status_to_db(FriendshipStatus::Accepted)
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(result.map(|r| {
+            // 设置方处于哪一侧，别名就取哪一列
+            let note = if r.user_id == user_id.to_string() {
+                r.user_remark
+            } else {
+                r.friend_remark
+            };
+            (
+                Friendship {
+                    id: Uuid::parse_str(&r.id).unwrap(),
+                    user_id: Uuid::parse_str(&r.user_id).unwrap(),
+                    friend_id: Uuid::parse_str(&r.friend_id).unwrap(),
+                    status: status_code(&r.status),
+                    created_at: Utc.from_utc_datetime(&r.created_at),
+                    updated_at: Utc.from_utc_datetime(&r.updated_at),
+                },
+                note,
+            )
+        }))
+    }
+
     // 检查好友关系
     pub async fn check_friendship(&self, user_id: Uuid, friend_id: Uuid) -> Result<Option<FriendshipStatus>> {
         let result = sqlx::query!(
@@ -205,15 +430,452 @@ impl FriendshipRepository {
         .fetch_optional(&self.pool)
         .await?;
         
-        Ok(result.map(|r| {
-            let status_code = r.status.parse::<i32>().unwrap_or(0);
-            match status_code {
-                0 => FriendshipStatus::Pending,
-                1 => FriendshipStatus::Accepted,
-                2 => FriendshipStatus::Rejected,
-                3 => FriendshipStatus::Blocked,
-                _ => FriendshipStatus::Pending,
-            }
+        Ok(result.map(|r| match status_code(&r.status) {
+            1 => FriendshipStatus::Accepted,
+            2 => FriendshipStatus::Rejected,
+            3 => FriendshipStatus::Blocked,
+            _ => FriendshipStatus::Pending,
         }))
     }
-}
\ No newline at end of file
+    
+    // 拉黑用户：覆盖两个方向上已有的任何关系，user_id为拉黑发起方
+    pub async fn block_user(&self, user_id: Uuid, blocked_id: Uuid) -> Result<Friendship> {
+        let mut tx = self.pool.begin().await?;
+        
+        // 删除已有关系，拉黑记录总是以发起方为user_id，便于取消时校验
+        sqlx::query!(
+            r#"
+            DELETE FROM friendships
+            WHERE (user_id = $1 AND friend_id = $2) OR (user_id = $2 AND friend_id = $1)
+            "#,
+            user_id.to_string(),
+            blocked_id.to_string()
+        )
+        .execute(&mut *tx)
+        .await?;
+        
+        let now = Utc::now();
+        let now_naive = now.naive_utc();
+        
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO friendships (id, user_id, friend_id, status, created_at, updated_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING id, user_id, friend_id, status, created_at, updated_at
+            "#,
+            Uuid::new_v4().to_string(),
+            user_id.to_string(),
+            blocked_id.to_string(),
+            status_to_db(FriendshipStatus::Blocked),
+            now_naive,
+            now_naive
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        
+        tx.commit().await?;
+        
+        Ok(Friendship {
+            id: Uuid::parse_str(&result.id).unwrap(),
+            user_id: Uuid::parse_str(&result.user_id).unwrap(),
+            friend_id: Uuid::parse_str(&result.friend_id).unwrap(),
+            status: status_code(&result.status),
+            created_at: Utc.from_utc_datetime(&result.created_at),
+            updated_at: Utc.from_utc_datetime(&result.updated_at),
+        })
+    }
+    
+    // 删除所有已过期的PENDING请求，返回删除条数，由后台任务定时调用
+    pub async fn expire_requests(&self) -> Result<u64> {
+        let rows_affected = sqlx::query!(
+            r#"
+            DELETE FROM friendships
+            WHERE status = $1 AND expires_at IS NOT NULL AND expires_at < $2
+            "#,
+            status_to_db(FriendshipStatus::Pending),
+            Utc::now().naive_utc()
+        )
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        Ok(rows_affected)
+    }
+
+    // 取消拉黑：仅删除user_id发起的拉黑记录，被拉黑方无法取消
+    pub async fn unblock_user(&self, user_id: Uuid, blocked_id: Uuid) -> Result<bool> {
+        let rows_affected = sqlx::query!(
+            r#"
+            DELETE FROM friendships
+            WHERE user_id = $1 AND friend_id = $2 AND status = $3
+            "#,
+            user_id.to_string(),
+            blocked_id.to_string(),
+            status_to_db(FriendshipStatus::Blocked)
+        )
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+        
+        Ok(rows_affected > 0)
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn test_pool() -> PgPool {
+        PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap()
+    }
+
+    async fn insert_user(pool: &PgPool, id: &Uuid) {
+        sqlx::query("INSERT INTO users (id, username, email, password) VALUES ($1, $1, $1 || '@test.local', 'pw')")
+            .bind(id.to_string())
+            .execute(pool)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore = "需要DATABASE_URL指向的PostgreSQL"]
+    async fn test_accept_updates_original_request_row() {
+        let pool = test_pool().await;
+        let repo = FriendshipRepository::new(pool.clone());
+
+        let requester_id = Uuid::new_v4();
+        let accepter_id = Uuid::new_v4();
+        insert_user(&pool, &requester_id).await;
+        insert_user(&pool, &accepter_id).await;
+
+        // 请求行存储为 (user_id=发起方, friend_id=接收方)
+        let request = repo
+            .create_friend_request(requester_id, accepter_id, 7)
+            .await
+            .unwrap();
+        assert_eq!(request.status, FriendshipStatus::Pending as i32);
+
+        // 接受时按原始行的列顺序匹配，状态翻转为Accepted
+        let accepted = repo
+            .accept_friend_request(requester_id, accepter_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(accepted.id, request.id);
+        assert_eq!(accepted.user_id, requester_id);
+        assert_eq!(accepted.friend_id, accepter_id);
+        assert_eq!(accepted.status, FriendshipStatus::Accepted as i32);
+
+        // 清理测试数据（friendships级联删除）
+        for id in [&requester_id, &accepter_id] {
+            sqlx::query("DELETE FROM users WHERE id = $1")
+                .bind(id.to_string())
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+    }
+
+    #[tokio::test]
+    #[ignore = "需要DATABASE_URL指向的PostgreSQL"]
+    async fn test_expired_request_cannot_be_accepted_and_gets_cleaned() {
+        let pool = test_pool().await;
+        let repo = FriendshipRepository::new(pool.clone());
+
+        let requester_id = Uuid::new_v4();
+        let accepter_id = Uuid::new_v4();
+        insert_user(&pool, &requester_id).await;
+        insert_user(&pool, &accepter_id).await;
+
+        let request = repo
+            .create_friend_request(requester_id, accepter_id, 7)
+            .await
+            .unwrap();
+
+        // 将过期时间回拨到过去，模拟超时未处理的请求
+        sqlx::query("UPDATE friendships SET expires_at = NOW() - INTERVAL '1 hour' WHERE id = $1")
+            .bind(request.id.to_string())
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        // 过期请求视为不存在，无法接受
+        assert!(repo
+            .accept_friend_request(requester_id, accepter_id)
+            .await
+            .unwrap()
+            .is_none());
+
+        // 清理任务删除过期行，未过期的请求不受影响
+        let fresh_id = Uuid::new_v4();
+        insert_user(&pool, &fresh_id).await;
+        repo.create_friend_request(fresh_id, accepter_id, 7).await.unwrap();
+        assert!(repo.expire_requests().await.unwrap() >= 1);
+        assert_eq!(
+            repo.check_friendship(requester_id, accepter_id).await.unwrap(),
+            None
+        );
+        assert!(repo
+            .accept_friend_request(fresh_id, accepter_id)
+            .await
+            .unwrap()
+            .is_some());
+
+        // 清理测试数据（friendships级联删除）
+        for id in [&requester_id, &accepter_id, &fresh_id] {
+            sqlx::query("DELETE FROM users WHERE id = $1")
+                .bind(id.to_string())
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+    }
+
+    #[tokio::test]
+    #[ignore = "需要DATABASE_URL指向的PostgreSQL"]
+    async fn test_friend_list_pagination_slices_and_counts() {
+        let pool = test_pool().await;
+        let repo = FriendshipRepository::new(pool.clone());
+
+        let user_id = Uuid::new_v4();
+        insert_user(&pool, &user_id).await;
+
+        // 建立5个已接受的好友关系
+        let mut friend_ids = Vec::new();
+        for _ in 0..5 {
+            let friend_id = Uuid::new_v4();
+            insert_user(&pool, &friend_id).await;
+            repo.create_friend_request(user_id, friend_id, 7).await.unwrap();
+            repo.accept_friend_request(user_id, friend_id).await.unwrap().unwrap();
+            friend_ids.push(friend_id);
+        }
+
+        // 每页2条：前两页各2条，第三页1条，总数恒为5
+        let (page1, total) = repo.get_friend_list(user_id, 1, 2).await.unwrap();
+        assert_eq!(page1.len(), 2);
+        assert_eq!(total, 5);
+        let (page2, total) = repo.get_friend_list(user_id, 2, 2).await.unwrap();
+        assert_eq!(page2.len(), 2);
+        assert_eq!(total, 5);
+        let (page3, total) = repo.get_friend_list(user_id, 3, 2).await.unwrap();
+        assert_eq!(page3.len(), 1);
+        assert_eq!(total, 5);
+
+        // 各页互不重叠，合并后覆盖全部好友
+        let mut seen: Vec<Uuid> = page1
+            .iter()
+            .chain(page2.iter())
+            .chain(page3.iter())
+            .map(|f| f.id)
+            .collect();
+        seen.sort();
+        seen.dedup();
+        assert_eq!(seen.len(), 5);
+
+        // 待处理请求的分页同样返回总数
+        let requester_id = Uuid::new_v4();
+        insert_user(&pool, &requester_id).await;
+        repo.create_friend_request(requester_id, user_id, 7).await.unwrap();
+        let (requests, total) = repo.get_friend_requests(user_id, 1, 10).await.unwrap();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(total, 1);
+
+        // 清理测试数据（friendships级联删除）
+        for id in friend_ids.iter().chain([&user_id, &requester_id]) {
+            sqlx::query("DELETE FROM users WHERE id = $1")
+                .bind(id.to_string())
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+    }
+
+    #[tokio::test]
+    #[ignore = "需要DATABASE_URL指向的PostgreSQL"]
+    async fn test_block_rejects_request_and_unblock_allows_again() {
+        let pool = test_pool().await;
+        let repo = FriendshipRepository::new(pool.clone());
+
+        let user_id = Uuid::new_v4();
+        let blocked_id = Uuid::new_v4();
+        insert_user(&pool, &user_id).await;
+        insert_user(&pool, &blocked_id).await;
+
+        // 拉黑后两个方向的检查都返回Blocked，发送好友请求会被服务层拒绝
+        let friendship = repo.block_user(user_id, blocked_id).await.unwrap();
+        assert_eq!(friendship.status, FriendshipStatus::Blocked as i32);
+        assert_eq!(
+            repo.check_friendship(user_id, blocked_id).await.unwrap(),
+            Some(FriendshipStatus::Blocked)
+        );
+        assert_eq!(
+            repo.check_friendship(blocked_id, user_id).await.unwrap(),
+            Some(FriendshipStatus::Blocked)
+        );
+
+        // 被拉黑方无法取消拉黑
+        assert!(!repo.unblock_user(blocked_id, user_id).await.unwrap());
+
+        // 发起方取消拉黑后关系清空，可以重新发送好友请求
+        assert!(repo.unblock_user(user_id, blocked_id).await.unwrap());
+        assert_eq!(
+            repo.check_friendship(user_id, blocked_id).await.unwrap(),
+            None
+        );
+        let request = repo
+            .create_friend_request(blocked_id, user_id, 7)
+            .await
+            .unwrap();
+        assert_eq!(request.status, FriendshipStatus::Pending as i32);
+
+        // 清理测试数据（friendships级联删除）
+        for id in [&user_id, &blocked_id] {
+            sqlx::query("DELETE FROM users WHERE id = $1")
+                .bind(id.to_string())
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+    }
+
+    #[tokio::test]
+    #[ignore = "需要DATABASE_URL指向的PostgreSQL"]
+    async fn test_sent_requests_only_listed_for_sender() {
+        let pool = test_pool().await;
+        let repo = FriendshipRepository::new(pool.clone());
+
+        let sender_id = Uuid::new_v4();
+        let receiver_id = Uuid::new_v4();
+        insert_user(&pool, &sender_id).await;
+        insert_user(&pool, &receiver_id).await;
+
+        let request = repo
+            .create_friend_request(sender_id, receiver_id, 7)
+            .await
+            .unwrap();
+
+        // 发起方的发出列表包含该请求
+        let (sent, total) = repo.get_sent_friend_requests(sender_id, 1, 10).await.unwrap();
+        assert_eq!(total, 1);
+        assert!(sent.iter().any(|r| r.id == request.id));
+
+        // 接收方的发出列表为空（该请求在其入站列表里）
+        let (sent, total) = repo.get_sent_friend_requests(receiver_id, 1, 10).await.unwrap();
+        assert_eq!(total, 0);
+        assert!(sent.is_empty());
+
+        // 被处理后不再出现在发出列表
+        repo.accept_friend_request(sender_id, receiver_id).await.unwrap().unwrap();
+        let (_, total) = repo.get_sent_friend_requests(sender_id, 1, 10).await.unwrap();
+        assert_eq!(total, 0);
+
+        // 清理测试数据（friendships级联删除）
+        for id in [&sender_id, &receiver_id] {
+            sqlx::query("DELETE FROM users WHERE id = $1")
+                .bind(id.to_string())
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+    }
+
+    #[tokio::test]
+    #[ignore = "需要DATABASE_URL指向的PostgreSQL"]
+    async fn test_friend_remark_only_visible_to_setter() {
+        let pool = test_pool().await;
+        let repo = FriendshipRepository::new(pool.clone());
+
+        let alice_id = Uuid::new_v4();
+        let bob_id = Uuid::new_v4();
+        insert_user(&pool, &alice_id).await;
+        insert_user(&pool, &bob_id).await;
+
+        repo.create_friend_request(alice_id, bob_id, 7).await.unwrap();
+        repo.accept_friend_request(alice_id, bob_id).await.unwrap().unwrap();
+
+        // 发起方（user_id侧）设置备注
+        assert!(repo.set_friend_remark(alice_id, bob_id, "老同学").await.unwrap());
+
+        let (friends, _) = repo.get_friend_list(alice_id, 1, 10).await.unwrap();
+        let bob = friends.iter().find(|f| f.id == bob_id).unwrap();
+        assert_eq!(bob.remark.as_deref(), Some("老同学"));
+
+        // 对方看不到该备注
+        let (friends, _) = repo.get_friend_list(bob_id, 1, 10).await.unwrap();
+        let alice = friends.iter().find(|f| f.id == alice_id).unwrap();
+        assert_eq!(alice.remark, None);
+
+        // 接收方（friend_id侧）设置自己的备注，双方各见各的
+        assert!(repo.set_friend_remark(bob_id, alice_id, "同事").await.unwrap());
+        let (friends, _) = repo.get_friend_list(bob_id, 1, 10).await.unwrap();
+        let alice = friends.iter().find(|f| f.id == alice_id).unwrap();
+        assert_eq!(alice.remark.as_deref(), Some("同事"));
+        let (friends, _) = repo.get_friend_list(alice_id, 1, 10).await.unwrap();
+        let bob = friends.iter().find(|f| f.id == bob_id).unwrap();
+        assert_eq!(bob.remark.as_deref(), Some("老同学"));
+
+        // 空备注表示清除
+        assert!(repo.set_friend_remark(alice_id, bob_id, "").await.unwrap());
+        let (friends, _) = repo.get_friend_list(alice_id, 1, 10).await.unwrap();
+        let bob = friends.iter().find(|f| f.id == bob_id).unwrap();
+        assert_eq!(bob.remark, None);
+
+        // 非好友关系设置备注应返回false
+        let stranger_id = Uuid::new_v4();
+        insert_user(&pool, &stranger_id).await;
+        assert!(!repo.set_friend_remark(alice_id, stranger_id, "x").await.unwrap());
+
+        // 清理测试数据（friendships级联删除）
+        for id in [&alice_id, &bob_id, &stranger_id] {
+            sqlx::query("DELETE FROM users WHERE id = $1")
+                .bind(id.to_string())
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+    }
+
+    #[tokio::test]
+    #[ignore = "需要DATABASE_URL指向的PostgreSQL"]
+    async fn test_set_note_returns_friendship_and_stays_private() {
+        let pool = test_pool().await;
+        let repo = FriendshipRepository::new(pool.clone());
+
+        let alice_id = Uuid::new_v4();
+        let bob_id = Uuid::new_v4();
+        insert_user(&pool, &alice_id).await;
+        insert_user(&pool, &bob_id).await;
+
+        // 未接受的关系不能设置别名
+        repo.create_friend_request(alice_id, bob_id, 7).await.unwrap();
+        assert!(repo.set_note(alice_id, bob_id, "室友").await.unwrap().is_none());
+
+        repo.accept_friend_request(alice_id, bob_id).await.unwrap().unwrap();
+
+        // 返回更新后的关系与设置方视角的别名
+        let (friendship, note) = repo.set_note(alice_id, bob_id, "室友").await.unwrap().unwrap();
+        assert_eq!(friendship.status, FriendshipStatus::Accepted as i32);
+        assert_eq!(note.as_deref(), Some("室友"));
+
+        // 对方视角的别名相互独立
+        let (_, bob_note) = repo.set_note(bob_id, alice_id, "").await.unwrap().unwrap();
+        assert_eq!(bob_note, None);
+        let (friends, _) = repo.get_friend_list(alice_id, 1, 10).await.unwrap();
+        let bob = friends.iter().find(|f| f.id == bob_id).unwrap();
+        assert_eq!(bob.remark.as_deref(), Some("室友"));
+
+        // 空别名表示清除
+        let (_, cleared) = repo.set_note(alice_id, bob_id, "").await.unwrap().unwrap();
+        assert_eq!(cleared, None);
+
+        // 清理测试数据（friendships级联删除）
+        for id in [&alice_id, &bob_id] {
+            sqlx::query("DELETE FROM users WHERE id = $1")
+                .bind(id.to_string())
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+    }
+}
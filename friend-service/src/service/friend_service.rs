@@ -1,8 +1,12 @@
 use common::proto::friend::{
     SendFriendRequestRequest, AcceptFriendRequestRequest, RejectFriendRequestRequest,
     GetFriendListRequest, GetFriendRequestsRequest, DeleteFriendRequest, DeleteFriendResponse,
-    CheckFriendshipRequest, CheckFriendshipResponse, FriendshipResponse, GetFriendListResponse,
-    GetFriendRequestsResponse,
+    CheckFriendshipRequest, CheckFriendshipResponse, FriendshipResponse, FriendshipStatus,
+    GetFriendListResponse, GetFriendRequestsResponse,
+    GetSentFriendRequestsRequest, GetSentFriendRequestsResponse,
+    BlockUserRequest, UnblockUserRequest, UnblockUserResponse,
+    UpdateFriendRemarkRequest, UpdateFriendRemarkResponse,
+    SetFriendNoteRequest, SetFriendNoteResponse,
 };
 use common::proto::friend::friend_service_server::FriendService;
 use sqlx::PgPool;
@@ -14,12 +18,18 @@ use crate::repository::friendship_repository::FriendshipRepository;
 
 pub struct FriendServiceImpl {
     repository: FriendshipRepository,
+    /// 单个用户未处理的出站好友请求上限，取配置friend.max_pending_requests
+    max_pending_requests: i64,
+    /// 好友请求有效天数，取配置friend.request_ttl_days
+    request_ttl_days: i64,
 }
 
 impl FriendServiceImpl {
-    pub fn new(pool: PgPool) -> Self {
+    pub fn new(pool: PgPool, max_pending_requests: u32, request_ttl_days: u32) -> Self {
         Self {
             repository: FriendshipRepository::new(pool),
+            max_pending_requests: max_pending_requests as i64,
+            request_ttl_days: request_ttl_days as i64,
         }
     }
 }
@@ -41,6 +51,10 @@ impl FriendService for FriendServiceImpl {
         
         // 检查是否已存在好友关系
         match self.repository.check_friendship(user_id, friend_id).await {
+            // 任一方向存在拉黑时都不允许发送好友请求
+            Ok(Some(FriendshipStatus::Blocked)) => {
+                return Err(Status::permission_denied("存在拉黑关系，无法发送好友请求"));
+            }
             Ok(Some(_)) => {
                 return Err(Status::already_exists("已经存在好友关系或请求"));
             }
@@ -50,9 +64,21 @@ impl FriendService for FriendServiceImpl {
                 return Err(Status::internal("内部服务错误"));
             }
         }
-        
+
+        // 未处理的出站请求达到上限时拒绝，防止批量骚扰
+        match self.repository.count_pending_outgoing(user_id).await {
+            Ok(pending) if pending >= self.max_pending_requests => {
+                return Err(Status::resource_exhausted("待处理的好友请求过多，请等待对方处理后再试"));
+            }
+            Ok(_) => {}
+            Err(e) => {
+                error!("统计待处理好友请求失败: {}", e);
+                return Err(Status::internal("内部服务错误"));
+            }
+        }
+
         // 创建好友请求
-        match self.repository.create_friend_request(user_id, friend_id).await {
+        match self.repository.create_friend_request(user_id, friend_id, self.request_ttl_days).await {
             Ok(friendship) => {
                 info!("创建好友请求成功: {:?}", friendship);
                 Ok(Response::new(FriendshipResponse {
@@ -80,12 +106,13 @@ impl FriendService for FriendServiceImpl {
             .map_err(|e| Status::invalid_argument(format!("无效的好友ID: {}", e)))?;
         
         match self.repository.accept_friend_request(user_id, friend_id).await {
-            Ok(friendship) => {
+            Ok(Some(friendship)) => {
                 info!("接受好友请求成功: {:?}", friendship);
                 Ok(Response::new(FriendshipResponse {
                     friendship: Some(friendship.to_proto()),
                 }))
             }
+            Ok(None) => Err(Status::not_found("好友请求不存在或已过期")),
             Err(e) => {
                 error!("接受好友请求失败: {}", e);
                 Err(Status::internal("接受好友请求失败"))
@@ -130,14 +157,23 @@ impl FriendService for FriendServiceImpl {
         let user_id = req.user_id.parse::<Uuid>()
             .map_err(|e| Status::invalid_argument(format!("无效的用户ID: {}", e)))?;
         
-        match self.repository.get_friend_list(user_id).await {
-            Ok(friends) => {
+        // 设置默认分页参数，page_size上限100
+        let page = if req.page <= 0 { 1 } else { req.page };
+        let page_size = if req.page_size <= 0 || req.page_size > 100 {
+            10
+        } else {
+            req.page_size
+        };
+        
+        match self.repository.get_friend_list(user_id, page, page_size).await {
+            Ok((friends, total)) => {
                 let proto_friends = friends.into_iter()
                     .map(|f| f.to_proto())
                     .collect();
                 
                 Ok(Response::new(GetFriendListResponse {
                     friends: proto_friends,
+                    total,
                 }))
             }
             Err(e) => {
@@ -157,14 +193,23 @@ impl FriendService for FriendServiceImpl {
         let user_id = req.user_id.parse::<Uuid>()
             .map_err(|e| Status::invalid_argument(format!("无效的用户ID: {}", e)))?;
         
-        match self.repository.get_friend_requests(user_id).await {
-            Ok(requests) => {
+        // 设置默认分页参数，page_size上限100
+        let page = if req.page <= 0 { 1 } else { req.page };
+        let page_size = if req.page_size <= 0 || req.page_size > 100 {
+            10
+        } else {
+            req.page_size
+        };
+        
+        match self.repository.get_friend_requests(user_id, page, page_size).await {
+            Ok((requests, total)) => {
                 let proto_requests = requests.into_iter()
                     .map(|r| r.to_proto())
                     .collect();
                 
                 Ok(Response::new(GetFriendRequestsResponse {
                     requests: proto_requests,
+                    total,
                 }))
             }
             Err(e) => {
@@ -200,6 +245,161 @@ impl FriendService for FriendServiceImpl {
         }
     }
     
+    // 拉黑用户
+    async fn block_user(
+        &self,
+        request: Request<BlockUserRequest>,
+    ) -> Result<Response<FriendshipResponse>, Status> {
+        let req = request.into_inner();
+        
+        let user_id = req.user_id.parse::<Uuid>()
+            .map_err(|e| Status::invalid_argument(format!("无效的用户ID: {}", e)))?;
+        
+        let blocked_id = req.blocked_id.parse::<Uuid>()
+            .map_err(|e| Status::invalid_argument(format!("无效的被拉黑用户ID: {}", e)))?;
+        
+        if user_id == blocked_id {
+            return Err(Status::invalid_argument("不能拉黑自己"));
+        }
+        
+        match self.repository.block_user(user_id, blocked_id).await {
+            Ok(friendship) => {
+                info!("拉黑用户成功: {} -> {}", user_id, blocked_id);
+                Ok(Response::new(FriendshipResponse {
+                    friendship: Some(friendship.to_proto()),
+                }))
+            }
+            Err(e) => {
+                error!("拉黑用户失败: {}", e);
+                Err(Status::internal("拉黑用户失败"))
+            }
+        }
+    }
+    
+    // 取消拉黑
+    async fn unblock_user(
+        &self,
+        request: Request<UnblockUserRequest>,
+    ) -> Result<Response<UnblockUserResponse>, Status> {
+        let req = request.into_inner();
+        
+        let user_id = req.user_id.parse::<Uuid>()
+            .map_err(|e| Status::invalid_argument(format!("无效的用户ID: {}", e)))?;
+        
+        let blocked_id = req.blocked_id.parse::<Uuid>()
+            .map_err(|e| Status::invalid_argument(format!("无效的被拉黑用户ID: {}", e)))?;
+        
+        match self.repository.unblock_user(user_id, blocked_id).await {
+            Ok(success) => {
+                info!("取消拉黑: {} -> {}, 结果: {}", user_id, blocked_id, success);
+                Ok(Response::new(UnblockUserResponse {
+                    success,
+                }))
+            }
+            Err(e) => {
+                error!("取消拉黑失败: {}", e);
+                Err(Status::internal("取消拉黑失败"))
+            }
+        }
+    }
+    
+    // 获取自己发出的好友请求列表
+    async fn get_sent_friend_requests(
+        &self,
+        request: Request<GetSentFriendRequestsRequest>,
+    ) -> Result<Response<GetSentFriendRequestsResponse>, Status> {
+        let req = request.into_inner();
+
+        let user_id = req.user_id.parse::<Uuid>()
+            .map_err(|e| Status::invalid_argument(format!("无效的用户ID: {}", e)))?;
+
+        // 设置默认分页参数，page_size上限100
+        let page = if req.page <= 0 { 1 } else { req.page };
+        let page_size = if req.page_size <= 0 || req.page_size > 100 {
+            10
+        } else {
+            req.page_size
+        };
+
+        match self.repository.get_sent_friend_requests(user_id, page, page_size).await {
+            Ok((requests, total)) => {
+                let proto_requests = requests.into_iter()
+                    .map(|r| r.to_proto())
+                    .collect();
+
+                Ok(Response::new(GetSentFriendRequestsResponse {
+                    requests: proto_requests,
+                    total,
+                }))
+            }
+            Err(e) => {
+                error!("获取发出的好友请求列表失败: {}", e);
+                Err(Status::internal("获取发出的好友请求列表失败"))
+            }
+        }
+    }
+
+    // 设置好友备注
+    async fn update_friend_remark(
+        &self,
+        request: Request<UpdateFriendRemarkRequest>,
+    ) -> Result<Response<UpdateFriendRemarkResponse>, Status> {
+        let req = request.into_inner();
+
+        let user_id = req.user_id.parse::<Uuid>()
+            .map_err(|e| Status::invalid_argument(format!("无效的用户ID: {}", e)))?;
+
+        let friend_id = req.friend_id.parse::<Uuid>()
+            .map_err(|e| Status::invalid_argument(format!("无效的好友ID: {}", e)))?;
+
+        match self.repository.set_friend_remark(user_id, friend_id, &req.remark).await {
+            Ok(success) => {
+                if !success {
+                    return Err(Status::not_found("好友关系不存在"));
+                }
+                info!("设置好友备注: {} -> {}", user_id, friend_id);
+                Ok(Response::new(UpdateFriendRemarkResponse {
+                    success,
+                }))
+            }
+            Err(e) => {
+                error!("设置好友备注失败: {}", e);
+                Err(Status::internal("设置好友备注失败"))
+            }
+        }
+    }
+
+    // 设置好友私密别名（note），仅设置方自己可见，返回更新后的好友关系
+    async fn set_friend_note(
+        &self,
+        request: Request<SetFriendNoteRequest>,
+    ) -> Result<Response<SetFriendNoteResponse>, Status> {
+        let req = request.into_inner();
+
+        let user_id = req.user_id.parse::<Uuid>()
+            .map_err(|e| Status::invalid_argument(format!("无效的用户ID: {}", e)))?;
+
+        let friend_id = req.friend_id.parse::<Uuid>()
+            .map_err(|e| Status::invalid_argument(format!("无效的好友ID: {}", e)))?;
+
+        let note = req.note.as_deref().unwrap_or("");
+
+        match self.repository.set_note(user_id, friend_id, note).await {
+            Ok(Some((friendship, note))) => {
+                info!("设置好友别名: {} -> {}", user_id, friend_id);
+                Ok(Response::new(SetFriendNoteResponse {
+                    friendship: Some(friendship.to_proto()),
+                    note,
+                }))
+            }
+            Ok(None) => Err(Status::not_found("好友关系不存在")),
+            Err(e) => {
+                error!("设置好友别名失败: {}", e);
+                Err(Status::internal("设置好友别名失败"))
+            }
+        }
+    }
+
     // 检查好友关系
     async fn check_friendship(
         &self,
@@ -225,4 +425,74 @@ impl FriendService for FriendServiceImpl {
             }
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn test_pool() -> PgPool {
+        PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap()
+    }
+
+    async fn insert_user(pool: &PgPool, id: &Uuid) {
+        sqlx::query("INSERT INTO users (id, username, email, password) VALUES ($1, $1, $1 || '@test.local', 'pw')")
+            .bind(id.to_string())
+            .execute(pool)
+            .await
+            .unwrap();
+    }
+
+    async fn send(service: &FriendServiceImpl, user_id: Uuid, friend_id: Uuid) -> Result<(), Status> {
+        service
+            .send_friend_request(Request::new(SendFriendRequestRequest {
+                user_id: user_id.to_string(),
+                friend_id: friend_id.to_string(),
+            }))
+            .await
+            .map(|_| ())
+    }
+
+    #[tokio::test]
+    #[ignore = "需要DATABASE_URL指向的PostgreSQL"]
+    async fn test_pending_request_cap_blocks_until_processed() {
+        let pool = test_pool().await;
+        // 上限设为2，便于触发
+        let service = FriendServiceImpl::new(pool.clone(), 2, 7);
+
+        let sender_id = Uuid::new_v4();
+        let targets: Vec<Uuid> = (0..3).map(|_| Uuid::new_v4()).collect();
+        insert_user(&pool, &sender_id).await;
+        for id in &targets {
+            insert_user(&pool, id).await;
+        }
+
+        // 上限以内正常发送
+        send(&service, sender_id, targets[0]).await.unwrap();
+        send(&service, sender_id, targets[1]).await.unwrap();
+
+        // 达到上限后被拒绝
+        let err = send(&service, sender_id, targets[2]).await.unwrap_err();
+        assert_eq!(err.code(), tonic::Code::ResourceExhausted);
+
+        // 对方处理掉一条后可以继续发送
+        service
+            .accept_friend_request(Request::new(AcceptFriendRequestRequest {
+                user_id: sender_id.to_string(),
+                friend_id: targets[0].to_string(),
+            }))
+            .await
+            .unwrap();
+        send(&service, sender_id, targets[2]).await.unwrap();
+
+        // 清理测试数据（friendships级联删除）
+        for id in std::iter::once(&sender_id).chain(targets.iter()) {
+            sqlx::query("DELETE FROM users WHERE id = $1")
+                .bind(id.to_string())
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+    }
+}
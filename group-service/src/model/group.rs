@@ -3,7 +3,6 @@ use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use std::time::SystemTime;
 use prost_types;
-use common::message::GroupMemSeq;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Group {
@@ -12,6 +11,10 @@ pub struct Group {
     pub description: String,
     pub avatar_url: String,
     pub owner_id: Uuid,
+    /// 置顶群公告，未设置时为空串
+    pub announcement: String,
+    pub announcement_updated_at: Option<DateTime<Utc>>,
+    pub announcement_updated_by: Option<Uuid>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -24,15 +27,18 @@ impl Group {
             description,
             avatar_url,
             owner_id,
+            announcement: String::new(),
+            announcement_updated_at: None,
+            announcement_updated_by: None,
             created_at: Utc::now(),
             updated_at: Utc::now(),
         }
     }
-    
+
     pub fn to_proto(&self, member_count: i32) -> common::proto::group::Group {
         let created_system_time = SystemTime::from(self.created_at);
         let updated_system_time = SystemTime::from(self.updated_at);
-        
+
         common::proto::group::Group {
             id: self.id.to_string(),
             name: self.name.clone(),
@@ -42,6 +48,14 @@ impl Group {
             member_count,
             created_at: Some(prost_types::Timestamp::from(created_system_time)),
             updated_at: Some(prost_types::Timestamp::from(updated_system_time)),
+            announcement: self.announcement.clone(),
+            announcement_updated_at: self
+                .announcement_updated_at
+                .map(|t| prost_types::Timestamp::from(SystemTime::from(t))),
+            announcement_updated_by: self
+                .announcement_updated_by
+                .map(|id| id.to_string())
+                .unwrap_or_default(),
         }
     }
 }
@@ -0,0 +1,29 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use std::time::SystemTime;
+use prost_types;
+
+/// 入群申请，批准后转为群成员并删除申请行
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JoinRequest {
+    pub id: Uuid,
+    pub group_id: Uuid,
+    pub user_id: Uuid,
+    pub username: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl JoinRequest {
+    pub fn to_proto(&self) -> common::proto::group::JoinRequest {
+        let created_system_time = SystemTime::from(self.created_at);
+
+        common::proto::group::JoinRequest {
+            id: self.id.to_string(),
+            group_id: self.group_id.to_string(),
+            user_id: self.user_id.to_string(),
+            username: self.username.clone(),
+            created_at: Some(prost_types::Timestamp::from(created_system_time)),
+        }
+    }
+}
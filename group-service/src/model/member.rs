@@ -15,6 +15,7 @@ pub struct Member {
     pub avatar_url: Option<String>,
     pub role: i32,
     pub joined_at: DateTime<Utc>,
+    pub muted_until: Option<DateTime<Utc>>,
 }
 
 impl Member {
@@ -29,6 +30,7 @@ impl Member {
             avatar_url,
             role: role as i32,
             joined_at: Utc::now(),
+            muted_until: None,
         }
     }
     
@@ -44,6 +46,8 @@ impl Member {
             avatar_url: self.avatar_url.clone(),
             role: self.role,
             joined_at: Some(prost_types::Timestamp::from(joined_system_time)),
+            muted_until: self.muted_until
+                .map(|t| prost_types::Timestamp::from(SystemTime::from(t))),
         }
     }
 }
\ No newline at end of file
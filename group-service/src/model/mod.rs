@@ -1,2 +1,3 @@
 pub mod group;
+pub mod join_request;
 pub mod member;
\ No newline at end of file
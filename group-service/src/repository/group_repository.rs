@@ -7,11 +7,16 @@ use crate::model::group::{Group, UserGroup};
 
 pub struct GroupRepository {
     pool: PgPool,
+    /// 新建群组写入的成员数上限，取配置group.max_members
+    default_max_members: i32,
 }
 
 impl GroupRepository {
-    pub fn new(pool: PgPool) -> Self {
-        Self { pool }
+    pub fn new(pool: PgPool, default_max_members: u32) -> Self {
+        Self {
+            pool,
+            default_max_members: default_max_members as i32,
+        }
     }
     
     // 创建群组
@@ -24,15 +29,16 @@ impl GroupRepository {
         
         let result = sqlx::query!(
             r#"
-            INSERT INTO groups (id, name, description, avatar_url, owner_id, created_at, updated_at)
-            VALUES ($1, $2, $3, $4, $5, $6, $7)
-            RETURNING id, name, description, avatar_url, owner_id, created_at, updated_at
+            INSERT INTO groups (id, name, description, avatar_url, owner_id, max_members, created_at, updated_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            RETURNING id, name, description, avatar_url, owner_id, announcement, announcement_updated_at, announcement_updated_by, created_at, updated_at
             "#,
             group.id.to_string(),
             group.name,
             group.description,
             group.avatar_url,
             group.owner_id.to_string(),
+            self.default_max_members,
             created_at_naive,
             updated_at_naive
         )
@@ -45,6 +51,9 @@ impl GroupRepository {
             description: result.description.unwrap_or_default(),
             avatar_url: result.avatar_url.unwrap_or_default(),
             owner_id: Uuid::parse_str(&result.owner_id).unwrap(),
+            announcement: result.announcement.unwrap_or_default(),
+            announcement_updated_at: result.announcement_updated_at.map(|t| Utc.from_utc_datetime(&t)),
+            announcement_updated_by: result.announcement_updated_by.and_then(|s| Uuid::parse_str(&s).ok()),
             created_at: Utc.from_utc_datetime(&result.created_at),
             updated_at: Utc.from_utc_datetime(&result.updated_at),
         })
@@ -54,7 +63,7 @@ impl GroupRepository {
     pub async fn get_group(&self, group_id: Uuid) -> Result<Group> {
         let result = sqlx::query!(
             r#"
-            SELECT id, name, description, avatar_url, owner_id, created_at, updated_at
+            SELECT id, name, description, avatar_url, owner_id, announcement, announcement_updated_at, announcement_updated_by, created_at, updated_at
             FROM groups
             WHERE id = $1
             "#,
@@ -69,6 +78,9 @@ impl GroupRepository {
             description: result.description.unwrap_or_default(),
             avatar_url: result.avatar_url.unwrap_or_default(),
             owner_id: Uuid::parse_str(&result.owner_id).unwrap(),
+            announcement: result.announcement.unwrap_or_default(),
+            announcement_updated_at: result.announcement_updated_at.map(|t| Utc.from_utc_datetime(&t)),
+            announcement_updated_by: result.announcement_updated_by.and_then(|s| Uuid::parse_str(&s).ok()),
             created_at: Utc.from_utc_datetime(&result.created_at),
             updated_at: Utc.from_utc_datetime(&result.updated_at),
         })
@@ -89,7 +101,7 @@ impl GroupRepository {
             UPDATE groups
             SET name = $1, description = $2, avatar_url = $3, updated_at = $4
             WHERE id = $5
-            RETURNING id, name, description, avatar_url, owner_id, created_at, updated_at
+            RETURNING id, name, description, avatar_url, owner_id, announcement, announcement_updated_at, announcement_updated_by, created_at, updated_at
             "#,
             name.unwrap_or(current.name),
             description.unwrap_or(current.description),
@@ -106,19 +118,35 @@ impl GroupRepository {
             description: result.description.unwrap_or_default(),
             avatar_url: result.avatar_url.unwrap_or_default(),
             owner_id: Uuid::parse_str(&result.owner_id).unwrap(),
+            announcement: result.announcement.unwrap_or_default(),
+            announcement_updated_at: result.announcement_updated_at.map(|t| Utc.from_utc_datetime(&t)),
+            announcement_updated_by: result.announcement_updated_by.and_then(|s| Uuid::parse_str(&s).ok()),
             created_at: Utc.from_utc_datetime(&result.created_at),
             updated_at: Utc.from_utc_datetime(&result.updated_at),
         })
     }
     
-    // 删除群组
+    // 删除群组：成员行与群组行在同一事务内删除，
+    // 不依赖数据库层面的级联配置，任一步失败整体回滚
     pub async fn delete_group(&self, group_id: Uuid, user_id: Uuid) -> Result<bool> {
         // 先检查是否是群主
         let group = self.get_group(group_id).await?;
         if group.owner_id != user_id {
             return Err(anyhow::anyhow!("只有群主可以删除群组"));
         }
-        
+
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query!(
+            r#"
+            DELETE FROM group_members
+            WHERE group_id = $1
+            "#,
+            group_id.to_string()
+        )
+        .execute(&mut *tx)
+        .await?;
+
         let rows_affected = sqlx::query!(
             r#"
             DELETE FROM groups
@@ -126,13 +154,122 @@ impl GroupRepository {
             "#,
             group_id.to_string()
         )
-        .execute(&self.pool)
+        .execute(&mut *tx)
         .await?
         .rows_affected();
-        
+
+        tx.commit().await?;
+
         Ok(rows_affected > 0)
     }
     
+    // 转让群主：新旧群主角色互换（旧群主降为管理员）并更新owner_id
+    // 两张表的写入放在同一事务内，保证原子性
+    pub async fn transfer_ownership(
+        &self,
+        group_id: Uuid,
+        current_owner_id: Uuid,
+        new_owner_id: Uuid,
+    ) -> Result<Group> {
+        use common::proto::group::MemberRole;
+
+        use crate::repository::member_repository::MemberRepository;
+
+        let mut tx = self.pool.begin().await?;
+
+        // 新群主升为Owner，旧群主降为Admin
+        MemberRepository::update_member_role_in_tx(&mut tx, group_id, new_owner_id, MemberRole::Owner)
+            .await?;
+        MemberRepository::update_member_role_in_tx(&mut tx, group_id, current_owner_id, MemberRole::Admin)
+            .await?;
+
+        let now_naive = Utc::now().naive_utc();
+        let result = sqlx::query!(
+            r#"
+            UPDATE groups
+            SET owner_id = $1, updated_at = $2
+            WHERE id = $3 AND owner_id = $4
+            RETURNING id, name, description, avatar_url, owner_id, announcement, announcement_updated_at, announcement_updated_by, created_at, updated_at
+            "#,
+            new_owner_id.to_string(),
+            now_naive,
+            group_id.to_string(),
+            current_owner_id.to_string()
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("只有群主可以转让群组"))?;
+
+        tx.commit().await?;
+
+        Ok(Group {
+            id: Uuid::parse_str(&result.id).unwrap(),
+            name: result.name,
+            description: result.description.unwrap_or_default(),
+            avatar_url: result.avatar_url.unwrap_or_default(),
+            owner_id: Uuid::parse_str(&result.owner_id).unwrap(),
+            announcement: result.announcement.unwrap_or_default(),
+            announcement_updated_at: result.announcement_updated_at.map(|t| Utc.from_utc_datetime(&t)),
+            announcement_updated_by: result.announcement_updated_by.and_then(|s| Uuid::parse_str(&s).ok()),
+            created_at: Utc.from_utc_datetime(&result.created_at),
+            updated_at: Utc.from_utc_datetime(&result.updated_at),
+        })
+    }
+
+    // 设置群公告：管理员及以上可操作，空串表示清除公告
+    pub async fn set_announcement(&self, group_id: Uuid, by_id: Uuid, text: String) -> Result<Group> {
+        use common::proto::group::MemberRole;
+
+        use crate::repository::member_repository::MemberRepository;
+
+        // 验证操作者角色
+        let row = sqlx::query!(
+            r#"
+            SELECT role
+            FROM group_members
+            WHERE group_id = $1 AND user_id = $2
+            "#,
+            group_id.to_string(),
+            by_id.to_string()
+        )
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("用户不是群组成员"))?;
+
+        if MemberRepository::role_code(&row.role) < MemberRole::Admin as i32 {
+            return Err(anyhow::anyhow!("没有权限设置群公告"));
+        }
+
+        let now_naive = Utc::now().naive_utc();
+        let result = sqlx::query!(
+            r#"
+            UPDATE groups
+            SET announcement = $1, announcement_updated_at = $2, announcement_updated_by = $3, updated_at = $2
+            WHERE id = $4
+            RETURNING id, name, description, avatar_url, owner_id, announcement, announcement_updated_at, announcement_updated_by, created_at, updated_at
+            "#,
+            text,
+            now_naive,
+            by_id.to_string(),
+            group_id.to_string()
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(Group {
+            id: Uuid::parse_str(&result.id).unwrap(),
+            name: result.name,
+            description: result.description.unwrap_or_default(),
+            avatar_url: result.avatar_url.unwrap_or_default(),
+            owner_id: Uuid::parse_str(&result.owner_id).unwrap(),
+            announcement: result.announcement.unwrap_or_default(),
+            announcement_updated_at: result.announcement_updated_at.map(|t| Utc.from_utc_datetime(&t)),
+            announcement_updated_by: result.announcement_updated_by.and_then(|s| Uuid::parse_str(&s).ok()),
+            created_at: Utc.from_utc_datetime(&result.created_at),
+            updated_at: Utc.from_utc_datetime(&result.updated_at),
+        })
+    }
+
     // 获取群组成员数量
     pub async fn get_member_count(&self, group_id: Uuid) -> Result<i32> {
         let result = sqlx::query!(
@@ -149,6 +286,70 @@ impl GroupRepository {
         Ok(result.count.unwrap_or(0) as i32)
     }
     
+    // 全文搜索公开群组（按名称和描述），返回(群组, 成员数)列表与匹配总数
+    // tsvector表达式须与idx_groups_search索引中的一致才能走索引
+    pub async fn search_groups(
+        &self,
+        query: &str,
+        page: i32,
+        page_size: i32,
+    ) -> Result<(Vec<(Group, i32)>, i32)> {
+        let offset = (page - 1) * page_size;
+
+        let rows = sqlx::query!(
+            r#"
+            SELECT id, name, description, avatar_url, owner_id, announcement, announcement_updated_at, announcement_updated_by, created_at, updated_at,
+                   (SELECT COUNT(*) FROM group_members WHERE group_id = groups.id) as member_count
+            FROM groups
+            WHERE is_public
+              AND to_tsvector('simple', name || ' ' || COALESCE(description, '')) @@ plainto_tsquery('simple', $1)
+            ORDER BY created_at DESC
+            LIMIT $2 OFFSET $3
+            "#,
+            query,
+            page_size as i64,
+            offset as i64
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let groups = rows
+            .into_iter()
+            .map(|result| {
+                let member_count = result.member_count.unwrap_or(0) as i32;
+                (
+                    Group {
+                        id: Uuid::parse_str(&result.id).unwrap(),
+                        name: result.name,
+                        description: result.description.unwrap_or_default(),
+                        avatar_url: result.avatar_url.unwrap_or_default(),
+                        owner_id: Uuid::parse_str(&result.owner_id).unwrap(),
+                        announcement: result.announcement.unwrap_or_default(),
+                        announcement_updated_at: result.announcement_updated_at.map(|t| Utc.from_utc_datetime(&t)),
+                        announcement_updated_by: result.announcement_updated_by.and_then(|s| Uuid::parse_str(&s).ok()),
+                        created_at: Utc.from_utc_datetime(&result.created_at),
+                        updated_at: Utc.from_utc_datetime(&result.updated_at),
+                    },
+                    member_count,
+                )
+            })
+            .collect();
+
+        let total: i64 = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) as "count!"
+            FROM groups
+            WHERE is_public
+              AND to_tsvector('simple', name || ' ' || COALESCE(description, '')) @@ plainto_tsquery('simple', $1)
+            "#,
+            query
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok((groups, total as i32))
+    }
+
     // 获取用户加入的群组列表
     pub async fn get_user_groups(&self, user_id: Uuid) -> Result<Vec<UserGroup>> {
         let groups = sqlx::query!(
@@ -183,4 +384,236 @@ impl GroupRepository {
         
         Ok(result)
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn test_pool() -> PgPool {
+        let url = std::env::var("DATABASE_URL").expect("需要设置DATABASE_URL");
+        PgPool::connect(&url).await.unwrap()
+    }
+
+    async fn insert_user(pool: &PgPool, id: Uuid) {
+        sqlx::query(
+            "INSERT INTO users (id, username, email, password) VALUES ($1, $1, $1 || '@test.local', 'pw')",
+        )
+        .bind(id.to_string())
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    // 直接按表结构插入成员行（add_member的角色编码与DDL约束不一致，测试里不经过它）
+    async fn insert_member(pool: &PgPool, group_id: Uuid, user_id: Uuid, role: &str) {
+        sqlx::query("INSERT INTO group_members (id, group_id, user_id, role) VALUES ($1, $2, $3, $4)")
+            .bind(Uuid::new_v4().to_string())
+            .bind(group_id.to_string())
+            .bind(user_id.to_string())
+            .bind(role)
+            .execute(pool)
+            .await
+            .unwrap();
+    }
+
+    async fn member_rows(pool: &PgPool, group_id: Uuid) -> i64 {
+        sqlx::query_scalar("SELECT COUNT(*) FROM group_members WHERE group_id = $1")
+            .bind(group_id.to_string())
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    async fn delete_user(pool: &PgPool, id: Uuid) {
+        sqlx::query("DELETE FROM users WHERE id = $1")
+            .bind(id.to_string())
+            .execute(pool)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore = "需要DATABASE_URL指向的PostgreSQL"]
+    async fn test_delete_group_removes_member_rows() {
+        let pool = test_pool().await;
+        let repo = GroupRepository::new(pool.clone(), 500);
+
+        let owner_id = Uuid::new_v4();
+        let member_id = Uuid::new_v4();
+        insert_user(&pool, owner_id).await;
+        insert_user(&pool, member_id).await;
+
+        let group = repo
+            .create_group("删除测试群".to_string(), String::new(), String::new(), owner_id)
+            .await
+            .unwrap();
+        insert_member(&pool, group.id, owner_id, "OWNER").await;
+        insert_member(&pool, group.id, member_id, "MEMBER").await;
+        assert_eq!(member_rows(&pool, group.id).await, 2);
+
+        assert!(repo.delete_group(group.id, owner_id).await.unwrap());
+
+        // 群组与成员行一并删除
+        assert_eq!(member_rows(&pool, group.id).await, 0);
+        assert!(repo.get_group(group.id).await.is_err());
+
+        delete_user(&pool, owner_id).await;
+        delete_user(&pool, member_id).await;
+    }
+
+    #[tokio::test]
+    #[ignore = "需要DATABASE_URL指向的PostgreSQL"]
+    async fn test_set_announcement_requires_admin() {
+        let pool = test_pool().await;
+        let repo = GroupRepository::new(pool.clone(), 500);
+
+        let owner_id = Uuid::new_v4();
+        let admin_id = Uuid::new_v4();
+        let member_id = Uuid::new_v4();
+        insert_user(&pool, owner_id).await;
+        insert_user(&pool, admin_id).await;
+        insert_user(&pool, member_id).await;
+
+        let group = repo
+            .create_group("公告测试群".to_string(), String::new(), String::new(), owner_id)
+            .await
+            .unwrap();
+        insert_member(&pool, group.id, owner_id, "OWNER").await;
+        insert_member(&pool, group.id, admin_id, "ADMIN").await;
+        insert_member(&pool, group.id, member_id, "MEMBER").await;
+
+        // 新建群组公告为空
+        assert_eq!(group.announcement, "");
+        assert!(group.announcement_updated_at.is_none());
+
+        // 管理员可以设置公告，更新时间与操作者一并记录
+        let updated = repo
+            .set_announcement(group.id, admin_id, "周五晚八点全员会议".to_string())
+            .await
+            .unwrap();
+        assert_eq!(updated.announcement, "周五晚八点全员会议");
+        assert!(updated.announcement_updated_at.is_some());
+        assert_eq!(updated.announcement_updated_by, Some(admin_id));
+
+        // 普通成员没有权限
+        let err = repo
+            .set_announcement(group.id, member_id, "越权公告".to_string())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("没有权限"));
+
+        // 非成员同样被拒绝，且公告保持不变
+        let outsider_id = Uuid::new_v4();
+        insert_user(&pool, outsider_id).await;
+        assert!(repo
+            .set_announcement(group.id, outsider_id, "外人公告".to_string())
+            .await
+            .is_err());
+        let current = repo.get_group(group.id).await.unwrap();
+        assert_eq!(current.announcement, "周五晚八点全员会议");
+
+        repo.delete_group(group.id, owner_id).await.unwrap();
+        delete_user(&pool, owner_id).await;
+        delete_user(&pool, admin_id).await;
+        delete_user(&pool, member_id).await;
+        delete_user(&pool, outsider_id).await;
+    }
+
+    #[tokio::test]
+    #[ignore = "需要DATABASE_URL指向的PostgreSQL"]
+    async fn test_failed_delete_leaves_group_and_members_intact() {
+        let pool = test_pool().await;
+        let repo = GroupRepository::new(pool.clone(), 500);
+
+        let owner_id = Uuid::new_v4();
+        let member_id = Uuid::new_v4();
+        insert_user(&pool, owner_id).await;
+        insert_user(&pool, member_id).await;
+
+        let group = repo
+            .create_group("回滚测试群".to_string(), String::new(), String::new(), owner_id)
+            .await
+            .unwrap();
+        insert_member(&pool, group.id, owner_id, "OWNER").await;
+        insert_member(&pool, group.id, member_id, "MEMBER").await;
+
+        // 非群主删除被拒绝，群组和成员都保持原样
+        assert!(repo.delete_group(group.id, member_id).await.is_err());
+        assert!(repo.get_group(group.id).await.is_ok());
+        assert_eq!(member_rows(&pool, group.id).await, 2);
+
+        // 模拟删到一半失败：成员删除后事务回滚，成员行应完整恢复
+        let mut tx = pool.begin().await.unwrap();
+        sqlx::query("DELETE FROM group_members WHERE group_id = $1")
+            .bind(group.id.to_string())
+            .execute(&mut *tx)
+            .await
+            .unwrap();
+        tx.rollback().await.unwrap();
+        assert_eq!(member_rows(&pool, group.id).await, 2);
+
+        repo.delete_group(group.id, owner_id).await.unwrap();
+        delete_user(&pool, owner_id).await;
+        delete_user(&pool, member_id).await;
+    }
+
+    #[tokio::test]
+    #[ignore = "需要DATABASE_URL指向的PostgreSQL"]
+    async fn test_search_groups_public_only_with_paging() {
+        let pool = test_pool().await;
+        let repo = GroupRepository::new(pool.clone(), 500);
+
+        let owner_id = Uuid::new_v4();
+        insert_user(&pool, owner_id).await;
+
+        // 用唯一token做关键词，避免与库中已有数据相互干扰
+        let token = Uuid::new_v4().simple().to_string();
+        let insert_group = |id: Uuid, name: String, description: String, is_public: bool| {
+            let pool = pool.clone();
+            async move {
+                sqlx::query(
+                    "INSERT INTO groups (id, name, description, owner_id, is_public) VALUES ($1, $2, $3, $4, $5)",
+                )
+                .bind(id.to_string())
+                .bind(name)
+                .bind(description)
+                .bind(owner_id.to_string())
+                .bind(is_public)
+                .execute(&pool)
+                .await
+                .unwrap();
+            }
+        };
+
+        let by_name = Uuid::new_v4();
+        let by_desc = Uuid::new_v4();
+        let private_group = Uuid::new_v4();
+        let unrelated = Uuid::new_v4();
+        insert_group(by_name, format!("{} 兴趣社区", token), "公开讨论区".to_string(), true).await;
+        insert_group(by_desc, "搜索测试群".to_string(), format!("关于 {} 的讨论", token), true).await;
+        insert_group(private_group, format!("{} 私密群", token), String::new(), false).await;
+        insert_group(unrelated, "无关群组".to_string(), "别的话题".to_string(), true).await;
+        insert_member(&pool, by_name, owner_id, "OWNER").await;
+
+        // 名称与描述都参与匹配，私密群与无关群不出现
+        let (results, total) = repo.search_groups(&token, 1, 10).await.unwrap();
+        assert_eq!(total, 2);
+        let ids: Vec<Uuid> = results.iter().map(|(g, _)| g.id).collect();
+        assert!(ids.contains(&by_name) && ids.contains(&by_desc));
+
+        // 成员数来自group_members计数
+        let by_name_count = results.iter().find(|(g, _)| g.id == by_name).unwrap().1;
+        let by_desc_count = results.iter().find(|(g, _)| g.id == by_desc).unwrap().1;
+        assert_eq!((by_name_count, by_desc_count), (1, 0));
+
+        // 分页：每页1个共2页，越界页为空，总数不变
+        let (page1, total) = repo.search_groups(&token, 1, 1).await.unwrap();
+        assert_eq!((page1.len(), total), (1, 2));
+        let (page3, total) = repo.search_groups(&token, 3, 1).await.unwrap();
+        assert_eq!((page3.len(), total), (0, 2));
+
+        // owner级联删除群组与成员行
+        delete_user(&pool, owner_id).await;
+    }
 }
\ No newline at end of file
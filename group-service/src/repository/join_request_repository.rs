@@ -0,0 +1,444 @@
+use anyhow::Result;
+use sqlx::PgPool;
+use uuid::Uuid;
+use common::proto::group::MemberRole;
+use chrono::{Utc, TimeZone};
+
+use crate::model::join_request::JoinRequest;
+use crate::model::member::Member;
+use crate::repository::member_repository::MemberRepository;
+
+pub struct JoinRequestRepository {
+    pool: PgPool,
+}
+
+impl JoinRequestRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    // 提交入群申请
+    pub async fn request_join(&self, group_id: Uuid, user_id: Uuid) -> Result<()> {
+        // 群组必须存在
+        let group = sqlx::query!(
+            r#"
+            SELECT id
+            FROM groups
+            WHERE id = $1
+            "#,
+            group_id.to_string()
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        if group.is_none() {
+            return Err(anyhow::anyhow!("群组不存在"));
+        }
+
+        // 已是成员的用户无需申请
+        let member = sqlx::query!(
+            r#"
+            SELECT id
+            FROM group_members
+            WHERE group_id = $1 AND user_id = $2
+            "#,
+            group_id.to_string(),
+            user_id.to_string()
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        if member.is_some() {
+            return Err(anyhow::anyhow!("用户已经是群组成员"));
+        }
+
+        // 重复申请由唯一约束挡下，不覆盖原申请时间
+        let rows_affected = sqlx::query!(
+            r#"
+            INSERT INTO group_join_requests (id, group_id, user_id)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (group_id, user_id) DO NOTHING
+            "#,
+            Uuid::new_v4().to_string(),
+            group_id.to_string(),
+            user_id.to_string()
+        )
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        if rows_affected == 0 {
+            return Err(anyhow::anyhow!("已提交过入群申请"));
+        }
+
+        Ok(())
+    }
+
+    // 批准入群申请：在同一事务内删除申请并创建成员
+    pub async fn approve_join(&self, group_id: Uuid, user_id: Uuid, approved_by_id: Uuid) -> Result<Member> {
+        self.require_admin(group_id, approved_by_id).await?;
+
+        let mut tx = self.pool.begin().await?;
+
+        // 与add_member相同：锁定群组行后在事务内计数，防止并发加入突破上限
+        let group = sqlx::query!(
+            r#"
+            SELECT max_members
+            FROM groups
+            WHERE id = $1
+            FOR UPDATE
+            "#,
+            group_id.to_string()
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let max_members = match group {
+            Some(g) => g.max_members,
+            None => return Err(anyhow::anyhow!("群组不存在")),
+        };
+
+        let deleted = sqlx::query!(
+            r#"
+            DELETE FROM group_join_requests
+            WHERE group_id = $1 AND user_id = $2
+            "#,
+            group_id.to_string(),
+            user_id.to_string()
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        if deleted == 0 {
+            return Err(anyhow::anyhow!("入群申请不存在"));
+        }
+
+        let current: i64 = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) as "count!"
+            FROM group_members
+            WHERE group_id = $1
+            "#,
+            group_id.to_string()
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        if current >= max_members as i64 {
+            return Err(anyhow::anyhow!("群组成员数已达上限: {}", max_members));
+        }
+
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO group_members (id, group_id, user_id, role)
+            VALUES ($1, $2, $3, 'MEMBER')
+            RETURNING id, joined_at
+            "#,
+            Uuid::new_v4().to_string(),
+            group_id.to_string(),
+            user_id.to_string()
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        // 申请人的用户信息
+        let user = sqlx::query!(
+            r#"
+            SELECT username, nickname, avatar_url
+            FROM users
+            WHERE id = $1
+            "#,
+            user_id.to_string()
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(Member {
+            id: Uuid::parse_str(&result.id).unwrap(),
+            group_id,
+            user_id,
+            username: user.username,
+            nickname: user.nickname,
+            avatar_url: user.avatar_url,
+            role: MemberRole::Member as i32,
+            joined_at: Utc.from_utc_datetime(&result.joined_at),
+            muted_until: None,
+        })
+    }
+
+    // 拒绝入群申请
+    pub async fn reject_join(&self, group_id: Uuid, user_id: Uuid, rejected_by_id: Uuid) -> Result<bool> {
+        self.require_admin(group_id, rejected_by_id).await?;
+
+        let rows_affected = sqlx::query!(
+            r#"
+            DELETE FROM group_join_requests
+            WHERE group_id = $1 AND user_id = $2
+            "#,
+            group_id.to_string(),
+            user_id.to_string()
+        )
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        Ok(rows_affected > 0)
+    }
+
+    // 获取群组的待处理入群申请列表（按申请时间升序）
+    pub async fn list_join_requests(&self, group_id: Uuid) -> Result<Vec<JoinRequest>> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT r.id, r.group_id, r.user_id, r.created_at, u.username
+            FROM group_join_requests r
+            JOIN users u ON r.user_id = u.id
+            WHERE r.group_id = $1
+            ORDER BY r.created_at ASC
+            "#,
+            group_id.to_string()
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|r| JoinRequest {
+                id: Uuid::parse_str(&r.id).unwrap(),
+                group_id: Uuid::parse_str(&r.group_id).unwrap(),
+                user_id: Uuid::parse_str(&r.user_id).unwrap(),
+                username: r.username,
+                created_at: Utc.from_utc_datetime(&r.created_at),
+            })
+            .collect())
+    }
+
+    // 审批操作要求管理员及以上角色
+    async fn require_admin(&self, group_id: Uuid, by_id: Uuid) -> Result<()> {
+        let result = sqlx::query!(
+            r#"
+            SELECT role
+            FROM group_members
+            WHERE group_id = $1 AND user_id = $2
+            "#,
+            group_id.to_string(),
+            by_id.to_string()
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        match result {
+            Some(r) if MemberRepository::role_code(&r.role) >= MemberRole::Admin as i32 => Ok(()),
+            Some(_) => Err(anyhow::anyhow!("没有权限处理入群申请")),
+            None => Err(anyhow::anyhow!("操作者不是群组成员")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn test_pool() -> PgPool {
+        let url = std::env::var("DATABASE_URL").expect("需要设置DATABASE_URL");
+        PgPool::connect(&url).await.unwrap()
+    }
+
+    async fn insert_user(pool: &PgPool, id: Uuid, username: &str) {
+        sqlx::query(
+            "INSERT INTO users (id, username, email, password) VALUES ($1, $2, $2 || '@test.local', 'pw')",
+        )
+        .bind(id.to_string())
+        .bind(username)
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    async fn insert_group(pool: &PgPool, group_id: Uuid, owner_id: Uuid, max_members: i32) {
+        sqlx::query("INSERT INTO groups (id, name, owner_id, max_members) VALUES ($1, '入群申请测试群', $2, $3)")
+            .bind(group_id.to_string())
+            .bind(owner_id.to_string())
+            .bind(max_members)
+            .execute(pool)
+            .await
+            .unwrap();
+    }
+
+    // 直接按表结构插入成员行（add_member的角色编码与DDL约束不一致，测试里不经过它）
+    async fn insert_member(pool: &PgPool, group_id: Uuid, user_id: Uuid, role: &str) {
+        sqlx::query(
+            "INSERT INTO group_members (id, group_id, user_id, role) VALUES ($1, $2, $3, $4)",
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(group_id.to_string())
+        .bind(user_id.to_string())
+        .bind(role)
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    async fn delete_user(pool: &PgPool, id: Uuid) {
+        sqlx::query("DELETE FROM users WHERE id = $1")
+            .bind(id.to_string())
+            .execute(pool)
+            .await
+            .unwrap();
+    }
+
+    async fn delete_group(pool: &PgPool, id: Uuid) {
+        sqlx::query("DELETE FROM groups WHERE id = $1")
+            .bind(id.to_string())
+            .execute(pool)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore = "需要DATABASE_URL指向的PostgreSQL"]
+    async fn test_request_join_rejects_duplicates_and_members() {
+        let pool = test_pool().await;
+        let repo = JoinRequestRepository::new(pool.clone());
+
+        let group_id = Uuid::new_v4();
+        let owner = Uuid::new_v4();
+        let applicant = Uuid::new_v4();
+        for id in [owner, applicant] {
+            insert_user(&pool, id, &format!("join-{}", id)).await;
+        }
+        insert_group(&pool, group_id, owner, 500).await;
+        insert_member(&pool, group_id, owner, "OWNER").await;
+
+        // 首次申请成功，重复申请被拒绝且不产生新行
+        repo.request_join(group_id, applicant).await.unwrap();
+        let err = repo.request_join(group_id, applicant).await.unwrap_err();
+        assert!(err.to_string().contains("已提交过"));
+        let requests = repo.list_join_requests(group_id).await.unwrap();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].user_id, applicant);
+
+        // 已是成员的用户不能申请
+        let err = repo.request_join(group_id, owner).await.unwrap_err();
+        assert!(err.to_string().contains("已经是群组成员"));
+
+        // 不存在的群组不能申请
+        let err = repo.request_join(Uuid::new_v4(), applicant).await.unwrap_err();
+        assert!(err.to_string().contains("群组不存在"));
+
+        delete_group(&pool, group_id).await;
+        for id in [owner, applicant] {
+            delete_user(&pool, id).await;
+        }
+    }
+
+    #[tokio::test]
+    #[ignore = "需要DATABASE_URL指向的PostgreSQL"]
+    async fn test_approve_join_requires_admin() {
+        let pool = test_pool().await;
+        let repo = JoinRequestRepository::new(pool.clone());
+
+        let group_id = Uuid::new_v4();
+        let owner = Uuid::new_v4();
+        let member = Uuid::new_v4();
+        let applicant = Uuid::new_v4();
+        for id in [owner, member, applicant] {
+            insert_user(&pool, id, &format!("approve-{}", id)).await;
+        }
+        insert_group(&pool, group_id, owner, 500).await;
+        insert_member(&pool, group_id, owner, "OWNER").await;
+        insert_member(&pool, group_id, member, "MEMBER").await;
+
+        repo.request_join(group_id, applicant).await.unwrap();
+
+        // 普通成员与非成员都无权审批
+        let err = repo.approve_join(group_id, applicant, member).await.unwrap_err();
+        assert!(err.to_string().contains("没有权限"));
+        let err = repo.approve_join(group_id, applicant, Uuid::new_v4()).await.unwrap_err();
+        assert!(err.to_string().contains("不是群组成员"));
+        let err = repo.reject_join(group_id, applicant, member).await.unwrap_err();
+        assert!(err.to_string().contains("没有权限"));
+
+        // 审批失败时申请保持待处理
+        assert_eq!(repo.list_join_requests(group_id).await.unwrap().len(), 1);
+
+        delete_group(&pool, group_id).await;
+        for id in [owner, member, applicant] {
+            delete_user(&pool, id).await;
+        }
+    }
+
+    #[tokio::test]
+    #[ignore = "需要DATABASE_URL指向的PostgreSQL"]
+    async fn test_request_approve_member_flow() {
+        let pool = test_pool().await;
+        let repo = JoinRequestRepository::new(pool.clone());
+        let member_repo = MemberRepository::new(pool.clone());
+
+        let group_id = Uuid::new_v4();
+        let owner = Uuid::new_v4();
+        let applicant = Uuid::new_v4();
+        let rejected = Uuid::new_v4();
+        for id in [owner, applicant, rejected] {
+            insert_user(&pool, id, &format!("flow-{}", id)).await;
+        }
+        insert_group(&pool, group_id, owner, 500).await;
+        insert_member(&pool, group_id, owner, "OWNER").await;
+
+        repo.request_join(group_id, applicant).await.unwrap();
+        repo.request_join(group_id, rejected).await.unwrap();
+
+        // 批准：申请删除、成员创建，角色为MEMBER
+        let member = repo.approve_join(group_id, applicant, owner).await.unwrap();
+        assert_eq!(member.user_id, applicant);
+        assert_eq!(member.role, MemberRole::Member as i32);
+        let (is_member, role, _) = member_repo.check_membership(group_id, applicant).await.unwrap();
+        assert!(is_member);
+        assert_eq!(role, Some(MemberRole::Member as i32));
+
+        // 已处理的申请不能再次批准
+        let err = repo.approve_join(group_id, applicant, owner).await.unwrap_err();
+        assert!(err.to_string().contains("入群申请不存在"));
+
+        // 拒绝：申请删除且不产生成员
+        assert!(repo.reject_join(group_id, rejected, owner).await.unwrap());
+        let (is_member, _, _) = member_repo.check_membership(group_id, rejected).await.unwrap();
+        assert!(!is_member);
+        assert!(repo.list_join_requests(group_id).await.unwrap().is_empty());
+
+        delete_group(&pool, group_id).await;
+        for id in [owner, applicant, rejected] {
+            delete_user(&pool, id).await;
+        }
+    }
+
+    #[tokio::test]
+    #[ignore = "需要DATABASE_URL指向的PostgreSQL"]
+    async fn test_approve_join_respects_member_cap() {
+        let pool = test_pool().await;
+        let repo = JoinRequestRepository::new(pool.clone());
+
+        let group_id = Uuid::new_v4();
+        let owner = Uuid::new_v4();
+        let applicant = Uuid::new_v4();
+        for id in [owner, applicant] {
+            insert_user(&pool, id, &format!("cap-join-{}", id)).await;
+        }
+        insert_group(&pool, group_id, owner, 1).await;
+        insert_member(&pool, group_id, owner, "OWNER").await;
+
+        repo.request_join(group_id, applicant).await.unwrap();
+
+        // 群已满时批准失败，申请保持待处理
+        let err = repo.approve_join(group_id, applicant, owner).await.unwrap_err();
+        assert!(err.to_string().contains("已达上限"));
+        assert_eq!(repo.list_join_requests(group_id).await.unwrap().len(), 1);
+
+        delete_group(&pool, group_id).await;
+        for id in [owner, applicant] {
+            delete_user(&pool, id).await;
+        }
+    }
+}
@@ -1,5 +1,5 @@
 use anyhow::Result;
-use sqlx::PgPool;
+use sqlx::{PgPool, Row};
 use uuid::Uuid;
 use common::proto::group::MemberRole;
 use chrono::{Utc, TimeZone};
@@ -22,12 +22,47 @@ impl MemberRepository {
         
         // 将DateTime<Utc>转换为NaiveDateTime
         let joined_at_naive = member.joined_at.naive_utc();
-        
+
+        // 锁定群组行后在同一事务内计数，防止并发加入突破上限
+        let mut tx = self.pool.begin().await?;
+
+        let group = sqlx::query!(
+            r#"
+            SELECT max_members
+            FROM groups
+            WHERE id = $1
+            FOR UPDATE
+            "#,
+            member.group_id.to_string()
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let max_members = match group {
+            Some(g) => g.max_members,
+            None => return Err(anyhow::anyhow!("群组不存在")),
+        };
+
+        let current: i64 = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) as "count!"
+            FROM group_members
+            WHERE group_id = $1
+            "#,
+            member.group_id.to_string()
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        if current >= max_members as i64 {
+            return Err(anyhow::anyhow!("群组成员数已达上限: {}", max_members));
+        }
+
         let result = sqlx::query!(
             r#"
             INSERT INTO group_members (id, group_id, user_id, role, joined_at)
             VALUES ($1, $2, $3, $4, $5)
-            RETURNING id, group_id, user_id, role, joined_at
+            RETURNING id, group_id, user_id, role, joined_at, muted_until
             "#,
             member.id.to_string(),
             member.group_id.to_string(),
@@ -35,9 +70,11 @@ impl MemberRepository {
             member.role.to_string(),
             joined_at_naive
         )
-        .fetch_one(&self.pool)
+        .fetch_one(&mut *tx)
         .await?;
-        
+
+        tx.commit().await?;
+
         Ok(Member {
             id: Uuid::parse_str(&result.id).unwrap(),
             group_id: Uuid::parse_str(&result.group_id).unwrap(),
@@ -47,9 +84,10 @@ impl MemberRepository {
             avatar_url: member.avatar_url,
             role: result.role.parse::<i32>().unwrap_or(0),
             joined_at: Utc.from_utc_datetime(&result.joined_at),
+            muted_until: result.muted_until.map(|t| Utc.from_utc_datetime(&t)),
         })
     }
-    
+
     // 移除群组成员
     pub async fn remove_member(&self, group_id: Uuid, user_id: Uuid, removed_by_id: Uuid) -> Result<bool> {
         // 验证移除权限
@@ -102,7 +140,7 @@ impl MemberRepository {
             UPDATE group_members
             SET role = $1
             WHERE group_id = $2 AND user_id = $3
-            RETURNING id, group_id, user_id, role, joined_at
+            RETURNING id, group_id, user_id, role, joined_at, muted_until
             "#,
             (role as i32).to_string(),
             group_id.to_string(),
@@ -120,16 +158,44 @@ impl MemberRepository {
             avatar_url: member_info.avatar_url,
             role: result.role.parse::<i32>().unwrap_or(0),
             joined_at: Utc.from_utc_datetime(&result.joined_at),
+            muted_until: result.muted_until.map(|t| Utc.from_utc_datetime(&t)),
         })
     }
-    
+
+    // 在事务内更新成员角色（不做权限校验，由调用方保证；供群主转让等跨表操作使用）
+    pub async fn update_member_role_in_tx(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        group_id: Uuid,
+        user_id: Uuid,
+        role: MemberRole,
+    ) -> Result<()> {
+        let rows_affected = sqlx::query!(
+            r#"
+            UPDATE group_members
+            SET role = $1
+            WHERE group_id = $2 AND user_id = $3
+            "#,
+            (role as i32).to_string(),
+            group_id.to_string(),
+            user_id.to_string()
+        )
+        .execute(&mut **tx)
+        .await?
+        .rows_affected();
+
+        if rows_affected == 0 {
+            return Err(anyhow::anyhow!("用户不是群组成员"));
+        }
+        Ok(())
+    }
+
     // 获取群组成员
     pub async fn get_member(&self, group_id: Uuid, user_id: Uuid) -> Result<Member> {
         // 在真实环境中，这需要从user-service获取用户信息
         // 这里简化处理，仅从数据库获取基本信息
         let result = sqlx::query!(
             r#"
-            SELECT m.id, m.group_id, m.user_id, m.role, m.joined_at, 
+            SELECT m.id, m.group_id, m.user_id, m.role, m.joined_at, m.muted_until,
                    u.username, u.nickname, u.avatar_url
             FROM group_members m
             JOIN users u ON m.user_id = u.id
@@ -150,9 +216,19 @@ impl MemberRepository {
             avatar_url: result.avatar_url,
             role: result.role.parse::<i32>().unwrap_or(0),
             joined_at: Utc.from_utc_datetime(&result.joined_at),
+            muted_until: result.muted_until.map(|t| Utc.from_utc_datetime(&t)),
         })
     }
-    
+
+    // 解析角色列的编码：add_member写入数字串，DDL约束的是英文枚举，两种都接受
+    pub(crate) fn role_code(role: &str) -> i32 {
+        role.parse::<i32>().unwrap_or(match role {
+            "OWNER" => MemberRole::Owner as i32,
+            "ADMIN" => MemberRole::Admin as i32,
+            _ => MemberRole::Member as i32,
+        })
+    }
+
     // 获取成员角色
     pub async fn get_member_role(&self, group_id: Uuid, user_id: Uuid) -> Result<i32> {
         let result = sqlx::query!(
@@ -168,50 +244,148 @@ impl MemberRepository {
         .await?;
         
         match result {
-            Some(r) => Ok(r.role.parse::<i32>().unwrap_or(0)),
+            Some(r) => Ok(Self::role_code(&r.role)),
             None => Err(anyhow::anyhow!("用户不是群组成员")),
         }
     }
     
-    // 获取群组成员列表
-    pub async fn get_members(&self, group_id: Uuid) -> Result<Vec<Member>> {
+    // 获取群组成员列表（分页），返回成员列表与总数
+    // sort_by仅接受白名单内的字段，其他取值回退到默认排序（角色降序+加入时间升序）
+    pub async fn get_members(
+        &self,
+        group_id: Uuid,
+        page: i32,
+        page_size: i32,
+        sort_by: &str,
+        descending: bool,
+    ) -> Result<(Vec<Member>, i32)> {
+        let offset = (page - 1) * page_size;
+        let direction = if descending { "DESC" } else { "ASC" };
+        // ORDER BY无法参数化，只拼接白名单字段与方向
+        let order_by = match sort_by {
+            "role" => format!("m.role {}, m.joined_at ASC", direction),
+            "joined_at" => format!("m.joined_at {}", direction),
+            "username" => format!("u.username {}", direction),
+            _ => "m.role DESC, m.joined_at ASC".to_string(),
+        };
+
         // 在真实环境中，这需要从user-service获取用户信息
-        let members = sqlx::query!(
+        let sql = format!(
             r#"
-            SELECT m.id, m.group_id, m.user_id, m.role, m.joined_at,
+            SELECT m.id, m.group_id, m.user_id, m.role, m.joined_at, m.muted_until,
                    u.username, u.nickname, u.avatar_url
             FROM group_members m
             JOIN users u ON m.user_id = u.id
             WHERE m.group_id = $1
-            ORDER BY m.role DESC, m.joined_at ASC
+            ORDER BY {}
+            LIMIT $2 OFFSET $3
             "#,
-            group_id.to_string()
-        )
-        .fetch_all(&self.pool)
-        .await?;
-        
+            order_by
+        );
+        let members = sqlx::query(&sql)
+            .bind(group_id.to_string())
+            .bind(page_size as i64)
+            .bind(offset as i64)
+            .fetch_all(&self.pool)
+            .await?;
+
         let result = members
             .into_iter()
             .map(|m| Member {
-                id: Uuid::parse_str(&m.id).unwrap(),
-                group_id: Uuid::parse_str(&m.group_id).unwrap(),
-                user_id: Uuid::parse_str(&m.user_id).unwrap(),
-                username: m.username,
-                nickname: m.nickname,
-                avatar_url: m.avatar_url,
-                role: m.role.parse::<i32>().unwrap_or(0),
-                joined_at: Utc.from_utc_datetime(&m.joined_at),
+                id: Uuid::parse_str(m.get("id")).unwrap(),
+                group_id: Uuid::parse_str(m.get("group_id")).unwrap(),
+                user_id: Uuid::parse_str(m.get("user_id")).unwrap(),
+                username: m.get("username"),
+                nickname: m.get("nickname"),
+                avatar_url: m.get("avatar_url"),
+                role: m.get::<String, _>("role").parse::<i32>().unwrap_or(0),
+                joined_at: Utc.from_utc_datetime(&m.get("joined_at")),
+                muted_until: m
+                    .get::<Option<chrono::NaiveDateTime>, _>("muted_until")
+                    .map(|t| Utc.from_utc_datetime(&t)),
             })
             .collect();
-        
-        Ok(result)
+
+        let total: i64 = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) as "count!"
+            FROM group_members
+            WHERE group_id = $1
+            "#,
+            group_id.to_string()
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok((result, total as i32))
     }
     
-    // 检查用户是否是群组成员
-    pub async fn check_membership(&self, group_id: Uuid, user_id: Uuid) -> Result<(bool, Option<i32>)> {
+    // 设置成员免打扰截止时间
+    pub async fn mute_member(&self, group_id: Uuid, user_id: Uuid, muted_by_id: Uuid, muted_until: chrono::DateTime<Utc>) -> Result<bool> {
+        // 验证静音权限（与remove_member相同的角色层级规则）
+        let muter_role = self.get_member_role(group_id, muted_by_id).await?;
+        let member_role = self.get_member_role(group_id, user_id).await?;
+
+        if muter_role < MemberRole::Admin as i32 {
+            return Err(anyhow::anyhow!("没有权限静音成员"));
+        }
+
+        if muter_role <= member_role {
+            return Err(anyhow::anyhow!("无法静音同级或更高级别的成员"));
+        }
+
+        let rows_affected = sqlx::query!(
+            r#"
+            UPDATE group_members
+            SET muted_until = $1
+            WHERE group_id = $2 AND user_id = $3
+            "#,
+            muted_until.naive_utc(),
+            group_id.to_string(),
+            user_id.to_string()
+        )
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        Ok(rows_affected > 0)
+    }
+
+    // 取消成员免打扰
+    pub async fn unmute_member(&self, group_id: Uuid, user_id: Uuid, unmuted_by_id: Uuid) -> Result<bool> {
+        // 权限要求与mute_member相同
+        let unmuter_role = self.get_member_role(group_id, unmuted_by_id).await?;
+        let member_role = self.get_member_role(group_id, user_id).await?;
+
+        if unmuter_role < MemberRole::Admin as i32 {
+            return Err(anyhow::anyhow!("没有权限取消静音"));
+        }
+
+        if unmuter_role <= member_role {
+            return Err(anyhow::anyhow!("无法对同级或更高级别的成员取消静音"));
+        }
+
+        let rows_affected = sqlx::query!(
+            r#"
+            UPDATE group_members
+            SET muted_until = NULL
+            WHERE group_id = $1 AND user_id = $2
+            "#,
+            group_id.to_string(),
+            user_id.to_string()
+        )
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        Ok(rows_affected > 0)
+    }
+
+    // 检查用户是否是群组成员，同时返回角色与当前是否处于静音中
+    pub async fn check_membership(&self, group_id: Uuid, user_id: Uuid) -> Result<(bool, Option<i32>, bool)> {
         let result = sqlx::query!(
             r#"
-            SELECT role
+            SELECT role, muted_until
             FROM group_members
             WHERE group_id = $1 AND user_id = $2
             "#,
@@ -220,10 +394,278 @@ impl MemberRepository {
         )
         .fetch_optional(&self.pool)
         .await?;
-        
+
         match result {
-            Some(r) => Ok((true, Some(r.role.parse::<i32>().unwrap_or(0)))),
-            None => Ok((false, None)),
+            Some(r) => {
+                // 过期的muted_until视为未静音，无需后台清理
+                let is_muted = r
+                    .muted_until
+                    .map(|t| Utc.from_utc_datetime(&t) > Utc::now())
+                    .unwrap_or(false);
+                Ok((true, Some(Self::role_code(&r.role)), is_muted))
+            }
+            None => Ok((false, None, false)),
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn test_pool() -> PgPool {
+        let url = std::env::var("DATABASE_URL").expect("需要设置DATABASE_URL");
+        PgPool::connect(&url).await.unwrap()
+    }
+
+    async fn insert_user(pool: &PgPool, id: Uuid, username: &str) {
+        sqlx::query(
+            "INSERT INTO users (id, username, email, password) VALUES ($1, $2, $2 || '@test.local', 'pw')",
+        )
+        .bind(id.to_string())
+        .bind(username)
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    async fn insert_group(pool: &PgPool, group_id: Uuid, owner_id: Uuid) {
+        sqlx::query("INSERT INTO groups (id, name, owner_id) VALUES ($1, '成员分页测试群', $2)")
+            .bind(group_id.to_string())
+            .bind(owner_id.to_string())
+            .execute(pool)
+            .await
+            .unwrap();
+    }
+
+    async fn insert_group_with_max(pool: &PgPool, group_id: Uuid, owner_id: Uuid, max_members: i32) {
+        sqlx::query("INSERT INTO groups (id, name, owner_id, max_members) VALUES ($1, '成员上限测试群', $2, $3)")
+            .bind(group_id.to_string())
+            .bind(owner_id.to_string())
+            .bind(max_members)
+            .execute(pool)
+            .await
+            .unwrap();
+    }
+
+    // 直接按表结构插入成员行（add_member的角色编码与DDL约束不一致，测试里不经过它）
+    async fn insert_member(pool: &PgPool, group_id: Uuid, user_id: Uuid, role: &str, joined_offset_secs: i64) {
+        sqlx::query(
+            "INSERT INTO group_members (id, group_id, user_id, role, joined_at)
+             VALUES ($1, $2, $3, $4, NOW() + ($5 || ' seconds')::interval)",
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(group_id.to_string())
+        .bind(user_id.to_string())
+        .bind(role)
+        .bind(joined_offset_secs.to_string())
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    async fn delete_user(pool: &PgPool, id: Uuid) {
+        sqlx::query("DELETE FROM users WHERE id = $1")
+            .bind(id.to_string())
+            .execute(pool)
+            .await
+            .unwrap();
+    }
+
+    async fn delete_group(pool: &PgPool, id: Uuid) {
+        sqlx::query("DELETE FROM groups WHERE id = $1")
+            .bind(id.to_string())
+            .execute(pool)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore = "需要DATABASE_URL指向的PostgreSQL"]
+    async fn test_get_members_page_boundaries() {
+        let pool = test_pool().await;
+        let repo = MemberRepository::new(pool.clone());
+
+        let group_id = Uuid::new_v4();
+        let user_ids: Vec<Uuid> = (0..5).map(|_| Uuid::new_v4()).collect();
+        for (i, id) in user_ids.iter().enumerate() {
+            insert_user(&pool, *id, &format!("page-{}-{}", i, id)).await;
+        }
+        insert_group(&pool, group_id, user_ids[0]).await;
+        for (i, id) in user_ids.iter().enumerate() {
+            insert_member(&pool, group_id, *id, "MEMBER", i as i64).await;
+        }
+
+        // 5个成员按每页2个分页：2 + 2 + 1，越界页为空，总数始终为5
+        let (page1, total) = repo.get_members(group_id, 1, 2, "", false).await.unwrap();
+        assert_eq!((page1.len(), total), (2, 5));
+        let (page2, _) = repo.get_members(group_id, 2, 2, "", false).await.unwrap();
+        assert_eq!(page2.len(), 2);
+        let (page3, total) = repo.get_members(group_id, 3, 2, "", false).await.unwrap();
+        assert_eq!((page3.len(), total), (1, 5));
+        let (page4, _) = repo.get_members(group_id, 4, 2, "", false).await.unwrap();
+        assert!(page4.is_empty());
+
+        // 各页之间无重叠
+        let mut seen: Vec<Uuid> = page1.iter().chain(&page2).chain(&page3).map(|m| m.user_id).collect();
+        seen.sort();
+        seen.dedup();
+        assert_eq!(seen.len(), 5);
+
+        delete_group(&pool, group_id).await;
+        for id in &user_ids {
+            delete_user(&pool, *id).await;
+        }
+    }
+
+    #[tokio::test]
+    #[ignore = "需要DATABASE_URL指向的PostgreSQL"]
+    async fn test_get_members_sort_options() {
+        let pool = test_pool().await;
+        let repo = MemberRepository::new(pool.clone());
+
+        let group_id = Uuid::new_v4();
+        let owner = Uuid::new_v4();
+        let admin = Uuid::new_v4();
+        let member = Uuid::new_v4();
+        insert_user(&pool, owner, &format!("cc-{}", owner)).await;
+        insert_user(&pool, admin, &format!("bb-{}", admin)).await;
+        insert_user(&pool, member, &format!("aa-{}", member)).await;
+        insert_group(&pool, group_id, owner).await;
+        // 加入时间：owner最早，member最晚
+        insert_member(&pool, group_id, owner, "OWNER", 0).await;
+        insert_member(&pool, group_id, admin, "ADMIN", 10).await;
+        insert_member(&pool, group_id, member, "MEMBER", 20).await;
+
+        let ids = |members: &[Member]| members.iter().map(|m| m.user_id).collect::<Vec<_>>();
+
+        // 默认排序：角色降序（OWNER > MEMBER > ADMIN）+加入时间升序
+        let (default_order, _) = repo.get_members(group_id, 1, 10, "", false).await.unwrap();
+        assert_eq!(ids(&default_order), vec![owner, member, admin]);
+        // 非白名单字段同样回退到默认排序
+        let (fallback, _) = repo.get_members(group_id, 1, 10, "id; DROP TABLE users", true).await.unwrap();
+        assert_eq!(ids(&fallback), ids(&default_order));
+
+        let (by_joined, _) = repo.get_members(group_id, 1, 10, "joined_at", false).await.unwrap();
+        assert_eq!(ids(&by_joined), vec![owner, admin, member]);
+        let (by_joined_desc, _) = repo.get_members(group_id, 1, 10, "joined_at", true).await.unwrap();
+        assert_eq!(ids(&by_joined_desc), vec![member, admin, owner]);
+
+        let (by_username, _) = repo.get_members(group_id, 1, 10, "username", false).await.unwrap();
+        assert_eq!(ids(&by_username), vec![member, admin, owner]);
+
+        let (by_role, _) = repo.get_members(group_id, 1, 10, "role", true).await.unwrap();
+        assert_eq!(ids(&by_role), vec![owner, member, admin]);
+
+        delete_group(&pool, group_id).await;
+        delete_user(&pool, owner).await;
+        delete_user(&pool, admin).await;
+        delete_user(&pool, member).await;
+    }
+
+    #[tokio::test]
+    #[ignore = "需要DATABASE_URL指向的PostgreSQL"]
+    async fn test_mute_member_permissions_and_expiry() {
+        let pool = test_pool().await;
+        let repo = MemberRepository::new(pool.clone());
+
+        let group_id = Uuid::new_v4();
+        let owner = Uuid::new_v4();
+        let admin = Uuid::new_v4();
+        let member = Uuid::new_v4();
+        let member2 = Uuid::new_v4();
+        for id in [owner, admin, member, member2] {
+            insert_user(&pool, id, &format!("mute-{}", id)).await;
+        }
+        insert_group(&pool, group_id, owner).await;
+        insert_member(&pool, group_id, owner, "OWNER", 0).await;
+        insert_member(&pool, group_id, admin, "ADMIN", 0).await;
+        insert_member(&pool, group_id, member, "MEMBER", 0).await;
+        insert_member(&pool, group_id, member2, "MEMBER", 0).await;
+
+        let until = Utc::now() + chrono::Duration::hours(1);
+
+        // 普通成员没有静音权限
+        let err = repo.mute_member(group_id, member2, member, until).await.unwrap_err();
+        assert!(err.to_string().contains("没有权限"));
+
+        // 管理员无法静音同级或更高级别的成员
+        let err = repo.mute_member(group_id, owner, admin, until).await.unwrap_err();
+        assert!(err.to_string().contains("无法静音"));
+
+        // 非群成员不能被静音
+        assert!(repo.mute_member(group_id, Uuid::new_v4(), admin, until).await.is_err());
+
+        // 管理员静音普通成员，check_membership反映静音状态
+        assert!(repo.mute_member(group_id, member, admin, until).await.unwrap());
+        let (is_member, role, is_muted) = repo.check_membership(group_id, member).await.unwrap();
+        assert!(is_member && is_muted);
+        assert_eq!(role, Some(MemberRole::Member as i32));
+
+        // 取消静音也需要管理员权限
+        assert!(repo.unmute_member(group_id, member, member2).await.is_err());
+        assert!(repo.unmute_member(group_id, member, admin).await.unwrap());
+        let (_, _, is_muted) = repo.check_membership(group_id, member).await.unwrap();
+        assert!(!is_muted);
+
+        // muted_until在过去时视为已自动到期
+        let expired = Utc::now() - chrono::Duration::hours(1);
+        assert!(repo.mute_member(group_id, member, admin, expired).await.unwrap());
+        let (_, _, is_muted) = repo.check_membership(group_id, member).await.unwrap();
+        assert!(!is_muted);
+
+        // 非群成员的查询不报静音
+        let (is_member, role, is_muted) = repo.check_membership(group_id, Uuid::new_v4()).await.unwrap();
+        assert!(!is_member && role.is_none() && !is_muted);
+
+        delete_group(&pool, group_id).await;
+        for id in [owner, admin, member, member2] {
+            delete_user(&pool, id).await;
+        }
+    }
+
+    #[tokio::test]
+    #[ignore = "需要DATABASE_URL指向的PostgreSQL"]
+    async fn test_add_member_rejected_when_group_full() {
+        let pool = test_pool().await;
+        let repo = MemberRepository::new(pool.clone());
+
+        let group_id = Uuid::new_v4();
+        let owner = Uuid::new_v4();
+        let member = Uuid::new_v4();
+        let third = Uuid::new_v4();
+        for id in [owner, member, third] {
+            insert_user(&pool, id, &format!("cap-{}", id)).await;
+        }
+        insert_group_with_max(&pool, group_id, owner, 2).await;
+        insert_member(&pool, group_id, owner, "OWNER", 0).await;
+        insert_member(&pool, group_id, member, "MEMBER", 0).await;
+
+        // 已满2人，第三个加入被拒绝
+        let err = repo
+            .add_member(group_id, third, "cap-third".to_string(), None, None, MemberRole::Member)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("已达上限"));
+
+        // 拒绝发生在插入前，成员数不变
+        let count: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM group_members WHERE group_id = $1")
+                .bind(group_id.to_string())
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(count, 2);
+
+        // 不存在的群组同样无法加入
+        let err = repo
+            .add_member(Uuid::new_v4(), third, "cap-third".to_string(), None, None, MemberRole::Member)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("群组不存在"));
+
+        delete_group(&pool, group_id).await;
+        for id in [owner, member, third] {
+            delete_user(&pool, id).await;
+        }
+    }
+}
@@ -1,2 +1,3 @@
 pub mod group_repository;
+pub mod join_request_repository;
 pub mod member_repository;
\ No newline at end of file
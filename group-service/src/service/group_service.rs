@@ -4,26 +4,35 @@ use common::proto::group::{
     GetMembersRequest, GetUserGroupsRequest, CheckMembershipRequest,
     DeleteGroupResponse, MemberResponse, GetMembersResponse, GetUserGroupsResponse,
     CheckMembershipResponse, GroupResponse, RemoveMemberResponse, MemberRole,
+    MuteMemberRequest, MuteMemberResponse, UnmuteMemberRequest, UnmuteMemberResponse,
+    TransferOwnershipRequest, TransferOwnershipResponse, UpdateGroupAnnouncementRequest,
+    SearchGroupsRequest, SearchGroupsResponse,
+    RequestJoinRequest, RequestJoinResponse, ApproveJoinRequest,
+    RejectJoinRequest, RejectJoinResponse, ListJoinRequestsRequest, ListJoinRequestsResponse,
 };
 use common::proto::group::group_service_server::GroupService;
+use chrono::TimeZone;
 use sqlx::PgPool;
 use tonic::{Request, Response, Status};
 use uuid::Uuid;
 use tracing::{info, error};
 
 use crate::repository::group_repository::GroupRepository;
+use crate::repository::join_request_repository::JoinRequestRepository;
 use crate::repository::member_repository::MemberRepository;
 
 pub struct GroupServiceImpl {
     group_repository: GroupRepository,
     member_repository: MemberRepository,
+    join_request_repository: JoinRequestRepository,
 }
 
 impl GroupServiceImpl {
-    pub fn new(pool: PgPool) -> Self {
+    pub fn new(pool: PgPool, default_max_members: u32) -> Self {
         Self {
-            group_repository: GroupRepository::new(pool.clone()),
-            member_repository: MemberRepository::new(pool),
+            group_repository: GroupRepository::new(pool.clone(), default_max_members),
+            member_repository: MemberRepository::new(pool.clone()),
+            join_request_repository: JoinRequestRepository::new(pool),
         }
     }
 }
@@ -89,10 +98,11 @@ impl GroupService for GroupServiceImpl {
         match self.group_repository.get_group(group_id).await {
             Ok(group) => {
                 // 获取成员数量
-                let member_count = match self.group_repository.get_member_count(group_id).await {
-                    Ok(count) => count,
-                    Err(_) => 0,
-                };
+                let member_count = self
+                    .group_repository
+                    .get_member_count(group_id)
+                    .await
+                    .unwrap_or_default();
                 
                 Ok(Response::new(GroupResponse {
                     group: Some(group.to_proto(member_count)),
@@ -123,10 +133,11 @@ impl GroupService for GroupServiceImpl {
         ).await {
             Ok(group) => {
                 // 获取成员数量
-                let member_count = match self.group_repository.get_member_count(group_id).await {
-                    Ok(count) => count,
-                    Err(_) => 0,
-                };
+                let member_count = self
+                    .group_repository
+                    .get_member_count(group_id)
+                    .await
+                    .unwrap_or_default();
                 
                 info!("更新群组信息成功: {:?}", group);
                 Ok(Response::new(GroupResponse {
@@ -203,7 +214,7 @@ impl GroupService for GroupServiceImpl {
         
         // 检查用户是否已经是成员
         match self.member_repository.check_membership(group_id, user_id).await {
-            Ok((is_member, _)) => {
+            Ok((is_member, _, _)) => {
                 if is_member {
                     return Err(Status::already_exists("用户已经是群组成员"));
                 }
@@ -231,7 +242,11 @@ impl GroupService for GroupServiceImpl {
             }
             Err(e) => {
                 error!("添加群组成员失败: {}", e);
-                Err(Status::internal("添加群组成员失败"))
+                if e.to_string().contains("已达上限") {
+                    Err(Status::failed_precondition(e.to_string()))
+                } else {
+                    Err(Status::internal("添加群组成员失败"))
+                }
             }
         }
     }
@@ -263,9 +278,7 @@ impl GroupService for GroupServiceImpl {
             }
             Err(e) => {
                 error!("移除群组成员失败: {}", e);
-                if e.to_string().contains("没有权限") {
-                    Err(Status::permission_denied(e.to_string()))
-                } else if e.to_string().contains("无法移除") {
+                if e.to_string().contains("没有权限") || e.to_string().contains("无法移除") {
                     Err(Status::permission_denied(e.to_string()))
                 } else {
                     Err(Status::internal("移除群组成员失败"))
@@ -299,9 +312,7 @@ impl GroupService for GroupServiceImpl {
             }
             Err(e) => {
                 error!("更新成员角色失败: {}", e);
-                if e.to_string().contains("只有群主") {
-                    Err(Status::permission_denied(e.to_string()))
-                } else if e.to_string().contains("无法将成员提升") {
+                if e.to_string().contains("只有群主") || e.to_string().contains("无法将成员提升") {
                     Err(Status::permission_denied(e.to_string()))
                 } else {
                     Err(Status::internal("更新成员角色失败"))
@@ -310,6 +321,65 @@ impl GroupService for GroupServiceImpl {
         }
     }
     
+    // 转让群主
+    async fn transfer_ownership(
+        &self,
+        request: Request<TransferOwnershipRequest>,
+    ) -> Result<Response<TransferOwnershipResponse>, Status> {
+        let req = request.into_inner();
+
+        let group_id = req.group_id.parse::<Uuid>()
+            .map_err(|e| Status::invalid_argument(format!("无效的群组ID: {}", e)))?;
+
+        let current_owner_id = req.current_owner_id.parse::<Uuid>()
+            .map_err(|e| Status::invalid_argument(format!("无效的当前群主ID: {}", e)))?;
+
+        let new_owner_id = req.new_owner_id.parse::<Uuid>()
+            .map_err(|e| Status::invalid_argument(format!("无效的新群主ID: {}", e)))?;
+
+        if current_owner_id == new_owner_id {
+            return Err(Status::invalid_argument("新群主不能是当前群主"));
+        }
+
+        // 校验当前群主身份
+        let group = self.group_repository.get_group(group_id).await
+            .map_err(|e| {
+                error!("获取群组信息失败: {}", e);
+                Status::not_found("群组不存在")
+            })?;
+        if group.owner_id != current_owner_id {
+            return Err(Status::permission_denied("只有群主可以转让群组"));
+        }
+
+        // 新群主必须已是群成员
+        if self.member_repository.get_member_role(group_id, new_owner_id).await.is_err() {
+            return Err(Status::failed_precondition("新群主必须已是群组成员"));
+        }
+
+        match self.group_repository.transfer_ownership(group_id, current_owner_id, new_owner_id).await {
+            Ok(group) => {
+                let member_count = self
+                    .group_repository
+                    .get_member_count(group_id)
+                    .await
+                    .unwrap_or_default();
+
+                info!("群组 {} 群主已从 {} 转让给 {}", group_id, current_owner_id, new_owner_id);
+                Ok(Response::new(TransferOwnershipResponse {
+                    group: Some(group.to_proto(member_count)),
+                }))
+            }
+            Err(e) => {
+                error!("转让群主失败: {}", e);
+                if e.to_string().contains("只有群主") {
+                    Err(Status::permission_denied(e.to_string()))
+                } else {
+                    Err(Status::internal("转让群主失败"))
+                }
+            }
+        }
+    }
+
     // 获取群组成员列表
     async fn get_members(
         &self,
@@ -319,15 +389,28 @@ impl GroupService for GroupServiceImpl {
         
         let group_id = req.group_id.parse::<Uuid>()
             .map_err(|e| Status::invalid_argument(format!("无效的群组ID: {}", e)))?;
-        
-        match self.member_repository.get_members(group_id).await {
-            Ok(members) => {
+
+        // 设置默认分页参数，page_size上限100
+        let page = if req.page <= 0 { 1 } else { req.page };
+        let page_size = if req.page_size <= 0 || req.page_size > 100 {
+            10
+        } else {
+            req.page_size
+        };
+
+        match self
+            .member_repository
+            .get_members(group_id, page, page_size, &req.sort_by, req.descending)
+            .await
+        {
+            Ok((members, total)) => {
                 let proto_members = members.into_iter()
                     .map(|m| m.to_proto())
                     .collect();
-                
+
                 Ok(Response::new(GetMembersResponse {
                     members: proto_members,
+                    total,
                 }))
             }
             Err(e) => {
@@ -363,7 +446,312 @@ impl GroupService for GroupServiceImpl {
             }
         }
     }
-    
+
+    // 全文搜索公开群组
+    async fn search_groups(
+        &self,
+        request: Request<SearchGroupsRequest>,
+    ) -> Result<Response<SearchGroupsResponse>, Status> {
+        let req = request.into_inner();
+
+        let query = req.query.trim();
+        if query.is_empty() {
+            return Err(Status::invalid_argument("搜索关键词不能为空"));
+        }
+
+        // 设置默认分页参数，page_size上限100
+        let page = if req.page <= 0 { 1 } else { req.page };
+        let page_size = if req.page_size <= 0 || req.page_size > 100 {
+            10
+        } else {
+            req.page_size
+        };
+
+        match self.group_repository.search_groups(query, page, page_size).await {
+            Ok((groups, total)) => {
+                let proto_groups = groups.into_iter()
+                    .map(|(g, member_count)| g.to_proto(member_count))
+                    .collect();
+
+                Ok(Response::new(SearchGroupsResponse {
+                    groups: proto_groups,
+                    total,
+                }))
+            }
+            Err(e) => {
+                error!("搜索群组失败: {}", e);
+                Err(Status::internal("搜索群组失败"))
+            }
+        }
+    }
+
+    // 申请加入群组
+    async fn request_join(
+        &self,
+        request: Request<RequestJoinRequest>,
+    ) -> Result<Response<RequestJoinResponse>, Status> {
+        let req = request.into_inner();
+
+        let group_id = req.group_id.parse::<Uuid>()
+            .map_err(|e| Status::invalid_argument(format!("无效的群组ID: {}", e)))?;
+
+        let user_id = req.user_id.parse::<Uuid>()
+            .map_err(|e| Status::invalid_argument(format!("无效的用户ID: {}", e)))?;
+
+        match self.join_request_repository.request_join(group_id, user_id).await {
+            Ok(()) => {
+                info!("提交入群申请成功: group_id={}, user_id={}", group_id, user_id);
+                Ok(Response::new(RequestJoinResponse { success: true }))
+            }
+            Err(e) => {
+                error!("提交入群申请失败: {}", e);
+                if e.to_string().contains("群组不存在") {
+                    Err(Status::not_found("群组不存在"))
+                } else if e.to_string().contains("已经是群组成员") || e.to_string().contains("已提交过") {
+                    Err(Status::already_exists(e.to_string()))
+                } else {
+                    Err(Status::internal("提交入群申请失败"))
+                }
+            }
+        }
+    }
+
+    // 批准入群申请
+    async fn approve_join(
+        &self,
+        request: Request<ApproveJoinRequest>,
+    ) -> Result<Response<MemberResponse>, Status> {
+        let req = request.into_inner();
+
+        let group_id = req.group_id.parse::<Uuid>()
+            .map_err(|e| Status::invalid_argument(format!("无效的群组ID: {}", e)))?;
+
+        let user_id = req.user_id.parse::<Uuid>()
+            .map_err(|e| Status::invalid_argument(format!("无效的用户ID: {}", e)))?;
+
+        let approved_by_id = req.approved_by_id.parse::<Uuid>()
+            .map_err(|e| Status::invalid_argument(format!("无效的操作者ID: {}", e)))?;
+
+        match self.join_request_repository.approve_join(group_id, user_id, approved_by_id).await {
+            Ok(member) => {
+                info!("批准入群申请成功: {:?}", member);
+                Ok(Response::new(MemberResponse {
+                    member: Some(member.to_proto()),
+                }))
+            }
+            Err(e) => {
+                error!("批准入群申请失败: {}", e);
+                if e.to_string().contains("没有权限") || e.to_string().contains("不是群组成员") {
+                    Err(Status::permission_denied(e.to_string()))
+                } else if e.to_string().contains("入群申请不存在") || e.to_string().contains("群组不存在") {
+                    Err(Status::not_found(e.to_string()))
+                } else if e.to_string().contains("已达上限") {
+                    Err(Status::failed_precondition(e.to_string()))
+                } else {
+                    Err(Status::internal("批准入群申请失败"))
+                }
+            }
+        }
+    }
+
+    // 拒绝入群申请
+    async fn reject_join(
+        &self,
+        request: Request<RejectJoinRequest>,
+    ) -> Result<Response<RejectJoinResponse>, Status> {
+        let req = request.into_inner();
+
+        let group_id = req.group_id.parse::<Uuid>()
+            .map_err(|e| Status::invalid_argument(format!("无效的群组ID: {}", e)))?;
+
+        let user_id = req.user_id.parse::<Uuid>()
+            .map_err(|e| Status::invalid_argument(format!("无效的用户ID: {}", e)))?;
+
+        let rejected_by_id = req.rejected_by_id.parse::<Uuid>()
+            .map_err(|e| Status::invalid_argument(format!("无效的操作者ID: {}", e)))?;
+
+        match self.join_request_repository.reject_join(group_id, user_id, rejected_by_id).await {
+            Ok(success) => {
+                if success {
+                    info!("拒绝入群申请成功: group_id={}, user_id={}", group_id, user_id);
+                    Ok(Response::new(RejectJoinResponse { success }))
+                } else {
+                    Err(Status::not_found("入群申请不存在"))
+                }
+            }
+            Err(e) => {
+                error!("拒绝入群申请失败: {}", e);
+                if e.to_string().contains("没有权限") || e.to_string().contains("不是群组成员") {
+                    Err(Status::permission_denied(e.to_string()))
+                } else {
+                    Err(Status::internal("拒绝入群申请失败"))
+                }
+            }
+        }
+    }
+
+    // 获取入群申请列表
+    async fn list_join_requests(
+        &self,
+        request: Request<ListJoinRequestsRequest>,
+    ) -> Result<Response<ListJoinRequestsResponse>, Status> {
+        let req = request.into_inner();
+
+        let group_id = req.group_id.parse::<Uuid>()
+            .map_err(|e| Status::invalid_argument(format!("无效的群组ID: {}", e)))?;
+
+        let requested_by_id = req.requested_by_id.parse::<Uuid>()
+            .map_err(|e| Status::invalid_argument(format!("无效的操作者ID: {}", e)))?;
+
+        // 申请列表仅管理员及以上可见
+        match self.member_repository.get_member_role(group_id, requested_by_id).await {
+            Ok(role) => {
+                if role < MemberRole::Admin as i32 {
+                    return Err(Status::permission_denied("没有查看入群申请的权限"));
+                }
+            }
+            Err(_) => {
+                return Err(Status::permission_denied("操作者不是群组成员"));
+            }
+        }
+
+        match self.join_request_repository.list_join_requests(group_id).await {
+            Ok(requests) => {
+                let proto_requests = requests.into_iter()
+                    .map(|r| r.to_proto())
+                    .collect();
+
+                Ok(Response::new(ListJoinRequestsResponse {
+                    requests: proto_requests,
+                }))
+            }
+            Err(e) => {
+                error!("获取入群申请列表失败: {}", e);
+                Err(Status::internal("获取入群申请列表失败"))
+            }
+        }
+    }
+
+    // 设置成员免打扰
+    async fn mute_member(
+        &self,
+        request: Request<MuteMemberRequest>,
+    ) -> Result<Response<MuteMemberResponse>, Status> {
+        let req = request.into_inner();
+
+        let group_id = req.group_id.parse::<Uuid>()
+            .map_err(|e| Status::invalid_argument(format!("无效的群组ID: {}", e)))?;
+
+        let user_id = req.user_id.parse::<Uuid>()
+            .map_err(|e| Status::invalid_argument(format!("无效的用户ID: {}", e)))?;
+
+        let muted_until = chrono::Utc.timestamp_millis_opt(req.mute_until_epoch_ms)
+            .single()
+            .ok_or_else(|| Status::invalid_argument("无效的静音截止时间"))?;
+
+        if muted_until <= chrono::Utc::now() {
+            return Err(Status::invalid_argument("静音截止时间必须晚于当前时间"));
+        }
+
+        let muted_by_id = req.muted_by_id.parse::<Uuid>()
+            .map_err(|e| Status::invalid_argument(format!("无效的操作者ID: {}", e)))?;
+
+        match self.member_repository.mute_member(group_id, user_id, muted_by_id, muted_until).await {
+            Ok(success) => {
+                if success {
+                    info!("设置成员免打扰成功: group_id={}, user_id={}, muted_until={}", group_id, user_id, muted_until);
+                    Ok(Response::new(MuteMemberResponse { success }))
+                } else {
+                    Err(Status::not_found("用户不是群组成员"))
+                }
+            }
+            Err(e) => {
+                error!("设置成员免打扰失败: {}", e);
+                if e.to_string().contains("没有权限") || e.to_string().contains("无法静音") {
+                    Err(Status::permission_denied(e.to_string()))
+                } else {
+                    Err(Status::internal("设置成员免打扰失败"))
+                }
+            }
+        }
+    }
+
+    // 取消成员免打扰
+    async fn unmute_member(
+        &self,
+        request: Request<UnmuteMemberRequest>,
+    ) -> Result<Response<UnmuteMemberResponse>, Status> {
+        let req = request.into_inner();
+
+        let group_id = req.group_id.parse::<Uuid>()
+            .map_err(|e| Status::invalid_argument(format!("无效的群组ID: {}", e)))?;
+
+        let user_id = req.user_id.parse::<Uuid>()
+            .map_err(|e| Status::invalid_argument(format!("无效的用户ID: {}", e)))?;
+
+        let unmuted_by_id = req.unmuted_by_id.parse::<Uuid>()
+            .map_err(|e| Status::invalid_argument(format!("无效的操作者ID: {}", e)))?;
+
+        match self.member_repository.unmute_member(group_id, user_id, unmuted_by_id).await {
+            Ok(success) => {
+                if success {
+                    info!("取消成员免打扰成功: group_id={}, user_id={}", group_id, user_id);
+                    Ok(Response::new(UnmuteMemberResponse { success }))
+                } else {
+                    Err(Status::not_found("用户不是群组成员"))
+                }
+            }
+            Err(e) => {
+                error!("取消成员免打扰失败: {}", e);
+                if e.to_string().contains("没有权限") || e.to_string().contains("无法对") {
+                    Err(Status::permission_denied(e.to_string()))
+                } else {
+                    Err(Status::internal("取消成员免打扰失败"))
+                }
+            }
+        }
+    }
+
+    // 设置群公告
+    async fn update_group_announcement(
+        &self,
+        request: Request<UpdateGroupAnnouncementRequest>,
+    ) -> Result<Response<GroupResponse>, Status> {
+        let req = request.into_inner();
+
+        let group_id = req.group_id.parse::<Uuid>()
+            .map_err(|e| Status::invalid_argument(format!("无效的群组ID: {}", e)))?;
+
+        let updated_by_id = req.updated_by_id.parse::<Uuid>()
+            .map_err(|e| Status::invalid_argument(format!("无效的操作者ID: {}", e)))?;
+
+        match self.group_repository.set_announcement(group_id, updated_by_id, req.announcement).await {
+            Ok(group) => {
+                // 获取成员数量
+                let member_count = self
+                    .group_repository
+                    .get_member_count(group_id)
+                    .await
+                    .unwrap_or_default();
+
+                info!("设置群公告成功: group_id={}, updated_by={}", group_id, updated_by_id);
+                Ok(Response::new(GroupResponse {
+                    group: Some(group.to_proto(member_count)),
+                }))
+            }
+            Err(e) => {
+                error!("设置群公告失败: {}", e);
+                if e.to_string().contains("没有权限") {
+                    Err(Status::permission_denied(e.to_string()))
+                } else if e.to_string().contains("不是群组成员") {
+                    Err(Status::not_found("用户不是群组成员"))
+                } else {
+                    Err(Status::internal("设置群公告失败"))
+                }
+            }
+        }
+    }
+
     // 检查用户是否在群组中
     async fn check_membership(
         &self,
@@ -378,14 +766,11 @@ impl GroupService for GroupServiceImpl {
             .map_err(|e| Status::invalid_argument(format!("无效的用户ID: {}", e)))?;
         
         match self.member_repository.check_membership(group_id, user_id).await {
-            Ok((is_member, role)) => {
+            Ok((is_member, role, is_muted)) => {
                 Ok(Response::new(CheckMembershipResponse {
                     is_member,
-                    role: if is_member {
-                        role.map(|r| r.into())
-                    } else {
-                        None
-                    },
+                    role: if is_member { role } else { None },
+                    is_muted,
                 }))
             }
             Err(e) => {
@@ -0,0 +1,11 @@
+//! 端到端集成测试支撑库
+//!
+//! 通过testcontainers启动Postgres/Redis/Consul，并以子进程方式拉起各服务
+//! 二进制和API网关，供tests/下的场景测试复用。
+//!
+//! 运行前提：
+//! 1. 本机有可用的Docker守护进程；
+//! 2. 先执行 `cargo build --workspace` 编译出各服务二进制；
+//! 3. 场景测试默认`#[ignore]`，通过 `cargo test -p integration-tests -- --ignored` 运行。
+
+pub mod fixtures;
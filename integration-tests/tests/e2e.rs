@@ -0,0 +1,119 @@
+//! 端到端场景测试
+//!
+//! 场景一：注册 -> 登录 -> 携带令牌通过网关访问需认证路由
+//!
+//! 运行方式（需要Docker）：
+//!   cargo build --workspace
+//!   cargo test -p integration-tests -- --ignored
+
+use std::time::Duration;
+
+use common::proto::auth::auth_service_client::AuthServiceClient;
+use common::proto::auth::CreateTokenRequest;
+use common::proto::user::user_service_client::UserServiceClient;
+use common::proto::user::{CreateUserRequest, VerifyPasswordRequest};
+use integration_tests::fixtures::{self, TestEnv};
+use reqwest::StatusCode;
+
+#[tokio::test]
+#[ignore = "需要Docker环境，且需先cargo build --workspace"]
+async fn register_login_authenticated_proxy() -> anyhow::Result<()> {
+    // 启动基础设施并初始化表结构
+    let env = TestEnv::start().await?;
+    env.apply_ddl().await?;
+
+    // 拉起auth/user服务和网关
+    let auth_port = fixtures::free_port()?;
+    let user_port = fixtures::free_port()?;
+    let gateway_port = fixtures::free_port()?;
+
+    let _auth = env.spawn_service("auth-service", auth_port)?;
+    let _user = env.spawn_service("user-service", user_port)?;
+    let _gateway = env.spawn_gateway(gateway_port)?;
+
+    let client = reqwest::Client::new();
+    fixtures::wait_http_ok(
+        &client,
+        &format!("http://127.0.0.1:{}/health", auth_port + 1),
+        30,
+    )
+    .await?;
+    fixtures::wait_http_ok(
+        &client,
+        &format!("http://127.0.0.1:{}/health", user_port + 1),
+        30,
+    )
+    .await?;
+    fixtures::wait_http_ok(
+        &client,
+        &format!("http://127.0.0.1:{}/health", gateway_port),
+        30,
+    )
+    .await?;
+
+    // 注册用户
+    let mut user_client =
+        UserServiceClient::connect(format!("http://127.0.0.1:{}", user_port)).await?;
+    let created = user_client
+        .create_user(CreateUserRequest {
+            username: "e2e_user".to_string(),
+            email: "e2e@example.com".to_string(),
+            password: "password123".to_string(),
+            nickname: "e2e".to_string(),
+            avatar_url: "".to_string(),
+        })
+        .await?
+        .into_inner();
+    let user = created.user.expect("注册应返回用户信息");
+
+    // 登录：验证密码后由auth-service签发令牌
+    let verified = user_client
+        .verify_password(VerifyPasswordRequest {
+            username: "e2e_user".to_string(),
+            password: "password123".to_string(),
+        })
+        .await?
+        .into_inner();
+    assert!(verified.valid, "正确密码应校验通过");
+
+    let mut auth_client =
+        AuthServiceClient::connect(format!("http://127.0.0.1:{}", auth_port)).await?;
+    let tokens = auth_client
+        .create_token(CreateTokenRequest {
+            user_id: user.id.clone(),
+            username: user.username.clone(),
+            roles: vec![],
+        })
+        .await?
+        .into_inner();
+    assert!(!tokens.access_token.is_empty());
+
+    // 不带令牌访问需认证路由应被拒绝
+    let url = format!("http://127.0.0.1:{}/api/users", gateway_port);
+    let resp = client.get(&url).send().await?;
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+    // 携带令牌访问：等待Consul健康检查通过后，网关应将请求代理到user-service
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(60);
+    loop {
+        let resp = client
+            .get(&url)
+            .bearer_auth(&tokens.access_token)
+            .send()
+            .await?;
+        let status = resp.status();
+        assert_ne!(status, StatusCode::UNAUTHORIZED, "有效令牌不应被拒绝");
+        if status.is_success() {
+            break;
+        }
+        // 服务实例尚未通过健康检查时网关返回503，继续等待
+        assert!(
+            tokio::time::Instant::now() < deadline,
+            "等待网关代理成功超时，最后状态: {}",
+            status
+        );
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,138 @@
+use std::io::{Read, Write};
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+/// 压缩帧的标志字节前缀。
+/// 为避免与bincode裸帧的首字节冲突，接收端在前缀之外
+/// 还要求紧随的gzip魔数（0x1f 0x8b）才按压缩帧处理。
+pub const COMPRESSED_FLAG: u8 = 0x01;
+
+/// gzip魔数
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// 编码出站帧
+///
+/// 压缩开启且载荷超过阈值时，gzip压缩后加COMPRESSED_FLAG前缀；
+/// 否则原样返回（与存量客户端保持兼容）。
+/// 压缩后反而变大（已压缩的图片/随机数据）时同样回退为裸帧。
+pub fn encode_frame(payload: Vec<u8>, compression: bool, threshold: usize) -> Vec<u8> {
+    if !compression || payload.len() <= threshold {
+        return payload;
+    }
+
+    let mut encoder = GzEncoder::new(Vec::with_capacity(payload.len() / 2 + 1), Compression::default());
+    if encoder.write_all(&payload).is_err() {
+        return payload;
+    }
+    let compressed = match encoder.finish() {
+        Ok(compressed) => compressed,
+        Err(_) => return payload,
+    };
+
+    if compressed.len() + 1 >= payload.len() {
+        return payload;
+    }
+
+    let mut frame = Vec::with_capacity(compressed.len() + 1);
+    frame.push(COMPRESSED_FLAG);
+    frame.extend_from_slice(&compressed);
+    frame
+}
+
+/// 解码入站帧
+///
+/// 检测COMPRESSED_FLAG前缀加gzip魔数并解压，其余帧原样返回
+pub fn decode_frame(frame: &[u8]) -> std::io::Result<Vec<u8>> {
+    if frame.len() > 3 && frame[0] == COMPRESSED_FLAG && frame[1..3] == GZIP_MAGIC {
+        let mut decoder = GzDecoder::new(&frame[1..]);
+        let mut payload = Vec::new();
+        decoder.read_to_end(&mut payload)?;
+        return Ok(payload);
+    }
+    Ok(frame.to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    /// 10KB的可压缩载荷（重复文本）
+    fn compressible_payload() -> Vec<u8> {
+        "大群消息带很多emoji🎉🎉🎉 ".repeat(300).into_bytes()
+    }
+
+    #[test]
+    fn test_roundtrip_over_threshold() {
+        let payload = compressible_payload();
+        let frame = encode_frame(payload.clone(), true, 1024);
+
+        assert_eq!(frame[0], COMPRESSED_FLAG);
+        assert!(frame.len() < payload.len());
+        assert_eq!(decode_frame(&frame).unwrap(), payload);
+    }
+
+    #[test]
+    fn test_small_or_disabled_frames_pass_through() {
+        let small = b"hi".to_vec();
+        assert_eq!(encode_frame(small.clone(), true, 1024), small);
+
+        let payload = compressible_payload();
+        assert_eq!(encode_frame(payload.clone(), false, 1024), payload);
+
+        // 裸帧解码原样返回
+        assert_eq!(decode_frame(&payload).unwrap(), payload);
+    }
+
+    #[test]
+    fn test_incompressible_payload_falls_back_to_raw() {
+        // 伪随机数据几乎不可压缩，应回退为裸帧
+        let mut payload = Vec::with_capacity(8192);
+        let mut x: u32 = 0x2545_f491;
+        for _ in 0..8192 {
+            x ^= x << 13;
+            x ^= x >> 17;
+            x ^= x << 5;
+            payload.push(x as u8);
+        }
+        assert_eq!(encode_frame(payload.clone(), true, 1024), payload);
+    }
+
+    /// 吞吐量基准：比较10KB载荷开启/关闭压缩的编码吞吐，
+    /// 人工评估CPU开销与带宽节省的取舍
+    #[test]
+    #[ignore = "基准测试，手动运行：cargo test -p msg-gateway -- --ignored bench"]
+    fn bench_compression_throughput_10kb() {
+        let payload = compressible_payload();
+        let rounds = 2000;
+
+        let start = Instant::now();
+        let mut raw_bytes = 0usize;
+        for _ in 0..rounds {
+            raw_bytes += encode_frame(payload.clone(), false, 1024).len();
+        }
+        let raw_elapsed = start.elapsed();
+
+        let start = Instant::now();
+        let mut compressed_bytes = 0usize;
+        for _ in 0..rounds {
+            compressed_bytes += encode_frame(payload.clone(), true, 1024).len();
+        }
+        let compressed_elapsed = start.elapsed();
+
+        let mib = |bytes: usize, secs: f64| bytes as f64 / 1024.0 / 1024.0 / secs;
+        println!(
+            "不压缩: {:.1} MiB/s（线上字节 {}）",
+            mib(rounds * payload.len(), raw_elapsed.as_secs_f64()),
+            raw_bytes
+        );
+        println!(
+            "压缩:   {:.1} MiB/s（线上字节 {}，压缩率 {:.1}%）",
+            mib(rounds * payload.len(), compressed_elapsed.as_secs_f64()),
+            compressed_bytes,
+            compressed_bytes as f64 * 100.0 / raw_bytes as f64
+        );
+    }
+}
@@ -1,4 +1,7 @@
+#![allow(clippy::result_large_err)]
+
 mod client;
+mod compression;
 mod manager;
 pub mod rpc;
 pub mod ws_server;
@@ -1,14 +1,12 @@
-use tracing::Level;
-
 use common::config::AppConfig;
 use msg_gateway::ws_server::WsServer;
 
 #[tokio::main]
 async fn main() {
-    tracing_subscriber::fmt()
-        .with_max_level(Level::DEBUG)
-        .init();
-    WsServer::start(AppConfig::from_file(Some("./config/config.yaml")).unwrap()).await
+    let config = AppConfig::from_file(Some("./config/config.yaml")).unwrap();
+    // 根据配置初始化日志，guard需持有到进程退出
+    let _log_guard = common::logging::init(&config.log);
+    WsServer::start(config).await
 }
 #[cfg(test)]
 mod tests {
@@ -12,7 +12,7 @@ use common::message::{
     ContentType, GroupMemSeq, Msg, MsgResponse, MsgType, PlatformType, SendMsgRequest,
 };
 use cache::Cache;
-use common::service_registry::LbWithServiceDiscovery;
+use tonic::transport::Channel;
 
 type UserID = String;
 /// client hub
@@ -24,14 +24,28 @@ pub struct Manager {
     tx: mpsc::Sender<Msg>,
     pub hub: Hub,
     pub cache: Arc<dyn Cache>,
-    pub chat_rpc: ChatServiceClient<LbWithServiceDiscovery>,
+    pub chat_rpc: ChatServiceClient<Channel>,
+    /// 是否压缩出站消息
+    compression: bool,
+    /// 压缩阈值（字节），仅压缩超过该大小的消息
+    compress_threshold: usize,
 }
 
 #[allow(dead_code)]
 impl Manager {
     pub async fn new(tx: mpsc::Sender<Msg>, config: &AppConfig) -> Self {
         let cache = cache::cache(config);
-        let chat_rpc = utils::get_rpc_client(config, config.rpc.chat.name.clone())
+        // 配置了rpc.chat.tls时以mTLS连接chat服务
+        let mut endpoint = tonic::transport::Endpoint::from_shared(config.rpc.chat.url())
+            .expect("chat rpc url invalid");
+        if let Some(tls) = &config.rpc.chat.tls {
+            let tls_config =
+                common::tls::client_tls_config(tls).expect("chat rpc tls config invalid");
+            endpoint = endpoint
+                .tls_config(tls_config)
+                .expect("chat rpc tls config invalid");
+        }
+        let chat_rpc = ChatServiceClient::connect(endpoint)
             .await
             .expect("chat rpc can't open");
         Manager {
@@ -39,9 +53,16 @@ impl Manager {
             hub: Arc::new(DashMap::new()),
             cache,
             chat_rpc,
+            compression: config.websocket.compression,
+            compress_threshold: config.websocket.compress_threshold_bytes,
         }
     }
 
+    /// 按压缩配置编码出站帧
+    fn encode_frame(&self, payload: Vec<u8>) -> Vec<u8> {
+        crate::compression::encode_frame(payload, self.compression, self.compress_threshold)
+    }
+
     pub async fn send_group(&self, obj_ids: Vec<GroupMemSeq>, mut msg: Msg) {
         self.send_to_self(&msg.send_id, &msg).await;
 
@@ -75,7 +96,7 @@ impl Manager {
                         return;
                     }
                 };
-                if let Err(e) = sender.send_binary(content).await {
+                if let Err(e) = sender.send_binary(self.encode_frame(content)).await {
                     error!("send to self error: {}", e)
                 }
             }
@@ -101,7 +122,7 @@ impl Manager {
                     }
                 };
                 if let Some(client) = clients.iter().next() {
-                    if let Err(e) = client.value().send_binary(content).await {
+                    if let Err(e) = client.value().send_binary(self.encode_frame(content)).await {
                         error!("send message error: {}", e);
                     }
                 }
@@ -114,6 +135,7 @@ impl Manager {
                         return;
                     }
                 };
+                let content = self.encode_frame(content);
                 let mut iter = clients.iter();
                 if let Some(first_client) = iter.next() {
                     if let Err(e) = first_client.value().send_binary(content.clone()).await {
@@ -130,12 +152,23 @@ impl Manager {
         }
     }
 
+    /// 当前连接数（所有用户全平台客户端之和）
+    pub fn connection_count(&self) -> usize {
+        self.hub.iter().map(|entry| entry.value().len()).sum()
+    }
+
+    /// 上报连接数指标
+    fn report_connection_count(&self) {
+        metrics::gauge!("ws_connection_count").set(self.connection_count() as f64);
+    }
+
     // register client
     pub async fn register(&mut self, id: String, client: Client) {
         self.hub
             .entry(id)
             .or_default()
             .insert(client.platform, client);
+        self.report_connection_count();
     }
 
     pub async fn unregister(&mut self, id: String, platform: PlatformType) {
@@ -150,6 +183,7 @@ impl Manager {
         if flag {
             self.hub.remove(&id);
         }
+        self.report_connection_count();
         debug!("unregister client: {:?}", id);
     }
 
@@ -4,11 +4,13 @@ use tonic::transport::Server;
 use tonic::{async_trait, Request, Response, Status};
 use tracing::{debug, info};
 
-use common::config::{Component, AppConfig};
+use common::config::AppConfig;
 use common::error::Error;
 use common::message::msg_service_server::MsgServiceServer;
 use common::message::{
-    msg_service_server::MsgService, SendGroupMsgRequest, SendMsgRequest, SendMsgResponse,
+    msg_service_server::MsgService, GetUnreadCountRequest, GetUnreadCountResponse,
+    MarkReadRequest, MarkReadResponse, Msg, MsgRead, MsgType, SendGroupMsgRequest, SendMsgRequest,
+    SendMsgResponse,
 };
 use common::service_registry::ServiceRegistry;
 use crate::manager::Manager;
@@ -34,11 +36,7 @@ impl MsgRpcService {
             "/health",
             "15s",
         ).await.map_err(|e| Error::Internal(e.to_string()))?;
-        info!("<ws> rpc service register to service register center");
-
-        // open health check
-        let health_service = HealthServer::new(HealthService::new());
-        info!("<ws> rpc service health check started");
+        info!("<ws> rpc service register to service register center, 服务ID: {}", service_id);
 
         let service = Self::new(manager);
         let svc = MsgServiceServer::new(service);
@@ -47,8 +45,15 @@ impl MsgRpcService {
             config.rpc.ws.rpc_server_url()
         );
 
-        Server::builder()
-            .add_service(health_service)
+        // 配置了rpc.ws.tls时启用mTLS
+        let mut builder = Server::builder();
+        if let Some(tls) = &config.rpc.ws.tls {
+            let tls_config = common::tls::server_tls_config(tls)?;
+            builder = builder
+                .tls_config(tls_config)
+                .map_err(|e| Error::Internal(e.to_string()))?;
+        }
+        builder
             .add_service(svc)
             .serve(config.rpc.ws.rpc_server_url().parse().unwrap())
             .await
@@ -102,4 +107,67 @@ impl MsgService for MsgRpcService {
         let response = Response::new(SendMsgResponse {});
         Ok(response)
     }
+
+    /// mark messages as read up to the given sequence,
+    /// then notify the conversation peer so the UI can update read indicators
+    async fn mark_read(
+        &self,
+        request: Request<MarkReadRequest>,
+    ) -> Result<Response<MarkReadResponse>, Status> {
+        let req = request.into_inner();
+        debug!("mark read request: {:?}", req);
+        if req.user_id.is_empty() || req.conversation_id.is_empty() {
+            return Err(Status::invalid_argument(
+                "user_id and conversation_id are required",
+            ));
+        }
+
+        // persist the read pointer; the key never expires
+        self.manager
+            .cache
+            .set_read_seq(&req.user_id, &req.conversation_id, req.last_read_seq)
+            .await?;
+
+        // broadcast READ_RECEIPT control message to the sender's active connections
+        let receipt = MsgRead {
+            msg_seq: vec![req.last_read_seq],
+            user_id: req.user_id.clone(),
+        };
+        let content = bincode::serialize(&receipt)
+            .map_err(|e| Status::internal(format!("serialize read receipt error: {}", e)))?;
+        let msg = Msg {
+            send_id: req.user_id,
+            receiver_id: req.conversation_id.clone(),
+            msg_type: MsgType::Read as i32,
+            content,
+            send_time: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_millis() as i64)
+                .unwrap_or_default(),
+            ..Default::default()
+        };
+        self.manager.send_single_msg(&req.conversation_id, &msg).await;
+
+        Ok(Response::new(MarkReadResponse {}))
+    }
+
+    /// query unread message count: max(0, latest_seq - read_seq)
+    async fn get_unread_count(
+        &self,
+        request: Request<GetUnreadCountRequest>,
+    ) -> Result<Response<GetUnreadCountResponse>, Status> {
+        let req = request.into_inner();
+        debug!("get unread count request: {:?}", req);
+
+        let latest_seq = self.manager.cache.get_seq(&req.user_id).await?;
+        let read_seq = self
+            .manager
+            .cache
+            .get_read_seq(&req.user_id, &req.conversation_id)
+            .await?;
+
+        Ok(Response::new(GetUnreadCountResponse {
+            unread_count: (latest_seq - read_seq).max(0),
+        }))
+    }
 }
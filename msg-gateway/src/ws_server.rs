@@ -1,8 +1,9 @@
-use std::borrow::Cow;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-use axum::extract::ws::CloseFrame;
+use axum::body::Bytes;
+use axum::extract::ws::{CloseFrame, Utf8Bytes};
 use axum::extract::{Path, State, WebSocketUpgrade};
 use axum::response::IntoResponse;
 use axum::routing::get;
@@ -14,18 +15,17 @@ use futures::{SinkExt, StreamExt};
 use jsonwebtoken::{decode, DecodingKey, Validation};
 use serde::{Deserialize, Serialize};
 use tokio::sync::{mpsc, RwLock};
-use tonic::transport::Channel;
 use tracing::{error, info, warn};
 
 use common::config::AppConfig;
 use common::error::Error;
 use common::message::{Msg, PlatformType};
+use common::service_registry::ServiceRegistry;
 
 use crate::client::Client;
 use crate::manager::Manager;
 use crate::rpc::MsgRpcService;
 
-pub const HEART_BEAT_INTERVAL: u64 = 30;
 pub const KNOCK_OFF_CODE: u16 = 4001;
 pub const UNAUTHORIZED_CODE: u16 = 4002;
 
@@ -33,6 +33,10 @@ pub const UNAUTHORIZED_CODE: u16 = 4002;
 pub struct AppState {
     manager: Manager,
     jwt_secret: String,
+    /// 心跳间隔：连接空闲超过该秒数后发送Ping
+    heartbeat_interval_secs: u64,
+    /// 心跳超时：超过该秒数未收到任何客户端帧则关闭连接
+    heartbeat_timeout_secs: u64,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -47,28 +51,28 @@ pub struct WsServer;
 impl WsServer {
     async fn register_service(config: &AppConfig) -> Result<(), Error> {
         // register service to service register center
-        let addr = format!(
-            "{}://{}:{}",
-            config.service_center.protocol, config.service_center.host, config.service_center.port
-        );
-        let channel = Channel::from_shared(addr).unwrap().connect().await.unwrap();
-        let mut client = ServiceRegistryClient::new(channel);
-        let service = ServiceInstance {
-            id: format!("{}-{}", utils::get_host_name()?, &config.websocket.name),
-            name: config.websocket.name.clone(),
-            address: config.websocket.host.clone(),
-            port: config.websocket.port as i32,
-            tags: config.websocket.tags.clone(),
-            version: "".to_string(),
-            metadata: Default::default(),
-            health_check: None,
-            status: 0,
-            scheme: Scheme::from(config.rpc.db.protocol.as_str()) as i32,
-        };
-        client.register_service(service).await.unwrap();
+        // 创建并注册到Consul
+        let service_registry = ServiceRegistry::from_env();
+        let service_id = service_registry
+            .register_service(
+                &config.websocket.name,
+                &config.websocket.host,
+                config.websocket.port as u32,
+                config.websocket.tags.clone(),
+                "/health",
+                "15s",
+            )
+            .await
+            .map_err(|e| Error::Internal(e.to_string()))?;
+        info!("websocket服务已注册到Consul, 服务ID: {}", service_id);
         Ok(())
     }
 
+    // 健康检查端点
+    async fn health_check() -> &'static str {
+        "OK"
+    }
+
     async fn test(State(state): State<AppState>) -> Result<String, Error> {
         let mut description = String::new();
 
@@ -98,15 +102,18 @@ impl WsServer {
         let app_state = AppState {
             manager: hub.clone(),
             jwt_secret: config.jwt.secret.clone(),
+            heartbeat_interval_secs: config.websocket.heartbeat_interval_secs,
+            heartbeat_timeout_secs: config.websocket.heartbeat_timeout_secs,
         };
 
         // run axum server
         let router = Router::new()
             .route(
-                "/ws/:user_id/conn/:pointer_id/:platform/:token",
+                "/ws/{user_id}/conn/{pointer_id}/{platform}/{token}",
                 get(Self::websocket_handler),
             )
             .route("/test", get(Self::test))
+            .route("/health", get(Self::health_check))
             .with_state(app_state);
         let addr = format!("{}:{}", config.websocket.host, config.websocket.port);
 
@@ -172,7 +179,7 @@ impl WsServer {
             if let Err(e) = ws_tx
                 .send(Message::Close(Some(CloseFrame {
                     code: UNAUTHORIZED_CODE,
-                    reason: Cow::Owned("knock off".to_string()),
+                    reason: Utf8Bytes::from("knock off"),
                 })))
                 .await
             {
@@ -192,21 +199,43 @@ impl WsServer {
         };
         hub.register(user_id.clone(), client).await;
 
-        // send ping message to client
+        // 心跳保活：空闲达到间隔后发送Ping，超时未收到任何客户端帧则关闭连接
+        let connected_at = Instant::now();
+        let last_activity = Arc::new(AtomicU64::new(0));
         let cloned_tx = shared_tx.clone();
+        let activity = last_activity.clone();
+        let heartbeat_interval = app_state.heartbeat_interval_secs;
+        let heartbeat_timeout = app_state.heartbeat_timeout_secs;
         let mut ping_task = tokio::spawn(async move {
             loop {
+                tokio::time::sleep(Duration::from_secs(heartbeat_interval)).await;
+                let idle = connected_at
+                    .elapsed()
+                    .as_secs()
+                    .saturating_sub(activity.load(Ordering::Relaxed));
+                // 超时未收到任何帧（含Pong），判定连接已死，NAT后静默断开的连接走这里清理
+                if idle >= heartbeat_timeout {
+                    warn!("heartbeat timeout after {}s idle, close connection", idle);
+                    if let Err(e) = cloned_tx.write().await.send(Message::Close(None)).await {
+                        error!("send heartbeat close error: {:?}", e);
+                    }
+                    // break this task, it will end this conn
+                    break;
+                }
+                // 活跃连接不发Ping，空闲满一个心跳间隔才探测
+                if idle < heartbeat_interval {
+                    continue;
+                }
                 if let Err(e) = cloned_tx
                     .write()
                     .await
-                    .send(Message::Ping(Vec::new()))
+                    .send(Message::Ping(Bytes::new()))
                     .await
                 {
                     error!("send ping error：{:?}", e);
                     // break this task, it will end this conn
                     break;
                 }
-                tokio::time::sleep(Duration::from_secs(HEART_BEAT_INTERVAL)).await;
             }
         });
 
@@ -221,7 +250,7 @@ impl WsServer {
                     .await
                     .send(Message::Close(Some(CloseFrame {
                         code: KNOCK_OFF_CODE,
-                        reason: Cow::Owned("knock off".to_string()),
+                        reason: Utf8Bytes::from("knock off"),
                     })))
                     .await
                 {
@@ -234,8 +263,11 @@ impl WsServer {
         let cloned_hub = hub.clone();
         let shared_tx = shared_tx.clone();
         // receive message from client
+        let rec_activity = last_activity.clone();
         let mut rec_task = tokio::spawn(async move {
             while let Some(Ok(msg)) = ws_rx.next().await {
+                // 收到任何帧都视为连接活跃（含Pong）
+                rec_activity.store(connected_at.elapsed().as_secs(), Ordering::Relaxed);
                 // 处理消息
                 match msg {
                     Message::Text(text) => {
@@ -254,7 +286,7 @@ impl WsServer {
                         if let Err(e) = shared_tx
                             .write()
                             .await
-                            .send(Message::Pong(Vec::new()))
+                            .send(Message::Pong(Bytes::new()))
                             .await
                         {
                             error!("reply ping error : {:?}", e);
@@ -271,9 +303,17 @@ impl WsServer {
                         break;
                     }
                     Message::Binary(b) => {
-                        let result = bincode::deserialize(&b);
+                        // 带COMPRESSED_FLAG前缀的压缩帧先解压
+                        let payload = match crate::compression::decode_frame(&b) {
+                            Ok(payload) => payload,
+                            Err(e) => {
+                                error!("decompress error: {:?}； source: {:?}", e, b);
+                                continue;
+                            }
+                        };
+                        let result = bincode::deserialize(&payload);
                         if result.is_err() {
-                            error!("deserialize error: {:?}； source: {:?}", result.err(), b);
+                            error!("deserialize error: {:?}； source: {:?}", result.err(), payload);
                             continue;
                         }
                         let msg: Msg = result.unwrap();
@@ -0,0 +1,77 @@
+//! 死信队列消费端
+//!
+//! 订阅`{topic}.dlq`，对每条死信递增`msg_server.dlq.messages`计数并
+//! 打印失败上下文头，供运维对DLQ增长配置告警
+
+use rdkafka::consumer::{CommitMode, Consumer, StreamConsumer};
+use rdkafka::message::{BorrowedMessage, Headers};
+use rdkafka::{ClientConfig, Message};
+use tracing::{error, info, warn};
+
+use common::config::AppConfig;
+
+#[tokio::main]
+async fn main() {
+    let config = AppConfig::from_file(Some("./config/config.yaml")).unwrap();
+    // 根据配置初始化日志，guard需持有到进程退出
+    let _log_guard = common::logging::init(&config.log);
+
+    let topic = msg_server::dlq::dlq_topic(&config.kafka.topic);
+    let consumer: StreamConsumer = ClientConfig::new()
+        // 独立消费组，不与主消费组的offset互相干扰
+        .set("group.id", format!("{}-dlq", config.kafka.group))
+        .set("bootstrap.servers", config.kafka.hosts.join(","))
+        .set("enable.auto.commit", "false")
+        .set(
+            "session.timeout.ms",
+            config.kafka.consumer.session_timeout.to_string(),
+        )
+        .set(
+            "socket.timeout.ms",
+            config.kafka.connect_timeout.to_string(),
+        )
+        .set("enable.partition.eof", "false")
+        .set(
+            "auto.offset.reset",
+            config.kafka.consumer.auto_offset_reset.clone(),
+        )
+        .create()
+        .expect("Consumer creation failed");
+
+    consumer
+        .subscribe(&[&topic])
+        .expect("Can't subscribe to dlq topic");
+    info!("死信队列消费端已启动, topic: {}", topic);
+
+    loop {
+        match consumer.recv().await {
+            Err(e) => error!("Kafka error: {}", e),
+            Ok(m) => {
+                metrics::counter!("msg_server.dlq.messages").increment(1);
+                warn!(
+                    "收到死信消息: original_topic={}, failure_reason={}, attempt_count={}, failed_at_epoch_ms={}",
+                    header_value(&m, "original_topic"),
+                    header_value(&m, "failure_reason"),
+                    header_value(&m, "attempt_count"),
+                    header_value(&m, "failed_at_epoch_ms"),
+                );
+                if let Err(e) = consumer.commit_message(&m, CommitMode::Async) {
+                    error!("Failed to commit message: {:?}", e);
+                }
+            }
+        }
+    }
+}
+
+/// 读取消息头的UTF-8值，缺失时返回"-"
+fn header_value(m: &BorrowedMessage<'_>, key: &str) -> String {
+    m.headers()
+        .and_then(|headers| {
+            headers
+                .iter()
+                .find(|h| h.key == key)
+                .and_then(|h| h.value)
+                .map(|v| String::from_utf8_lossy(v).into_owned())
+        })
+        .unwrap_or_else(|| "-".to_string())
+}
@@ -7,8 +7,10 @@ use tracing::{debug, error, info, warn};
 use common::config::AppConfig;
 use common::error::Error;
 use common::message::{GroupMemSeq, Msg, MsgRead, MsgType};
+use common::webhook::{WebhookDispatcher, WebhookEvent};
 use cache::Cache;
 
+use crate::db::{msg_rec_box_repo, DbRepo, MsgRecBoxRepo};
 use crate::pusher::{push_service, Pusher};
 
 /// message type: single, group, other
@@ -25,6 +27,8 @@ pub struct ConsumerService {
     pusher: Arc<dyn Pusher>,
     cache: Arc<dyn Cache>,
     seq_step: i32,
+    /// 出站Webhook分发器，未启用时为None
+    webhooks: Option<Arc<WebhookDispatcher>>,
 }
 
 impl ConsumerService {
@@ -65,6 +69,13 @@ impl ConsumerService {
         let cache = cache::cache(config);
         let msg_box = msg_rec_box_repo(config).await;
 
+        // 按配置启用出站Webhook
+        let webhooks = if config.webhook.enabled {
+            Some(WebhookDispatcher::start(config.webhook.clone()))
+        } else {
+            None
+        };
+
         Self {
             consumer,
             db,
@@ -72,6 +83,7 @@ impl ConsumerService {
             pusher,
             cache,
             seq_step,
+            webhooks,
         }
     }
 
@@ -131,6 +143,7 @@ impl ConsumerService {
             let db = self.db.clone();
             let msg_box = self.msg_box.clone();
             let to_db = tokio::spawn(async move {
+                // db写入失败仅记录日志，不阻塞消费，维持原有行为
                 if let Err(e) = Self::send_to_db(
                     db,
                     msg_box,
@@ -143,32 +156,50 @@ impl ConsumerService {
                 {
                     error!("failed to send message to db, error: {:?}", e);
                 }
+                Ok(())
             });
 
             tasks.push(to_db);
         }
 
         // send to pusher
+        // 群消息推送前过滤免打扰成员（收件箱仍保留完整成员列表）
+        let push_members = if msg_type == MsgType2::Group {
+            self.filter_muted_members(&msg.receiver_id, members).await
+        } else {
+            members
+        };
+        // 提前保留事件字段，msg随后被move进推送任务
+        let event_payload = serde_json::json!({
+            "server_id": msg.server_id,
+            "send_id": msg.send_id,
+            "receiver_id": msg.receiver_id,
+            "msg_type": msg.msg_type,
+            "seq": msg.seq,
+        });
+
         let pusher = self.pusher.clone();
+        // 推送失败向上传播：handle_msg返回错误后offset不会提交，
+        // 由Kafka重新投递该消息实现重试
         let to_pusher = tokio::spawn(async move {
             match msg_type {
-                MsgType2::Single => {
-                    if let Err(e) = pusher.push_single_msg(msg).await {
-                        error!("failed to send message to pusher, error: {:?}", e);
-                    }
-                }
-                MsgType2::Group => {
-                    if let Err(e) = pusher.push_group_msg(msg, members).await {
-                        error!("failed to send message to pusher, error: {:?}", e);
-                    }
-                }
+                MsgType2::Single => pusher.push_single_msg(msg).await,
+                MsgType2::Group => pusher.push_group_msg(msg, push_members).await,
             }
         });
         tasks.push(to_pusher);
 
-        futures::future::try_join_all(tasks)
+        for result in futures::future::try_join_all(tasks)
             .await
-            .map_err(|e| Error::Internal(e.to_string()))?;
+            .map_err(|e| Error::Internal(e.to_string()))?
+        {
+            result?;
+        }
+
+        // 推送成功后分发message.sent事件
+        if let Some(webhooks) = &self.webhooks {
+            webhooks.dispatch(WebhookEvent::new("message.sent", event_payload));
+        }
 
         Ok(())
     }
@@ -245,6 +276,26 @@ impl ConsumerService {
         }
     }
 
+    /// 过滤处于免打扰状态的成员，静音成员不推送在线消息
+    /// 查询失败时降级为不过滤，保证消息可达
+    async fn filter_muted_members(
+        &self,
+        group_id: &str,
+        members: Vec<GroupMemSeq>,
+    ) -> Vec<GroupMemSeq> {
+        match self.db.group.query_muted_members_id(group_id).await {
+            Ok(muted) if !muted.is_empty() => members
+                .into_iter()
+                .filter(|m| !muted.contains(&m.mem_id))
+                .collect(),
+            Ok(_) => members,
+            Err(err) => {
+                error!("failed to query muted members, skip filtering: {:?}", err);
+                members
+            }
+        }
+    }
+
     async fn handle_send_seq(&self, user_id: &str) -> Result<(), Error> {
         let send_seq = self.cache.get_send_seq(user_id).await?;
 
@@ -263,7 +314,8 @@ impl ConsumerService {
     }
 
     async fn handle_msg_read(&self, msg: Msg) -> Result<(), Error> {
-        let data: MsgRead = bincode::deserialize(&msg.content)?;
+        let data: MsgRead =
+            bincode::deserialize(&msg.content).map_err(|e| Error::Internal(e.to_string()))?;
 
         self.msg_box.msg_read(&data.user_id, &data.msg_seq).await?;
         Ok(())
@@ -0,0 +1,230 @@
+use sqlx::PgPool;
+
+use common::error::Error;
+use common::message::{ConversationSummary, Msg};
+
+/// 会话列表仓库
+///
+/// 从消息历史(messages表)按会话分组取每个会话的最新一条消息，
+/// 未读数来自收件箱(msg_rec_box表)。单聊会话以对端用户ID标识，
+/// 群聊会话以群组ID标识（发送群消息时receiver_id即群组ID）
+pub struct ConversationRepo {
+    pool: PgPool,
+}
+
+impl ConversationRepo {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// 查询用户最近的会话，按最新消息时间倒序
+    ///
+    /// before_timestamp大于0时只返回最新消息早于该时间戳（毫秒）的
+    /// 会话，供客户端向前翻页；单聊取双向消息，群聊取用户所在群的消息
+    pub async fn get_conversations(
+        &self,
+        user_id: &str,
+        limit: i64,
+        before_timestamp: i64,
+    ) -> Result<Vec<ConversationSummary>, Error> {
+        let rows = sqlx::query!(
+            r#"
+            WITH convs AS (
+                SELECT DISTINCT ON (conversation_id)
+                    conversation_id, is_group, server_id, local_id, send_id, receiver_id,
+                    group_id, msg_type, content_type, content, seq, send_seq, platform, send_time
+                FROM (
+                    SELECT
+                        CASE
+                            WHEN m.group_id IS NOT NULL THEN m.group_id
+                            WHEN m.send_id = $1 THEN m.receiver_id
+                            ELSE m.send_id
+                        END AS conversation_id,
+                        (m.group_id IS NOT NULL) AS is_group,
+                        m.server_id, m.local_id, m.send_id, m.receiver_id, m.group_id,
+                        m.msg_type, m.content_type, m.content, m.seq, m.send_seq,
+                        m.platform, m.send_time
+                    FROM messages m
+                    WHERE (m.group_id IS NULL AND (m.send_id = $1 OR m.receiver_id = $1))
+                       OR (m.group_id IS NOT NULL AND EXISTS (
+                             SELECT 1 FROM group_members gm
+                             WHERE gm.group_id = m.group_id AND gm.user_id = $1))
+                ) t
+                ORDER BY conversation_id, send_time DESC
+            )
+            SELECT
+                c.conversation_id AS "conversation_id!",
+                c.is_group AS "is_group!",
+                c.server_id, c.local_id, c.send_id, c.receiver_id, c.group_id,
+                c.msg_type, c.content_type, c.content, c.seq, c.send_seq,
+                c.platform, c.send_time,
+                COALESCE(u.unread, 0) AS "unread_count!"
+            FROM convs c
+            LEFT JOIN (
+                SELECT COALESCE(m2.group_id, b.send_id) AS conversation_id, COUNT(*) AS unread
+                FROM msg_rec_box b
+                JOIN messages m2 ON m2.server_id = b.server_id
+                WHERE b.user_id = $1 AND b.is_read = FALSE
+                GROUP BY 1
+            ) u ON u.conversation_id = c.conversation_id
+            WHERE ($3::bigint <= 0 OR c.send_time < $3::bigint)
+            ORDER BY c.send_time DESC
+            LIMIT $2
+            "#,
+            user_id,
+            limit,
+            before_timestamp
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| ConversationSummary {
+                conversation_id: row.conversation_id,
+                conversation_type: if row.is_group { 1 } else { 0 },
+                last_msg: Some(Msg {
+                    send_id: row.send_id,
+                    receiver_id: row.receiver_id,
+                    local_id: row.local_id.unwrap_or_default(),
+                    server_id: row.server_id,
+                    send_time: row.send_time,
+                    seq: row.seq,
+                    msg_type: row.msg_type,
+                    content_type: row.content_type,
+                    content: row.content.unwrap_or_default(),
+                    group_id: row.group_id.unwrap_or_default(),
+                    platform: row.platform,
+                    send_seq: row.send_seq,
+                    ..Default::default()
+                }),
+                unread_count: row.unread_count,
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    async fn test_pool() -> PgPool {
+        let url = std::env::var("DATABASE_URL").expect("测试需要DATABASE_URL");
+        PgPool::connect(&url).await.expect("无法连接测试数据库")
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn insert_message(
+        pool: &PgPool,
+        send_id: &str,
+        receiver_id: &str,
+        group_id: Option<&str>,
+        send_time: i64,
+    ) -> String {
+        let server_id = Uuid::new_v4().to_string();
+        sqlx::query(
+            "INSERT INTO messages (server_id, send_id, receiver_id, group_id, msg_type, content_type, content, send_time)
+             VALUES ($1, $2, $3, $4, 0, 1, $5, $6)",
+        )
+        .bind(&server_id)
+        .bind(send_id)
+        .bind(receiver_id)
+        .bind(group_id)
+        .bind(b"hi".to_vec())
+        .bind(send_time)
+        .execute(pool)
+        .await
+        .unwrap();
+        server_id
+    }
+
+    async fn insert_inbox(pool: &PgPool, user_id: &str, server_id: &str, send_id: &str, seq: i64) {
+        sqlx::query(
+            "INSERT INTO msg_rec_box (user_id, seq, server_id, send_id, msg_type, content_type, content, send_time)
+             VALUES ($1, $2, $3, $4, 0, 1, $5, 0)",
+        )
+        .bind(user_id)
+        .bind(seq)
+        .bind(server_id)
+        .bind(send_id)
+        .bind(b"hi".to_vec())
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    async fn cleanup(pool: &PgPool, user_id: &str, peer: &str) {
+        sqlx::query("DELETE FROM msg_rec_box WHERE user_id = $1")
+            .bind(user_id)
+            .execute(pool)
+            .await
+            .unwrap();
+        sqlx::query("DELETE FROM messages WHERE send_id IN ($1, $2) OR receiver_id IN ($1, $2)")
+            .bind(user_id)
+            .bind(peer)
+            .execute(pool)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore = "需要DATABASE_URL指向的PostgreSQL"]
+    async fn test_conversations_latest_message_and_unread_count() {
+        let pool = test_pool().await;
+        let repo = ConversationRepo::new(pool.clone());
+        let user = Uuid::new_v4().to_string();
+        let peer = Uuid::new_v4().to_string();
+
+        // 同一单聊会话的三条消息：两条对端发来（未读），一条自己发出
+        let m1 = insert_message(&pool, &peer, &user, None, 1000).await;
+        insert_message(&pool, &user, &peer, None, 2000).await;
+        let m3 = insert_message(&pool, &peer, &user, None, 3000).await;
+        insert_inbox(&pool, &user, &m1, &peer, 1).await;
+        insert_inbox(&pool, &user, &m3, &peer, 2).await;
+
+        let convs = repo.get_conversations(&user, 20, 0).await.unwrap();
+        assert_eq!(convs.len(), 1);
+        let conv = &convs[0];
+        // 单聊会话以对端标识，取最新一条消息，未读数只统计收件箱未读
+        assert_eq!(conv.conversation_id, peer);
+        assert_eq!(conv.conversation_type, 0);
+        assert_eq!(conv.last_msg.as_ref().unwrap().send_time, 3000);
+        assert_eq!(conv.unread_count, 2);
+
+        // before_timestamp翻页：只返回最新消息早于游标的会话
+        let convs = repo.get_conversations(&user, 20, 3001).await.unwrap();
+        assert_eq!(convs.len(), 1);
+        let convs = repo.get_conversations(&user, 20, 3000).await.unwrap();
+        assert!(convs.is_empty());
+
+        cleanup(&pool, &user, &peer).await;
+    }
+
+    #[tokio::test]
+    #[ignore = "需要DATABASE_URL指向的PostgreSQL"]
+    async fn test_conversations_ordered_and_limited() {
+        let pool = test_pool().await;
+        let repo = ConversationRepo::new(pool.clone());
+        let user = Uuid::new_v4().to_string();
+        let peer_old = Uuid::new_v4().to_string();
+        let peer_new = Uuid::new_v4().to_string();
+
+        insert_message(&pool, &peer_old, &user, None, 1000).await;
+        insert_message(&pool, &peer_new, &user, None, 2000).await;
+
+        // 按最新消息时间倒序
+        let convs = repo.get_conversations(&user, 20, 0).await.unwrap();
+        assert_eq!(convs.len(), 2);
+        assert_eq!(convs[0].conversation_id, peer_new);
+        assert_eq!(convs[1].conversation_id, peer_old);
+
+        // limit只保留最近的N个会话
+        let convs = repo.get_conversations(&user, 1, 0).await.unwrap();
+        assert_eq!(convs.len(), 1);
+        assert_eq!(convs[0].conversation_id, peer_new);
+
+        cleanup(&pool, &user, &peer_old).await;
+        cleanup(&pool, &user, &peer_new).await;
+    }
+}
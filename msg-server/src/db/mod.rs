@@ -0,0 +1,72 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use sqlx::postgres::PgPoolOptions;
+use sqlx::PgPool;
+
+use common::config::AppConfig;
+use common::error::Error;
+use common::message::{GroupMemSeq, Msg};
+
+mod conversation;
+mod msg_rec_box;
+mod privacy;
+mod repo;
+
+pub use conversation::ConversationRepo;
+pub use msg_rec_box::PgMsgRecBox;
+pub use privacy::PrivacyRepo;
+pub use repo::{GroupDbRepo, MsgStoreRepo, SeqRepo};
+
+/// 消息收件箱，按接收者保存每条消息的副本
+#[async_trait]
+pub trait MsgRecBoxRepo: Send + Sync {
+    /// 保存单聊消息到收件箱
+    async fn save_message(&self, message: &Msg) -> Result<(), Error>;
+
+    /// 保存群聊消息到每个成员的收件箱
+    async fn save_group_msg(&self, message: Msg, members: Vec<GroupMemSeq>) -> Result<(), Error>;
+
+    /// 根据服务端消息ID删除消息
+    async fn delete_message(&self, message_id: &str) -> Result<(), Error>;
+
+    /// 标记消息已读
+    async fn msg_read(&self, user_id: &str, msg_seq: &[i64]) -> Result<(), Error>;
+}
+
+/// 消息服务数据库仓库集合
+pub struct DbRepo {
+    pub msg: MsgStoreRepo,
+    pub seq: SeqRepo,
+    pub group: GroupDbRepo,
+}
+
+impl DbRepo {
+    pub async fn new(config: &AppConfig) -> Self {
+        let pool = PgPoolOptions::new()
+            .max_connections(5)
+            .connect(&config.database.url())
+            .await
+            .expect("无法连接到PostgreSQL数据库");
+
+        Self::with_pool(pool, config.redis.seq_step)
+    }
+
+    pub fn with_pool(pool: PgPool, seq_step: i32) -> Self {
+        Self {
+            msg: MsgStoreRepo::new(pool.clone()),
+            seq: SeqRepo::new(pool.clone(), seq_step),
+            group: GroupDbRepo::new(pool),
+        }
+    }
+}
+
+/// 创建消息收件箱仓库
+pub async fn msg_rec_box_repo(config: &AppConfig) -> Arc<dyn MsgRecBoxRepo> {
+    let pool = PgPoolOptions::new()
+        .max_connections(5)
+        .connect(&config.database.url())
+        .await
+        .expect("无法连接到PostgreSQL数据库");
+    Arc::new(PgMsgRecBox::new(pool))
+}
@@ -0,0 +1,96 @@
+use async_trait::async_trait;
+use sqlx::PgPool;
+
+use common::error::Error;
+use common::message::{GroupMemSeq, Msg};
+
+use super::MsgRecBoxRepo;
+
+/// 基于PostgreSQL的消息收件箱实现
+pub struct PgMsgRecBox {
+    pool: PgPool,
+}
+
+impl PgMsgRecBox {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl MsgRecBoxRepo for PgMsgRecBox {
+    async fn save_message(&self, message: &Msg) -> Result<(), Error> {
+        sqlx::query!(
+            r#"
+            INSERT INTO msg_rec_box (user_id, seq, server_id, send_id, msg_type, content_type, content, send_time)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            ON CONFLICT (user_id, seq) DO NOTHING
+            "#,
+            message.receiver_id,
+            message.seq,
+            message.server_id,
+            message.send_id,
+            message.msg_type,
+            message.content_type,
+            message.content,
+            message.send_time
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn save_group_msg(&self, message: Msg, members: Vec<GroupMemSeq>) -> Result<(), Error> {
+        let user_ids: Vec<String> = members.iter().map(|m| m.mem_id.clone()).collect();
+        let seqs: Vec<i64> = members.iter().map(|m| m.cur_seq).collect();
+
+        sqlx::query!(
+            r#"
+            INSERT INTO msg_rec_box (user_id, seq, server_id, send_id, msg_type, content_type, content, send_time)
+            SELECT m.user_id, m.seq, $3, $4, $5, $6, $7, $8
+            FROM UNNEST($1::varchar[], $2::bigint[]) AS m(user_id, seq)
+            ON CONFLICT (user_id, seq) DO NOTHING
+            "#,
+            &user_ids,
+            &seqs,
+            message.server_id,
+            message.send_id,
+            message.msg_type,
+            message.content_type,
+            message.content,
+            message.send_time
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn delete_message(&self, message_id: &str) -> Result<(), Error> {
+        sqlx::query!(
+            r#"
+            DELETE FROM msg_rec_box WHERE server_id = $1
+            "#,
+            message_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn msg_read(&self, user_id: &str, msg_seq: &[i64]) -> Result<(), Error> {
+        sqlx::query!(
+            r#"
+            UPDATE msg_rec_box SET is_read = TRUE WHERE user_id = $1 AND seq = ANY($2)
+            "#,
+            user_id,
+            msg_seq
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
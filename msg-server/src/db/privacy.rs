@@ -0,0 +1,161 @@
+use sqlx::PgPool;
+
+use common::error::Error;
+
+/// 单聊隐私检查仓库
+///
+/// 读取接收方的msg_privacy设置（users.msg_privacy，见docs/250828_msg_privacy_DDL.sql），
+/// 在消息投递入口处决定是否放行：
+/// EVERYONE放行所有人，FRIENDS仅放行已接受的好友，NOBODY拒绝所有人
+pub struct PrivacyRepo {
+    pool: PgPool,
+}
+
+impl PrivacyRepo {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// 判断sender是否允许向receiver投递单聊消息
+    ///
+    /// 接收方不存在时放行，由后续存储环节处理；
+    /// 拒绝的结果不应向发送方透露具体隐私配置
+    pub async fn can_deliver_single_msg(
+        &self,
+        sender_id: &str,
+        receiver_id: &str,
+    ) -> Result<bool, Error> {
+        let privacy = sqlx::query_scalar!(
+            r#"
+            SELECT msg_privacy FROM users WHERE id = $1
+            "#,
+            receiver_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        match privacy.as_deref() {
+            None | Some("EVERYONE") => Ok(true),
+            Some("NOBODY") => Ok(false),
+            // FRIENDS：仅已接受的好友可以投递，方向不限
+            _ => {
+                let accepted = sqlx::query_scalar!(
+                    r#"
+                    SELECT COUNT(*) as "count!"
+                    FROM friendships
+                    WHERE ((user_id = $1 AND friend_id = $2) OR (user_id = $2 AND friend_id = $1))
+                      AND status = 'ACCEPTED'
+                    "#,
+                    sender_id,
+                    receiver_id
+                )
+                .fetch_one(&self.pool)
+                .await?;
+                Ok(accepted > 0)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    async fn test_pool() -> PgPool {
+        let url = std::env::var("DATABASE_URL").expect("测试需要DATABASE_URL");
+        PgPool::connect(&url).await.expect("无法连接测试数据库")
+    }
+
+    async fn insert_user(pool: &PgPool, id: &str) {
+        sqlx::query(
+            "INSERT INTO users (id, username, email, password) VALUES ($1, $1, $1 || '@test.local', 'pw')",
+        )
+        .bind(id)
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    async fn set_privacy(pool: &PgPool, id: &str, privacy: &str) {
+        sqlx::query("UPDATE users SET msg_privacy = $1 WHERE id = $2")
+            .bind(privacy)
+            .bind(id)
+            .execute(pool)
+            .await
+            .unwrap();
+    }
+
+    async fn delete_user(pool: &PgPool, id: &str) {
+        sqlx::query("DELETE FROM users WHERE id = $1")
+            .bind(id)
+            .execute(pool)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore = "需要DATABASE_URL指向的PostgreSQL"]
+    async fn test_privacy_levels_control_non_friend_delivery() {
+        let pool = test_pool().await;
+        let repo = PrivacyRepo::new(pool.clone());
+        let stranger = Uuid::new_v4().to_string();
+        let receiver = Uuid::new_v4().to_string();
+        insert_user(&pool, &stranger).await;
+        insert_user(&pool, &receiver).await;
+
+        // 默认EVERYONE：陌生人可以投递
+        assert!(repo.can_deliver_single_msg(&stranger, &receiver).await.unwrap());
+
+        // FRIENDS：陌生人被拒绝
+        set_privacy(&pool, &receiver, "FRIENDS").await;
+        assert!(!repo.can_deliver_single_msg(&stranger, &receiver).await.unwrap());
+
+        // NOBODY：陌生人被拒绝
+        set_privacy(&pool, &receiver, "NOBODY").await;
+        assert!(!repo.can_deliver_single_msg(&stranger, &receiver).await.unwrap());
+
+        delete_user(&pool, &stranger).await;
+        delete_user(&pool, &receiver).await;
+    }
+
+    #[tokio::test]
+    #[ignore = "需要DATABASE_URL指向的PostgreSQL"]
+    async fn test_friends_only_allows_accepted_friend_either_direction() {
+        let pool = test_pool().await;
+        let repo = PrivacyRepo::new(pool.clone());
+        let friend = Uuid::new_v4().to_string();
+        let receiver = Uuid::new_v4().to_string();
+        insert_user(&pool, &friend).await;
+        insert_user(&pool, &receiver).await;
+        set_privacy(&pool, &receiver, "FRIENDS").await;
+
+        // 好友关系存在但未接受时仍然拒绝
+        sqlx::query(
+            "INSERT INTO friendships (id, user_id, friend_id, status) VALUES ($1, $2, $3, 'PENDING')",
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(&receiver)
+        .bind(&friend)
+        .execute(&pool)
+        .await
+        .unwrap();
+        assert!(!repo.can_deliver_single_msg(&friend, &receiver).await.unwrap());
+
+        // 接受后放行，发送方在friend_id一侧也能命中
+        sqlx::query("UPDATE friendships SET status = 'ACCEPTED' WHERE user_id = $1 AND friend_id = $2")
+            .bind(&receiver)
+            .bind(&friend)
+            .execute(&pool)
+            .await
+            .unwrap();
+        assert!(repo.can_deliver_single_msg(&friend, &receiver).await.unwrap());
+
+        // NOBODY优先于好友关系
+        set_privacy(&pool, &receiver, "NOBODY").await;
+        assert!(!repo.can_deliver_single_msg(&friend, &receiver).await.unwrap());
+
+        delete_user(&pool, &friend).await;
+        delete_user(&pool, &receiver).await;
+    }
+}
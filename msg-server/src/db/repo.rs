@@ -0,0 +1,134 @@
+use sqlx::PgPool;
+
+use common::error::Error;
+use common::message::Msg;
+
+/// 消息历史仓库，保存消息到messages表
+pub struct MsgStoreRepo {
+    pool: PgPool,
+}
+
+impl MsgStoreRepo {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    // 保存消息历史
+    pub async fn save_message(&self, message: Msg) -> Result<(), Error> {
+        sqlx::query!(
+            r#"
+            INSERT INTO messages (server_id, local_id, send_id, receiver_id, group_id,
+                                  msg_type, content_type, content, seq, send_seq, platform, send_time)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+            ON CONFLICT (server_id) DO NOTHING
+            "#,
+            message.server_id,
+            message.local_id,
+            message.send_id,
+            message.receiver_id,
+            message.group_id,
+            message.msg_type,
+            message.content_type,
+            message.content,
+            message.seq,
+            message.send_seq,
+            message.platform,
+            message.send_time
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
+
+/// 用户序列号仓库，持久化max_seq到sequence表
+pub struct SeqRepo {
+    pool: PgPool,
+    seq_step: i64,
+}
+
+impl SeqRepo {
+    pub fn new(pool: PgPool, seq_step: i32) -> Self {
+        Self {
+            pool,
+            seq_step: seq_step as i64,
+        }
+    }
+
+    // 将用户的max_seq向前推进一个步长
+    pub async fn save_max_seq(&self, user_id: &str) -> Result<(), Error> {
+        sqlx::query!(
+            r#"
+            INSERT INTO sequence (user_id, max_seq)
+            VALUES ($1, $2)
+            ON CONFLICT (user_id)
+            DO UPDATE SET max_seq = sequence.max_seq + $2, updated_at = CURRENT_TIMESTAMP
+            "#,
+            user_id,
+            self.seq_step
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    // 批量推进max_seq
+    pub async fn save_max_seq_batch(&self, user_ids: &[String]) -> Result<(), Error> {
+        sqlx::query!(
+            r#"
+            INSERT INTO sequence (user_id, max_seq)
+            SELECT uid, $2 FROM UNNEST($1::varchar[]) AS uid
+            ON CONFLICT (user_id)
+            DO UPDATE SET max_seq = sequence.max_seq + $2, updated_at = CURRENT_TIMESTAMP
+            "#,
+            user_ids,
+            self.seq_step
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
+
+/// 群组成员查询仓库(降级路径: 缓存未命中时从数据库查询)
+pub struct GroupDbRepo {
+    pool: PgPool,
+}
+
+impl GroupDbRepo {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    // 查询群组全部成员ID
+    pub async fn query_group_members_id(&self, group_id: &str) -> Result<Vec<String>, Error> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT user_id FROM group_members WHERE group_id = $1
+            "#,
+            group_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|row| row.user_id).collect())
+    }
+
+    // 查询当前处于免打扰状态的成员ID
+    pub async fn query_muted_members_id(&self, group_id: &str) -> Result<Vec<String>, Error> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT user_id FROM group_members
+            WHERE group_id = $1 AND muted_until > (NOW() AT TIME ZONE 'UTC')
+            "#,
+            group_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|row| row.user_id).collect())
+    }
+}
@@ -0,0 +1,84 @@
+use std::time::Duration;
+
+use rdkafka::message::{Header, OwnedHeaders};
+use rdkafka::producer::{FutureProducer, FutureRecord};
+use tracing::{error, info};
+
+/// 死信topic后缀，死信topic名为`{原topic}.dlq`
+pub const DLQ_TOPIC_SUFFIX: &str = ".dlq";
+
+/// 根据原topic得到对应的死信topic名
+pub fn dlq_topic(topic: &str) -> String {
+    format!("{}{}", topic, DLQ_TOPIC_SUFFIX)
+}
+
+/// Kafka死信生产者
+///
+/// 消息在重试耗尽后写入`{topic}.dlq`，payload保持原样以便人工回放；
+/// 失败上下文通过消息头携带：original_topic、failure_reason、
+/// attempt_count、failed_at_epoch_ms
+pub struct KafkaDeadLetterProducer {
+    kafka: FutureProducer,
+    topic: String,
+}
+
+impl KafkaDeadLetterProducer {
+    /// 复用既有生产者连接，写入original_topic对应的死信topic
+    pub fn new(kafka: FutureProducer, original_topic: &str) -> Self {
+        Self {
+            kafka,
+            topic: dlq_topic(original_topic),
+        }
+    }
+
+    /// 将投递失败的消息写入死信topic
+    ///
+    /// 死信写入本身失败时只记录日志——此时Kafka大概率整体不可用，
+    /// 没有更下游的兜底可用
+    pub async fn publish(
+        &self,
+        payload: &str,
+        original_topic: &str,
+        failure_reason: &str,
+        attempt_count: u32,
+    ) {
+        let attempt_count = attempt_count.to_string();
+        let failed_at_epoch_ms = chrono::Utc::now().timestamp_millis().to_string();
+        let headers = OwnedHeaders::new()
+            .insert(Header {
+                key: "original_topic",
+                value: Some(original_topic),
+            })
+            .insert(Header {
+                key: "failure_reason",
+                value: Some(failure_reason),
+            })
+            .insert(Header {
+                key: "attempt_count",
+                value: Some(&attempt_count),
+            })
+            .insert(Header {
+                key: "failed_at_epoch_ms",
+                value: Some(&failed_at_epoch_ms),
+            });
+
+        let record: FutureRecord<String, str> = FutureRecord::to(&self.topic)
+            .payload(payload)
+            .headers(headers);
+
+        match self.kafka.send(record, Duration::from_secs(0)).await {
+            Ok(_) => info!("消息已写入死信队列: topic={}", self.topic),
+            Err((err, _)) => error!("写入死信队列失败，消息将丢失: {:?}", err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dlq_topic_appends_suffix() {
+        assert_eq!(dlq_topic("chat"), "chat.dlq");
+    }
+}
@@ -3,6 +3,8 @@ use consumer::ConsumerService;
 use productor::ChatRpcService;
 
 pub mod consumer;
+pub mod db;
+pub mod dlq;
 pub mod productor;
 mod pusher;
 
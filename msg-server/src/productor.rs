@@ -7,21 +7,64 @@ use rdkafka::client::DefaultClientContext;
 use rdkafka::error::KafkaError;
 use rdkafka::producer::{FutureProducer, FutureRecord};
 use rdkafka::ClientConfig;
+use sqlx::postgres::PgPoolOptions;
 use tonic::transport::Server;
 use tracing::{error, info};
 
-use common::config::{Component, AppConfig};
+use common::config::AppConfig;
+use common::service_registry::ServiceRegistry;
 use common::message::chat_service_server::{ChatService, ChatServiceServer};
-use common::message::{MsgResponse, MsgType, SendMsgRequest};
+use common::message::{
+    GetConversationsRequest, GetConversationsResponse, Msg, MsgResponse, MsgType, SendMsgRequest,
+};
+use common::utils::SequenceGenerator;
+
+use crate::db::{ConversationRepo, PrivacyRepo};
+use crate::dlq::KafkaDeadLetterProducer;
+
+/// 隐私拒绝时返回给发送方的通用失败，不暴露接收方的隐私配置
+const MSG_REJECTED_ERR: &str = "消息发送失败";
+
+/// 会话列表单次返回的默认与最大会话数
+const DEFAULT_CONVERSATION_LIMIT: i32 = 20;
+const MAX_CONVERSATION_LIMIT: i32 = 100;
 
 pub struct ChatRpcService {
     kafka: FutureProducer,
     topic: String,
+    privacy: PrivacyRepo,
+    /// 会话列表查询仓库
+    conversations: ConversationRepo,
+    /// 重试耗尽后兜底的死信生产者
+    dlq: KafkaDeadLetterProducer,
+    /// 会话序列号生成器（Redis预取区间）
+    seq: SequenceGenerator,
+    /// 应用层投递重试次数与间隔（毫秒），来自kafka.producer配置
+    max_retry: u32,
+    retry_interval: u64,
 }
 
 impl ChatRpcService {
-    pub fn new(kafka: FutureProducer, topic: String) -> Self {
-        Self { kafka, topic }
+    pub fn new(
+        kafka: FutureProducer,
+        topic: String,
+        privacy: PrivacyRepo,
+        conversations: ConversationRepo,
+        seq: SequenceGenerator,
+        max_retry: u32,
+        retry_interval: u64,
+    ) -> Self {
+        let dlq = KafkaDeadLetterProducer::new(kafka.clone(), &topic);
+        Self {
+            kafka,
+            topic,
+            privacy,
+            conversations,
+            dlq,
+            seq,
+            max_retry,
+            retry_interval,
+        }
     }
     pub async fn start(config: &AppConfig) {
         let broker = config.kafka.hosts.join(",");
@@ -50,25 +93,66 @@ impl ChatRpcService {
             .await
             .expect("Topic creation error");
 
+        // 死信topic随主topic一并确保存在
+        Self::ensure_topic_exists(
+            &crate::dlq::dlq_topic(&config.kafka.topic),
+            &broker,
+            config.kafka.connect_timeout,
+        )
+        .await
+        .expect("DLQ topic creation error");
+
         // register service
-        utils::register_service(config, Component::MessageServer)
+        // 创建并注册到Consul
+        let service_registry = ServiceRegistry::from_env();
+        let service_id = service_registry
+            .register_service(
+                &config.rpc.chat.name,
+                &config.rpc.chat.host,
+                config.rpc.chat.port as u32,
+                config.rpc.chat.tags.clone(),
+                "/health",
+                "15s",
+            )
             .await
             .expect("Service register error");
-        info!("<chat> rpc service register to service register center");
+        info!("<chat> rpc service register to service register center, 服务ID: {}", service_id);
 
-        // health check
-        let health_service = HealthServer::new(HealthService::new());
-        info!("<chat> rpc service health check started");
+        let pool = PgPoolOptions::new()
+            .max_connections(5)
+            .connect(&config.database.url())
+            .await
+            .expect("无法连接到PostgreSQL数据库");
+
+        let redis_client =
+            redis::Client::open(config.redis.url()).expect("无法创建Redis客户端");
+        let seq = SequenceGenerator::new(redis_client, config.redis.seq_step);
 
-        let chat_rpc = Self::new(producer, config.kafka.topic.clone());
+        let chat_rpc = Self::new(
+            producer,
+            config.kafka.topic.clone(),
+            PrivacyRepo::new(pool.clone()),
+            ConversationRepo::new(pool),
+            seq,
+            config.kafka.producer.max_retry,
+            config.kafka.producer.retry_interval,
+        );
         let service = ChatServiceServer::new(chat_rpc);
         info!(
             "<chat> rpc service started at {}",
             config.rpc.chat.rpc_server_url()
         );
 
-        Server::builder()
-            .add_service(health_service)
+        // 配置了rpc.chat.tls时启用mTLS
+        let mut builder = Server::builder();
+        if let Some(tls) = &config.rpc.chat.tls {
+            let tls_config =
+                common::tls::server_tls_config(tls).expect("chat rpc tls config invalid");
+            builder = builder
+                .tls_config(tls_config)
+                .expect("chat rpc tls config invalid");
+        }
+        builder
             .add_service(service)
             .serve(config.rpc.chat.rpc_server_url().parse().unwrap())
             .await
@@ -78,7 +162,7 @@ impl ChatRpcService {
     async fn ensure_topic_exists(
         topic_name: &str,
         brokers: &str,
-        timeout: u16,
+        timeout: u64,
     ) -> Result<(), KafkaError> {
         // Create Kafka AdminClient
         let admin_client: AdminClient<DefaultClientContext> = ClientConfig::new()
@@ -127,6 +211,33 @@ impl ChatService for ChatRpcService {
             .message
             .ok_or(tonic::Status::invalid_argument("message is empty"))?;
 
+        // 单聊消息先过接收方隐私检查，拒绝时对发送方只返回通用失败
+        if msg.msg_type == MsgType::SingleMsg as i32 {
+            let allowed = match self
+                .privacy
+                .can_deliver_single_msg(&msg.send_id, &msg.receiver_id)
+                .await
+            {
+                Ok(allowed) => allowed,
+                Err(err) => {
+                    error!("隐私检查失败: {:?}", err);
+                    false
+                }
+            };
+            if !allowed {
+                info!(
+                    "单聊消息被接收方隐私设置拒绝: {} -> {}",
+                    msg.send_id, msg.receiver_id
+                );
+                return Ok(tonic::Response::new(MsgResponse {
+                    local_id: msg.local_id,
+                    server_id: String::new(),
+                    send_time: 0,
+                    err: MSG_REJECTED_ERR.to_string(),
+                }));
+            }
+        }
+
         // generate msg id
         if !(msg.msg_type == MsgType::GroupDismissOrExitReceived as i32
             || msg.msg_type == MsgType::GroupInvitationReceived as i32
@@ -136,22 +247,49 @@ impl ChatService for ChatRpcService {
         }
         msg.send_time = chrono::Utc::now().timestamp_millis();
 
-        // send msg to kafka
+        // 进入Kafka前分配会话内单调递增的服务端序列号
+        msg.server_seq = self
+            .seq
+            .next_seq(&conversation_id(&msg))
+            .await
+            .map_err(|e| {
+                error!("分配服务端序列号失败: {:?}", e);
+                tonic::Status::internal("分配服务端序列号失败")
+            })?;
+
+        // send msg to kafka，应用层按配置重试，重试耗尽后写入死信队列
         let payload = serde_json::to_string(&msg).unwrap();
-        // let kafka generate key, then we need set FutureRecord<String, type>
-        let record: FutureRecord<String, String> = FutureRecord::to(&self.topic).payload(&payload);
-
-        info!("send msg to kafka: {:?}", record);
-        let err = match self.kafka.send(record, Duration::from_secs(0)).await {
-            Ok(_) => String::new(),
-            Err((err, msg)) => {
-                error!(
-                    "send msg to kafka error: {:?}; owned message: {:?}",
-                    err, msg
-                );
-                err.to_string()
+        let max_attempts = self.max_retry.max(1);
+        let mut err = String::new();
+        for attempt in 1..=max_attempts {
+            // let kafka generate key, then we need set FutureRecord<String, type>
+            let record: FutureRecord<String, String> =
+                FutureRecord::to(&self.topic).payload(&payload);
+
+            info!("send msg to kafka: {:?}", record);
+            match self.kafka.send(record, Duration::from_secs(0)).await {
+                Ok(_) => {
+                    err.clear();
+                    break;
+                }
+                Err((e, owned)) => {
+                    error!(
+                        "send msg to kafka error (第{}/{}次): {:?}; owned message: {:?}",
+                        attempt, max_attempts, e, owned
+                    );
+                    err = e.to_string();
+                    if attempt < max_attempts {
+                        tokio::time::sleep(Duration::from_millis(self.retry_interval)).await;
+                    }
+                }
             }
-        };
+        }
+
+        if !err.is_empty() {
+            self.dlq
+                .publish(&payload, &self.topic, &err, max_attempts)
+                .await;
+        }
 
         return Ok(tonic::Response::new(MsgResponse {
             local_id: msg.local_id,
@@ -160,4 +298,79 @@ impl ChatService for ChatRpcService {
             err,
         }));
     }
+
+    /// 查询用户最近的会话列表（每个会话带最新一条消息和未读数）
+    async fn get_conversations(
+        &self,
+        request: tonic::Request<GetConversationsRequest>,
+    ) -> Result<tonic::Response<GetConversationsResponse>, tonic::Status> {
+        let req = request.into_inner();
+        if req.user_id.is_empty() {
+            return Err(tonic::Status::invalid_argument("user_id is empty"));
+        }
+
+        // 未指定limit时取默认值，并限制单次返回的会话数上限
+        let limit = if req.limit <= 0 {
+            DEFAULT_CONVERSATION_LIMIT
+        } else {
+            req.limit.min(MAX_CONVERSATION_LIMIT)
+        };
+
+        let conversations = self
+            .conversations
+            .get_conversations(&req.user_id, limit as i64, req.before_timestamp)
+            .await
+            .map_err(|e| {
+                error!("查询会话列表失败: {:?}", e);
+                tonic::Status::internal("查询会话列表失败")
+            })?;
+
+        Ok(tonic::Response::new(GetConversationsResponse {
+            conversations,
+        }))
+    }
+}
+
+/// 消息所属会话的序列号键：群聊按群ID，单聊按无序的用户对，
+/// 保证同一会话双方的消息共享一条序列
+fn conversation_id(msg: &Msg) -> String {
+    if !msg.group_id.is_empty() {
+        format!("group:{}", msg.group_id)
+    } else if msg.send_id <= msg.receiver_id {
+        format!("single:{}:{}", msg.send_id, msg.receiver_id)
+    } else {
+        format!("single:{}:{}", msg.receiver_id, msg.send_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conversation_id_is_direction_independent() {
+        let a_to_b = Msg {
+            send_id: "alice".to_string(),
+            receiver_id: "bob".to_string(),
+            ..Default::default()
+        };
+        let b_to_a = Msg {
+            send_id: "bob".to_string(),
+            receiver_id: "alice".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(conversation_id(&a_to_b), conversation_id(&b_to_a));
+        assert_eq!(conversation_id(&a_to_b), "single:alice:bob");
+    }
+
+    #[test]
+    fn test_conversation_id_prefers_group() {
+        let msg = Msg {
+            send_id: "alice".to_string(),
+            receiver_id: "g1".to_string(),
+            group_id: "g1".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(conversation_id(&msg), "group:g1");
+    }
 }
@@ -0,0 +1,59 @@
+//! Pusher的内存测试替身：记录推送内容，可配置为失败
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use common::error::Error;
+use common::message::{GroupMemSeq, Msg};
+use tonic::async_trait;
+
+use super::Pusher;
+
+/// 确定性的内存Pusher，供消费者/离线队列/扇出逻辑的单元测试使用，
+/// 无需真实的msg-gateway和服务发现
+#[derive(Debug, Default)]
+pub struct MockPusher {
+    single_msgs: Mutex<Vec<Msg>>,
+    group_msgs: Mutex<Vec<(Msg, Vec<GroupMemSeq>)>>,
+    fail: AtomicBool,
+}
+
+impl MockPusher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 配置后续推送是否失败
+    pub fn set_fail(&self, fail: bool) {
+        self.fail.store(fail, Ordering::SeqCst);
+    }
+
+    /// 已推送的单聊消息
+    pub fn single_msgs(&self) -> Vec<Msg> {
+        self.single_msgs.lock().unwrap().clone()
+    }
+
+    /// 已推送的群聊消息及其成员列表
+    pub fn group_msgs(&self) -> Vec<(Msg, Vec<GroupMemSeq>)> {
+        self.group_msgs.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl Pusher for MockPusher {
+    async fn push_single_msg(&self, msg: Msg) -> Result<(), Error> {
+        if self.fail.load(Ordering::SeqCst) {
+            return Err(Error::Internal("mock pusher failure".to_string()));
+        }
+        self.single_msgs.lock().unwrap().push(msg);
+        Ok(())
+    }
+
+    async fn push_group_msg(&self, msg: Msg, members: Vec<GroupMemSeq>) -> Result<(), Error> {
+        if self.fail.load(Ordering::SeqCst) {
+            return Err(Error::Internal("mock pusher failure".to_string()));
+        }
+        self.group_msgs.lock().unwrap().push((msg, members));
+        Ok(())
+    }
+}
@@ -7,6 +7,8 @@ use common::{
 };
 use tonic::async_trait;
 
+#[cfg(test)]
+pub mod mock;
 mod service;
 
 #[async_trait]
@@ -18,3 +20,71 @@ pub trait Pusher: Send + Sync + Debug {
 pub async fn push_service(config: &AppConfig) -> Arc<dyn Pusher> {
     Arc::new(service::PusherService::new(config).await)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::mock::MockPusher;
+    use super::*;
+
+    fn test_msg(send_id: &str, receiver_id: &str) -> Msg {
+        Msg {
+            send_id: send_id.to_string(),
+            receiver_id: receiver_id.to_string(),
+            content: b"hello".to_vec(),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_mock_pusher_records_pushed_messages() {
+        let mock = Arc::new(MockPusher::new());
+        let pusher: Arc<dyn Pusher> = mock.clone();
+
+        pusher.push_single_msg(test_msg("u1", "u2")).await.unwrap();
+
+        let recorded = mock.single_msgs();
+        assert_eq!(recorded.len(), 1);
+        assert_eq!(recorded[0].send_id, "u1");
+        assert_eq!(recorded[0].receiver_id, "u2");
+        assert_eq!(recorded[0].content, b"hello");
+
+        let members = vec![
+            GroupMemSeq {
+                mem_id: "m1".to_string(),
+                cur_seq: 1,
+                ..Default::default()
+            },
+            GroupMemSeq {
+                mem_id: "m2".to_string(),
+                cur_seq: 2,
+                ..Default::default()
+            },
+        ];
+        pusher
+            .push_group_msg(test_msg("u1", "g1"), members)
+            .await
+            .unwrap();
+
+        let recorded = mock.group_msgs();
+        assert_eq!(recorded.len(), 1);
+        assert_eq!(recorded[0].0.receiver_id, "g1");
+        assert_eq!(recorded[0].1.len(), 2);
+        assert_eq!(recorded[0].1[0].mem_id, "m1");
+    }
+
+    #[tokio::test]
+    async fn test_mock_pusher_failure_propagates() {
+        let mock = Arc::new(MockPusher::new());
+        let pusher: Arc<dyn Pusher> = mock.clone();
+
+        // 推送失败必须返回错误：消费者据此不提交offset，由Kafka重投递实现重试
+        mock.set_fail(true);
+        assert!(pusher.push_single_msg(test_msg("u1", "u2")).await.is_err());
+        assert!(mock.single_msgs().is_empty());
+
+        // 恢复后推送成功
+        mock.set_fail(false);
+        pusher.push_single_msg(test_msg("u1", "u2")).await.unwrap();
+        assert_eq!(mock.single_msgs().len(), 1);
+    }
+}
@@ -7,72 +7,57 @@ use async_trait::async_trait;
 use dashmap::DashMap;
 use tokio::sync::mpsc;
 use tonic::transport::{Channel, Endpoint};
-use tower::discover::Change;
 use tracing::{debug, error};
 
 use common::config::AppConfig;
 use common::message::msg_service_client::MsgServiceClient;
 use common::message::{GroupMemSeq, Msg, SendGroupMsgRequest, SendMsgRequest};
+use common::service_registry::ServiceRegistry;
 
 use super::Pusher;
 
-#[derive(Debug)]
 pub struct PusherService {
     ws_rpc_list: Arc<DashMap<SocketAddr, MsgServiceClient<Channel>>>,
-    service_center: ServiceClient,
+    service_registry: ServiceRegistry,
     sub_svr_name: String,
+    /// ws服务的客户端TLS配置，未配置时明文连接
+    ws_tls: Option<common::config::TlsConfig>,
+}
+
+impl std::fmt::Debug for PusherService {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PusherService")
+            .field("sub_svr_name", &self.sub_svr_name)
+            .finish()
+    }
 }
 
 impl PusherService {
     pub async fn new(config: &AppConfig) -> Self {
         let sub_svr_name = config.rpc.ws.name.clone();
         let ws_rpc_list = Arc::new(DashMap::new());
-        let cloned_list = ws_rpc_list.clone();
-        let (tx, mut rx) = mpsc::channel::<Change<SocketAddr, Endpoint>>(100);
-
-        // read the service from the worker
-        tokio::spawn(async move {
-            while let Some(change) = rx.recv().await {
-                debug!("receive service change: {:?}", change);
-                match change {
-                    Change::Insert(service_id, client) => {
-                        match MsgServiceClient::connect(client).await {
-                            Ok(client) => {
-                                cloned_list.insert(service_id, client);
-                            }
-                            Err(err) => {
-                                error!("connect to ws service error: {:?}", err);
-                            }
-                        };
-                    }
-                    Change::Remove(service_id) => {
-                        cloned_list.remove(&service_id);
-                    }
-                }
-            }
-        });
-
-        utils::get_chan_(config, sub_svr_name.clone(), tx)
-            .await
-            .unwrap();
-
-        let service_center = ServiceClient::builder()
-            .server_host(config.service_center.host.clone())
-            .server_port(config.service_center.port)
-            .connect_timeout(Duration::from_millis(config.service_center.timeout))
-            .build()
-            .await
-            .unwrap();
-        Self {
+        let service_registry = ServiceRegistry::from_env();
+
+        let service = Self {
             ws_rpc_list,
-            service_center,
+            service_registry,
             sub_svr_name,
+            ws_tls: config.rpc.ws.tls.clone(),
+        };
+
+        // 初始化时尝试发现一次ws服务，失败不致命，推送时会重试
+        match service.service_registry.discover_service(&service.sub_svr_name).await {
+            Ok(list) => service.handle_sub_services(list).await,
+            Err(err) => error!("discover ws service error: {:?}", err),
         }
+
+        service
     }
 
-    pub async fn handle_sub_services(&self, services: Vec<Service>) {
+    pub async fn handle_sub_services(&self, services: Vec<String>) {
         for service in services {
-            let addr = format!("{}:{}", service.address, service.port);
+            // service url format: http://host:port
+            let addr = service.trim_start_matches("http://").to_string();
             let socket: SocketAddr = match addr.parse() {
                 Ok(sa) => sa,
                 Err(err) => {
@@ -80,15 +65,34 @@ impl PusherService {
                     continue;
                 }
             };
-            let addr = format!("{}://{}", service.scheme, addr);
             // connect to ws service
-            let endpoint = match Endpoint::from_shared(addr) {
+            let endpoint = match Endpoint::from_shared(service) {
                 Ok(ep) => ep.connect_timeout(Duration::from_secs(5)),
                 Err(err) => {
                     error!("connect to ws service error: {:?}", err);
                     continue;
                 }
             };
+            // 配置了rpc.ws.tls时以mTLS连接
+            let endpoint = match &self.ws_tls {
+                Some(tls) => {
+                    let tls_config = match common::tls::client_tls_config(tls) {
+                        Ok(cfg) => cfg,
+                        Err(err) => {
+                            error!("load ws tls config error: {:?}", err);
+                            continue;
+                        }
+                    };
+                    match endpoint.tls_config(tls_config) {
+                        Ok(ep) => ep,
+                        Err(err) => {
+                            error!("apply ws tls config error: {:?}", err);
+                            continue;
+                        }
+                    }
+                }
+                None => endpoint,
+            };
             let ws = match MsgServiceClient::connect(endpoint).await {
                 Ok(client) => client,
                 Err(err) => {
@@ -108,9 +112,9 @@ impl Pusher for PusherService {
 
         let ws_rpc = self.ws_rpc_list.clone();
         if ws_rpc.is_empty() {
-            let mut client = self.service_center.clone();
-            let list = client
-                .query_with_name(self.sub_svr_name.clone())
+            let list = self
+                .service_registry
+                .discover_service(&self.sub_svr_name)
                 .await
                 .map_err(|e| Error::Internal(e.to_string()))?;
             self.handle_sub_services(list).await;
@@ -150,9 +154,9 @@ impl Pusher for PusherService {
         // extract request
         let ws_rpc = self.ws_rpc_list.clone();
         if ws_rpc.is_empty() {
-            let mut client = self.service_center.clone();
-            let list = client
-                .query_with_name(self.sub_svr_name.clone())
+            let list = self
+                .service_registry
+                .discover_service(&self.sub_svr_name)
                 .await
                 .map_err(|e| Error::Internal(e.to_string()))?;
             self.handle_sub_services(list).await;
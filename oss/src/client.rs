@@ -146,6 +146,23 @@ impl Oss for S3Client {
         self.delete(&self.bucket, key).await
     }
 
+    async fn presigned_download_url(&self, key: &str, expires_secs: u64) -> Result<String, Error> {
+        let presigning = aws_sdk_s3::presigning::PresigningConfig::expires_in(
+            std::time::Duration::from_secs(expires_secs),
+        )
+        .map_err(|e| Error::Internal(e.to_string()))?;
+
+        let request = self
+            .client
+            .get_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .presigned(presigning)
+            .await?;
+
+        Ok(request.uri().to_string())
+    }
+
     async fn upload_avatar(&self, key: &str, content: Vec<u8>) -> Result<(), Error> {
         self.upload(&self.avatar_bucket, key, content).await
     }
@@ -14,6 +14,8 @@ pub trait Oss: Debug + Send + Sync {
     async fn upload_file(&self, key: &str, content: Vec<u8>) -> Result<(), Error>;
     async fn download_file(&self, key: &str) -> Result<Bytes, Error>;
     async fn delete_file(&self, key: &str) -> Result<(), Error>;
+    /// 生成文件的预签名下载链接，expires_secs为链接有效期
+    async fn presigned_download_url(&self, key: &str, expires_secs: u64) -> Result<String, Error>;
 
     async fn upload_avatar(&self, key: &str, content: Vec<u8>) -> Result<(), Error>;
     async fn download_avatar(&self, key: &str) -> Result<Bytes, Error>;
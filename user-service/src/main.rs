@@ -5,12 +5,10 @@ use clap::Parser;
 use sqlx::postgres::PgPoolOptions;
 use std::net::SocketAddr;
 use tonic::transport::Server;
-use tracing::{info, warn, error, Level};
-use tracing_subscriber::FmtSubscriber;
+use tracing::{info, warn, error};
 use tokio::signal;
 use tokio::sync::oneshot;
 use axum::{Router, routing::get};
-use axum_server;
 
 mod model;
 mod repository;
@@ -35,16 +33,14 @@ async fn main() -> Result<()> {
     // 加载.env文件
     dotenv::from_path(&args.config).ok();
     
-    // 初始化日志
-    let subscriber = FmtSubscriber::builder()
-        .with_max_level(Level::INFO)
-        .finish();
-    tracing::subscriber::set_global_default(subscriber)?;
-    
-    info!("正在启动用户服务...");
-    
     // 加载配置
     let config = AppConfig::new()?;
+
+    // 根据配置初始化日志，guard需持有到进程退出
+    let _log_guard = common::logging::init(&config.log);
+
+    info!("正在启动用户服务...");
+
     let host = &config.server.host;
     let port = config.server.port;
     let addr = format!("{}:{}", host, port).parse::<SocketAddr>()?;
@@ -66,7 +62,32 @@ async fn main() -> Result<()> {
     };
     
     // 初始化用户服务
-    let user_service = UserServiceImpl::new(db_pool);
+    let mut user_service = UserServiceImpl::new(db_pool);
+
+    // 按配置启用出站Webhook
+    if config.webhook.enabled {
+        info!("出站Webhook已启用，订阅数: {}", config.webhook.subscriptions.len());
+        user_service = user_service
+            .with_webhooks(common::webhook::WebhookDispatcher::start(config.webhook.clone()));
+    }
+
+    // 头像上传写入OSS的avatar bucket，公开URL由endpoint和bucket拼接
+    let oss_client = oss::oss(&config).await;
+    user_service = user_service.with_avatar_storage(
+        oss_client.clone(),
+        format!(
+            "{}/{}",
+            config.oss.endpoint.trim_end_matches('/'),
+            config.oss.avatar_bucket
+        ),
+        config.oss.max_avatar_bytes,
+    );
+
+    // 启用GDPR用户数据导出需要可用的OSS（MinIO）存储归档
+    if std::env::var("ENABLE_DATA_EXPORT").map(|v| v == "true").unwrap_or(false) {
+        info!("用户数据导出已启用，归档存储: {}", config.oss.endpoint);
+        user_service = user_service.with_oss(oss_client);
+    }
     
     // 创建HTTP服务器用于健康检查
     let health_port = port + 1;
@@ -84,6 +105,9 @@ async fn main() -> Result<()> {
     ).await?;
     
     info!("用户服务已注册到Consul, 服务ID: {}", service_id);
+
+    // 删除账号时需要通过服务发现调用auth-service失效令牌
+    user_service = user_service.with_registry(service_registry.clone());
     
     // 设置关闭通道
     let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
@@ -93,7 +117,14 @@ async fn main() -> Result<()> {
     info!("用户服务启动，监听地址: {}", addr);
     
     // 创建服务器并运行
-    let server = Server::builder()
+    let mut builder = Server::builder();
+    // 配置了server.tls时启用mTLS
+    if let Some(tls) = &config.server.tls {
+        builder = builder.tls_config(common::tls::server_tls_config(tls)?)?;
+    }
+    let server = builder
+        // 每个请求包一层带request_id的span，日志可跨服务关联
+        .layer(common::logging::RequestIdSpanLayer)
         .add_service(UserServiceServer::new(user_service))
         .serve_with_shutdown(addr, async {
             let _ = shutdown_rx.await;
@@ -179,7 +210,7 @@ async fn shutdown_signal(tx: oneshot::Sender<()>, service_registry: ServiceRegis
     }
     
     // 发送关闭信号
-    if let Err(_) = tx.send(()) {
+    if tx.send(()).is_err() {
         warn!("无法发送关闭信号，接收端可能已关闭");
     }
     
@@ -14,6 +14,10 @@ pub struct User {
     pub avatar_url: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// 在线状态：ONLINE/AWAY/DND/OFFLINE
+    pub status: String,
+    /// 最近一次状态变更时间，从未上线过为None
+    pub last_seen: Option<DateTime<Utc>>,
 }
 
 /// 创建用户请求数据
@@ -53,6 +57,11 @@ impl From<User> for user::User {
                 seconds: user.updated_at.timestamp(),
                 nanos: user.updated_at.timestamp_subsec_nanos() as i32,
             }),
+            status: user.status,
+            last_seen: user.last_seen.map(|t| Timestamp {
+                seconds: t.timestamp(),
+                nanos: t.timestamp_subsec_nanos() as i32,
+            }),
         }
     }
 }
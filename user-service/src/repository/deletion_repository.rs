@@ -0,0 +1,337 @@
+use common::Result;
+use sqlx::PgPool;
+use tracing::{debug, info};
+
+/// 账号删除仓库
+///
+/// 承担账号删除流程中的数据库清理步骤。所有方法都是幂等的：
+/// 重复执行只会命中0行，因此删除流程失败后可以安全重跑。
+pub struct DeletionRepository {
+    pool: PgPool,
+}
+
+impl DeletionRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// 删除用户发起或收到的全部好友关系，返回删除行数
+    pub async fn remove_friendships(&self, user_id: &str) -> Result<u64> {
+        let rows_affected = sqlx::query!(
+            r#"
+            DELETE FROM friendships
+            WHERE user_id = $1 OR friend_id = $1
+            "#,
+            user_id
+        )
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        debug!("用户 {} 的 {} 条好友关系已删除", user_id, rows_affected);
+        Ok(rows_affected)
+    }
+
+    /// 处理用户的群组关系
+    ///
+    /// 用户拥有的群组转让给最早加入的最高角色成员；没有其他成员的群组直接删除；
+    /// 最后移除用户的全部群组成员记录。整个过程在一个事务内完成。
+    pub async fn handle_groups(&self, user_id: &str) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        // 用户拥有的群组
+        let owned_groups = sqlx::query!(
+            r#"
+            SELECT id FROM groups WHERE owner_id = $1
+            "#,
+            user_id
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+
+        for group in owned_groups {
+            // 最高角色优先，同角色按加入时间；
+            // role兼容DDL的字符串编码与服务内的数字编码
+            let successor = sqlx::query!(
+                r#"
+                SELECT user_id FROM group_members
+                WHERE group_id = $1 AND user_id <> $2
+                ORDER BY CASE role
+                    WHEN 'OWNER' THEN 3 WHEN '2' THEN 3
+                    WHEN 'ADMIN' THEN 2 WHEN '1' THEN 2
+                    ELSE 1 END DESC, joined_at ASC
+                LIMIT 1
+                "#,
+                group.id,
+                user_id
+            )
+            .fetch_optional(&mut *tx)
+            .await?;
+
+            match successor {
+                Some(successor) => {
+                    // 转让给继任者（role取DDL check_role约束的取值）
+                    sqlx::query!(
+                        r#"
+                        UPDATE group_members SET role = 'OWNER'
+                        WHERE group_id = $1 AND user_id = $2
+                        "#,
+                        group.id,
+                        successor.user_id
+                    )
+                    .execute(&mut *tx)
+                    .await?;
+
+                    sqlx::query!(
+                        r#"
+                        UPDATE groups SET owner_id = $1, updated_at = CURRENT_TIMESTAMP
+                        WHERE id = $2
+                        "#,
+                        successor.user_id,
+                        group.id
+                    )
+                    .execute(&mut *tx)
+                    .await?;
+
+                    info!("群组 {} 已转让给 {}", group.id, successor.user_id);
+                }
+                None => {
+                    // 没有其他成员，删除群组（成员记录级联删除）
+                    sqlx::query!(
+                        r#"
+                        DELETE FROM groups WHERE id = $1
+                        "#,
+                        group.id
+                    )
+                    .execute(&mut *tx)
+                    .await?;
+
+                    info!("群组 {} 没有其他成员，已删除", group.id);
+                }
+            }
+        }
+
+        // 移除用户的全部群组成员记录
+        sqlx::query!(
+            r#"
+            DELETE FROM group_members WHERE user_id = $1
+            "#,
+            user_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// 匿名化用户发送的消息：清空内容，保留会话结构，返回处理行数
+    pub async fn anonymize_messages(&self, user_id: &str) -> Result<u64> {
+        let rows_affected = sqlx::query!(
+            r#"
+            UPDATE messages SET content = NULL
+            WHERE send_id = $1 AND content IS NOT NULL
+            "#,
+            user_id
+        )
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        debug!("用户 {} 的 {} 条消息已匿名化", user_id, rows_affected);
+        Ok(rows_affected)
+    }
+
+    /// 软删除用户：标记deleted_at并匿名化档案字段
+    ///
+    /// 用户名/邮箱改写为不可登录的占位值，密码清空。
+    /// 已删除的用户不会被重复处理（deleted_at IS NULL守卫），返回是否实际执行
+    pub async fn soft_delete_user(&self, user_id: &str) -> Result<bool> {
+        let rows_affected = sqlx::query!(
+            r#"
+            UPDATE users
+            SET username = 'deleted_' || substring(id, 1, 8),
+                email = id || '@deleted.invalid',
+                nickname = NULL,
+                avatar_url = NULL,
+                password = '',
+                deleted_at = CURRENT_TIMESTAMP
+            WHERE id = $1 AND deleted_at IS NULL
+            "#,
+            user_id
+        )
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        Ok(rows_affected > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    async fn test_pool() -> PgPool {
+        PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap()
+    }
+
+    async fn insert_user(pool: &PgPool, id: &str) {
+        sqlx::query("INSERT INTO users (id, username, email, password) VALUES ($1, $1, $1 || '@test.local', 'pw')")
+            .bind(id)
+            .execute(pool)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore = "需要DATABASE_URL指向的PostgreSQL"]
+    async fn test_deletion_cleans_all_subsystems_and_is_rerunnable() {
+        let pool = test_pool().await;
+        let repo = DeletionRepository::new(pool.clone());
+
+        let user_id = Uuid::new_v4().to_string();
+        let friend_id = Uuid::new_v4().to_string();
+        insert_user(&pool, &user_id).await;
+        insert_user(&pool, &friend_id).await;
+
+        // 好友关系
+        sqlx::query("INSERT INTO friendships (id, user_id, friend_id, status) VALUES ($1, $2, $3, 'ACCEPTED')")
+            .bind(Uuid::new_v4().to_string())
+            .bind(&user_id)
+            .bind(&friend_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        // 有其他成员的群组：应转让给friend
+        let shared_group = Uuid::new_v4().to_string();
+        sqlx::query("INSERT INTO groups (id, name, owner_id) VALUES ($1, 'shared', $2)")
+            .bind(&shared_group)
+            .bind(&user_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        for (member, role) in [(&user_id, "OWNER"), (&friend_id, "MEMBER")] {
+            sqlx::query("INSERT INTO group_members (id, group_id, user_id, role) VALUES ($1, $2, $3, $4)")
+                .bind(Uuid::new_v4().to_string())
+                .bind(&shared_group)
+                .bind(member)
+                .bind(role)
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+
+        // 只有自己的群组：应被删除
+        let solo_group = Uuid::new_v4().to_string();
+        sqlx::query("INSERT INTO groups (id, name, owner_id) VALUES ($1, 'solo', $2)")
+            .bind(&solo_group)
+            .bind(&user_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("INSERT INTO group_members (id, group_id, user_id, role) VALUES ($1, $2, $3, 'OWNER')")
+            .bind(Uuid::new_v4().to_string())
+            .bind(&solo_group)
+            .bind(&user_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        // 已发送的消息
+        let message_id = Uuid::new_v4().to_string();
+        sqlx::query(
+            "INSERT INTO messages (server_id, send_id, receiver_id, msg_type, content_type, content, send_time) \
+             VALUES ($1, $2, $3, 0, 0, $4, 0)",
+        )
+        .bind(&message_id)
+        .bind(&user_id)
+        .bind(&friend_id)
+        .bind("hello".as_bytes())
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        // 执行全部清理步骤
+        assert_eq!(repo.remove_friendships(&user_id).await.unwrap(), 1);
+        repo.handle_groups(&user_id).await.unwrap();
+        assert_eq!(repo.anonymize_messages(&user_id).await.unwrap(), 1);
+        assert!(repo.soft_delete_user(&user_id).await.unwrap());
+
+        // 好友关系已删除
+        let friendships: i64 = sqlx::query_scalar("SELECT count(*) FROM friendships WHERE user_id = $1 OR friend_id = $1")
+            .bind(&user_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(friendships, 0);
+
+        // 共享群组已转让，solo群组已删除，用户不再是任何群组成员
+        let new_owner: String = sqlx::query_scalar("SELECT owner_id FROM groups WHERE id = $1")
+            .bind(&shared_group)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(new_owner, friend_id);
+        let solo_exists: i64 = sqlx::query_scalar("SELECT count(*) FROM groups WHERE id = $1")
+            .bind(&solo_group)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(solo_exists, 0);
+        let memberships: i64 = sqlx::query_scalar("SELECT count(*) FROM group_members WHERE user_id = $1")
+            .bind(&user_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(memberships, 0);
+
+        // 消息内容已清空，记录保留
+        let content: Option<Vec<u8>> = sqlx::query_scalar("SELECT content FROM messages WHERE server_id = $1")
+            .bind(&message_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert!(content.is_none());
+
+        // 用户已软删除且档案已匿名化
+        let (username, deleted): (String, bool) = sqlx::query_as(
+            "SELECT username, deleted_at IS NOT NULL FROM users WHERE id = $1",
+        )
+        .bind(&user_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert!(username.starts_with("deleted_"));
+        assert!(deleted);
+
+        // 重跑整个流程是安全的：全部命中0行，软删除被守卫跳过
+        assert_eq!(repo.remove_friendships(&user_id).await.unwrap(), 0);
+        repo.handle_groups(&user_id).await.unwrap();
+        assert_eq!(repo.anonymize_messages(&user_id).await.unwrap(), 0);
+        assert!(!repo.soft_delete_user(&user_id).await.unwrap());
+
+        // 清理测试数据
+        sqlx::query("DELETE FROM messages WHERE server_id = $1")
+            .bind(&message_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("DELETE FROM groups WHERE id = $1")
+            .bind(&shared_group)
+            .execute(&pool)
+            .await
+            .unwrap();
+        for id in [&user_id, &friend_id] {
+            sqlx::query("DELETE FROM users WHERE id = $1")
+                .bind(id)
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+    }
+}
@@ -0,0 +1,216 @@
+use chrono::{TimeZone, Utc};
+use common::{Error, Result};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use tracing::debug;
+
+/// 用户数据导出仓库
+///
+/// 按用户聚合档案、好友关系、群组成员关系与消息，供GDPR数据导出使用。
+/// 所有查询都以user_id过滤，不会带出其他用户的私有数据。
+pub struct ExportRepository {
+    pool: PgPool,
+}
+
+impl ExportRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// 汇总用户数据为JSON归档（不包含密码等凭证字段）
+    pub async fn export_user_data(&self, user_id: &str) -> Result<Value> {
+        debug!("开始汇总用户数据，用户ID: {}", user_id);
+
+        let profile = self.profile(user_id).await?;
+        let friendships = self.friendships(user_id).await?;
+        let group_memberships = self.group_memberships(user_id).await?;
+        let messages = self.messages(user_id).await?;
+
+        Ok(build_archive(
+            user_id,
+            profile,
+            friendships,
+            group_memberships,
+            messages,
+        ))
+    }
+
+    /// 用户档案（不含password）
+    async fn profile(&self, user_id: &str) -> Result<Value> {
+        let row = sqlx::query!(
+            r#"
+            SELECT id, username, email, nickname, avatar_url, created_at, updated_at
+            FROM users
+            WHERE id = $1
+            "#,
+            user_id
+        )
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| Error::NotFound(format!("用户 {} 不存在", user_id)))?;
+
+        Ok(json!({
+            "id": row.id,
+            "username": row.username,
+            "email": row.email,
+            "nickname": row.nickname,
+            "avatar_url": row.avatar_url,
+            "created_at": Utc.from_utc_datetime(&row.created_at).to_rfc3339(),
+            "updated_at": Utc.from_utc_datetime(&row.updated_at).to_rfc3339(),
+        }))
+    }
+
+    /// 用户发起或收到的好友关系
+    async fn friendships(&self, user_id: &str) -> Result<Value> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT id, user_id, friend_id, status, created_at, updated_at
+            FROM friendships
+            WHERE user_id = $1 OR friend_id = $1
+            ORDER BY created_at
+            "#,
+            user_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let friendships: Vec<Value> = rows
+            .into_iter()
+            .map(|row| {
+                json!({
+                    "id": row.id,
+                    "user_id": row.user_id,
+                    "friend_id": row.friend_id,
+                    "status": row.status,
+                    "created_at": Utc.from_utc_datetime(&row.created_at).to_rfc3339(),
+                    "updated_at": Utc.from_utc_datetime(&row.updated_at).to_rfc3339(),
+                })
+            })
+            .collect();
+
+        Ok(Value::Array(friendships))
+    }
+
+    /// 用户的群组成员关系
+    async fn group_memberships(&self, user_id: &str) -> Result<Value> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT m.group_id, g.name, m.role, m.joined_at
+            FROM group_members m
+            JOIN groups g ON g.id = m.group_id
+            WHERE m.user_id = $1
+            ORDER BY m.joined_at
+            "#,
+            user_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let memberships: Vec<Value> = rows
+            .into_iter()
+            .map(|row| {
+                json!({
+                    "group_id": row.group_id,
+                    "group_name": row.name,
+                    "role": row.role,
+                    "joined_at": Utc.from_utc_datetime(&row.joined_at).to_rfc3339(),
+                })
+            })
+            .collect();
+
+        Ok(Value::Array(memberships))
+    }
+
+    /// 用户发送或接收的消息（不包含其他用户之间的会话）
+    async fn messages(&self, user_id: &str) -> Result<Value> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT server_id, send_id, receiver_id, group_id, msg_type, content_type, content, send_time
+            FROM messages
+            WHERE send_id = $1 OR receiver_id = $1
+            ORDER BY send_time
+            "#,
+            user_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let messages: Vec<Value> = rows
+            .into_iter()
+            .map(|row| {
+                let content = row
+                    .content
+                    .map(|bytes| String::from_utf8_lossy(&bytes).into_owned());
+                json!({
+                    "server_id": row.server_id,
+                    "send_id": row.send_id,
+                    "receiver_id": row.receiver_id,
+                    "group_id": row.group_id,
+                    "msg_type": row.msg_type,
+                    "content_type": row.content_type,
+                    "content": content,
+                    "send_time": row.send_time,
+                })
+            })
+            .collect();
+
+        Ok(Value::Array(messages))
+    }
+}
+
+/// 组装导出归档，各部分均已按user_id过滤
+pub(crate) fn build_archive(
+    user_id: &str,
+    profile: Value,
+    friendships: Value,
+    group_memberships: Value,
+    messages: Value,
+) -> Value {
+    json!({
+        "format_version": 1,
+        "user_id": user_id,
+        "exported_at": Utc::now().to_rfc3339(),
+        "profile": profile,
+        "friendships": friendships,
+        "group_memberships": group_memberships,
+        "messages": messages,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_archive_contains_expected_sections() {
+        let archive = build_archive(
+            "user-1",
+            json!({"id": "user-1", "username": "alice", "email": "alice@example.com"}),
+            json!([{"user_id": "user-1", "friend_id": "user-2", "status": "ACCEPTED"}]),
+            json!([{"group_id": "group-1", "group_name": "测试群", "role": "MEMBER"}]),
+            json!([{"send_id": "user-1", "receiver_id": "user-2", "content": "hello"}]),
+        );
+
+        assert_eq!(archive["user_id"], "user-1");
+        for section in ["profile", "friendships", "group_memberships", "messages"] {
+            assert!(!archive[section].is_null(), "归档缺少{}部分", section);
+        }
+        assert_eq!(archive["friendships"].as_array().unwrap().len(), 1);
+        assert_eq!(archive["messages"][0]["content"], "hello");
+    }
+
+    #[test]
+    fn test_archive_profile_excludes_credentials() {
+        // 档案部分来自不含password列的查询，归档中不应出现凭证字段
+        let archive = build_archive(
+            "user-1",
+            json!({"id": "user-1", "username": "alice", "email": "alice@example.com"}),
+            json!([]),
+            json!([]),
+            json!([]),
+        );
+
+        assert!(archive["profile"].get("password").is_none());
+        assert_eq!(archive["profile"]["username"], "alice");
+    }
+}
@@ -1 +1,3 @@
-pub mod user_repository; 
\ No newline at end of file
+pub mod user_repository;
+pub mod export_repository;
+pub mod deletion_repository;
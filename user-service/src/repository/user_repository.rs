@@ -39,7 +39,7 @@ impl UserRepository {
             r#"
             INSERT INTO users (id, username, email, password, nickname, avatar_url)
             VALUES ($1, $2, $3, $4, $5, $6)
-            RETURNING id, username, email, password, nickname, avatar_url, created_at, updated_at
+            RETURNING id, username, email, password, nickname, avatar_url, created_at, updated_at, status, last_seen
             "#,
             id.to_string(),
             data.username,
@@ -64,6 +64,8 @@ impl UserRepository {
             avatar_url: row.avatar_url,
             created_at: Utc.from_utc_datetime(&row.created_at),
             updated_at: Utc.from_utc_datetime(&row.updated_at),
+            status: row.status,
+            last_seen: row.last_seen.map(|t| Utc.from_utc_datetime(&t)),
         };
         
         debug!("用户创建成功: {}", user.id);
@@ -77,9 +79,9 @@ impl UserRepository {
         
         let row = sqlx::query!(
             r#"
-            SELECT id, username, email, password, nickname, avatar_url, created_at, updated_at
+            SELECT id, username, email, password, nickname, avatar_url, created_at, updated_at, status, last_seen
             FROM users
-            WHERE id = $1
+            WHERE id = $1 AND deleted_at IS NULL
             "#,
             uuid.to_string()
         )
@@ -103,18 +105,67 @@ impl UserRepository {
             avatar_url: row.avatar_url,
             created_at: Utc.from_utc_datetime(&row.created_at),
             updated_at: Utc.from_utc_datetime(&row.updated_at),
+            status: row.status,
+            last_seen: row.last_seen.map(|t| Utc.from_utc_datetime(&t)),
         };
         
         Ok(user)
     }
     
+    /// 按ID列表批量查询用户，单条SQL完成，不存在的ID静默跳过
+    pub async fn get_users_by_ids(&self, ids: &[String]) -> Result<Vec<User>> {
+        // 先整体校验ID格式，避免把非法输入拼进数组参数
+        for id in ids {
+            Uuid::parse_str(id)
+                .map_err(|_| Error::BadRequest(format!("无效的用户ID格式: {}", id)))?;
+        }
+
+        // 重复ID只查询一次，结果中每个用户也只出现一次
+        let mut unique_ids: Vec<String> = ids.to_vec();
+        unique_ids.sort();
+        unique_ids.dedup();
+
+        let rows = sqlx::query!(
+            r#"
+            SELECT id, username, email, password, nickname, avatar_url, created_at, updated_at, status, last_seen
+            FROM users
+            WHERE id = ANY($1) AND deleted_at IS NULL
+            "#,
+            &unique_ids
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|err| {
+            error!("批量查询用户失败: {}", err);
+            Error::Database(err)
+        })?;
+
+        let users = rows
+            .into_iter()
+            .map(|row| User {
+                id: Uuid::parse_str(&row.id).unwrap(),
+                username: row.username,
+                email: row.email,
+                password: row.password,
+                nickname: row.nickname,
+                avatar_url: row.avatar_url,
+                created_at: Utc.from_utc_datetime(&row.created_at),
+                updated_at: Utc.from_utc_datetime(&row.updated_at),
+                status: row.status,
+                last_seen: row.last_seen.map(|t| Utc.from_utc_datetime(&t)),
+            })
+            .collect();
+
+        Ok(users)
+    }
+
     /// 根据用户名查询用户
     pub async fn get_user_by_username(&self, username: &str) -> Result<User> {
         let row = sqlx::query!(
             r#"
-            SELECT id, username, email, password, nickname, avatar_url, created_at, updated_at
+            SELECT id, username, email, password, nickname, avatar_url, created_at, updated_at, status, last_seen
             FROM users
-            WHERE username = $1
+            WHERE username = $1 AND deleted_at IS NULL
             "#,
             username
         )
@@ -138,6 +189,8 @@ impl UserRepository {
             avatar_url: row.avatar_url,
             created_at: Utc.from_utc_datetime(&row.created_at),
             updated_at: Utc.from_utc_datetime(&row.updated_at),
+            status: row.status,
+            last_seen: row.last_seen.map(|t| Utc.from_utc_datetime(&t)),
         };
         
         Ok(user)
@@ -147,9 +200,9 @@ impl UserRepository {
     pub async fn get_user_by_email(&self, email: &str) -> Result<User> {
         let row = sqlx::query!(
             r#"
-            SELECT id, username, email, password, nickname, avatar_url, created_at, updated_at
+            SELECT id, username, email, password, nickname, avatar_url, created_at, updated_at, status, last_seen
             FROM users
-            WHERE email = $1
+            WHERE email = $1 AND deleted_at IS NULL
             "#,
             email
         )
@@ -173,6 +226,8 @@ impl UserRepository {
             avatar_url: row.avatar_url,
             created_at: Utc.from_utc_datetime(&row.created_at),
             updated_at: Utc.from_utc_datetime(&row.updated_at),
+            status: row.status,
+            last_seen: row.last_seen.map(|t| Utc.from_utc_datetime(&t)),
         };
         
         Ok(user)
@@ -204,7 +259,7 @@ impl UserRepository {
                 password = COALESCE($4, password),
                 updated_at = NOW()
             WHERE id = $5
-            RETURNING id, username, email, password, nickname, avatar_url, created_at, updated_at
+            RETURNING id, username, email, password, nickname, avatar_url, created_at, updated_at, status, last_seen
             "#,
             data.email.as_deref(),
             data.nickname.as_deref(),
@@ -228,6 +283,8 @@ impl UserRepository {
             avatar_url: row.avatar_url,
             created_at: Utc.from_utc_datetime(&row.created_at),
             updated_at: Utc.from_utc_datetime(&row.updated_at),
+            status: row.status,
+            last_seen: row.last_seen.map(|t| Utc.from_utc_datetime(&t)),
         };
         
         debug!("用户更新成功: {}", updated_user.id);
@@ -260,9 +317,10 @@ impl UserRepository {
         // 查询符合条件的用户
         let rows = sqlx::query!(
             r#"
-            SELECT id, username, email, password, nickname, avatar_url, created_at, updated_at
+            SELECT id, username, email, password, nickname, avatar_url, created_at, updated_at, status, last_seen
             FROM users
-            WHERE username ILIKE $1 OR email ILIKE $1 OR COALESCE(nickname, '') ILIKE $1
+            WHERE deleted_at IS NULL
+              AND (username ILIKE $1 OR email ILIKE $1 OR COALESCE(nickname, '') ILIKE $1)
             ORDER BY username
             LIMIT $2 OFFSET $3
             "#,
@@ -287,6 +345,8 @@ impl UserRepository {
                 avatar_url: row.avatar_url,
                 created_at: Utc.from_utc_datetime(&row.created_at),
                 updated_at: Utc.from_utc_datetime(&row.updated_at),
+                status: row.status,
+                last_seen: row.last_seen.map(|t| Utc.from_utc_datetime(&t)),
             }
         }).collect();
         
@@ -295,7 +355,8 @@ impl UserRepository {
             r#"
             SELECT COUNT(*) as total
             FROM users
-            WHERE username ILIKE $1 OR email ILIKE $1 OR COALESCE(nickname, '') ILIKE $1
+            WHERE deleted_at IS NULL
+              AND (username ILIKE $1 OR email ILIKE $1 OR COALESCE(nickname, '') ILIKE $1)
             "#
         )
         .bind(&search_pattern)
@@ -309,4 +370,257 @@ impl UserRepository {
         
         Ok((users, total as i32))
     }
-} 
\ No newline at end of file
+
+    /// 查询单聊隐私设置（EVERYONE/FRIENDS/NOBODY）
+    pub async fn get_message_privacy(&self, id: &str) -> Result<String> {
+        let uuid = Uuid::parse_str(id)
+            .map_err(|_| Error::BadRequest(format!("无效的用户ID格式: {}", id)))?;
+
+        let row = sqlx::query!(
+            r#"
+            SELECT msg_privacy
+            FROM users
+            WHERE id = $1 AND deleted_at IS NULL
+            "#,
+            uuid.to_string()
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|err| {
+            if let sqlx::Error::RowNotFound = err {
+                Error::NotFound(format!("用户ID {} 不存在", id))
+            } else {
+                error!("查询隐私设置失败: {}", err);
+                Error::Database(err)
+            }
+        })?;
+
+        Ok(row.msg_privacy)
+    }
+
+    /// 更新单聊隐私设置，取值需与check_msg_privacy约束一致
+    pub async fn update_message_privacy(&self, id: &str, privacy: &str) -> Result<String> {
+        let uuid = Uuid::parse_str(id)
+            .map_err(|_| Error::BadRequest(format!("无效的用户ID格式: {}", id)))?;
+
+        if !matches!(privacy, "EVERYONE" | "FRIENDS" | "NOBODY") {
+            return Err(Error::BadRequest(format!("无效的隐私设置: {}", privacy)));
+        }
+
+        let result = sqlx::query!(
+            r#"
+            UPDATE users
+            SET msg_privacy = $1, updated_at = NOW()
+            WHERE id = $2
+            "#,
+            privacy,
+            uuid.to_string()
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|err| {
+            error!("更新隐私设置失败: {}", err);
+            Error::Database(err)
+        })?;
+
+        if result.rows_affected() == 0 {
+            return Err(Error::NotFound(format!("用户ID {} 不存在", id)));
+        }
+
+        debug!("用户 {} 隐私设置更新为 {}", id, privacy);
+        Ok(privacy.to_string())
+    }
+
+    /// 查询在线状态，返回(status, last_seen)
+    pub async fn get_status(&self, id: &str) -> Result<(String, Option<chrono::DateTime<Utc>>)> {
+        let uuid = Uuid::parse_str(id)
+            .map_err(|_| Error::BadRequest(format!("无效的用户ID格式: {}", id)))?;
+
+        let row = sqlx::query!(
+            r#"
+            SELECT status, last_seen
+            FROM users
+            WHERE id = $1 AND deleted_at IS NULL
+            "#,
+            uuid.to_string()
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|err| {
+            if let sqlx::Error::RowNotFound = err {
+                Error::NotFound(format!("用户ID {} 不存在", id))
+            } else {
+                error!("查询在线状态失败: {}", err);
+                Error::Database(err)
+            }
+        })?;
+
+        Ok((row.status, row.last_seen.map(|t| Utc.from_utc_datetime(&t))))
+    }
+
+    /// 更新在线状态并刷新last_seen，取值需与check_status约束一致
+    pub async fn update_status(&self, id: &str, status: &str) -> Result<(String, Option<chrono::DateTime<Utc>>)> {
+        let uuid = Uuid::parse_str(id)
+            .map_err(|_| Error::BadRequest(format!("无效的用户ID格式: {}", id)))?;
+
+        if !matches!(status, "ONLINE" | "AWAY" | "DND" | "OFFLINE") {
+            return Err(Error::BadRequest(format!("无效的在线状态: {}", status)));
+        }
+
+        let row = sqlx::query!(
+            r#"
+            UPDATE users
+            SET status = $1, last_seen = NOW()
+            WHERE id = $2 AND deleted_at IS NULL
+            RETURNING status, last_seen
+            "#,
+            status,
+            uuid.to_string()
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|err| {
+            if let sqlx::Error::RowNotFound = err {
+                Error::NotFound(format!("用户ID {} 不存在", id))
+            } else {
+                error!("更新在线状态失败: {}", err);
+                Error::Database(err)
+            }
+        })?;
+
+        debug!("用户 {} 在线状态更新为 {}", id, status);
+        Ok((row.status, row.last_seen.map(|t| Utc.from_utc_datetime(&t))))
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::deletion_repository::DeletionRepository;
+
+    async fn test_pool() -> PgPool {
+        PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    #[ignore = "需要DATABASE_URL指向的PostgreSQL"]
+    async fn test_soft_deleted_user_is_invisible_and_username_is_freed() {
+        let pool = test_pool().await;
+        let repo = UserRepository::new(pool.clone());
+
+        let username = format!("u_{}", &Uuid::new_v4().to_string()[..8]);
+        let email = format!("{}@test.local", username);
+        let user = repo
+            .create_user(CreateUserData {
+                username: username.clone(),
+                email: email.clone(),
+                password: "password123".to_string(),
+                nickname: None,
+                avatar_url: None,
+            })
+            .await
+            .unwrap();
+        let user_id = user.id.to_string();
+
+        // 删除前各查询路径都能找到该用户
+        assert!(repo.get_user_by_id(&user_id).await.is_ok());
+        assert!(repo.get_user_by_username(&username).await.is_ok());
+        assert!(repo.get_user_by_email(&email).await.is_ok());
+        assert_eq!(repo.get_users_by_ids(std::slice::from_ref(&user_id)).await.unwrap().len(), 1);
+        let (found, total) = repo.search_users(&username, 1, 10).await.unwrap();
+        assert_eq!(total, 1);
+        assert_eq!(found.len(), 1);
+
+        // 软删除后所有查询路径都不再返回该用户
+        assert!(DeletionRepository::new(pool.clone())
+            .soft_delete_user(&user_id)
+            .await
+            .unwrap());
+        assert!(matches!(repo.get_user_by_id(&user_id).await, Err(Error::NotFound(_))));
+        assert!(matches!(repo.get_user_by_username(&username).await, Err(Error::NotFound(_))));
+        assert!(matches!(repo.get_user_by_email(&email).await, Err(Error::NotFound(_))));
+        assert!(repo.get_users_by_ids(std::slice::from_ref(&user_id)).await.unwrap().is_empty());
+        let (found, total) = repo.search_users(&username, 1, 10).await.unwrap();
+        assert_eq!(total, 0);
+        assert!(found.is_empty());
+
+        // 匿名化释放了用户名和邮箱，可以重新注册
+        let reregistered = repo
+            .create_user(CreateUserData {
+                username: username.clone(),
+                email: email.clone(),
+                password: "password123".to_string(),
+                nickname: None,
+                avatar_url: None,
+            })
+            .await
+            .unwrap();
+        assert_ne!(reregistered.id.to_string(), user_id);
+
+        // 清理测试数据
+        for id in [&user_id, &reregistered.id.to_string()] {
+            sqlx::query("DELETE FROM users WHERE id = $1")
+                .bind(id)
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+    }
+
+    #[tokio::test]
+    #[ignore = "需要DATABASE_URL指向的PostgreSQL"]
+    async fn test_status_transitions_and_bulk_query() {
+        let pool = test_pool().await;
+        let repo = UserRepository::new(pool.clone());
+
+        let username = format!("u_{}", &Uuid::new_v4().to_string()[..8]);
+        let user = repo
+            .create_user(CreateUserData {
+                username: username.clone(),
+                email: format!("{}@test.local", username),
+                password: "password123".to_string(),
+                nickname: None,
+                avatar_url: None,
+            })
+            .await
+            .unwrap();
+        let user_id = user.id.to_string();
+
+        // 新用户默认离线且从未上线
+        let (status, last_seen) = repo.get_status(&user_id).await.unwrap();
+        assert_eq!(status, "OFFLINE");
+        assert!(last_seen.is_none());
+
+        // 状态变更会刷新last_seen
+        let (status, first_seen) = repo.update_status(&user_id, "ONLINE").await.unwrap();
+        assert_eq!(status, "ONLINE");
+        let first_seen = first_seen.expect("上线后last_seen应有值");
+
+        let (status, away_seen) = repo.update_status(&user_id, "AWAY").await.unwrap();
+        assert_eq!(status, "AWAY");
+        assert!(away_seen.unwrap() >= first_seen);
+
+        // 非法取值被拒绝且不影响当前状态
+        assert!(matches!(
+            repo.update_status(&user_id, "INVISIBLE").await,
+            Err(Error::BadRequest(_))
+        ));
+        assert_eq!(repo.get_status(&user_id).await.unwrap().0, "AWAY");
+
+        // 批量查询携带状态
+        let users = repo
+            .get_users_by_ids(std::slice::from_ref(&user_id))
+            .await
+            .unwrap();
+        assert_eq!(users.len(), 1);
+        assert_eq!(users[0].status, "AWAY");
+        assert!(users[0].last_seen.is_some());
+
+        sqlx::query("DELETE FROM users WHERE id = $1")
+            .bind(&user_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+    }
+}
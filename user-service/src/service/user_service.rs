@@ -1,27 +1,163 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
 use common::Error;
+use common::service_registry::ServiceRegistry;
+use common::webhook::{WebhookDispatcher, WebhookEvent};
+use common::proto::auth::auth_service_client::AuthServiceClient;
+use common::proto::auth::InvalidateUserTokensRequest;
 use common::proto::user::{
     user_service_server::UserService,
     CreateUserRequest, UpdateUserRequest, GetUserByIdRequest, GetUserByUsernameRequest,
     VerifyPasswordRequest, VerifyPasswordResponse, SearchUsersRequest, SearchUsersResponse,
+    BatchGetUsersRequest, BatchGetUsersResponse,
+    GetMessagePrivacyRequest, UpdateMessagePrivacyRequest, MessagePrivacyResponse,
+    DeleteAccountRequest, DeleteAccountResponse,
+    ExportUserDataRequest, ExportUserDataResponse,
+    GetUserDataExportRequest, GetUserDataExportResponse,
+    UploadAvatarRequest, UploadAvatarResponse,
+    UpdateStatusRequest, GetStatusRequest, StatusResponse,
     UserResponse, User as ProtoUser
 };
+use oss::Oss;
 use sqlx::PgPool;
+use tokio::sync::RwLock;
 use tonic::{Request, Response, Status};
-use tracing::{info, error, debug};
+use tracing::{info, error, debug, warn};
 use crate::model::user::{CreateUserData, UpdateUserData};
+use crate::repository::deletion_repository::DeletionRepository;
+use crate::repository::export_repository::ExportRepository;
 use crate::repository::user_repository::UserRepository;
 
+/// 导出归档预签名下载链接的有效期（秒）
+const EXPORT_URL_TTL_SECS: u64 = 3600;
+
+/// 头像允许的MIME类型及对应的对象键扩展名
+const AVATAR_CONTENT_TYPES: &[(&str, &str)] = &[
+    ("image/png", "png"),
+    ("image/jpeg", "jpg"),
+    ("image/webp", "webp"),
+    ("image/gif", "gif"),
+];
+
+/// 头像存储配置，见[`UserServiceImpl::with_avatar_storage`]
+struct AvatarStorage {
+    oss: Arc<dyn Oss>,
+    /// 公开访问的URL前缀（endpoint + avatar bucket）
+    url_base: String,
+    /// 上传大小上限（字节）
+    max_bytes: usize,
+}
+
+/// 导出任务状态
+enum ExportState {
+    Pending,
+    Completed { object_key: String },
+    Failed,
+}
+
+/// 一次用户数据导出任务
+struct ExportJob {
+    user_id: String,
+    state: ExportState,
+}
+
+/// 仅用户本人或管理员可以操作该用户的数据（导出、删除）
+fn is_self_or_admin(requester_id: &str, user_id: &str, requester_is_admin: bool) -> bool {
+    requester_is_admin || (!requester_id.is_empty() && requester_id == user_id)
+}
+
 /// 用户服务实现
 pub struct UserServiceImpl {
     repository: UserRepository,
+    pool: PgPool,
+    /// 出站Webhook分发器，未启用时为None
+    webhooks: Option<Arc<WebhookDispatcher>>,
+    /// 导出归档存储，未配置OSS时数据导出不可用
+    oss: Option<Arc<dyn Oss>>,
+    /// 进行中/已完成的导出任务，按export_id索引
+    exports: Arc<RwLock<HashMap<String, ExportJob>>>,
+    /// 头像存储，未配置时头像上传不可用
+    avatars: Option<AvatarStorage>,
+    /// 服务注册中心，删除账号时用于发现auth-service失效令牌
+    registry: Option<ServiceRegistry>,
 }
 
 impl UserServiceImpl {
     pub fn new(pool: PgPool) -> Self {
         Self {
-            repository: UserRepository::new(pool),
+            repository: UserRepository::new(pool.clone()),
+            pool,
+            webhooks: None,
+            oss: None,
+            exports: Arc::new(RwLock::new(HashMap::new())),
+            avatars: None,
+            registry: None,
         }
     }
+
+    /// 启用出站Webhook事件
+    pub fn with_webhooks(mut self, webhooks: Arc<WebhookDispatcher>) -> Self {
+        self.webhooks = Some(webhooks);
+        self
+    }
+
+    /// 启用基于OSS的用户数据导出
+    pub fn with_oss(mut self, oss: Arc<dyn Oss>) -> Self {
+        self.oss = Some(oss);
+        self
+    }
+
+    /// 启用头像上传：图片写入OSS的avatar bucket，
+    /// url_base为公开访问的URL前缀，max_bytes为上传大小上限
+    pub fn with_avatar_storage(mut self, oss: Arc<dyn Oss>, url_base: String, max_bytes: usize) -> Self {
+        self.avatars = Some(AvatarStorage {
+            oss,
+            url_base: url_base.trim_end_matches('/').to_string(),
+            max_bytes,
+        });
+        self
+    }
+
+    /// 启用服务发现，删除账号时才能调用auth-service失效令牌
+    pub fn with_registry(mut self, registry: ServiceRegistry) -> Self {
+        self.registry = Some(registry);
+        self
+    }
+
+    /// 调用auth-service使用户全部令牌失效
+    ///
+    /// 未启用服务发现时跳过并返回Ok(false)，由调用方记录告警
+    async fn invalidate_tokens(&self, user_id: &str) -> std::result::Result<bool, Error> {
+        let Some(registry) = &self.registry else {
+            return Ok(false);
+        };
+
+        let endpoints = registry
+            .discover_service("auth-service")
+            .await
+            .map_err(|e| Error::Internal(e.to_string()))?;
+        let endpoint = endpoints
+            .first()
+            .ok_or_else(|| Error::Internal("没有可用的auth-service实例".to_string()))?;
+
+        let mut client = AuthServiceClient::connect(endpoint.clone())
+            .await
+            .map_err(|e| Error::Internal(format!("连接auth-service失败: {}", e)))?;
+        let response = client
+            .invalidate_user_tokens(InvalidateUserTokensRequest {
+                user_id: user_id.to_string(),
+            })
+            .await
+            .map_err(Error::TonicStatus)?;
+
+        info!(
+            "用户 {} 的 {} 个令牌已失效",
+            user_id,
+            response.into_inner().revoked_count
+        );
+        Ok(true)
+    }
 }
 
 #[tonic::async_trait]
@@ -47,7 +183,19 @@ impl UserService for UserServiceImpl {
         };
         
         info!("成功创建用户 {}", user.id);
-        
+
+        // 分发user.created事件（异步投递，不阻塞响应）
+        if let Some(webhooks) = &self.webhooks {
+            webhooks.dispatch(WebhookEvent::new(
+                "user.created",
+                serde_json::json!({
+                    "user_id": user.id,
+                    "username": user.username,
+                    "email": user.email,
+                }),
+            ));
+        }
+
         // 返回响应
         Ok(Response::new(UserResponse {
             user: Some(ProtoUser::from(user)),
@@ -198,4 +346,628 @@ impl UserService for UserServiceImpl {
             total,
         }))
     }
-} 
\ No newline at end of file
+
+    /// 按ID列表批量获取用户
+    async fn batch_get_users(
+        &self,
+        request: Request<BatchGetUsersRequest>,
+    ) -> std::result::Result<Response<BatchGetUsersResponse>, Status> {
+        let req = request.into_inner();
+        debug!("批量获取用户请求，数量: {}", req.user_ids.len());
+
+        // 单次批量上限500，超出直接拒绝
+        if req.user_ids.len() > 500 {
+            return Err(Status::invalid_argument(format!(
+                "单次最多查询500个用户，收到{}个",
+                req.user_ids.len()
+            )));
+        }
+
+        let users = match self.repository.get_users_by_ids(&req.user_ids).await {
+            Ok(users) => users,
+            Err(err) => {
+                error!("批量获取用户失败: {}", err);
+                return Err(err.into());
+            }
+        };
+
+        let users: Vec<ProtoUser> = users.into_iter().map(ProtoUser::from).collect();
+        Ok(Response::new(BatchGetUsersResponse { users }))
+    }
+
+    /// 查询单聊隐私设置
+    async fn get_message_privacy(
+        &self,
+        request: Request<GetMessagePrivacyRequest>,
+    ) -> std::result::Result<Response<MessagePrivacyResponse>, Status> {
+        let req = request.into_inner();
+        debug!("查询隐私设置请求，用户ID: {}", req.user_id);
+
+        match self.repository.get_message_privacy(&req.user_id).await {
+            Ok(privacy) => Ok(Response::new(MessagePrivacyResponse { privacy })),
+            Err(err) => {
+                error!("查询隐私设置失败: {}", err);
+                Err(err.into())
+            }
+        }
+    }
+
+    /// 更新单聊隐私设置
+    async fn update_message_privacy(
+        &self,
+        request: Request<UpdateMessagePrivacyRequest>,
+    ) -> std::result::Result<Response<MessagePrivacyResponse>, Status> {
+        let req = request.into_inner();
+        debug!("更新隐私设置请求，用户ID: {}，设置: {}", req.user_id, req.privacy);
+
+        match self
+            .repository
+            .update_message_privacy(&req.user_id, &req.privacy)
+            .await
+        {
+            Ok(privacy) => Ok(Response::new(MessagePrivacyResponse { privacy })),
+            Err(err) => {
+                error!("更新隐私设置失败: {}", err);
+                Err(err.into())
+            }
+        }
+    }
+
+    /// 更新在线状态
+    async fn update_status(
+        &self,
+        request: Request<UpdateStatusRequest>,
+    ) -> std::result::Result<Response<StatusResponse>, Status> {
+        let req = request.into_inner();
+        debug!("更新在线状态请求，用户ID: {}，状态: {}", req.user_id, req.status);
+
+        match self.repository.update_status(&req.user_id, &req.status).await {
+            Ok((status, last_seen)) => Ok(Response::new(StatusResponse {
+                status,
+                last_seen: last_seen.map(|t| prost_types::Timestamp {
+                    seconds: t.timestamp(),
+                    nanos: t.timestamp_subsec_nanos() as i32,
+                }),
+            })),
+            Err(err) => {
+                error!("更新在线状态失败: {}", err);
+                Err(err.into())
+            }
+        }
+    }
+
+    /// 查询在线状态
+    async fn get_status(
+        &self,
+        request: Request<GetStatusRequest>,
+    ) -> std::result::Result<Response<StatusResponse>, Status> {
+        let req = request.into_inner();
+        debug!("查询在线状态请求，用户ID: {}", req.user_id);
+
+        match self.repository.get_status(&req.user_id).await {
+            Ok((status, last_seen)) => Ok(Response::new(StatusResponse {
+                status,
+                last_seen: last_seen.map(|t| prost_types::Timestamp {
+                    seconds: t.timestamp(),
+                    nanos: t.timestamp_subsec_nanos() as i32,
+                }),
+            })),
+            Err(err) => {
+                error!("查询在线状态失败: {}", err);
+                Err(err.into())
+            }
+        }
+    }
+
+    /// 删除账号（GDPR）
+    ///
+    /// 按顺序执行各清理步骤：失效令牌、删除好友关系、处理群组、
+    /// 匿名化消息、软删除用户。每个步骤都是幂等的，
+    /// 中途失败后重新调用会从头跑一遍并安全地跳过已完成的工作。
+    async fn delete_account(
+        &self,
+        request: Request<DeleteAccountRequest>,
+    ) -> std::result::Result<Response<DeleteAccountResponse>, Status> {
+        let req = request.into_inner();
+        debug!("删除账号请求，用户ID: {}", req.user_id);
+
+        if !is_self_or_admin(&req.requester_id, &req.user_id, req.requester_is_admin) {
+            return Err(Status::permission_denied("只有用户本人或管理员可以删除账号"));
+        }
+
+        // 确认用户存在（软删除后的用户仍然存在，重跑不会被拒绝）
+        if let Err(err) = self.repository.get_user_by_id(&req.user_id).await {
+            error!("删除账号失败: {}", err);
+            return Err(err.into());
+        }
+
+        let mut completed_steps = Vec::new();
+        let step_failed = |step: &str, err: Error| {
+            error!("删除账号步骤 {} 失败: {}，流程可安全重跑", step, err);
+            Status::internal(format!("删除账号步骤 {} 失败，可安全重试", step))
+        };
+
+        // 1. 失效全部令牌，阻止已删除账号继续访问
+        match self.invalidate_tokens(&req.user_id).await {
+            Ok(true) => completed_steps.push("tokens_invalidated".to_string()),
+            Ok(false) => warn!("未启用服务发现，跳过令牌失效步骤"),
+            Err(err) => return Err(step_failed("tokens_invalidated", err)),
+        }
+
+        let deletion = DeletionRepository::new(self.pool.clone());
+
+        // 2. 删除好友关系
+        deletion
+            .remove_friendships(&req.user_id)
+            .await
+            .map_err(|err| step_failed("friendships_removed", err))?;
+        completed_steps.push("friendships_removed".to_string());
+
+        // 3. 转让/删除拥有的群组，退出其余群组
+        deletion
+            .handle_groups(&req.user_id)
+            .await
+            .map_err(|err| step_failed("groups_handled", err))?;
+        completed_steps.push("groups_handled".to_string());
+
+        // 4. 匿名化已发送的消息
+        deletion
+            .anonymize_messages(&req.user_id)
+            .await
+            .map_err(|err| step_failed("messages_anonymized", err))?;
+        completed_steps.push("messages_anonymized".to_string());
+
+        // 5. 软删除用户档案
+        let deleted = deletion
+            .soft_delete_user(&req.user_id)
+            .await
+            .map_err(|err| step_failed("user_soft_deleted", err))?;
+        if !deleted {
+            debug!("用户 {} 已是删除状态，跳过软删除", req.user_id);
+        }
+        completed_steps.push("user_soft_deleted".to_string());
+
+        info!("账号 {} 删除完成，步骤: {:?}", req.user_id, completed_steps);
+
+        // 分发user.deleted事件，供下游服务清理各自的缓存/索引
+        if let Some(webhooks) = &self.webhooks {
+            webhooks.dispatch(WebhookEvent::new(
+                "user.deleted",
+                serde_json::json!({
+                    "user_id": req.user_id,
+                    "completed_steps": completed_steps,
+                }),
+            ));
+        }
+
+        Ok(Response::new(DeleteAccountResponse { completed_steps }))
+    }
+
+    /// 导出用户数据（GDPR）
+    ///
+    /// 汇总与上传在后台任务中执行，调用方通过GetUserDataExport轮询结果
+    async fn export_user_data(
+        &self,
+        request: Request<ExportUserDataRequest>,
+    ) -> std::result::Result<Response<ExportUserDataResponse>, Status> {
+        let req = request.into_inner();
+        debug!("导出用户数据请求，用户ID: {}", req.user_id);
+
+        if !is_self_or_admin(&req.requester_id, &req.user_id, req.requester_is_admin) {
+            return Err(Status::permission_denied("只有用户本人或管理员可以导出用户数据"));
+        }
+
+        let Some(oss) = self.oss.clone() else {
+            return Err(Status::failed_precondition("数据导出未启用"));
+        };
+
+        // 确认用户存在后再创建任务
+        if let Err(err) = self.repository.get_user_by_id(&req.user_id).await {
+            error!("导出用户数据失败: {}", err);
+            return Err(err.into());
+        }
+
+        let export_id = uuid::Uuid::new_v4().to_string();
+        self.exports.write().await.insert(
+            export_id.clone(),
+            ExportJob {
+                user_id: req.user_id.clone(),
+                state: ExportState::Pending,
+            },
+        );
+
+        // 数据量可能很大，在后台任务中汇总并上传
+        let pool = self.pool.clone();
+        let exports = self.exports.clone();
+        let user_id = req.user_id;
+        let id = export_id.clone();
+        tokio::spawn(async move {
+            let result = async {
+                let archive = ExportRepository::new(pool).export_user_data(&user_id).await?;
+                let body = serde_json::to_vec_pretty(&archive).map_err(Error::Json)?;
+                let object_key = format!("exports/{}/{}.json", user_id, id);
+                oss.upload_file(&object_key, body).await?;
+                Ok::<String, Error>(object_key)
+            }
+            .await;
+
+            let mut exports = exports.write().await;
+            if let Some(job) = exports.get_mut(&id) {
+                job.state = match result {
+                    Ok(object_key) => {
+                        info!("用户数据导出完成，用户ID: {}, 归档: {}", user_id, object_key);
+                        ExportState::Completed { object_key }
+                    }
+                    Err(err) => {
+                        error!("用户数据导出失败，用户ID: {}: {}", user_id, err);
+                        ExportState::Failed
+                    }
+                };
+            }
+        });
+
+        Ok(Response::new(ExportUserDataResponse {
+            export_id,
+            status: "pending".to_string(),
+        }))
+    }
+
+    /// 查询导出任务状态，完成后返回预签名下载链接
+    async fn get_user_data_export(
+        &self,
+        request: Request<GetUserDataExportRequest>,
+    ) -> std::result::Result<Response<GetUserDataExportResponse>, Status> {
+        let req = request.into_inner();
+
+        let exports = self.exports.read().await;
+        let job = exports
+            .get(&req.export_id)
+            .ok_or_else(|| Status::not_found("导出任务不存在"))?;
+
+        if !is_self_or_admin(&req.requester_id, &job.user_id, req.requester_is_admin) {
+            return Err(Status::permission_denied("只有用户本人或管理员可以查看导出结果"));
+        }
+
+        let (status, download_url) = match &job.state {
+            ExportState::Pending => ("pending".to_string(), String::new()),
+            ExportState::Failed => ("failed".to_string(), String::new()),
+            ExportState::Completed { object_key } => {
+                let oss = self
+                    .oss
+                    .as_ref()
+                    .ok_or_else(|| Status::failed_precondition("数据导出未启用"))?;
+                let url = oss
+                    .presigned_download_url(object_key, EXPORT_URL_TTL_SECS)
+                    .await
+                    .map_err(Status::from)?;
+                ("completed".to_string(), url)
+            }
+        };
+
+        Ok(Response::new(GetUserDataExportResponse {
+            export_id: req.export_id,
+            status,
+            download_url,
+        }))
+    }
+
+    /// 上传头像到OSS并更新用户的avatar_url
+    async fn upload_avatar(
+        &self,
+        request: Request<UploadAvatarRequest>,
+    ) -> std::result::Result<Response<UploadAvatarResponse>, Status> {
+        let req = request.into_inner();
+
+        let avatars = self
+            .avatars
+            .as_ref()
+            .ok_or_else(|| Status::failed_precondition("未配置OSS，头像上传不可用"))?;
+
+        // 仅接受图片类型，同时决定对象键的扩展名
+        let content_type = req.content_type.to_ascii_lowercase();
+        let Some((_, ext)) = AVATAR_CONTENT_TYPES
+            .iter()
+            .find(|(mime, _)| *mime == content_type)
+        else {
+            return Err(Status::invalid_argument(format!(
+                "不支持的头像类型: {}",
+                req.content_type
+            )));
+        };
+        if req.content.is_empty() {
+            return Err(Status::invalid_argument("头像内容为空"));
+        }
+        if req.content.len() > avatars.max_bytes {
+            return Err(Status::invalid_argument(format!(
+                "头像大小超过限制: {} > {} 字节",
+                req.content.len(),
+                avatars.max_bytes
+            )));
+        }
+
+        // 用户必须存在且未删除
+        if let Err(err) = self.repository.get_user_by_id(&req.user_id).await {
+            error!("上传头像失败，查询用户出错: {}", err);
+            return Err(err.into());
+        }
+
+        // 每次上传生成新的对象键，避免浏览器/CDN缓存旧头像
+        let key = format!("avatars/{}/{}.{}", req.user_id, uuid::Uuid::new_v4(), ext);
+        avatars
+            .oss
+            .upload_avatar(&key, req.content)
+            .await
+            .map_err(|err| {
+                error!("上传头像到OSS失败: {}", err);
+                Status::internal("上传头像失败")
+            })?;
+
+        let avatar_url = format!("{}/{}", avatars.url_base, key);
+        let updated = self
+            .repository
+            .update_user(
+                &req.user_id,
+                UpdateUserData {
+                    nickname: None,
+                    email: None,
+                    avatar_url: Some(avatar_url.clone()),
+                    password: None,
+                },
+            )
+            .await
+            .map_err(|err| {
+                error!("更新用户头像URL失败: {}", err);
+                Status::from(err)
+            })?;
+
+        debug!("用户 {} 头像已更新: {}", req.user_id, avatar_url);
+        Ok(Response::new(UploadAvatarResponse {
+            avatar_url: updated.avatar_url.unwrap_or(avatar_url),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::Error as CommonError;
+
+    /// 记录上传调用的OSS桩实现，仅头像接口有效
+    #[derive(Debug, Default)]
+    struct MockOss {
+        avatar_uploads: std::sync::Mutex<Vec<(String, Vec<u8>)>>,
+    }
+
+    #[async_trait::async_trait]
+    impl Oss for MockOss {
+        async fn file_exists(&self, _key: &str, _local_md5: &str) -> std::result::Result<bool, CommonError> {
+            unimplemented!()
+        }
+
+        async fn upload_file(&self, _key: &str, _content: Vec<u8>) -> std::result::Result<(), CommonError> {
+            unimplemented!()
+        }
+
+        async fn download_file(&self, _key: &str) -> std::result::Result<bytes::Bytes, CommonError> {
+            unimplemented!()
+        }
+
+        async fn delete_file(&self, _key: &str) -> std::result::Result<(), CommonError> {
+            unimplemented!()
+        }
+
+        async fn presigned_download_url(
+            &self,
+            _key: &str,
+            _expires_secs: u64,
+        ) -> std::result::Result<String, CommonError> {
+            unimplemented!()
+        }
+
+        async fn upload_avatar(&self, key: &str, content: Vec<u8>) -> std::result::Result<(), CommonError> {
+            self.avatar_uploads
+                .lock()
+                .unwrap()
+                .push((key.to_string(), content));
+            Ok(())
+        }
+
+        async fn download_avatar(&self, _key: &str) -> std::result::Result<bytes::Bytes, CommonError> {
+            unimplemented!()
+        }
+
+        async fn delete_avatar(&self, _key: &str) -> std::result::Result<(), CommonError> {
+            unimplemented!()
+        }
+    }
+
+    /// 带MockOss头像存储的服务实例；lazy连接池在校验被拒绝的用例中不会真正连库
+    fn service_with_mock_oss(pool: PgPool, max_bytes: usize) -> (UserServiceImpl, Arc<MockOss>) {
+        let mock = Arc::new(MockOss::default());
+        let service = UserServiceImpl::new(pool).with_avatar_storage(
+            mock.clone(),
+            "http://127.0.0.1:9000/rustIM-avatar".to_string(),
+            max_bytes,
+        );
+        (service, mock)
+    }
+
+    #[tokio::test]
+    async fn test_upload_avatar_rejects_non_image_and_oversize() {
+        let pool = PgPool::connect_lazy("postgres://localhost/unused").unwrap();
+        let (service, mock) = service_with_mock_oss(pool, 16);
+
+        // 非图片类型
+        let err = service
+            .upload_avatar(Request::new(UploadAvatarRequest {
+                user_id: uuid::Uuid::new_v4().to_string(),
+                content: b"not an image".to_vec(),
+                content_type: "text/plain".to_string(),
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::InvalidArgument);
+
+        // 超过大小上限
+        let err = service
+            .upload_avatar(Request::new(UploadAvatarRequest {
+                user_id: uuid::Uuid::new_v4().to_string(),
+                content: vec![0u8; 17],
+                content_type: "image/png".to_string(),
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::InvalidArgument);
+
+        // 两次都不应触达OSS
+        assert!(mock.avatar_uploads.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    #[ignore = "需要DATABASE_URL指向的PostgreSQL"]
+    async fn test_upload_avatar_stores_object_and_updates_user() {
+        let pool = PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        let (service, mock) = service_with_mock_oss(pool.clone(), 1024);
+
+        let id = uuid::Uuid::new_v4().to_string();
+        sqlx::query("INSERT INTO users (id, username, email, password) VALUES ($1, $1, $1 || '@test.local', 'pw')")
+            .bind(&id)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let content = b"\x89PNG fake image bytes".to_vec();
+        let resp = service
+            .upload_avatar(Request::new(UploadAvatarRequest {
+                user_id: id.clone(),
+                content: content.clone(),
+                content_type: "image/png".to_string(),
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+
+        // 对象键位于该用户的avatars目录下，扩展名与MIME类型对应
+        let (key, uploaded) = {
+            let uploads = mock.avatar_uploads.lock().unwrap();
+            assert_eq!(uploads.len(), 1);
+            uploads[0].clone()
+        };
+        assert!(key.starts_with(&format!("avatars/{}/", id)), "对象键: {}", key);
+        assert!(key.ends_with(".png"));
+        assert_eq!(uploaded, content);
+
+        // 返回的URL指向该对象，且已写入users.avatar_url
+        assert_eq!(
+            resp.avatar_url,
+            format!("http://127.0.0.1:9000/rustIM-avatar/{}", key)
+        );
+        let stored: Option<String> = sqlx::query_scalar("SELECT avatar_url FROM users WHERE id = $1")
+            .bind(&id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(stored.as_deref(), Some(resp.avatar_url.as_str()));
+
+        sqlx::query("DELETE FROM users WHERE id = $1")
+            .bind(&id)
+            .execute(&pool)
+            .await
+            .unwrap();
+    }
+
+    #[test]
+    fn test_only_self_or_admin_can_access() {
+        // 用户本人可导出
+        assert!(is_self_or_admin("user-1", "user-1", false));
+        // 管理员可代为导出
+        assert!(is_self_or_admin("admin-1", "user-1", true));
+        // 其他用户不可导出
+        assert!(!is_self_or_admin("user-2", "user-1", false));
+        // 空的发起者ID不可导出
+        assert!(!is_self_or_admin("", "", false));
+    }
+
+    #[tokio::test]
+    #[ignore = "需要DATABASE_URL指向的PostgreSQL"]
+    async fn test_batch_get_users_caps_batch_and_skips_missing_ids() {
+        let pool = PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        let service = UserServiceImpl::new(pool.clone());
+
+        // 超过500个ID直接拒绝
+        let too_many: Vec<String> = (0..501).map(|_| uuid::Uuid::new_v4().to_string()).collect();
+        let err = service
+            .batch_get_users(Request::new(BatchGetUsersRequest { user_ids: too_many }))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::InvalidArgument);
+
+        // 两个存在的ID加一个不存在的ID，只返回存在的两个
+        let existing: Vec<String> = (0..2).map(|_| uuid::Uuid::new_v4().to_string()).collect();
+        for id in &existing {
+            sqlx::query("INSERT INTO users (id, username, email, password) VALUES ($1, $1, $1 || '@test.local', 'pw')")
+                .bind(id)
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+        let mut user_ids = existing.clone();
+        user_ids.push(uuid::Uuid::new_v4().to_string());
+
+        let resp = service
+            .batch_get_users(Request::new(BatchGetUsersRequest { user_ids }))
+            .await
+            .unwrap()
+            .into_inner();
+        let mut got: Vec<String> = resp.users.into_iter().map(|u| u.id).collect();
+        got.sort();
+        let mut expected = existing.clone();
+        expected.sort();
+        assert_eq!(got, expected);
+
+        // 清理测试数据
+        for id in &existing {
+            sqlx::query("DELETE FROM users WHERE id = $1")
+                .bind(id)
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+    }
+
+    #[tokio::test]
+    #[ignore = "需要DATABASE_URL指向的PostgreSQL"]
+    async fn test_batch_get_users_deduplicates_input() {
+        let pool = PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        let service = UserServiceImpl::new(pool.clone());
+
+        let id = uuid::Uuid::new_v4().to_string();
+        sqlx::query("INSERT INTO users (id, username, email, password) VALUES ($1, $1, $1 || '@test.local', 'pw')")
+            .bind(&id)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        // 同一ID重复出现，响应里该用户只出现一次
+        let user_ids = vec![id.clone(), id.clone(), id.clone()];
+        let resp = service
+            .batch_get_users(Request::new(BatchGetUsersRequest { user_ids }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(resp.users.len(), 1);
+        assert_eq!(resp.users[0].id, id);
+
+        sqlx::query("DELETE FROM users WHERE id = $1")
+            .bind(&id)
+            .execute(&pool)
+            .await
+            .unwrap();
+    }
+}
\ No newline at end of file